@@ -0,0 +1,1024 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use regex::Regex;
+use serde_json::{Value, json};
+use sha1::{Digest, Sha1};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{Row, SqlitePool};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::cloze::{is_cloze, parse_deletions};
+use crate::database::Database;
+use crate::models::Card;
+use crate::zip_archive::{ZipEntry, read_zip, write_zip};
+
+/// Exported notes keep their zettel_id in this field, so importing them again keeps it
+pub const ZETTEL_ID_FIELD: &str = "Zettel ID";
+
+/// Imported notes without a zettel_id field are named after their Anki note id
+const ZETTEL_ID_PREFIX: &str = "anki-";
+
+/// Anki separates note fields with the ASCII unit separator
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+/// Anki's built-in deck, which isn't imported as a topic
+const DEFAULT_DECK_ID: i64 = 1;
+const DEFAULT_DECK_NAME: &str = "Default";
+
+/// Anki's starting ease, in permille
+const DEFAULT_FACTOR: i64 = 2500;
+
+/// Anki `due` values above this are timestamps rather than days since collection creation
+const DUE_TIMESTAMP_THRESHOLD: i64 = 1_000_000_000;
+
+const BASIC_MODEL_NAME: &str = "Learning System";
+const CLOZE_MODEL_NAME: &str = "Learning System Cloze";
+
+static BLOCK_BREAK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)<br\s*/?>|</(?:div|p|li|h[1-6])>").expect("block break pattern is valid")
+});
+static LIST_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<li[^>]*>").expect("list item pattern is valid"));
+static HTML_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<[^>]*>").expect("html tag pattern is valid"));
+static BLANK_LINES: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\n{3,}").expect("blank lines pattern is valid"));
+// Anki's [$]...[/$] and MathJax \(...\), and their display variants
+static ANKI_INLINE_MATH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)\[\$\](.*?)\[/\$\]|\\\((.*?)\\\)").expect("inline math pattern is valid")
+});
+static ANKI_DISPLAY_MATH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)\[\$\$\](.*?)\[/\$\$\]|\\\[(.*?)\\\]").expect("display math pattern is valid")
+});
+static DISPLAY_MATH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)\$\$(.+?)\$\$").expect("display math pattern is valid"));
+static INLINE_MATH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$([^$\n]+?)\$").expect("inline math pattern is valid"));
+
+/// What an import did
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AnkiImportReport {
+    pub created: Vec<String>,
+    pub skipped: Vec<String>, // Notes whose zettel_id already exists
+    pub topics_created: Vec<String>,
+    pub reviews_imported: usize,
+}
+
+/// An Anki collection file unpacked from a package, removed when dropped
+struct CollectionFile {
+    path: PathBuf,
+}
+
+impl CollectionFile {
+    fn new() -> Self {
+        Self {
+            path: std::env::temp_dir().join(format!("anki-collection-{}.anki2", Uuid::new_v4())),
+        }
+    }
+
+    async fn open(&self) -> Result<SqlitePool> {
+        let options = SqliteConnectOptions::new()
+            .filename(&self.path)
+            .create_if_missing(true)
+            // Keep everything in the one file that goes into the package
+            .journal_mode(SqliteJournalMode::Delete);
+        Ok(SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?)
+    }
+}
+
+impl Drop for CollectionFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+struct AnkiModel {
+    cloze: bool,
+    fields: Vec<String>,
+}
+
+struct AnkiNote {
+    id: i64,
+    model_id: i64,
+    fields: Vec<String>,
+    tags: Vec<String>,
+}
+
+struct AnkiCard {
+    id: i64,
+    note_id: i64,
+    deck_id: i64,
+    card_type: i64, // 0 new, 1 learning, 2 review, 3 relearning
+    due: i64,
+    interval: i64, // Days, or negative seconds while learning
+    factor: i64,
+    reps: i32,
+    lapses: i32,
+    data: String,
+}
+
+struct AnkiReview {
+    card_id: i64,
+    time_ms: i64,
+    ease: i32,
+    interval: i64,
+    factor: i64,
+}
+
+struct AnkiCollection {
+    created: DateTime<Utc>,
+    decks: HashMap<i64, String>,
+    models: HashMap<i64, AnkiModel>,
+    notes: Vec<AnkiNote>,
+    cards: Vec<AnkiCard>,
+    reviews: Vec<AnkiReview>,
+}
+
+/// Import an Anki `.apkg` package. Each note becomes a card with topics from its deck
+/// and tags, the scheduling state of its first card, and the review history of all its
+/// cards. Notes whose zettel_id already exists are skipped, so a package can be
+/// imported again safely. Media files are not imported.
+pub async fn import_apkg(db: &Database, package: &[u8]) -> Result<AnkiImportReport> {
+    let entries = read_zip(package).context("Failed to read Anki package")?;
+    let find = |name: &str| entries.iter().find(|entry| entry.name == name);
+
+    // Packages from recent Anki versions hold a zstd-compressed collection next to a
+    // placeholder collection.anki2
+    let Some(collection) = find("collection.anki21").or_else(|| {
+        find("collection.anki21b")
+            .is_none()
+            .then(|| find("collection.anki2"))
+            .flatten()
+    }) else {
+        if find("collection.anki21b").is_some() {
+            bail!(
+                "This package uses Anki's newer compressed format; export it again with \"Support older Anki versions\" enabled"
+            );
+        }
+        bail!("No Anki collection found in the package");
+    };
+
+    let file = CollectionFile::new();
+    fs::write(&file.path, &collection.data)?;
+    let pool = file.open().await?;
+    let collection = read_collection(&pool).await;
+    pool.close().await;
+
+    import_collection(db, collection?).await
+}
+
+async fn read_collection(pool: &SqlitePool) -> Result<AnkiCollection> {
+    let col = sqlx::query("SELECT crt, models, decks FROM col")
+        .fetch_one(pool)
+        .await
+        .context("Not an Anki collection")?;
+    let created = Utc
+        .timestamp_opt(col.get::<i64, _>("crt"), 0)
+        .single()
+        .unwrap_or_else(Utc::now);
+
+    let decks: HashMap<String, Value> = serde_json::from_str(&col.get::<String, _>("decks"))?;
+    let decks = decks
+        .into_iter()
+        .filter_map(|(id, deck)| Some((id.parse().ok()?, deck["name"].as_str()?.to_string())))
+        .collect();
+
+    let models: HashMap<String, Value> = serde_json::from_str(&col.get::<String, _>("models"))?;
+    let models = models
+        .into_iter()
+        .filter_map(|(id, model)| {
+            let mut fields: Vec<(i64, String)> = model["flds"]
+                .as_array()?
+                .iter()
+                .filter_map(|field| {
+                    Some((field["ord"].as_i64()?, field["name"].as_str()?.to_string()))
+                })
+                .collect();
+            fields.sort();
+            Some((
+                id.parse().ok()?,
+                AnkiModel {
+                    cloze: model["type"].as_i64() == Some(1),
+                    fields: fields.into_iter().map(|(_, name)| name).collect(),
+                },
+            ))
+        })
+        .collect();
+
+    let notes = sqlx::query("SELECT id, mid, flds, tags FROM notes ORDER BY id")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| AnkiNote {
+            id: row.get("id"),
+            model_id: row.get("mid"),
+            fields: row
+                .get::<String, _>("flds")
+                .split(FIELD_SEPARATOR)
+                .map(str::to_string)
+                .collect(),
+            tags: row
+                .get::<String, _>("tags")
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+        })
+        .collect();
+
+    let cards = sqlx::query("SELECT * FROM cards ORDER BY nid, ord")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| AnkiCard {
+            id: row.get("id"),
+            note_id: row.get("nid"),
+            deck_id: row.get("did"),
+            card_type: row.get("type"),
+            due: row.get("due"),
+            interval: row.get("ivl"),
+            factor: row.get("factor"),
+            reps: row.get("reps"),
+            lapses: row.get("lapses"),
+            data: row.try_get("data").unwrap_or_default(),
+        })
+        .collect();
+
+    let reviews = sqlx::query("SELECT id, cid, ease, ivl, factor FROM revlog ORDER BY id")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| AnkiReview {
+            card_id: row.get("cid"),
+            time_ms: row.get("id"),
+            ease: row.get("ease"),
+            interval: row.get("ivl"),
+            factor: row.get("factor"),
+        })
+        .collect();
+
+    Ok(AnkiCollection {
+        created,
+        decks,
+        models,
+        notes,
+        cards,
+        reviews,
+    })
+}
+
+async fn import_collection(db: &Database, collection: AnkiCollection) -> Result<AnkiImportReport> {
+    // Every note is imported in one transaction, so a failed import leaves no partial cards
+    let mut tx = db.pool.begin().await?;
+    let mut report = AnkiImportReport::default();
+
+    let mut topics: HashMap<String, Uuid> = HashMap::new();
+    for row in sqlx::query("SELECT id, name FROM topics")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        topics.insert(
+            row.get("name"),
+            Uuid::parse_str(&row.get::<String, _>("id"))?,
+        );
+    }
+
+    let mut cards_by_note: HashMap<i64, Vec<&AnkiCard>> = HashMap::new();
+    for card in &collection.cards {
+        cards_by_note.entry(card.note_id).or_default().push(card);
+    }
+    let mut reviews_by_card: HashMap<i64, Vec<&AnkiReview>> = HashMap::new();
+    for review in &collection.reviews {
+        reviews_by_card
+            .entry(review.card_id)
+            .or_default()
+            .push(review);
+    }
+
+    for note in &collection.notes {
+        let model = collection.models.get(&note.model_id);
+        let (zettel_id, title, content) = note_to_card_text(note, model);
+        let exists = sqlx::query("SELECT 1 FROM cards WHERE zettel_id = ?1")
+            .bind(&zettel_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        if exists {
+            report.skipped.push(zettel_id);
+            continue;
+        }
+
+        let anki_cards = cards_by_note.get(&note.id).cloned().unwrap_or_default();
+        let deck_names = anki_cards
+            .iter()
+            .filter(|card| card.deck_id != DEFAULT_DECK_ID)
+            .filter_map(|card| collection.decks.get(&card.deck_id));
+        let mut topic_ids = Vec::new();
+        for name in deck_names.chain(&note.tags) {
+            let topic_id = match topics.get(name) {
+                Some(id) => *id,
+                None => {
+                    let id = Uuid::new_v4();
+                    sqlx::query("INSERT INTO topics (id, name, description) VALUES (?1, ?2, NULL)")
+                        .bind(id.to_string())
+                        .bind(name)
+                        .execute(&mut *tx)
+                        .await?;
+                    report.topics_created.push(name.clone());
+                    topics.insert(name.clone(), id);
+                    id
+                }
+            };
+            if !topic_ids.contains(&topic_id) {
+                topic_ids.push(topic_id);
+            }
+        }
+
+        let mut reviews: Vec<&AnkiReview> = anki_cards
+            .iter()
+            .flat_map(|anki_card| {
+                reviews_by_card
+                    .get(&anki_card.id)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .filter(|review| (1..=4).contains(&review.ease))
+            .collect();
+        reviews.sort_by_key(|review| review.time_ms);
+
+        let now = Utc::now();
+        let mut card = Card {
+            id: Uuid::new_v4(),
+            zettel_id: zettel_id.clone(),
+            title,
+            content,
+            creation_date: now,
+            last_reviewed: None,
+            next_review: now,
+            difficulty: 0.0,
+            stability: 0.0,
+            retrievability: 0.0,
+            reps: 0,
+            lapses: 0,
+            state: "New".to_string(),
+            links: None,
+        };
+        let last_review = reviews
+            .last()
+            .and_then(|review| Utc.timestamp_millis_opt(review.time_ms).single());
+        if let Some(anki_card) = anki_cards.first() {
+            apply_anki_schedule(&mut card, anki_card, collection.created, last_review);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO cards (id, zettel_id, title, content, creation_date, last_reviewed, next_review,
+                             difficulty, stability, retrievability, reps, lapses, state, links)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, NULL)
+            "#,
+        )
+        .bind(card.id.to_string())
+        .bind(&card.zettel_id)
+        .bind(&card.title)
+        .bind(&card.content)
+        .bind(card.creation_date.to_rfc3339())
+        .bind(card.last_reviewed.map(|d| d.to_rfc3339()))
+        .bind(card.next_review.to_rfc3339())
+        .bind(card.difficulty)
+        .bind(card.stability)
+        .bind(card.retrievability)
+        .bind(card.reps)
+        .bind(card.lapses)
+        .bind(&card.state)
+        .execute(&mut *tx)
+        .await?;
+
+        for topic_id in topic_ids {
+            sqlx::query("INSERT INTO card_topics (card_id, topic_id) VALUES (?1, ?2)")
+                .bind(card.id.to_string())
+                .bind(topic_id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for review in &reviews {
+            sqlx::query(
+                "INSERT INTO reviews (id, card_id, review_date, rating, interval, ease_factor) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(card.id.to_string())
+            .bind(
+                Utc.timestamp_millis_opt(review.time_ms)
+                    .single()
+                    .unwrap_or(now)
+                    .to_rfc3339(),
+            )
+            .bind(review.ease)
+            .bind(interval_days(review.interval))
+            .bind(review.factor as f64 / 1000.0)
+            .execute(&mut *tx)
+            .await?;
+        }
+        report.reviews_imported += reviews.len();
+
+        report.created.push(zettel_id);
+    }
+
+    tx.commit().await?;
+    info!(
+        created = report.created.len(),
+        skipped = report.skipped.len(),
+        reviews = report.reviews_imported,
+        "Anki import finished"
+    );
+    Ok(report)
+}
+
+/// The zettel_id, title and content for a note. Cloze notes keep their cloze text as
+/// content; other notes use their first field as the title and the rest as content.
+fn note_to_card_text(
+    note: &AnkiNote,
+    model: Option<&AnkiModel>,
+) -> (String, Option<String>, String) {
+    let field_name = |index: usize| {
+        model
+            .and_then(|model| model.fields.get(index))
+            .map(String::as_str)
+            .unwrap_or_default()
+    };
+
+    let mut zettel_id = None;
+    let mut title = None;
+    let mut fields = Vec::new();
+    for (index, field) in note.fields.iter().enumerate() {
+        let text = html_to_text(field);
+        match field_name(index) {
+            ZETTEL_ID_FIELD => zettel_id = Some(text).filter(|id| !id.is_empty()),
+            "Title" if model.is_some_and(|model| model.cloze) => {
+                title = Some(text).filter(|title| !title.is_empty())
+            }
+            _ => fields.push(text),
+        }
+    }
+
+    let zettel_id = zettel_id.unwrap_or_else(|| format!("{}{}", ZETTEL_ID_PREFIX, note.id));
+    if model.is_some_and(|model| model.cloze) {
+        let content = fields
+            .into_iter()
+            .filter(|field| !field.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        return (zettel_id, title, content);
+    }
+
+    let mut fields = fields.into_iter();
+    let first = fields.next().unwrap_or_default();
+    let rest: Vec<String> = fields.filter(|field| !field.is_empty()).collect();
+    if rest.is_empty() {
+        (zettel_id, None, first)
+    } else {
+        (
+            zettel_id,
+            Some(first).filter(|t| !t.is_empty()),
+            rest.join("\n\n"),
+        )
+    }
+}
+
+/// Set the card's FSRS state from an Anki card. New cards keep the state they were
+/// created with.
+fn apply_anki_schedule(
+    card: &mut Card,
+    anki_card: &AnkiCard,
+    collection_created: DateTime<Utc>,
+    last_review: Option<DateTime<Utc>>,
+) {
+    card.state = match anki_card.card_type {
+        1 => "Learning",
+        2 => "Review",
+        3 => "Relearning",
+        _ => return,
+    }
+    .to_string();
+
+    card.next_review = if anki_card.due > DUE_TIMESTAMP_THRESHOLD {
+        Utc.timestamp_opt(anki_card.due, 0)
+            .single()
+            .unwrap_or_else(Utc::now)
+    } else {
+        collection_created + Duration::days(anki_card.due)
+    };
+
+    // Collections scheduled with FSRS store its memory state with the card
+    let memory: Option<Value> = serde_json::from_str(&anki_card.data).ok();
+    let memory_value = |key: &str| memory.as_ref().and_then(|m| m[key].as_f64());
+    let interval = interval_days(anki_card.interval);
+    card.stability = memory_value("s").unwrap_or(interval);
+    card.difficulty = memory_value("d").unwrap_or_else(|| difficulty_from_factor(anki_card.factor));
+    card.reps = anki_card.reps;
+    card.lapses = anki_card.lapses;
+
+    let last_reviewed =
+        last_review.unwrap_or(card.next_review - Duration::seconds((interval * 86400.0) as i64));
+    card.last_reviewed = Some(last_reviewed);
+    let elapsed_days = (Utc::now() - last_reviewed).num_days().max(0) as f64;
+    card.retrievability = if card.stability > 0.0 {
+        (-(elapsed_days / card.stability)).exp()
+    } else {
+        0.0
+    };
+}
+
+/// Anki intervals are days, or negative seconds for learning steps
+fn interval_days(interval: i64) -> f64 {
+    if interval < 0 {
+        -interval as f64 / 86400.0
+    } else {
+        interval as f64
+    }
+}
+
+/// Map Anki's ease (1300-3000+ permille) onto our 1-10 difficulty, 2500 being 3
+fn difficulty_from_factor(factor: i64) -> f64 {
+    let factor = if factor > 0 { factor } else { DEFAULT_FACTOR };
+    (13.0 - factor as f64 / 250.0).clamp(1.0, 10.0)
+}
+
+fn factor_from_difficulty(difficulty: f64) -> i64 {
+    ((13.0 - difficulty.clamp(1.0, 10.0)) * 250.0)
+        .round()
+        .max(1300.0) as i64
+}
+
+/// Plain text from an Anki field: line breaks kept, other markup dropped, and Anki's
+/// math delimiters turned into `$...$` and `$$...$$`
+pub fn html_to_text(html: &str) -> String {
+    let text = BLOCK_BREAK.replace_all(html, "\n");
+    let text = LIST_ITEM.replace_all(&text, "- ");
+    let text = HTML_TAG.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    let text = ANKI_DISPLAY_MATH.replace_all(&text, |caps: &regex::Captures| {
+        format!(
+            "$${}$$",
+            caps.get(1).or(caps.get(2)).map_or("", |m| m.as_str())
+        )
+    });
+    let text = ANKI_INLINE_MATH.replace_all(&text, |caps: &regex::Captures| {
+        format!(
+            "${}$",
+            caps.get(1).or(caps.get(2)).map_or("", |m| m.as_str())
+        )
+    });
+    BLANK_LINES.replace_all(text.trim(), "\n\n").into_owned()
+}
+
+/// An Anki field from card text: HTML escaped, line breaks as `<br>` and math in
+/// MathJax delimiters
+pub fn text_to_html(text: &str) -> String {
+    let html = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let html = DISPLAY_MATH.replace_all(&html, r"\[$1\]");
+    let html = INLINE_MATH.replace_all(&html, r"\($1\)");
+    html.replace('\n', "<br>")
+}
+
+/// Anki's duplicate check: the first 8 hex digits of the SHA-1 of the sort field
+fn field_checksum(field: &str) -> i64 {
+    let digest = Sha1::digest(html_to_text(field).as_bytes());
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) as i64
+}
+
+/// Export every card as an Anki `.apkg` package. Each card becomes a note in the deck
+/// of its first topic (or the default deck) tagged with its other topics, with its FSRS
+/// state and review history. Cloze cards use a cloze note type.
+pub async fn export_apkg(db: &Database) -> Result<Vec<u8>> {
+    // Oldest first, so note ids follow creation order
+    let mut cards = db.get_all_cards().await?;
+    cards.sort_by_key(|card| card.creation_date);
+    let now = Utc::now();
+    // Review due dates count days from collection creation, so start before any of them
+    let collection_created = cards
+        .iter()
+        .flat_map(|card| [card.creation_date, card.next_review])
+        .min()
+        .unwrap_or(now)
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .map(|start| start.and_utc())
+        .unwrap_or(now);
+
+    let file = CollectionFile::new();
+    let pool = file.open().await?;
+    let result = write_collection(db, &pool, &cards, collection_created, now).await;
+    pool.close().await;
+    result?;
+
+    let package = write_zip(&[
+        ZipEntry {
+            name: "collection.anki2".to_string(),
+            data: fs::read(&file.path)?,
+        },
+        ZipEntry {
+            name: "media".to_string(),
+            data: b"{}".to_vec(),
+        },
+    ])?;
+    info!(cards = cards.len(), "Anki export finished");
+    Ok(package)
+}
+
+async fn write_collection(
+    db: &Database,
+    pool: &SqlitePool,
+    cards: &[Card],
+    collection_created: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    for statement in ANKI_SCHEMA {
+        sqlx::query(statement).execute(pool).await?;
+    }
+
+    let base_id = now.timestamp_millis();
+    let modified = now.timestamp();
+    let basic_model_id = base_id - 1;
+    let cloze_model_id = base_id - 2;
+
+    let mut decks: BTreeMap<String, i64> = BTreeMap::new();
+    let mut review_ids: BTreeSet<i64> = BTreeSet::new();
+    let mut tx = pool.begin().await?;
+
+    for (index, card) in cards.iter().enumerate() {
+        let note_id = base_id + index as i64;
+        let topics = db.get_topic_names_for_card(card.id).await?;
+        let deck_id = match topics.first() {
+            Some(name) => {
+                let next_id = base_id + cards.len() as i64 + decks.len() as i64;
+                *decks.entry(name.clone()).or_insert(next_id)
+            }
+            None => DEFAULT_DECK_ID,
+        };
+        let tags: Vec<String> = topics
+            .iter()
+            .skip(1)
+            .map(|topic| topic.replace(' ', "_"))
+            .collect();
+
+        let cloze = is_cloze(&card.content);
+        let title = card.title.as_deref().map(text_to_html).unwrap_or_default();
+        let (model_id, fields) = if cloze {
+            (
+                cloze_model_id,
+                vec![text_to_html(&card.content), title, card.zettel_id.clone()],
+            )
+        } else {
+            (
+                basic_model_id,
+                vec![title, text_to_html(&card.content), card.zettel_id.clone()],
+            )
+        };
+        let sort_field = fields
+            .iter()
+            .find(|field| !field.is_empty())
+            .cloned()
+            .unwrap_or_default();
+
+        sqlx::query(
+            "INSERT INTO notes (id, guid, mid, mod, usn, tags, flds, sfld, csum, flags, data) VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+        )
+        .bind(note_id)
+        .bind(card.id.simple().to_string())
+        .bind(model_id)
+        .bind(modified)
+        .bind(if tags.is_empty() {
+            String::new()
+        } else {
+            format!(" {} ", tags.join(" "))
+        })
+        .bind(fields.join(&FIELD_SEPARATOR.to_string()))
+        .bind(html_to_text(&sort_field))
+        .bind(field_checksum(&sort_field))
+        .execute(&mut *tx)
+        .await?;
+
+        // One Anki card per cloze number, sharing the card's schedule
+        let ords: BTreeSet<i64> = if cloze {
+            parse_deletions(&card.content)
+                .iter()
+                .map(|deletion| deletion.index.max(1) as i64 - 1)
+                .collect()
+        } else {
+            BTreeSet::from([0])
+        };
+        let schedule = AnkiSchedule::from_card(card, collection_created, index as i64);
+        for ord in &ords {
+            sqlx::query(
+                "INSERT INTO cards (id, nid, did, ord, mod, usn, type, queue, due, ivl, factor, reps, lapses, left, odue, odid, flags, data) VALUES (?1, ?2, ?3, ?4, ?5, -1, ?6, ?7, ?8, ?9, ?10, ?11, ?12, 0, 0, 0, 0, ?13)",
+            )
+            .bind(note_id * 100 + ord)
+            .bind(note_id)
+            .bind(deck_id)
+            .bind(ord)
+            .bind(modified)
+            .bind(schedule.card_type)
+            .bind(schedule.queue)
+            .bind(schedule.due)
+            .bind(schedule.interval)
+            .bind(schedule.factor)
+            .bind(card.reps)
+            .bind(card.lapses)
+            .bind(&schedule.data)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Reviews belong to the note's first card
+        let first_card_id = note_id * 100 + ords.first().copied().unwrap_or(0);
+        for (position, review) in db.get_reviews_for_card(card.id).await?.iter().enumerate() {
+            // Review log ids are millisecond timestamps and must be unique
+            let mut review_id = review.review_date.timestamp_millis();
+            while !review_ids.insert(review_id) {
+                review_id += 1;
+            }
+            sqlx::query(
+                "INSERT INTO revlog (id, cid, usn, ease, ivl, lastIvl, factor, time, type) VALUES (?1, ?2, -1, ?3, ?4, 0, ?5, 0, ?6)",
+            )
+            .bind(review_id)
+            .bind(first_card_id)
+            .bind(review.rating)
+            .bind(review.interval.round() as i64)
+            .bind((review.ease_factor * 1000.0).round() as i64)
+            .bind(if position == 0 { 0 } else { 1 })
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    let mut deck_json = serde_json::Map::new();
+    deck_json.insert(
+        DEFAULT_DECK_ID.to_string(),
+        anki_deck(DEFAULT_DECK_ID, DEFAULT_DECK_NAME, modified),
+    );
+    for (name, id) in &decks {
+        deck_json.insert(id.to_string(), anki_deck(*id, name, modified));
+    }
+    let models = json!({
+        basic_model_id.to_string(): anki_model(
+            basic_model_id,
+            BASIC_MODEL_NAME,
+            false,
+            &["Title", "Content", ZETTEL_ID_FIELD],
+            "{{Title}}{{^Title}}{{Content}}{{/Title}}",
+            "{{FrontSide}}{{#Title}}<hr id=answer>{{Content}}{{/Title}}",
+            modified,
+        ),
+        cloze_model_id.to_string(): anki_model(
+            cloze_model_id,
+            CLOZE_MODEL_NAME,
+            true,
+            &["Text", "Title", ZETTEL_ID_FIELD],
+            "{{cloze:Text}}",
+            "{{cloze:Text}}",
+            modified,
+        ),
+    });
+
+    sqlx::query(
+        "INSERT INTO col (id, crt, mod, scm, ver, dty, usn, ls, conf, models, decks, dconf, tags) VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+    )
+    .bind(collection_created.timestamp())
+    .bind(base_id)
+    .bind(
+        json!({
+            "activeDecks": [DEFAULT_DECK_ID],
+            "curDeck": DEFAULT_DECK_ID,
+            "curModel": basic_model_id.to_string(),
+            "newSpread": 0,
+            "collapseTime": 1200,
+            "timeLim": 0,
+            "estTimes": true,
+            "dueCounts": true,
+            "sortType": "noteFld",
+            "sortBackwards": false,
+            "nextPos": cards.len() + 1,
+            "schedVer": 2,
+        })
+        .to_string(),
+    )
+    .bind(models.to_string())
+    .bind(Value::Object(deck_json).to_string())
+    .bind(json!({ "1": anki_deck_config(modified) }).to_string())
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// A card's schedule in Anki's terms
+struct AnkiSchedule {
+    card_type: i64,
+    queue: i64,
+    due: i64,
+    interval: i64,
+    factor: i64,
+    data: String,
+}
+
+impl AnkiSchedule {
+    fn from_card(card: &Card, collection_created: DateTime<Utc>, position: i64) -> Self {
+        let (card_type, queue) = match card.state.as_str() {
+            "Learning" => (1, 1),
+            "Review" => (2, 2),
+            "Relearning" => (3, 1),
+            _ => {
+                return Self {
+                    card_type: 0,
+                    queue: 0,
+                    due: position + 1,
+                    interval: 0,
+                    factor: 0,
+                    data: String::new(),
+                };
+            }
+        };
+
+        let due = if card_type == 2 {
+            (card.next_review - collection_created).num_days()
+        } else {
+            card.next_review.timestamp()
+        };
+        let interval = match (card_type, card.last_reviewed) {
+            (2, Some(last)) => (card.next_review - last).num_days().max(1),
+            (2, None) => card.stability.round().max(1.0) as i64,
+            _ => 0,
+        };
+
+        Self {
+            card_type,
+            queue,
+            due,
+            interval,
+            factor: factor_from_difficulty(card.difficulty),
+            data: json!({ "s": card.stability, "d": card.difficulty }).to_string(),
+        }
+    }
+}
+
+fn anki_deck(id: i64, name: &str, modified: i64) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "mod": modified,
+        "usn": -1,
+        "desc": "",
+        "dyn": 0,
+        "conf": 1,
+        "collapsed": false,
+        "browserCollapsed": false,
+        "extendNew": 0,
+        "extendRev": 0,
+        "newToday": [0, 0],
+        "revToday": [0, 0],
+        "lrnToday": [0, 0],
+        "timeToday": [0, 0],
+    })
+}
+
+fn anki_deck_config(modified: i64) -> Value {
+    json!({
+        "id": 1,
+        "name": DEFAULT_DECK_NAME,
+        "mod": modified,
+        "usn": -1,
+        "maxTaken": 60,
+        "autoplay": true,
+        "timer": 0,
+        "replayq": true,
+        "dyn": false,
+        "new": {
+            "bury": false,
+            "delays": [1.0, 10.0],
+            "initialFactor": DEFAULT_FACTOR,
+            "ints": [1, 4, 0],
+            "order": 1,
+            "perDay": 20,
+        },
+        "rev": {
+            "bury": false,
+            "ease4": 1.3,
+            "ivlFct": 1.0,
+            "maxIvl": 36500,
+            "perDay": 200,
+            "hardFactor": 1.2,
+        },
+        "lapse": {
+            "delays": [10.0],
+            "leechAction": 1,
+            "leechFails": 8,
+            "minInt": 1,
+            "mult": 0.0,
+        },
+    })
+}
+
+fn anki_model(
+    id: i64,
+    name: &str,
+    cloze: bool,
+    fields: &[&str],
+    question_format: &str,
+    answer_format: &str,
+    modified: i64,
+) -> Value {
+    let fields: Vec<Value> = fields
+        .iter()
+        .enumerate()
+        .map(|(ord, field)| {
+            json!({
+                "name": field,
+                "ord": ord,
+                "sticky": false,
+                "rtl": false,
+                "font": "Arial",
+                "size": 20,
+                "media": [],
+            })
+        })
+        .collect();
+
+    json!({
+        "id": id,
+        "name": name,
+        "type": if cloze { 1 } else { 0 },
+        "mod": modified,
+        "usn": -1,
+        "sortf": 0,
+        "did": DEFAULT_DECK_ID,
+        "tags": [],
+        "vers": [],
+        "flds": fields,
+        "tmpls": [{
+            "name": if cloze { "Cloze" } else { "Card 1" },
+            "ord": 0,
+            "qfmt": question_format,
+            "afmt": answer_format,
+            "bqfmt": "",
+            "bafmt": "",
+            "did": null,
+        }],
+        "req": [[0, "any", [0, 1]]],
+        "css": ".card { font-family: arial; font-size: 20px; text-align: center; color: black; background-color: white; }\n.cloze { font-weight: bold; color: blue; }",
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+    })
+}
+
+/// Tables of a schema 11 collection, which every Anki version can import
+const ANKI_SCHEMA: [&str; 5] = [
+    "CREATE TABLE col (id integer primary key, crt integer not null, mod integer not null, scm integer not null, ver integer not null, dty integer not null, usn integer not null, ls integer not null, conf text not null, models text not null, decks text not null, dconf text not null, tags text not null)",
+    "CREATE TABLE notes (id integer primary key, guid text not null, mid integer not null, mod integer not null, usn integer not null, tags text not null, flds text not null, sfld integer not null, csum integer not null, flags integer not null, data text not null)",
+    "CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null, ord integer not null, mod integer not null, usn integer not null, type integer not null, queue integer not null, due integer not null, ivl integer not null, factor integer not null, reps integer not null, lapses integer not null, left integer not null, odue integer not null, odid integer not null, flags integer not null, data text not null)",
+    "CREATE TABLE revlog (id integer primary key, cid integer not null, usn integer not null, ease integer not null, ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null, type integer not null)",
+    "CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null)",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_and_text_conversion() {
+        assert_eq!(
+            html_to_text("<div>Force &amp; mass</div><div>[$]F = ma[/$]<br>\\[E = mc^2\\]</div>"),
+            "Force & mass\n$F = ma$\n$$E = mc^2$$"
+        );
+        assert_eq!(
+            text_to_html("a < b\n$x^2$ and $$y$$"),
+            "a &lt; b<br>\\(x^2\\) and \\[y\\]"
+        );
+        assert_eq!(html_to_text(&text_to_html("a < b\n$x^2$")), "a < b\n$x^2$");
+    }
+
+    #[test]
+    fn test_factor_and_difficulty_round_trip() {
+        assert_eq!(difficulty_from_factor(DEFAULT_FACTOR), 3.0);
+        assert_eq!(difficulty_from_factor(0), 3.0);
+        assert_eq!(factor_from_difficulty(3.0), DEFAULT_FACTOR);
+        assert_eq!(factor_from_difficulty(10.0), 1300);
+    }
+}
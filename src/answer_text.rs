@@ -0,0 +1,39 @@
+//! Answer text handling shared by the local graders and rubric matching
+
+/// Lowercase, drop punctuation and leading articles, and collapse whitespace
+pub fn normalize_answer(answer: &str) -> String {
+    let cleaned: String = answer
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .to_lowercase();
+
+    let mut words: Vec<&str> = cleaned
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c| c == '.' || c == '-'))
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.len() > 1 && matches!(words[0], "a" | "an" | "the") {
+        words.remove(0);
+    }
+    words.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_answer() {
+        assert_eq!(normalize_answer("  The Powerhouse! "), "powerhouse");
+        assert_eq!(normalize_answer("Paris, France"), "paris france");
+        assert_eq!(normalize_answer("3.14."), "3.14");
+        assert_eq!(normalize_answer("The"), "the");
+    }
+}
@@ -1,23 +1,33 @@
 use axum::{
     Router,
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{StatusCode, header},
+    response::{
+        IntoResponse, Json, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
     routing::{delete, get, post, put},
 };
 use chrono::Utc;
+use futures_util::{Stream, stream};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use crate::{
-    card_service::CardService,
+    calibration::{CalibrationStats, calibrate_rating},
+    card_service::{CardService, QUESTIONS_PER_REVIEW},
+    csv_import::{CsvImportReport, CsvImportRequest, import_csv},
     errors::{ApiError, ErrorContext, classify_database_error},
-    llm_service::LLMService,
+    knowledge_graph::{GraphFormat, GraphNode, KnowledgeGraph},
+    llm_service::{GradingResult, LLMService},
     models::*,
+    question_cache::QuestionCache,
 };
 
 // Import logging macros
@@ -28,11 +38,14 @@ pub struct AppState {
     pub card_service: CardService,
     pub llm_service: LLMService,
     pub review_sessions: Arc<Mutex<HashMap<Uuid, ReviewSession>>>,
+    pub question_cache: QuestionCache, // Questions pre-generated for cards coming due
 }
 
 #[derive(Deserialize)]
 pub struct ReviewRequest {
     pub rating: i32,
+    #[serde(default)]
+    pub session_id: Option<Uuid>, // Review session the card was rated in, for calibration
 }
 
 #[derive(Deserialize)]
@@ -63,6 +76,38 @@ pub struct SearchParams {
     pub q: Option<String>,
 }
 
+#[derive(Deserialize)]
+pub struct UpdateCardParams {
+    #[serde(default)]
+    pub rewrite_references: bool, // Rewrite [[old_zettel_id]] links when the zettel_id changes
+}
+
+/// Largest neighborhood depth served, to keep responses bounded
+const MAX_GRAPH_DEPTH: usize = 10;
+
+#[derive(Deserialize)]
+pub struct GraphParams {
+    pub depth: Option<usize>, // Hops from the card; 1 when missing
+    #[serde(default)]
+    pub format: GraphFormat,
+}
+
+#[derive(Deserialize)]
+pub struct LinkParams {
+    pub relation: Option<LinkRelation>, // Only links with this relation
+}
+
+#[derive(Deserialize)]
+pub struct GraphPathParams {
+    pub from: Uuid,
+    pub to: Uuid,
+}
+
+#[derive(Deserialize)]
+pub struct UsageParams {
+    pub days: Option<i64>,
+}
+
 #[derive(Serialize)]
 pub struct ApiResponse<T> {
     pub success: bool,
@@ -78,6 +123,7 @@ pub struct ParallelProcessingMetrics {
     pub average_task_duration_ms: u64,
     pub processing_mode_used: String, // "parallel", "batch_fallback", "sequential_fallback"
     pub fallback_reason: Option<String>,
+    pub providers_used: Vec<String>, // Distinct "provider:model" entries that served the grades
 }
 
 #[derive(Serialize)]
@@ -107,10 +153,42 @@ impl<T> ApiResponse<T> {
 }
 
 // Card endpoints
+/// The saved card with its unresolved inline links. A failed lookup is logged rather
+/// than failing a save that already happened.
+async fn saved_card(
+    state: &AppState,
+    card: Card,
+    rewritten_references: Vec<RewrittenReference>,
+) -> SavedCard {
+    let unresolved_links = match state
+        .card_service
+        .unresolved_inline_links(&card.content)
+        .await
+    {
+        Ok(unresolved) => unresolved,
+        Err(e) => {
+            warn!(card_id = %card.id, error = %e, "Could not check inline links");
+            Vec::new()
+        }
+    };
+    if !unresolved_links.is_empty() {
+        debug!(
+            card_id = %card.id,
+            unresolved = ?unresolved_links,
+            "Card content links to zettel_ids that don't exist yet"
+        );
+    }
+    SavedCard {
+        card,
+        unresolved_links,
+        rewritten_references,
+    }
+}
+
 pub async fn create_card(
     State(state): State<AppState>,
     Json(request): Json<CreateCardWithZettelLinksRequest>,
-) -> Result<Json<ApiResponse<Card>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<Json<ApiResponse<SavedCard>>, (StatusCode, Json<ApiResponse<()>>)> {
     info!(
         zettel_id = %request.zettel_id,
         title = ?request.title,
@@ -128,7 +206,9 @@ pub async fn create_card(
                 zettel_id = %card.zettel_id,
                 "Card created successfully"
             );
-            Ok(Json(ApiResponse::success(card)))
+            Ok(Json(ApiResponse::success(
+                saved_card(&state, card, Vec::new()).await,
+            )))
         }
         Err(e) => {
             let classified_error = classify_database_error(&e);
@@ -202,37 +282,92 @@ pub async fn get_card_by_zettel_id(
 pub async fn update_card(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
+    Query(params): Query<UpdateCardParams>,
     Json(request): Json<UpdateCardWithZettelLinksRequest>,
-) -> Result<Json<ApiResponse<Card>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<Json<ApiResponse<SavedCard>>, (StatusCode, Json<ApiResponse<()>>)> {
     info!(
         card_id = %id,
         zettel_id = ?request.zettel_id,
+        rewrite_references = params.rewrite_references,
         "Updating card"
     );
+    let context = || ErrorContext::new("update_card", "card").with_id(&id.to_string());
+
+    // With rewrite_references the rename, its rewrites and the rest of the update are
+    // committed together
+    let result = if params.rewrite_references {
+        state
+            .card_service
+            .update_card_rewriting_references(id, request)
+            .await
+    } else {
+        state
+            .card_service
+            .update_card_with_zettel_links(id, request)
+            .await
+            .map(|card| card.map(|card| (card, Vec::new())))
+    };
 
-    match state
-        .card_service
-        .update_card_with_zettel_links(id, request)
-        .await
-    {
-        Ok(Some(card)) => {
+    match result {
+        Ok(Some((card, rewritten_references))) => {
             info!(
                 card_id = %id,
                 zettel_id = %card.zettel_id,
                 "Card updated successfully"
             );
-            Ok(Json(ApiResponse::success(card)))
+            state.question_cache.invalidate(id).await;
+            for reference in &rewritten_references {
+                state.question_cache.invalidate(reference.card_id).await;
+            }
+            Ok(Json(ApiResponse::success(
+                saved_card(&state, card, rewritten_references).await,
+            )))
         }
         Ok(None) => {
             let error = ApiError::NotFound(format!("Card with ID '{}' not found", id));
-            let context = ErrorContext::new("update_card", "card").with_id(&id.to_string());
-            Err(error.to_response_with_context(context))
+            Err(error.to_response_with_context(context()))
         }
         Err(e) => {
             let classified_error = classify_database_error(&e);
-            let context = ErrorContext::new("update_card", "card").with_id(&id.to_string());
-            Err(classified_error.to_response_with_context(context))
+            Err(classified_error.to_response_with_context(context()))
+        }
+    }
+}
+
+/// Rename a card's zettel_id and rewrite `[[old_zettel_id]]` links in every card's
+/// content in one transaction, or with `preview` list the cards that would change
+pub async fn rename_card_zettel_id(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<RenameZettelIdRequest>,
+) -> Result<Json<ApiResponse<ZettelRenameReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    info!(
+        card_id = %id,
+        new_zettel_id = %request.zettel_id,
+        preview = request.preview,
+        "Renaming card zettel_id"
+    );
+    let context = ErrorContext::new("rename_card_zettel_id", "card").with_id(&id.to_string());
+
+    match state
+        .card_service
+        .rename_zettel_id(id, &request.zettel_id, request.preview)
+        .await
+    {
+        Ok(Some(report)) => {
+            if !report.preview {
+                state.question_cache.invalidate(id).await;
+                for reference in &report.rewritten {
+                    state.question_cache.invalidate(reference.card_id).await;
+                }
+            }
+            Ok(Json(ApiResponse::success(report)))
         }
+        Ok(None) => {
+            let error = ApiError::NotFound(format!("Card with ID '{}' not found", id));
+            Err(error.to_response_with_context(context))
+        }
+        Err(e) => Err(classify_database_error(&e).to_response_with_context(context)),
     }
 }
 
@@ -269,9 +404,13 @@ pub async fn get_cards_due(
 pub async fn get_linked_cards(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<ApiResponse<Vec<Card>>>, StatusCode> {
-    match state.card_service.get_linked_cards(id).await {
-        Ok(cards) => Ok(Json(ApiResponse::success(cards))),
+    Query(params): Query<LinkParams>,
+) -> Result<Json<ApiResponse<Vec<LinkedCard>>>, StatusCode> {
+    match state.card_service.get_typed_links(id).await {
+        Ok(cards) => Ok(Json(ApiResponse::success(with_relation(
+            cards,
+            params.relation,
+        )))),
         Err(e) => {
             error!(card_id = %id, error = %e, "Error getting linked cards");
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -282,9 +421,13 @@ pub async fn get_linked_cards(
 pub async fn get_backlinks(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<ApiResponse<Vec<Card>>>, StatusCode> {
-    match state.card_service.get_backlinks(id).await {
-        Ok(cards) => Ok(Json(ApiResponse::success(cards))),
+    Query(params): Query<LinkParams>,
+) -> Result<Json<ApiResponse<Vec<LinkedCard>>>, StatusCode> {
+    match state.card_service.get_typed_backlinks(id).await {
+        Ok(cards) => Ok(Json(ApiResponse::success(with_relation(
+            cards,
+            params.relation,
+        )))),
         Err(e) => {
             error!(card_id = %id, error = %e, "Error getting backlinks");
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -292,6 +435,13 @@ pub async fn get_backlinks(
     }
 }
 
+fn with_relation(cards: Vec<LinkedCard>, relation: Option<LinkRelation>) -> Vec<LinkedCard> {
+    cards
+        .into_iter()
+        .filter(|linked| relation.is_none_or(|relation| linked.relation == relation))
+        .collect()
+}
+
 pub async fn search_cards(
     State(state): State<AppState>,
     Query(params): Query<SearchParams>,
@@ -307,6 +457,28 @@ pub async fn search_cards(
     }
 }
 
+/// Import cards from CSV or TSV text. Row problems are reported in the response
+/// rather than failing the request, and nothing is written when there are any.
+pub async fn import_cards_csv(
+    State(state): State<AppState>,
+    Json(request): Json<CsvImportRequest>,
+) -> Result<Json<ApiResponse<CsvImportReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    info!(
+        bytes = request.data.len(),
+        dry_run = request.options.dry_run,
+        "Importing cards from CSV"
+    );
+
+    match import_csv(&state.card_service, &request.data, &request.options).await {
+        Ok(report) => Ok(Json(ApiResponse::success(report))),
+        Err(e) => {
+            let error = ApiError::ValidationError(e.to_string());
+            let context = ErrorContext::new("import_cards_csv", "card");
+            Err(error.to_response_with_context(context))
+        }
+    }
+}
+
 // Topic endpoints
 pub async fn create_topic(
     State(state): State<AppState>,
@@ -428,7 +600,77 @@ async fn generate_session_questions(
     }
 }
 
-/// Create and store a new review session
+/// Questions for each card from the pre-generation cache, then the question bank,
+/// and only then a fresh LLM generation, whose results are banked for reuse
+async fn collect_session_questions(
+    state: &AppState,
+    cards: &[Card],
+) -> HashMap<Uuid, Vec<QuizQuestion>> {
+    let mut questions = HashMap::new();
+    let mut cache_hits = 0;
+    let mut bank_hits = 0;
+    let mut ungenerated_cards = Vec::new();
+
+    for card in cards {
+        if let Some(cached) = state.question_cache.take_questions(card.id).await {
+            questions.insert(card.id, cached);
+            cache_hits += 1;
+            continue;
+        }
+
+        match state
+            .card_service
+            .get_banked_questions(card, QUESTIONS_PER_REVIEW)
+            .await
+        {
+            Ok(banked) if !banked.is_empty() => {
+                questions.insert(card.id, banked);
+                bank_hits += 1;
+            }
+            Ok(_) => ungenerated_cards.push(card.clone()),
+            Err(e) => {
+                warn!(card_id = %card.id, error = %e, "Failed to load banked questions");
+                ungenerated_cards.push(card.clone());
+            }
+        }
+    }
+
+    info!(
+        cache_hits = cache_hits,
+        bank_hits = bank_hits,
+        generate_count = ungenerated_cards.len(),
+        "Collected stored questions for review session"
+    );
+
+    if !ungenerated_cards.is_empty() {
+        let generated = generate_session_questions(&state.llm_service, &ungenerated_cards).await;
+        for card in &ungenerated_cards {
+            if let Some(card_questions) = generated.get(&card.id)
+                && let Err(e) = state
+                    .card_service
+                    .bank_questions(card, card_questions)
+                    .await
+            {
+                warn!(card_id = %card.id, error = %e, "Failed to bank generated questions");
+            }
+        }
+        questions.extend(generated);
+    }
+
+    for card in cards {
+        if let Some(card_questions) = questions.get(&card.id)
+            && let Err(e) = state
+                .card_service
+                .record_question_use(card, card_questions)
+                .await
+        {
+            warn!(card_id = %card.id, error = %e, "Failed to record question use");
+        }
+    }
+
+    questions
+}
+
 fn create_and_store_session(
     review_sessions: &Arc<Mutex<HashMap<Uuid, ReviewSession>>>,
     cards: Vec<Card>,
@@ -478,8 +720,8 @@ pub async fn start_review_session(
         return Ok(Json(ApiResponse::success(empty_session)));
     }
 
-    // Step 2: Generate questions for all cards
-    let all_questions = generate_session_questions(&state.llm_service, &due_cards).await;
+    // Step 2: Reuse pre-generated and banked questions, generating the rest now
+    let all_questions = collect_session_questions(&state, &due_cards).await;
 
     // Step 3: Create and store the session
     let session = create_and_store_session(&state.review_sessions, due_cards, all_questions);
@@ -505,19 +747,29 @@ pub async fn get_review_session(
     }
 }
 
-pub async fn submit_session_answer(
+/// Graded answers from a review session, including partial credit per rubric criterion
+pub async fn get_session_answers(
     State(state): State<AppState>,
-    Path((session_id, card_id)): Path<(Uuid, Uuid)>,
-    Json(request): Json<QuizAnswerWithContext>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
-    info!(
-        session_id = %session_id,
-        card_id = %card_id,
-        question_index = request.question_index,
-        user_answer = %request.answer,
-        "Submitting answer for session-based quiz"
-    );
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<SessionAnswer>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!(session_id = %session_id, "Listing graded session answers");
+    match state.card_service.get_session_answers(session_id).await {
+        Ok(answers) => Ok(Json(ApiResponse::success(answers))),
+        Err(e) => {
+            let context = ErrorContext::new("get_session_answers", "session")
+                .with_id(&session_id.to_string());
+            Err(ApiError::DatabaseError(e).to_response_with_context(context))
+        }
+    }
+}
 
+/// Look up the card and question being answered in a review session
+async fn get_session_question(
+    state: &AppState,
+    session_id: Uuid,
+    card_id: Uuid,
+    question_index: usize,
+) -> Result<(Card, QuizQuestion), StatusCode> {
     // Get the session and validate it exists
     let session = {
         let sessions = state.review_sessions.lock().unwrap();
@@ -556,33 +808,149 @@ pub async fn submit_session_answer(
     };
 
     // Validate question index
-    if request.question_index >= questions.len() {
+    if question_index >= questions.len() {
         warn!(
             session_id = %session_id,
             card_id = %card_id,
-            question_index = request.question_index,
+            question_index = question_index,
             questions_count = questions.len(),
             "Invalid question index for session"
         );
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let question = &questions[request.question_index];
+    let question = questions[question_index].clone();
     debug!(
         session_id = %session_id,
         card_id = %card_id,
-        question_index = request.question_index,
+        question_index = question_index,
         question_type = %question.question_type,
         "Retrieved question from session for grading"
     );
 
+    Ok((card, question))
+}
+
+/// Keep a graded session answer with its partial credit. Grading has already succeeded,
+/// so a storage failure is logged rather than returned.
+async fn record_session_answer(
+    state: &AppState,
+    session_id: Uuid,
+    card_id: Uuid,
+    question_index: usize,
+    question: &QuizQuestion,
+    user_answer: &str,
+    result: &GradingResult,
+) {
+    let answer = SessionAnswer {
+        id: Uuid::new_v4(),
+        session_id,
+        card_id,
+        question_index,
+        question: question.clone(),
+        user_answer: user_answer.to_string(),
+        is_correct: result.is_correct,
+        feedback: result.feedback.clone(),
+        suggested_rating: result.suggested_rating,
+        score: result.score,
+        criterion_scores: result.criterion_scores.clone(),
+        served_by: result.served_by.clone(),
+        final_rating: None,
+        created_at: Utc::now(),
+    };
+
+    if let Err(e) = state.card_service.record_session_answer(&answer).await {
+        warn!(
+            session_id = %session_id,
+            card_id = %card_id,
+            question_index = question_index,
+            error = %e,
+            "Failed to store graded session answer"
+        );
+    }
+}
+
+/// Store answers graded together, which come back in the order they were submitted,
+/// then calibrate their suggested ratings
+async fn record_session_answers(
+    state: &AppState,
+    session_id: Uuid,
+    card_id: Uuid,
+    questions: &[QuizQuestion],
+    answers: &[QuestionAnswer],
+    results: &mut [BatchGradingResult],
+) {
+    for (answer, result) in answers.iter().zip(results.iter()) {
+        record_session_answer(
+            state,
+            session_id,
+            card_id,
+            answer.question_index,
+            &questions[answer.question_index],
+            &answer.answer,
+            &GradingResult::from(result.clone()),
+        )
+        .await;
+    }
+
+    if let Some(stats) = rating_calibration(state).await {
+        for (answer, result) in answers.iter().zip(results.iter_mut()) {
+            result.suggested_rating = calibrate_rating(
+                &stats,
+                result.served_by.as_deref(),
+                &questions[answer.question_index].question_type,
+                result.suggested_rating,
+            );
+        }
+    }
+}
+
+/// Calibration for adjusting suggested ratings, if enabled. Failing to load it only
+/// means suggestions go out unadjusted.
+async fn rating_calibration(state: &AppState) -> Option<Vec<CalibrationStats>> {
+    match state.card_service.rating_calibration().await {
+        Ok(stats) => stats,
+        Err(e) => {
+            warn!(error = %e, "Failed to load rating calibration");
+            None
+        }
+    }
+}
+
+/// Response payload for a graded session answer
+fn session_answer_json(result: &GradingResult) -> serde_json::Value {
+    json!({
+        "is_correct": result.is_correct,
+        "feedback": result.feedback,
+        "rating": result.suggested_rating,
+        "score": result.score,
+        "criterion_scores": result.criterion_scores
+    })
+}
+
+pub async fn submit_session_answer(
+    State(state): State<AppState>,
+    Path((session_id, card_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<QuizAnswerWithContext>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    info!(
+        session_id = %session_id,
+        card_id = %card_id,
+        question_index = request.question_index,
+        user_answer = %request.answer,
+        "Submitting answer for session-based quiz"
+    );
+
+    let (card, question) =
+        get_session_question(&state, session_id, card_id, request.question_index).await?;
+
     // Grade the answer using the actual question context
     match state
         .llm_service
-        .grade_answer(&card, question, &request.answer)
+        .grade_answer(&card, &question, &request.answer)
         .await
     {
-        Ok(grading_result) => {
+        Ok(mut grading_result) => {
             info!(
                 session_id = %session_id,
                 card_id = %card_id,
@@ -592,12 +960,29 @@ pub async fn submit_session_answer(
                 "Session answer graded successfully (FSRS update deferred until card completion)"
             );
 
+            record_session_answer(
+                &state,
+                session_id,
+                card_id,
+                request.question_index,
+                &question,
+                &request.answer,
+                &grading_result,
+            )
+            .await;
+            if let Some(stats) = rating_calibration(&state).await {
+                grading_result.suggested_rating = calibrate_rating(
+                    &stats,
+                    grading_result.served_by.as_deref(),
+                    &question.question_type,
+                    grading_result.suggested_rating,
+                );
+            }
+
             // Return grading result without updating FSRS - let user rating handle the final update
-            Ok(Json(ApiResponse::success(json!({
-                "is_correct": grading_result.is_correct,
-                "feedback": grading_result.feedback,
-                "rating": grading_result.suggested_rating
-            }))))
+            Ok(Json(ApiResponse::success(session_answer_json(
+                &grading_result,
+            ))))
         }
         Err(e) => {
             error!(
@@ -612,6 +997,94 @@ pub async fn submit_session_answer(
     }
 }
 
+/// Grade a session answer, streaming the feedback as server-sent events.
+///
+/// Emits `feedback` events carrying feedback text as it is generated, then a single
+/// `result` event with the same payload as `submit_session_answer`, or an `error` event.
+pub async fn stream_session_answer(
+    State(state): State<AppState>,
+    Path((session_id, card_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<QuizAnswerWithContext>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    info!(
+        session_id = %session_id,
+        card_id = %card_id,
+        question_index = request.question_index,
+        user_answer = %request.answer,
+        "Streaming grading for session-based quiz answer"
+    );
+
+    let (card, question) =
+        get_session_question(&state, session_id, card_id, request.question_index).await?;
+
+    let (tx, rx) = mpsc::unbounded_channel::<Event>();
+
+    tokio::spawn(async move {
+        let feedback_tx = tx.clone();
+        let result = state
+            .llm_service
+            .grade_answer_streaming(&card, &question, &request.answer, |feedback| {
+                // A closed channel means the client disconnected; finish grading anyway
+                let _ = feedback_tx.send(Event::default().event("feedback").data(feedback));
+            })
+            .await;
+
+        let event = match result {
+            Ok(mut grading_result) => {
+                info!(
+                    session_id = %session_id,
+                    card_id = %card_id,
+                    question_index = request.question_index,
+                    is_correct = grading_result.is_correct,
+                    suggested_rating = grading_result.suggested_rating,
+                    "Streamed session answer graded successfully"
+                );
+                record_session_answer(
+                    &state,
+                    session_id,
+                    card_id,
+                    request.question_index,
+                    &question,
+                    &request.answer,
+                    &grading_result,
+                )
+                .await;
+                if let Some(stats) = rating_calibration(&state).await {
+                    grading_result.suggested_rating = calibrate_rating(
+                        &stats,
+                        grading_result.served_by.as_deref(),
+                        &question.question_type,
+                        grading_result.suggested_rating,
+                    );
+                }
+                Event::default()
+                    .event("result")
+                    .json_data(session_answer_json(&grading_result))
+                    .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()))
+            }
+            Err(e) => {
+                error!(
+                    session_id = %session_id,
+                    card_id = %card_id,
+                    question_index = request.question_index,
+                    error = %e,
+                    "Error streaming session quiz answer grading"
+                );
+                Event::default()
+                    .event("error")
+                    .data(format!("Failed to grade answer: {}", e))
+            }
+        };
+        let _ = tx.send(event);
+    });
+
+    let events = stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok(event), rx))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
 pub async fn submit_batch_session_answers(
     State(state): State<AppState>,
     Path((session_id, card_id)): Path<(Uuid, Uuid)>,
@@ -691,19 +1164,29 @@ pub async fn submit_batch_session_answers(
                 card_content: card.content.clone(),
                 question: question.clone(),
                 user_answer: answer_request.answer.clone(),
+                card_id: Some(card_id),
             }
         })
         .collect();
 
     // Grade all answers in batch
     match state.llm_service.grade_batch_answers(&batch_requests).await {
-        Ok(grading_results) => {
+        Ok(mut grading_results) => {
             info!(
                 session_id = %session_id,
                 card_id = %card_id,
                 result_count = grading_results.len(),
                 "Batch answers graded successfully (FSRS updates deferred until card completion)"
             );
+            record_session_answers(
+                &state,
+                session_id,
+                card_id,
+                questions,
+                &request.answers,
+                &mut grading_results,
+            )
+            .await;
 
             Ok(Json(ApiResponse::success(grading_results)))
         }
@@ -815,9 +1298,16 @@ pub async fn submit_parallel_session_answers(
         )
         .await
     {
-        Ok((grading_results, mode_used, fallback_reason)) => {
+        Ok((mut grading_results, mode_used, fallback_reason)) => {
             let total_duration = start_time.elapsed();
 
+            let mut providers_used: Vec<String> = Vec::new();
+            for served_by in grading_results.iter().filter_map(|r| r.served_by.as_ref()) {
+                if !providers_used.contains(served_by) {
+                    providers_used.push(served_by.clone());
+                }
+            }
+
             // Calculate metrics
             let metrics = ParallelProcessingMetrics {
                 total_processing_time_ms: total_duration.as_millis() as u64,
@@ -834,6 +1324,7 @@ pub async fn submit_parallel_session_answers(
                     / grading_results.len().max(1) as u64,
                 processing_mode_used: mode_used,
                 fallback_reason,
+                providers_used,
             };
 
             info!(
@@ -842,8 +1333,18 @@ pub async fn submit_parallel_session_answers(
                 result_count = grading_results.len(),
                 processing_mode = %metrics.processing_mode_used,
                 total_duration_ms = metrics.total_processing_time_ms,
+                providers_used = ?metrics.providers_used,
                 "Parallel answers processed successfully (FSRS updates deferred until card completion)"
             );
+            record_session_answers(
+                &state,
+                session_id,
+                card_id,
+                questions,
+                &request.answers,
+                &mut grading_results,
+            )
+            .await;
 
             Ok(Json(ParallelApiResponse {
                 success: true,
@@ -911,6 +1412,8 @@ pub async fn submit_quiz_answer(
         question_type: "short_answer".to_string(),
         options: None,
         correct_answer: Some("Based on the card content".to_string()),
+        prompt_version: None,
+        rubric: None,
     };
 
     debug!(card_id = %card_id, "Using dummy question for legacy quiz answer endpoint");
@@ -974,7 +1477,24 @@ pub async fn review_card(
         .review_card(card_id, request.rating)
         .await
     {
-        Ok(Some(card)) => Ok(Json(ApiResponse::success(card))),
+        Ok(Some(card)) => {
+            // The cached questions have now been seen; regenerate before the next review
+            state.question_cache.invalidate(card_id).await;
+            if let Some(session_id) = request.session_id
+                && let Err(e) = state
+                    .card_service
+                    .record_final_rating(session_id, card_id, request.rating)
+                    .await
+            {
+                warn!(
+                    session_id = %session_id,
+                    card_id = %card_id,
+                    error = %e,
+                    "Failed to record final rating for calibration"
+                );
+            }
+            Ok(Json(ApiResponse::success(card)))
+        }
         Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(e) => {
             error!(card_id = %card_id, rating = request.rating, error = %e, "Error reviewing card");
@@ -993,6 +1513,7 @@ pub async fn delete_card(
         Ok(deleted) => {
             if deleted {
                 info!(card_id = %id, "Card deleted successfully");
+                state.question_cache.invalidate(id).await;
                 Ok(Json(ApiResponse::success(true)))
             } else {
                 let error = ApiError::NotFound(format!("Card with ID '{}' not found", id));
@@ -1008,19 +1529,279 @@ pub async fn delete_card(
     }
 }
 
+// Question feedback endpoints
+pub async fn get_card_questions(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<BankedQuestion>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!(card_id = %id, "Listing banked questions for card");
+    let context = || ErrorContext::new("get_card_questions", "card").with_id(&id.to_string());
+
+    let card = match state.card_service.get_card(id).await {
+        Ok(Some(card)) => card,
+        Ok(None) => {
+            let error = ApiError::NotFound(format!("Card with ID '{}' not found", id));
+            return Err(error.to_response_with_context(context()));
+        }
+        Err(e) => return Err(ApiError::DatabaseError(e).to_response_with_context(context())),
+    };
+
+    match state.card_service.list_card_questions(&card).await {
+        Ok(questions) => Ok(Json(ApiResponse::success(questions))),
+        Err(e) => Err(ApiError::DatabaseError(e).to_response_with_context(context())),
+    }
+}
+
+/// Shared response handling for endpoints that change a banked question. The card's
+/// cached questions are dropped so the change shows up in its next session.
+async fn question_feedback_response(
+    state: &AppState,
+    operation: &str,
+    id: Uuid,
+    result: anyhow::Result<Option<BankedQuestion>>,
+) -> Result<Json<ApiResponse<BankedQuestion>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let context = ErrorContext::new(operation, "question").with_id(&id.to_string());
+    match result {
+        Ok(Some(question)) => {
+            info!(
+                question_id = %id,
+                card_id = %question.card_id,
+                operation = operation,
+                "Banked question updated from review feedback"
+            );
+            state.question_cache.invalidate(question.card_id).await;
+            Ok(Json(ApiResponse::success(question)))
+        }
+        Ok(None) => {
+            let error = ApiError::NotFound(format!("Question with ID '{}' not found", id));
+            Err(error.to_response_with_context(context))
+        }
+        Err(e) => Err(classify_database_error(&e).to_response_with_context(context)),
+    }
+}
+
+pub async fn flag_question(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<FlagQuestionRequest>,
+) -> Result<Json<ApiResponse<BankedQuestion>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let reason = request.reason.trim();
+    if reason.is_empty() {
+        let error =
+            ApiError::ValidationError("A reason is required to flag a question".to_string());
+        let context = ErrorContext::new("flag_question", "question").with_id(&id.to_string());
+        return Err(error.to_response_with_context(context));
+    }
+
+    let result = state.card_service.flag_question(id, reason).await;
+    question_feedback_response(&state, "flag_question", id, result).await
+}
+
+pub async fn unflag_question(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<BankedQuestion>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let result = state.card_service.unflag_question(id).await;
+    question_feedback_response(&state, "unflag_question", id, result).await
+}
+
+pub async fn pin_question(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<BankedQuestion>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let result = state.card_service.set_question_pinned(id, true).await;
+    question_feedback_response(&state, "pin_question", id, result).await
+}
+
+pub async fn unpin_question(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<BankedQuestion>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let result = state.card_service.set_question_pinned(id, false).await;
+    question_feedback_response(&state, "unpin_question", id, result).await
+}
+
+pub async fn update_question(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateQuestionRequest>,
+) -> Result<Json<ApiResponse<BankedQuestion>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if request
+        .question
+        .as_deref()
+        .is_some_and(|text| text.trim().is_empty())
+    {
+        let error = ApiError::ValidationError("Question text cannot be empty".to_string());
+        let context = ErrorContext::new("update_question", "question").with_id(&id.to_string());
+        return Err(error.to_response_with_context(context));
+    }
+
+    let result = state.card_service.update_question(id, request).await;
+    question_feedback_response(&state, "update_question", id, result).await
+}
+
+// Knowledge graph endpoints
+fn graph_response(graph: KnowledgeGraph, format: GraphFormat) -> Response {
+    match format {
+        GraphFormat::Json => Json(ApiResponse::success(graph)).into_response(),
+        GraphFormat::GraphMl => (
+            [(header::CONTENT_TYPE, "application/graphml+xml")],
+            graph.to_graphml(),
+        )
+            .into_response(),
+        GraphFormat::Dot => (
+            [(header::CONTENT_TYPE, "text/vnd.graphviz")],
+            graph.to_dot(),
+        )
+            .into_response(),
+    }
+}
+
+async fn load_graph(
+    state: &AppState,
+    operation: &str,
+) -> Result<KnowledgeGraph, (StatusCode, Json<ApiResponse<()>>)> {
+    state.card_service.knowledge_graph().await.map_err(|e| {
+        ApiError::DatabaseError(e).to_response_with_context(ErrorContext::new(operation, "graph"))
+    })
+}
+
+/// Every card and link, as JSON, GraphML or DOT
+pub async fn get_graph(
+    State(state): State<AppState>,
+    Query(params): Query<GraphParams>,
+) -> Result<Response, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!(format = ?params.format, "Getting knowledge graph");
+    let graph = load_graph(&state, "get_graph").await?;
+    Ok(graph_response(graph, params.format))
+}
+
+/// The cards within `depth` links of a card, in either direction
+pub async fn get_card_graph(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<GraphParams>,
+) -> Result<Response, (StatusCode, Json<ApiResponse<()>>)> {
+    let depth = params.depth.unwrap_or(1).min(MAX_GRAPH_DEPTH);
+    debug!(card_id = %id, depth = depth, "Getting card neighborhood");
+
+    let graph = load_graph(&state, "get_card_graph").await?;
+    if !graph.contains(id) {
+        let error = ApiError::NotFound(format!("Card with ID '{}' not found", id));
+        let context = ErrorContext::new("get_card_graph", "card").with_id(&id.to_string());
+        return Err(error.to_response_with_context(context));
+    }
+    Ok(graph_response(graph.neighborhood(id, depth), params.format))
+}
+
+/// The shortest chain of links between two cards, following links either way
+pub async fn get_graph_path(
+    State(state): State<AppState>,
+    Query(params): Query<GraphPathParams>,
+) -> Result<Json<ApiResponse<Vec<GraphNode>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!(from = %params.from, to = %params.to, "Finding path between cards");
+
+    let graph = load_graph(&state, "get_graph_path").await?;
+    match graph.shortest_path(params.from, params.to) {
+        Some(path) => Ok(Json(ApiResponse::success(path))),
+        None => {
+            let error = ApiError::NotFound(format!(
+                "No path between cards '{}' and '{}'",
+                params.from, params.to
+            ));
+            Err(error.to_response_with_context(ErrorContext::new("get_graph_path", "graph")))
+        }
+    }
+}
+
+/// Cards with no links in or out
+pub async fn get_graph_orphans(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<GraphNode>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let graph = load_graph(&state, "get_graph_orphans").await?;
+    Ok(Json(ApiResponse::success(graph.orphans())))
+}
+
+// LLM usage endpoints
+pub async fn get_llm_usage(
+    State(state): State<AppState>,
+    Query(params): Query<UsageParams>,
+) -> Result<Json<ApiResponse<LLMUsageSummary>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let days = params.days.unwrap_or(30).clamp(1, 366);
+    debug!(days = days, "Getting LLM usage summary");
+
+    let Some(tracker) = state.llm_service.usage_tracker() else {
+        let error = ApiError::NotFound("LLM usage tracking is not enabled".to_string());
+        let context = ErrorContext::new("get_llm_usage", "llm_usage");
+        return Err(error.to_response_with_context(context));
+    };
+
+    match tracker.summary(days).await {
+        Ok(summary) => Ok(Json(ApiResponse::success(summary))),
+        Err(e) => {
+            let error = ApiError::DatabaseError(e);
+            let context = ErrorContext::new("get_llm_usage", "llm_usage");
+            Err(error.to_response_with_context(context))
+        }
+    }
+}
+
+/// How suggested ratings compare with final ratings, per grader and question type
+pub async fn get_rating_calibration(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<CalibrationStats>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Getting rating calibration statistics");
+    match state.card_service.calibration_stats().await {
+        Ok(stats) => Ok(Json(ApiResponse::success(stats))),
+        Err(e) => {
+            let context = ErrorContext::new("get_rating_calibration", "calibration");
+            Err(ApiError::DatabaseError(e).to_response_with_context(context))
+        }
+    }
+}
+
+/// Due cards whose prerequisites are likely forgotten, in review order
+pub async fn get_weak_foundations(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<WeakFoundation>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Checking prerequisites of due cards");
+    let due_cards = get_due_cards_for_session(&state.card_service).await?;
+    match state.card_service.weak_foundations(&due_cards).await {
+        Ok(warnings) => Ok(Json(ApiResponse::success(warnings))),
+        Err(e) => {
+            let context = ErrorContext::new("get_weak_foundations", "cards");
+            Err(ApiError::DatabaseError(e).to_response_with_context(context))
+        }
+    }
+}
+
 pub fn create_router(state: AppState) -> Router {
     Router::new()
         // Card routes
         .route("/api/cards", post(create_card))
         .route("/api/cards", get(get_all_cards))
         .route("/api/cards/search", get(search_cards))
+        .route("/api/cards/import/csv", post(import_cards_csv))
         .route("/api/cards/zettel/:zettel_id", get(get_card_by_zettel_id))
         .route("/api/cards/:id", get(get_card))
         .route("/api/cards/:id", put(update_card))
         .route("/api/cards/:id", delete(delete_card))
         .route("/api/cards/due", get(get_cards_due))
+        .route("/api/cards/:id/rename", post(rename_card_zettel_id))
         .route("/api/cards/:id/links", get(get_linked_cards))
         .route("/api/cards/:id/backlinks", get(get_backlinks))
+        .route("/api/cards/:id/graph", get(get_card_graph))
+        .route("/api/cards/:id/questions", get(get_card_questions))
+        // Question feedback routes
+        .route("/api/questions/:id", put(update_question))
+        .route("/api/questions/:id/flag", post(flag_question))
+        .route("/api/questions/:id/flag", delete(unflag_question))
+        .route("/api/questions/:id/pin", post(pin_question))
+        .route("/api/questions/:id/pin", delete(unpin_question))
+        // Knowledge graph routes
+        .route("/api/graph", get(get_graph))
+        .route("/api/graph/path", get(get_graph_path))
+        .route("/api/graph/orphans", get(get_graph_orphans))
         // Topic routes
         .route("/api/topics", post(create_topic))
         .route("/api/topics", get(get_topics))
@@ -1029,10 +1810,18 @@ pub fn create_router(state: AppState) -> Router {
         // Review session routes
         .route("/api/review/session/start", post(start_review_session))
         .route("/api/review/session/:id", get(get_review_session))
+        .route(
+            "/api/review/session/:session_id/answers",
+            get(get_session_answers),
+        )
         .route(
             "/api/review/session/:session_id/answer/:card_id",
             post(submit_session_answer),
         )
+        .route(
+            "/api/review/session/:session_id/answer/:card_id/stream",
+            post(stream_session_answer),
+        )
         .route(
             "/api/review/session/:session_id/answers/:card_id/batch",
             post(submit_batch_session_answers),
@@ -1043,6 +1832,10 @@ pub fn create_router(state: AppState) -> Router {
         )
         // Review routes
         .route("/api/cards/:id/review", post(review_card))
+        .route("/api/review/calibration", get(get_rating_calibration))
+        .route("/api/review/weak-foundations", get(get_weak_foundations))
+        // LLM usage routes
+        .route("/api/llm/usage", get(get_llm_usage))
         .with_state(state)
 }
 
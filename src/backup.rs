@@ -0,0 +1,467 @@
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::database::{Database, sync_links_column};
+use crate::models::{Backlink, Card, CardTopic, Review, Topic};
+
+/// Version of the backup layout. Bump it when fields are renamed or removed, and keep
+/// reading older versions.
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Validation problems listed in an error before the rest are summarized
+const MAX_REPORTED_PROBLEMS: usize = 10;
+
+/// Every card, topic, card topic, review and backlink in the database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub cards: Vec<Card>,
+    pub topics: Vec<Topic>,
+    pub card_topics: Vec<CardTopic>,
+    pub reviews: Vec<Review>,
+    pub backlinks: Vec<Backlink>,
+}
+
+/// One line of a JSON Lines backup. The header comes first.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BackupRecord {
+    Header {
+        format_version: u32,
+        exported_at: DateTime<Utc>,
+    },
+    Card(Card),
+    Topic(Topic),
+    CardTopic(CardTopic),
+    Review(Review),
+    Backlink(Backlink),
+}
+
+impl Backup {
+    pub async fn from_database(db: &Database) -> Result<Self> {
+        Ok(Backup {
+            format_version: BACKUP_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            cards: db.get_all_cards().await?,
+            topics: db.get_all_topics().await?,
+            card_topics: db.get_all_card_topics().await?,
+            reviews: db.get_all_reviews().await?,
+            backlinks: db.get_all_backlinks().await?,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// One record per line, so large backups can be streamed and diffed
+    pub fn to_jsonl(&self) -> Result<String> {
+        let header = BackupRecord::Header {
+            format_version: self.format_version,
+            exported_at: self.exported_at,
+        };
+        let records = std::iter::once(header)
+            .chain(self.cards.iter().cloned().map(BackupRecord::Card))
+            .chain(self.topics.iter().cloned().map(BackupRecord::Topic))
+            .chain(
+                self.card_topics
+                    .iter()
+                    .cloned()
+                    .map(BackupRecord::CardTopic),
+            )
+            .chain(self.reviews.iter().cloned().map(BackupRecord::Review))
+            .chain(self.backlinks.iter().cloned().map(BackupRecord::Backlink));
+
+        let mut output = String::new();
+        for record in records {
+            output.push_str(&serde_json::to_string(&record)?);
+            output.push('\n');
+        }
+        Ok(output)
+    }
+
+    /// Read a JSON or JSON Lines backup, telling them apart by the header line
+    pub fn parse(text: &str) -> Result<Self> {
+        let first_line = text
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("");
+        let backup = match serde_json::from_str::<BackupRecord>(first_line) {
+            Ok(BackupRecord::Header { .. }) => Self::from_jsonl(text)?,
+            _ => serde_json::from_str(text).context("Invalid JSON backup")?,
+        };
+        backup.check_version()?;
+        Ok(backup)
+    }
+
+    fn from_jsonl(text: &str) -> Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let Some((_, header)) = lines.next() else {
+            bail!("Empty backup");
+        };
+        let BackupRecord::Header {
+            format_version,
+            exported_at,
+        } = serde_json::from_str(header)?
+        else {
+            bail!("JSON Lines backup must start with a header record");
+        };
+
+        let mut backup = Backup {
+            format_version,
+            exported_at,
+            cards: Vec::new(),
+            topics: Vec::new(),
+            card_topics: Vec::new(),
+            reviews: Vec::new(),
+            backlinks: Vec::new(),
+        };
+        for (index, line) in lines {
+            let record: BackupRecord = serde_json::from_str(line)
+                .with_context(|| format!("Invalid backup record on line {}", index + 1))?;
+            match record {
+                BackupRecord::Header { .. } => bail!("Unexpected header on line {}", index + 1),
+                BackupRecord::Card(card) => backup.cards.push(card),
+                BackupRecord::Topic(topic) => backup.topics.push(topic),
+                BackupRecord::CardTopic(card_topic) => backup.card_topics.push(card_topic),
+                BackupRecord::Review(review) => backup.reviews.push(review),
+                BackupRecord::Backlink(backlink) => backup.backlinks.push(backlink),
+            }
+        }
+        Ok(backup)
+    }
+
+    fn check_version(&self) -> Result<()> {
+        match self.format_version {
+            0 => bail!("Backup has no format version"),
+            version if version > BACKUP_FORMAT_VERSION => bail!(
+                "Backup format version {} is newer than the supported version {}",
+                version,
+                BACKUP_FORMAT_VERSION
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Problems that would leave dangling references or duplicates. Cards outside the
+    /// backup may be referenced when they already exist in the target database.
+    pub fn validate(&self, existing_card_ids: &HashSet<Uuid>) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let mut card_ids = HashSet::new();
+        let mut zettel_ids = HashSet::new();
+        for card in &self.cards {
+            if !card_ids.insert(card.id) {
+                problems.push(format!("Duplicate card id {}", card.id));
+            }
+            if !zettel_ids.insert(card.zettel_id.as_str()) {
+                problems.push(format!("Duplicate zettel_id '{}'", card.zettel_id));
+            }
+        }
+        let mut topic_ids = HashSet::new();
+        let mut topic_names = HashSet::new();
+        for topic in &self.topics {
+            if !topic_ids.insert(topic.id) {
+                problems.push(format!("Duplicate topic id {}", topic.id));
+            }
+            if !topic_names.insert(topic.name.as_str()) {
+                problems.push(format!("Duplicate topic name '{}'", topic.name));
+            }
+        }
+
+        let known_card = |id: &Uuid| card_ids.contains(id) || existing_card_ids.contains(id);
+        for card in &self.cards {
+            match card_link_ids(card) {
+                Ok(links) => {
+                    for link in links.iter().filter(|link| !known_card(link)) {
+                        problems.push(format!(
+                            "Card {} links to unknown card {}",
+                            card.zettel_id, link
+                        ));
+                    }
+                }
+                Err(_) => problems.push(format!("Card {} has malformed links", card.zettel_id)),
+            }
+        }
+        for card_topic in &self.card_topics {
+            if !card_ids.contains(&card_topic.card_id) {
+                problems.push(format!(
+                    "Card topic references unknown card {}",
+                    card_topic.card_id
+                ));
+            }
+            if !topic_ids.contains(&card_topic.topic_id) {
+                problems.push(format!(
+                    "Card topic references unknown topic {}",
+                    card_topic.topic_id
+                ));
+            }
+        }
+        let mut review_ids = HashSet::new();
+        for review in &self.reviews {
+            if !review_ids.insert(review.id) {
+                problems.push(format!("Duplicate review id {}", review.id));
+            }
+            if !card_ids.contains(&review.card_id) {
+                problems.push(format!(
+                    "Review {} references unknown card {}",
+                    review.id, review.card_id
+                ));
+            }
+        }
+        for backlink in &self.backlinks {
+            if !card_ids.contains(&backlink.source_card_id) {
+                problems.push(format!(
+                    "Backlink references unknown source card {}",
+                    backlink.source_card_id
+                ));
+            }
+            if !known_card(&backlink.target_card_id) {
+                problems.push(format!(
+                    "Backlink references unknown target card {}",
+                    backlink.target_card_id
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+fn card_link_ids(card: &Card) -> Result<Vec<Uuid>> {
+    match card.links.as_deref() {
+        Some(links) => Ok(serde_json::from_str(links)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// What a restore did. Records whose id was taken get a new one, cards whose
+/// zettel_id was taken get a numbered suffix, and topics are merged by name.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct RestoreReport {
+    pub cards_imported: usize,
+    pub topics_imported: usize,
+    pub topics_merged: usize,
+    pub card_topics_imported: usize,
+    pub reviews_imported: usize,
+    pub backlinks_imported: usize,
+    pub remapped_card_ids: HashMap<Uuid, Uuid>,
+    pub renamed_zettel_ids: HashMap<String, String>,
+}
+
+/// Restore a backup into the database in a single transaction: either every record is
+/// imported or nothing is. Fails without changes when the backup doesn't validate.
+pub async fn restore_backup(db: &Database, backup: &Backup) -> Result<RestoreReport> {
+    backup.check_version()?;
+    let mut tx = db.pool.begin().await?;
+
+    let mut existing_card_ids = HashSet::new();
+    let mut existing_zettel_ids = HashSet::new();
+    for row in sqlx::query("SELECT id, zettel_id FROM cards")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        existing_card_ids.insert(Uuid::parse_str(&row.get::<String, _>("id"))?);
+        existing_zettel_ids.insert(row.get::<String, _>("zettel_id"));
+    }
+
+    let problems = backup.validate(&existing_card_ids);
+    if !problems.is_empty() {
+        let mut message = problems
+            .iter()
+            .take(MAX_REPORTED_PROBLEMS)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("; ");
+        if problems.len() > MAX_REPORTED_PROBLEMS {
+            message.push_str(&format!(
+                "; and {} more",
+                problems.len() - MAX_REPORTED_PROBLEMS
+            ));
+        }
+        return Err(anyhow!("Backup failed validation: {}", message));
+    }
+
+    let mut report = RestoreReport::default();
+
+    // Remap every card up front, so links can point at cards restored later
+    let mut card_ids: HashMap<Uuid, Uuid> = HashMap::new();
+    for card in &backup.cards {
+        let id = if existing_card_ids.contains(&card.id) {
+            let new_id = Uuid::new_v4();
+            report.remapped_card_ids.insert(card.id, new_id);
+            new_id
+        } else {
+            card.id
+        };
+        card_ids.insert(card.id, id);
+    }
+    let remap_card = |id: Uuid| card_ids.get(&id).copied().unwrap_or(id);
+
+    for card in &backup.cards {
+        let mut zettel_id = card.zettel_id.clone();
+        let mut suffix = 2;
+        while existing_zettel_ids.contains(&zettel_id) {
+            zettel_id = format!("{}-{}", card.zettel_id, suffix);
+            suffix += 1;
+        }
+        if zettel_id != card.zettel_id {
+            report
+                .renamed_zettel_ids
+                .insert(card.zettel_id.clone(), zettel_id.clone());
+        }
+        existing_zettel_ids.insert(zettel_id.clone());
+
+        let links = card
+            .links
+            .as_ref()
+            .map(|_| -> Result<String> {
+                let links: Vec<Uuid> = card_link_ids(card)?.into_iter().map(remap_card).collect();
+                Ok(serde_json::to_string(&links)?)
+            })
+            .transpose()?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO cards (id, zettel_id, title, content, creation_date, last_reviewed, next_review,
+                             difficulty, stability, retrievability, reps, lapses, state, links)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+            "#,
+        )
+        .bind(remap_card(card.id).to_string())
+        .bind(&zettel_id)
+        .bind(&card.title)
+        .bind(&card.content)
+        .bind(card.creation_date.to_rfc3339())
+        .bind(card.last_reviewed.map(|d| d.to_rfc3339()))
+        .bind(card.next_review.to_rfc3339())
+        .bind(card.difficulty)
+        .bind(card.stability)
+        .bind(card.retrievability)
+        .bind(card.reps)
+        .bind(card.lapses)
+        .bind(&card.state)
+        .bind(links)
+        .execute(&mut *tx)
+        .await?;
+        report.cards_imported += 1;
+    }
+
+    let mut existing_topics: HashMap<String, Uuid> = HashMap::new();
+    let mut existing_topic_ids = HashSet::new();
+    for row in sqlx::query("SELECT id, name FROM topics")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        let id = Uuid::parse_str(&row.get::<String, _>("id"))?;
+        existing_topic_ids.insert(id);
+        existing_topics.insert(row.get("name"), id);
+    }
+    let mut topic_ids: HashMap<Uuid, Uuid> = HashMap::new();
+    for topic in &backup.topics {
+        if let Some(&existing) = existing_topics.get(&topic.name) {
+            topic_ids.insert(topic.id, existing);
+            report.topics_merged += 1;
+            continue;
+        }
+
+        let id = if existing_topic_ids.contains(&topic.id) {
+            Uuid::new_v4()
+        } else {
+            topic.id
+        };
+        sqlx::query("INSERT INTO topics (id, name, description) VALUES (?1, ?2, ?3)")
+            .bind(id.to_string())
+            .bind(&topic.name)
+            .bind(&topic.description)
+            .execute(&mut *tx)
+            .await?;
+        topic_ids.insert(topic.id, id);
+        report.topics_imported += 1;
+    }
+
+    for card_topic in &backup.card_topics {
+        sqlx::query("INSERT OR IGNORE INTO card_topics (card_id, topic_id) VALUES (?1, ?2)")
+            .bind(remap_card(card_topic.card_id).to_string())
+            .bind(topic_ids[&card_topic.topic_id].to_string())
+            .execute(&mut *tx)
+            .await?;
+        report.card_topics_imported += 1;
+    }
+
+    for review in &backup.reviews {
+        let taken = sqlx::query("SELECT 1 FROM reviews WHERE id = ?1")
+            .bind(review.id.to_string())
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        let id = if taken { Uuid::new_v4() } else { review.id };
+
+        sqlx::query(
+            "INSERT INTO reviews (id, card_id, review_date, rating, interval, ease_factor) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .bind(id.to_string())
+        .bind(remap_card(review.card_id).to_string())
+        .bind(review.review_date.to_rfc3339())
+        .bind(review.rating)
+        .bind(review.interval)
+        .bind(review.ease_factor)
+        .execute(&mut *tx)
+        .await?;
+        report.reviews_imported += 1;
+    }
+
+    // Links are restored in each card's link order, so backups whose backlinks are
+    // missing some links still restore them; the backlinks then supply relations
+    for card in &backup.cards {
+        for target in card_link_ids(card)? {
+            sqlx::query(
+                "INSERT OR IGNORE INTO backlinks (source_card_id, target_card_id) SELECT ?1, id FROM cards WHERE id = ?2",
+            )
+            .bind(remap_card(card.id).to_string())
+            .bind(remap_card(target).to_string())
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    for backlink in &backup.backlinks {
+        sqlx::query(
+            r#"
+            INSERT INTO backlinks (source_card_id, target_card_id, relation, note, origin)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (source_card_id, target_card_id)
+            DO UPDATE SET relation = excluded.relation, note = excluded.note, origin = excluded.origin
+            "#,
+        )
+        .bind(remap_card(backlink.source_card_id).to_string())
+        .bind(remap_card(backlink.target_card_id).to_string())
+        .bind(backlink.relation.as_str())
+        .bind(&backlink.note)
+        .bind(backlink.origin.as_str())
+        .execute(&mut *tx)
+        .await?;
+        report.backlinks_imported += 1;
+    }
+    for card in &backup.cards {
+        sync_links_column(&mut tx, &remap_card(card.id).to_string()).await?;
+    }
+
+    tx.commit().await?;
+    info!(
+        cards = report.cards_imported,
+        topics = report.topics_imported,
+        reviews = report.reviews_imported,
+        remapped_cards = report.remapped_card_ids.len(),
+        "Backup restored"
+    );
+    Ok(report)
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use learning_system::Database;
+use learning_system::anki::{export_apkg, import_apkg};
+use std::env;
+use std::fs;
+
+fn print_usage() {
+    println!("Usage:");
+    println!("  cargo run --bin anki -- import <deck.apkg>");
+    println!("  cargo run --bin anki -- export <deck.apkg>");
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load environment variables
+    dotenvy::dotenv().ok();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (Some(command), Some(path)) = (args.first(), args.get(1)) else {
+        print_usage();
+        return Ok(());
+    };
+
+    let database_url =
+        env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:./learning_system.db".to_string());
+    println!("Connecting to database: {}", database_url);
+    let db = Database::new(&database_url).await?;
+
+    match command.as_str() {
+        "import" => {
+            println!("Importing Anki package: {}", path);
+            let report = import_apkg(&db, &fs::read(path)?).await?;
+
+            println!("\n=== Import Summary ===");
+            println!("Cards created: {}", report.created.len());
+            println!("Notes skipped (already imported): {}", report.skipped.len());
+            println!("Topics created: {}", report.topics_created.len());
+            println!("Reviews imported: {}", report.reviews_imported);
+        }
+        "export" => {
+            let package = export_apkg(&db).await?;
+            fs::write(path, &package)?;
+            println!("✓ Exported cards to {}", path);
+        }
+        _ => print_usage(),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use learning_system::Database;
+use learning_system::backup::{Backup, restore_backup};
+use std::env;
+use std::fs;
+
+fn print_usage() {
+    println!("Usage:");
+    println!("  cargo run --bin backup -- export <backup.json|backup.jsonl>");
+    println!("  cargo run --bin backup -- import <backup.json|backup.jsonl>");
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load environment variables
+    dotenvy::dotenv().ok();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (Some(command), Some(path)) = (args.first(), args.get(1)) else {
+        print_usage();
+        return Ok(());
+    };
+
+    let database_url =
+        env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:./learning_system.db".to_string());
+    println!("Connecting to database: {}", database_url);
+    let db = Database::new(&database_url).await?;
+
+    match command.as_str() {
+        "export" => {
+            let backup = Backup::from_database(&db).await?;
+            let output = if path.ends_with(".jsonl") {
+                backup.to_jsonl()?
+            } else {
+                backup.to_json()?
+            };
+            fs::write(path, output)?;
+            println!(
+                "✓ Exported {} cards, {} topics and {} reviews to {}",
+                backup.cards.len(),
+                backup.topics.len(),
+                backup.reviews.len(),
+                path
+            );
+        }
+        "import" => {
+            println!("Restoring backup: {}", path);
+            let backup = Backup::parse(&fs::read_to_string(path)?)?;
+            let report = restore_backup(&db, &backup).await?;
+
+            println!("\n=== Restore Summary ===");
+            println!("Cards imported: {}", report.cards_imported);
+            println!("Card ids remapped: {}", report.remapped_card_ids.len());
+            println!("Zettel ids renamed: {}", report.renamed_zettel_ids.len());
+            for (old, new) in &report.renamed_zettel_ids {
+                println!("  {} -> {}", old, new);
+            }
+            println!(
+                "Topics imported: {} (merged by name: {})",
+                report.topics_imported, report.topics_merged
+            );
+            println!("Card topics imported: {}", report.card_topics_imported);
+            println!("Reviews imported: {}", report.reviews_imported);
+            println!("Backlinks imported: {}", report.backlinks_imported);
+        }
+        _ => print_usage(),
+    }
+
+    Ok(())
+}
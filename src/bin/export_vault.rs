@@ -0,0 +1,46 @@
+use anyhow::Result;
+use learning_system::Database;
+use learning_system::vault_export::{export_html_site, export_markdown_vault};
+use std::env;
+use std::path::PathBuf;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load environment variables
+    dotenvy::dotenv().ok();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let html = args.contains(&"--html".to_string());
+    let Some(output_dir) = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map(PathBuf::from)
+    else {
+        println!("Usage: cargo run --bin export_vault -- <output-directory> [--html]");
+        return Ok(());
+    };
+
+    let database_url =
+        env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:./learning_system.db".to_string());
+    println!("Connecting to database: {}", database_url);
+    let db = Database::new(&database_url).await?;
+
+    let report = if html {
+        println!("Exporting HTML site to: {}", output_dir.display());
+        export_html_site(&db, &output_dir).await?
+    } else {
+        println!("Exporting Markdown vault to: {}", output_dir.display());
+        export_markdown_vault(&db, &output_dir).await?
+    };
+
+    println!("\n=== Export Summary ===");
+    println!("Files written: {}", report.files.len());
+    if !report.unresolved_links.is_empty() {
+        println!("Links to missing cards: {}", report.unresolved_links.len());
+        for (zettel_id, card_id) in &report.unresolved_links {
+            println!("  - {} -> {}", zettel_id, card_id);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use anyhow::{Result, bail};
+use learning_system::csv_import::{CsvImportOptions, CsvImportReport, import_csv};
+use learning_system::{CardService, Database};
+use std::env;
+use std::fs;
+
+fn print_usage() {
+    println!("Usage: cargo run --bin import_csv -- <file.csv|file.tsv> [options]");
+    println!("  --map field=Column   Read a field from a column; fields are");
+    println!("                       zettel_id, title, content, topics and links");
+    println!("  --tsv                Tab separated, even without a .tsv extension");
+    println!("  --separator <char>   Separator between topics or links in a cell (default ;)");
+    println!("  --prefix <prefix>    Prefix for generated zettel_ids (default CSV)");
+    println!("  --dry-run            Check every row and show what would be imported");
+}
+
+fn parse_args(args: &[String]) -> Result<(Option<String>, CsvImportOptions)> {
+    let mut options = CsvImportOptions::default();
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => options.dry_run = true,
+            "--tsv" => options.delimiter = Some('\t'),
+            "--map" => {
+                let Some((field, column)) = args.next().and_then(|m| m.split_once('=')) else {
+                    bail!("--map expects field=Column");
+                };
+                let column = Some(column.to_string());
+                match field {
+                    "zettel_id" => options.mapping.zettel_id = column,
+                    "title" => options.mapping.title = column,
+                    "content" => options.mapping.content = column,
+                    "topics" => options.mapping.topics = column,
+                    "links" => options.mapping.links = column,
+                    _ => bail!("Unknown field '{}' in --map", field),
+                }
+            }
+            "--separator" => match args.next().and_then(|s| s.chars().next()) {
+                Some(separator) => options.list_separator = separator,
+                None => bail!("--separator expects a character"),
+            },
+            "--prefix" => match args.next() {
+                Some(prefix) => options.zettel_prefix = prefix.clone(),
+                None => bail!("--prefix expects a prefix"),
+            },
+            _ if arg.starts_with("--") => bail!("Unknown option {}", arg),
+            _ => path = Some(arg.clone()),
+        }
+    }
+    Ok((path, options))
+}
+
+fn print_summary(report: &CsvImportReport) {
+    println!("\n=== Import Summary ===");
+    println!("Rows read: {}", report.rows);
+    if !report.errors.is_empty() {
+        println!("Rows with errors: {}", report.errors.len());
+        for error in &report.errors {
+            println!("  - line {}: {}", error.line, error.message);
+        }
+        println!("\n✗ Nothing was imported. Fix the rows above and try again.");
+        return;
+    }
+
+    if report.dry_run {
+        println!("Cards that WOULD BE created: {}", report.created.len());
+        println!(
+            "Topics that WOULD BE created: {}",
+            report.topics_created.len()
+        );
+    } else {
+        println!("Cards created: {}", report.created.len());
+        println!("Topics created: {}", report.topics_created.len());
+    }
+    if !report.generated_zettel_ids.is_empty() {
+        println!(
+            "Generated zettel_ids: {}",
+            report.generated_zettel_ids.join(", ")
+        );
+    }
+    if report.dry_run {
+        println!("\n** DRY RUN MODE - No changes were made **");
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load environment variables
+    dotenvy::dotenv().ok();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (Some(path), mut options) = parse_args(&args)? else {
+        print_usage();
+        return Ok(());
+    };
+    if path.ends_with(".tsv") {
+        options.delimiter = Some('\t');
+    }
+
+    println!("=== CSV Import ===");
+    if options.dry_run {
+        println!("** RUNNING IN DRY-RUN MODE **");
+        println!("This will check every row without making any changes.");
+    }
+
+    let database_url =
+        env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:./learning_system.db".to_string());
+    println!("Connecting to database: {}", database_url);
+    let card_service = CardService::new(Database::new(&database_url).await?);
+
+    println!("Reading rows from: {}", path);
+    let report = import_csv(&card_service, &fs::read_to_string(&path)?, &options).await?;
+    print_summary(&report);
+
+    Ok(())
+}
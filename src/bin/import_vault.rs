@@ -0,0 +1,73 @@
+use anyhow::Result;
+use learning_system::vault_import::{ImportReport, import_notes, read_vault};
+use learning_system::{CardService, Database};
+use std::env;
+use std::path::PathBuf;
+
+fn print_summary(report: &ImportReport) {
+    println!("\n=== Import Summary ===");
+    println!("Cards created: {}", report.created.len());
+    println!("Cards updated: {}", report.updated.len());
+    println!("Cards unchanged: {}", report.unchanged.len());
+    println!("Topics created: {}", report.topics_created.len());
+
+    if !report.unresolved_links.is_empty() {
+        println!("Unresolved links: {}", report.unresolved_links.len());
+        for (zettel_id, target) in &report.unresolved_links {
+            println!("  - {} -> [[{}]]", zettel_id, target);
+        }
+    }
+    if !report.errors.is_empty() {
+        println!("Errors encountered: {}", report.errors.len());
+        for error in &report.errors {
+            println!("  - {}", error);
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load environment variables
+    dotenvy::dotenv().ok();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let dry_run = args.contains(&"--dry-run".to_string());
+    let Some(vault_dir) = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map(PathBuf::from)
+    else {
+        println!("Usage: cargo run --bin import_vault -- <vault-directory> [--dry-run]");
+        return Ok(());
+    };
+
+    println!("=== Obsidian Vault Import ===");
+    println!("Reading notes from: {}", vault_dir.display());
+    let notes = read_vault(&vault_dir)?;
+    println!("Found {} notes", notes.len());
+
+    if dry_run {
+        println!("\n** DRY RUN MODE - No changes will be made **");
+        for note in &notes {
+            println!(
+                "\n{} ({})\n  Title: {}\n  Tags: {}\n  Links: {}",
+                note.zettel_id,
+                note.path.display(),
+                note.title.as_deref().unwrap_or("-"),
+                note.tags.join(", "),
+                note.links.join(", ")
+            );
+        }
+        return Ok(());
+    }
+
+    let database_url =
+        env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:./learning_system.db".to_string());
+    println!("Connecting to database: {}", database_url);
+    let card_service = CardService::new(Database::new(&database_url).await?);
+
+    let report = import_notes(&card_service, &notes).await?;
+    print_summary(&report);
+
+    Ok(())
+}
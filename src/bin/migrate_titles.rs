@@ -1,6 +1,6 @@
 use anyhow::Result;
 use learning_system::database::Database;
-use regex::Regex;
+use learning_system::markdown::extract_markdown_header;
 use sqlx::Row;
 use std::env;
 use uuid::Uuid;
@@ -67,38 +67,6 @@ impl CardUpdate {
     }
 }
 
-fn extract_markdown_header(content: &str) -> Option<(String, String)> {
-    let lines: Vec<&str> = content.lines().collect();
-
-    if lines.is_empty() {
-        return None;
-    }
-
-    let first_line = lines[0].trim();
-
-    // Regex to match markdown headers (# Header, ## Header, etc.)
-    let header_regex = Regex::new(r"^(#{1,6})\s+(.+)$").unwrap();
-
-    if let Some(captures) = header_regex.captures(first_line) {
-        let title = captures.get(2)?.as_str().trim().to_string();
-
-        if title.is_empty() {
-            return None;
-        }
-
-        // Remove the header line from content
-        let remaining_lines = if lines.len() > 1 {
-            lines[1..].join("\n").trim_start().to_string()
-        } else {
-            String::new()
-        };
-
-        Some((title, remaining_lines))
-    } else {
-        None
-    }
-}
-
 async fn find_cards_with_headers(db: &Database) -> Result<Vec<CardUpdate>> {
     let pool = &db.pool;
 
@@ -263,45 +231,3 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_extract_markdown_header() {
-        // Test basic header extraction
-        let content = "# This is a title\n\nThis is the content.";
-        let result = extract_markdown_header(content);
-        assert!(result.is_some());
-        let (title, new_content) = result.unwrap();
-        assert_eq!(title, "This is a title");
-        assert_eq!(new_content, "This is the content.");
-
-        // Test with different header levels
-        let content = "## Section Header\nContent here";
-        let result = extract_markdown_header(content);
-        assert!(result.is_some());
-        let (title, new_content) = result.unwrap();
-        assert_eq!(title, "Section Header");
-        assert_eq!(new_content, "Content here");
-
-        // Test with no header
-        let content = "Just regular content\nNo header here";
-        let result = extract_markdown_header(content);
-        assert!(result.is_none());
-
-        // Test with empty header
-        let content = "# \nContent";
-        let result = extract_markdown_header(content);
-        assert!(result.is_none());
-
-        // Test with only header
-        let content = "# Just a title";
-        let result = extract_markdown_header(content);
-        assert!(result.is_some());
-        let (title, new_content) = result.unwrap();
-        assert_eq!(title, "Just a title");
-        assert_eq!(new_content, "");
-    }
-}
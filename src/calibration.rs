@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::models::SessionAnswer;
+
+/// Groups need this many rated answers before their suggestions are adjusted
+pub const MIN_CALIBRATION_SAMPLES: usize = 20;
+
+/// Recorded as the model for answers graded before `served_by` was tracked
+const UNKNOWN_GRADER: &str = "unknown";
+
+/// How suggested ratings from one grader and question type compare with the
+/// ratings users finally gave
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationStats {
+    pub served_by: String,
+    pub question_type: String,
+    pub samples: usize,
+    pub mean_suggested: f64,
+    pub mean_final: f64,
+    pub bias: f64, // Mean of final minus suggested; positive means users rate higher
+    pub mean_absolute_error: f64, // Mean distance between suggested and final
+    pub agreement_rate: f64, // Share of answers where the user kept the suggestion
+}
+
+/// Calibration statistics per grader and question type, from session answers that
+/// have a final rating. Answers without one are ignored.
+pub fn calibration_stats(answers: &[SessionAnswer]) -> Vec<CalibrationStats> {
+    let mut groups: BTreeMap<(String, String), Vec<(i32, i32)>> = BTreeMap::new();
+    for answer in answers {
+        let Some(final_rating) = answer.final_rating else {
+            continue;
+        };
+        let served_by = answer.served_by.as_deref().unwrap_or(UNKNOWN_GRADER);
+        groups
+            .entry((served_by.to_string(), answer.question.question_type.clone()))
+            .or_default()
+            .push((answer.suggested_rating, final_rating));
+    }
+
+    groups
+        .into_iter()
+        .map(|((served_by, question_type), ratings)| {
+            let samples = ratings.len();
+            let mean = |value: &dyn Fn(&(i32, i32)) -> f64| {
+                ratings.iter().map(value).sum::<f64>() / samples as f64
+            };
+
+            CalibrationStats {
+                served_by,
+                question_type,
+                samples,
+                mean_suggested: mean(&|&(suggested, _)| suggested as f64),
+                mean_final: mean(&|&(_, final_rating)| final_rating as f64),
+                bias: mean(&|&(suggested, final_rating)| (final_rating - suggested) as f64),
+                mean_absolute_error: mean(&|&(suggested, final_rating)| {
+                    (final_rating - suggested).abs() as f64
+                }),
+                agreement_rate: mean(&|&(suggested, final_rating)| {
+                    if suggested == final_rating { 1.0 } else { 0.0 }
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Shift a suggested rating by the bias users have shown for its grader and question
+/// type, rounded to whole ratings. Groups with too few samples leave it unchanged.
+pub fn calibrate_rating(
+    stats: &[CalibrationStats],
+    served_by: Option<&str>,
+    question_type: &str,
+    rating: i32,
+) -> i32 {
+    let served_by = served_by.unwrap_or(UNKNOWN_GRADER);
+    stats
+        .iter()
+        .find(|s| s.served_by == served_by && s.question_type == question_type)
+        .filter(|s| s.samples >= MIN_CALIBRATION_SAMPLES)
+        .map_or(rating, |s| (rating + s.bias.round() as i32).clamp(1, 4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::QuizQuestion;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn answer(
+        served_by: &str,
+        question_type: &str,
+        suggested: i32,
+        final_rating: Option<i32>,
+    ) -> SessionAnswer {
+        SessionAnswer {
+            id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+            card_id: Uuid::new_v4(),
+            question_index: 0,
+            question: QuizQuestion {
+                question: "Question".to_string(),
+                question_type: question_type.to_string(),
+                options: None,
+                correct_answer: None,
+                prompt_version: None,
+                rubric: None,
+            },
+            user_answer: "Answer".to_string(),
+            is_correct: true,
+            feedback: "Feedback".to_string(),
+            suggested_rating: suggested,
+            score: None,
+            criterion_scores: None,
+            served_by: Some(served_by.to_string()),
+            final_rating,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_stats_grouped_by_grader_and_question_type() {
+        let answers = vec![
+            answer("openai:gpt-4o-mini", "short_answer", 4, Some(3)),
+            answer("openai:gpt-4o-mini", "short_answer", 3, Some(3)),
+            answer("openai:gpt-4o-mini", "multiple_choice", 3, Some(4)),
+            answer("local:rules", "short_answer", 3, None),
+        ];
+
+        let stats = calibration_stats(&answers);
+        assert_eq!(stats.len(), 2);
+
+        let short = &stats[1];
+        assert_eq!(
+            (short.served_by.as_str(), short.question_type.as_str()),
+            ("openai:gpt-4o-mini", "short_answer")
+        );
+        assert_eq!(short.samples, 2);
+        assert_eq!(short.mean_suggested, 3.5);
+        assert_eq!(short.bias, -0.5);
+        assert_eq!(short.mean_absolute_error, 0.5);
+        assert_eq!(short.agreement_rate, 0.5);
+    }
+
+    #[test]
+    fn test_calibration_needs_enough_samples() {
+        let generous: Vec<SessionAnswer> = (0..MIN_CALIBRATION_SAMPLES)
+            .map(|_| answer("openai:gpt-4o-mini", "short_answer", 4, Some(3)))
+            .collect();
+        let stats = calibration_stats(&generous);
+
+        assert_eq!(
+            calibrate_rating(&stats, Some("openai:gpt-4o-mini"), "short_answer", 4),
+            3
+        );
+        assert_eq!(
+            calibrate_rating(&stats, Some("openai:gpt-4o-mini"), "short_answer", 1),
+            1
+        );
+        assert_eq!(
+            calibrate_rating(&stats, Some("openai:gpt-4o-mini"), "problem_solving", 4),
+            4
+        );
+
+        let stats = calibration_stats(&generous[1..]);
+        assert_eq!(
+            calibrate_rating(&stats, Some("openai:gpt-4o-mini"), "short_answer", 4),
+            4
+        );
+    }
+}
@@ -1,16 +1,30 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use crate::database::Database;
+use crate::calibration::{CalibrationStats, calibration_stats};
+use crate::database::{CardUpdate, Database, NewCard};
 use crate::fsrs_scheduler::FSRSScheduler;
+use crate::knowledge_graph::KnowledgeGraph;
+use crate::markdown::{rewrite_wiki_links, wiki_links};
 use crate::models::*;
+use crate::question_variety::select_varied;
+
+/// Number of banked questions served for a card in one review session
+pub const QUESTIONS_PER_REVIEW: usize = 3;
+
+/// Prerequisites below this retrievability are reported as a weak foundation
+pub const DEFAULT_WEAK_FOUNDATION_THRESHOLD: f64 = 0.7;
 
 #[derive(Clone)]
 pub struct CardService {
     db: Database,
     scheduler: FSRSScheduler,
+    rating_calibration: bool, // Adjust suggested ratings toward how the user rates
+    postpone_lapsed_dependents: bool, // Hold back cards whose prerequisites are relearning
+    weak_foundation_threshold: f64,
 }
 
 impl CardService {
@@ -18,9 +32,31 @@ impl CardService {
         Self {
             db,
             scheduler: FSRSScheduler::new(),
+            rating_calibration: false,
+            postpone_lapsed_dependents: false,
+            weak_foundation_threshold: DEFAULT_WEAK_FOUNDATION_THRESHOLD,
         }
     }
 
+    pub fn with_rating_calibration(mut self, enabled: bool) -> Self {
+        self.rating_calibration = enabled;
+        self
+    }
+
+    /// Leave cards out of the optimized due list while one of their prerequisites is
+    /// relearning after a lapse
+    pub fn with_lapsed_dependent_postponement(mut self, enabled: bool) -> Self {
+        self.postpone_lapsed_dependents = enabled;
+        self
+    }
+
+    /// Report a card's prerequisites as weak foundations once their retrievability falls
+    /// below this threshold
+    pub fn with_weak_foundation_threshold(mut self, threshold: f64) -> Self {
+        self.weak_foundation_threshold = threshold;
+        self
+    }
+
     #[cfg(test)]
     #[allow(dead_code)]
     pub async fn new_in_memory() -> Result<Self> {
@@ -28,42 +64,85 @@ impl CardService {
         Ok(Self {
             db,
             scheduler: FSRSScheduler::new(),
+            rating_calibration: false,
+            postpone_lapsed_dependents: false,
+            weak_foundation_threshold: DEFAULT_WEAK_FOUNDATION_THRESHOLD,
         })
     }
 
     // Card CRUD operations
+    #[allow(dead_code)]
     pub async fn create_card(&self, request: CreateCardRequest) -> Result<Card> {
-        let card = self.db.create_card(request).await?;
-
-        // Create backlinks for any initial links, but only for existing cards
-        if let Some(ref links_json) = card.links {
-            let links: Vec<Uuid> = serde_json::from_str(links_json).unwrap_or_default();
-            if !links.is_empty() {
-                // Filter out non-existent cards before creating backlinks
-                let mut valid_links = Vec::new();
-                for link_id in links {
-                    if self.db.get_card(link_id).await?.is_some() {
-                        valid_links.push(link_id);
-                    }
-                }
-                if !valid_links.is_empty() {
-                    self.db.create_backlinks(card.id, &valid_links).await?;
-                }
-            }
+        self.insert_new_card(request, Vec::new()).await
+    }
+
+    /// Create a card with its topics, links and link relations in one transaction, then
+    /// link existing cards whose content was waiting for it
+    async fn insert_new_card(
+        &self,
+        request: CreateCardRequest,
+        link_relations: Vec<(Uuid, LinkRelation, Option<String>)>,
+    ) -> Result<Card> {
+        if self
+            .db
+            .get_card_by_zettel_id(&request.zettel_id)
+            .await?
+            .is_some()
+        {
+            return Err(anyhow::anyhow!(
+                "Zettelkasten ID '{}' already exists",
+                request.zettel_id
+            ));
         }
+        let card = Card::new(request.zettel_id, request.title, request.content);
+
+        // Inline [[zettel_id]] links in the content join the explicit links. Links are
+        // stored as backlinks rows, which skip cards that don't exist.
+        let inline_links = self.resolve_inline_links(card.id, &card.content).await?;
+        let links = merge_links(request.links.unwrap_or_default(), &inline_links);
+        let new = NewCard {
+            card,
+            topic_ids: request.topic_ids,
+            links,
+            link_relations,
+        };
+        self.db
+            .insert_cards(&[], std::slice::from_ref(&new))
+            .await?;
 
+        let card = self
+            .db
+            .get_card(new.card.id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Card {} was not saved", new.card.id))?;
+        self.link_waiting_references(&card).await?;
         Ok(card)
     }
 
+    /// Create a batch of cards and the topics they introduce in one transaction, then
+    /// link existing cards whose content was waiting for one of them
+    pub async fn import_cards(&self, topics: &[Topic], cards: &[NewCard]) -> Result<()> {
+        self.db.insert_cards(topics, cards).await?;
+        for new in cards {
+            self.link_waiting_references(&new.card).await?;
+        }
+        Ok(())
+    }
+
     pub async fn create_card_with_zettel_links(
         &self,
         request: CreateCardWithZettelLinksRequest,
     ) -> Result<Card> {
-        let links = if let Some(zettel_links) = request.zettel_links {
-            Some(self.resolve_zettel_ids_to_uuids(&zettel_links).await?)
-        } else {
-            None
-        };
+        let typed_links = self
+            .resolve_typed_links(request.typed_links.as_deref())
+            .await?;
+        let links = self
+            .resolve_request_links(
+                request.zettel_links,
+                request.typed_links.is_some(),
+                &typed_links,
+            )
+            .await?;
 
         let create_request = CreateCardRequest {
             zettel_id: request.zettel_id,
@@ -73,7 +152,7 @@ impl CardService {
             links,
         };
 
-        self.create_card(create_request).await // Use our own method to handle backlinks
+        self.insert_new_card(create_request, typed_links).await
     }
 
     pub async fn get_card(&self, id: Uuid) -> Result<Option<Card>> {
@@ -84,37 +163,54 @@ impl CardService {
         self.db.get_card_by_zettel_id(zettel_id).await
     }
 
+    #[allow(dead_code)]
     pub async fn update_card(&self, id: Uuid, request: UpdateCardRequest) -> Result<Option<Card>> {
+        let update = self.save_card_update(id, request, &[], false).await?;
+        Ok(update.map(|(card, _)| card))
+    }
+
+    /// Apply an edit to a card and save it, with the links, topics and banked questions
+    /// it changes, in one transaction. With `rewrite_references` a new zettel_id is also
+    /// written into every `[[old_zettel_id]]` link, the edited content's included, and
+    /// the rewritten cards are returned.
+    async fn save_card_update(
+        &self,
+        id: Uuid,
+        request: UpdateCardRequest,
+        typed_links: &[(Uuid, LinkRelation, Option<String>)],
+        rewrite_references: bool,
+    ) -> Result<Option<(Card, Vec<RewrittenReference>)>> {
         let mut card = match self.db.get_card(id).await? {
             Some(card) => card,
             None => return Ok(None),
         };
 
         // Get old links for backlink maintenance
-        let old_links: Vec<Uuid> = if let Some(ref links_json) = card.links {
-            serde_json::from_str(links_json).unwrap_or_default()
-        } else {
-            Vec::new()
-        };
+        let old_links = self.links_with_origin(card.id).await?;
+        let old_zettel_id = card.zettel_id.clone();
+        let old_content = card.content.clone();
 
         if let Some(new_zettel_id) = request.zettel_id {
+            let new_zettel_id = new_zettel_id.trim();
+            if new_zettel_id.is_empty() {
+                return Err(anyhow::anyhow!("A Zettelkasten ID is required"));
+            }
             // Validate that the new zettel_id doesn't already exist (unless it's the same card)
-            if let Some(existing) = self.db.get_card_by_zettel_id(&new_zettel_id).await? {
-                if existing.id != card.id {
-                    return Err(anyhow::anyhow!(
-                        "Zettelkasten ID '{}' already exists",
-                        new_zettel_id
-                    ));
-                }
+            if let Some(existing) = self.db.get_card_by_zettel_id(new_zettel_id).await?
+                && existing.id != card.id
+            {
+                return Err(anyhow::anyhow!(
+                    "Zettelkasten ID '{}' already exists",
+                    new_zettel_id
+                ));
             }
 
-            let old_zettel_id = card.zettel_id.clone();
-            card.zettel_id = new_zettel_id.clone();
+            card.zettel_id = new_zettel_id.to_string();
 
             // Handle Zettel ID change by checking for textual references
             // Note: The UUID-based links and backlinks will continue to work correctly
             // as they reference the card by its immutable UUID, not the Zettel ID
-            if old_zettel_id != new_zettel_id {
+            if old_zettel_id != new_zettel_id && !rewrite_references {
                 // Check if any other cards reference the old Zettel ID in their content
                 match self
                     .db
@@ -160,42 +256,167 @@ impl CardService {
         if let Some(title) = request.title {
             card.title = Some(title);
         }
-
         if let Some(content) = request.content {
             card.content = content;
         }
 
-        let mut new_links = old_links.clone();
-        let mut links_changed = false;
-        if let Some(links) = request.links {
-            new_links = links;
-            card.links = Some(serde_json::to_string(&new_links)?);
-            links_changed = true;
+        let mut rewritten = Vec::new();
+        let mut rewritten_contents = Vec::new();
+        if rewrite_references && card.zettel_id != old_zettel_id {
+            (rewritten, rewritten_contents) =
+                self.plan_reference_rewrites(&card, &old_zettel_id).await?;
+            if let Some(own) = rewritten_contents
+                .iter()
+                .position(|(id, _, _)| *id == card.id)
+            {
+                card.content = rewritten_contents.remove(own).1;
+            }
+        }
+        let content_changed = card.content != old_content;
+
+        // Inline links follow the content: links that came from [[zettel_id]]s are
+        // dropped when the content stops naming them, while explicit links stay until a
+        // request replaces them
+        let explicit_links = match request.links {
+            Some(links) => links,
+            None => old_links
+                .iter()
+                .filter(|(_, origin)| *origin == LinkOrigin::Explicit)
+                .map(|(id, _)| *id)
+                .collect(),
+        };
+        let inline_links = self.resolve_inline_links(card.id, &card.content).await?;
+        let new_links = merge_links(explicit_links, &inline_links);
+
+        // Banked questions were generated from the old content and no longer apply
+        let new_content_hash = content_changed.then(|| content_hash(&card.content));
+        card.links = self
+            .db
+            .save_card_update(&CardUpdate {
+                card: &card,
+                content_hash: new_content_hash.as_deref(),
+                links: (new_links != old_links).then_some(new_links.as_slice()),
+                link_relations: typed_links,
+                topic_ids: request.topic_ids.as_deref(),
+                rewritten_contents: &rewritten_contents,
+            })
+            .await?;
+        if content_changed {
+            debug!(card_id = %card.id, "Invalidated banked questions after content change");
+        }
+        if rewrite_references && card.zettel_id != old_zettel_id {
+            info!(
+                card_id = %card.id,
+                old_zettel_id = %old_zettel_id,
+                new_zettel_id = %card.zettel_id,
+                rewritten_cards = rewritten.len(),
+                "Zettel ID renamed and references rewritten"
+            );
         }
 
-        // Update the card in database
-        self.db.update_card_content(&card).await?;
+        if card.zettel_id != old_zettel_id {
+            self.link_waiting_references(&card).await?;
+        }
 
-        // Update backlinks if links changed, filtering out non-existent targets
-        if links_changed {
-            let mut valid_new_links = Vec::new();
-            for link_id in &new_links {
-                if self.db.get_card(*link_id).await?.is_some() {
-                    valid_new_links.push(*link_id);
-                }
+        Ok(Some((card, rewritten)))
+    }
+
+    /// The cards whose `[[old_zettel_id]]` links change when the card takes its new
+    /// zettel_id, and their rewritten content with its hash. The card itself is
+    /// rewritten from its edited content rather than the stored one.
+    async fn plan_reference_rewrites(
+        &self,
+        card: &Card,
+        old_zettel_id: &str,
+    ) -> Result<(Vec<RewrittenReference>, Vec<(Uuid, String, String)>)> {
+        let others = self
+            .db
+            .find_cards_referencing_zettel_id(old_zettel_id)
+            .await?
+            .into_iter()
+            .filter(|referencing| referencing.id != card.id);
+
+        let mut rewritten = Vec::new();
+        let mut rewritten_contents = Vec::new();
+        for referencing in std::iter::once(card.clone()).chain(others) {
+            let (content, occurrences) =
+                rewrite_wiki_links(&referencing.content, old_zettel_id, &card.zettel_id);
+            if occurrences == 0 {
+                continue;
             }
-            self.db
-                .update_backlinks(card.id, &old_links, &valid_new_links)
-                .await?;
+            // Cards are reported by the zettel_id they had before the rename
+            let zettel_id = if referencing.id == card.id {
+                old_zettel_id.to_string()
+            } else {
+                referencing.zettel_id
+            };
+            rewritten.push(RewrittenReference {
+                card_id: referencing.id,
+                zettel_id,
+                occurrences,
+            });
+            let hash = content_hash(&content);
+            rewritten_contents.push((referencing.id, content, hash));
+        }
+        Ok((rewritten, rewritten_contents))
+    }
+
+    /// Rename a card's zettel_id and point every `[[old_zettel_id]]` link in card content
+    /// at the new one, in a single transaction. A preview reports the cards that would be
+    /// rewritten without changing anything.
+    pub async fn rename_zettel_id(
+        &self,
+        id: Uuid,
+        new_zettel_id: &str,
+        preview: bool,
+    ) -> Result<Option<ZettelRenameReport>> {
+        let Some(mut card) = self.db.get_card(id).await? else {
+            return Ok(None);
+        };
+        let new_zettel_id = new_zettel_id.trim();
+        if new_zettel_id.is_empty() {
+            return Err(anyhow::anyhow!("A Zettelkasten ID is required"));
+        }
+        if let Some(existing) = self.db.get_card_by_zettel_id(new_zettel_id).await?
+            && existing.id != card.id
+        {
+            return Err(anyhow::anyhow!(
+                "Zettelkasten ID '{}' already exists",
+                new_zettel_id
+            ));
         }
 
-        // Handle topic updates if provided
-        if let Some(_topic_ids) = request.topic_ids {
-            // This would require additional methods in Database to handle topic updates
-            // For now, we'll skip this part but it should be implemented
+        let mut report = ZettelRenameReport {
+            card_id: card.id,
+            old_zettel_id: card.zettel_id.clone(),
+            new_zettel_id: new_zettel_id.to_string(),
+            preview,
+            rewritten: Vec::new(),
+        };
+        if card.zettel_id == new_zettel_id {
+            return Ok(Some(report));
+        }
+
+        if preview {
+            card.zettel_id = new_zettel_id.to_string();
+            (report.rewritten, _) = self
+                .plan_reference_rewrites(&card, &report.old_zettel_id)
+                .await?;
+            return Ok(Some(report));
         }
 
-        Ok(Some(card))
+        let request = UpdateCardRequest {
+            zettel_id: Some(new_zettel_id.to_string()),
+            title: None,
+            content: None,
+            topic_ids: None,
+            links: None,
+        };
+        let Some((_, rewritten)) = self.save_card_update(id, request, &[], true).await? else {
+            return Ok(None);
+        };
+        report.rewritten = rewritten;
+        Ok(Some(report))
     }
 
     pub async fn update_card_with_zettel_links(
@@ -203,11 +424,41 @@ impl CardService {
         id: Uuid,
         request: UpdateCardWithZettelLinksRequest,
     ) -> Result<Option<Card>> {
-        let links = if let Some(zettel_links) = request.zettel_links {
-            Some(self.resolve_zettel_ids_to_uuids(&zettel_links).await?)
-        } else {
-            None
-        };
+        let (update_request, typed_links) = self.resolve_zettel_link_update(request).await?;
+        let update = self
+            .save_card_update(id, update_request, &typed_links, false)
+            .await?;
+        Ok(update.map(|(card, _)| card))
+    }
+
+    /// Update a card like `update_card_with_zettel_links`, also pointing every
+    /// `[[old_zettel_id]]` link at a new zettel_id in the same transaction. Returns the
+    /// card and the cards whose content was rewritten.
+    pub async fn update_card_rewriting_references(
+        &self,
+        id: Uuid,
+        request: UpdateCardWithZettelLinksRequest,
+    ) -> Result<Option<(Card, Vec<RewrittenReference>)>> {
+        let (update_request, typed_links) = self.resolve_zettel_link_update(request).await?;
+        self.save_card_update(id, update_request, &typed_links, true)
+            .await
+    }
+
+    /// A zettel_id based update request resolved to card ids, with its typed links
+    async fn resolve_zettel_link_update(
+        &self,
+        request: UpdateCardWithZettelLinksRequest,
+    ) -> Result<(UpdateCardRequest, Vec<(Uuid, LinkRelation, Option<String>)>)> {
+        let typed_links = self
+            .resolve_typed_links(request.typed_links.as_deref())
+            .await?;
+        let links = self
+            .resolve_request_links(
+                request.zettel_links,
+                request.typed_links.is_some(),
+                &typed_links,
+            )
+            .await?;
 
         let update_request = UpdateCardRequest {
             zettel_id: request.zettel_id,
@@ -216,14 +467,212 @@ impl CardService {
             topic_ids: request.topic_ids,
             links,
         };
+        Ok((update_request, typed_links))
+    }
+
+    /// Typed links resolved to card ids, with blank notes dropped
+    async fn resolve_typed_links(
+        &self,
+        typed_links: Option<&[TypedZettelLink]>,
+    ) -> Result<Vec<(Uuid, LinkRelation, Option<String>)>> {
+        let mut resolved = Vec::new();
+        for link in typed_links.unwrap_or_default() {
+            let ids = self
+                .resolve_zettel_ids_to_uuids(std::slice::from_ref(&link.zettel_id))
+                .await?;
+            let note = link
+                .note
+                .as_deref()
+                .map(str::trim)
+                .filter(|note| !note.is_empty())
+                .map(str::to_string);
+            resolved.extend(ids.into_iter().map(|id| (id, link.relation, note.clone())));
+        }
+        Ok(resolved)
+    }
 
-        self.update_card(id, update_request).await
+    /// The explicit links a request asks for: its zettel_links followed by its typed
+    /// links. None when it has neither, which leaves an existing card's links alone.
+    async fn resolve_request_links(
+        &self,
+        zettel_links: Option<Vec<String>>,
+        has_typed_links: bool,
+        typed_links: &[(Uuid, LinkRelation, Option<String>)],
+    ) -> Result<Option<Vec<Uuid>>> {
+        let mut links = match zettel_links {
+            Some(zettel_links) => self.resolve_zettel_ids_to_uuids(&zettel_links).await?,
+            None if has_typed_links => Vec::new(),
+            None => return Ok(None),
+        };
+        for (id, _, _) in typed_links {
+            if !links.contains(id) {
+                links.push(*id);
+            }
+        }
+        Ok(Some(links))
     }
 
     pub async fn delete_card(&self, id: Uuid) -> Result<bool> {
         self.db.delete_card(id).await
     }
 
+    // Question bank operations
+    /// Up to `limit` banked questions for the card's current content, least used first.
+    /// Pinned questions come first and flagged ones are never served. Questions asked
+    /// in the card's most recent session are only served when nothing else is left,
+    /// and a mix of question types is preferred.
+    pub async fn get_banked_questions(
+        &self,
+        card: &Card,
+        limit: usize,
+    ) -> Result<Vec<QuizQuestion>> {
+        let (pinned, banked): (Vec<_>, Vec<_>) = self
+            .list_card_questions(card)
+            .await?
+            .into_iter()
+            .filter(|b| b.flag_reason.is_none())
+            .partition(|b| b.pinned);
+
+        let mut questions: Vec<QuizQuestion> =
+            pinned.into_iter().take(limit).map(|b| b.question).collect();
+        let limit = limit - questions.len();
+
+        let last_session = banked.iter().filter_map(|b| b.last_used_at).max();
+        let (recent, fresh): (Vec<_>, Vec<_>) = banked
+            .into_iter()
+            .partition(|b| last_session.is_some() && b.last_used_at == last_session);
+
+        let mut rotated = select_varied(fresh.into_iter().map(|b| b.question).collect(), limit);
+        let missing = limit.saturating_sub(rotated.len());
+        rotated.extend(recent.into_iter().take(missing).map(|b| b.question));
+        questions.extend(rotated);
+        Ok(questions)
+    }
+
+    /// Every banked question for the card's current content plus its pinned ones,
+    /// including flagged questions
+    pub async fn list_card_questions(&self, card: &Card) -> Result<Vec<BankedQuestion>> {
+        self.db
+            .get_banked_questions(card.id, &content_hash(&card.content))
+            .await
+    }
+
+    /// Report a question as wrong or ambiguous. Flagged questions are no longer served
+    /// and are shown to the generator as examples to avoid.
+    pub async fn flag_question(&self, id: Uuid, reason: &str) -> Result<Option<BankedQuestion>> {
+        info!(question_id = %id, reason = %reason, "Flagging banked question");
+        self.db.set_banked_question_flag(id, Some(reason)).await
+    }
+
+    pub async fn unflag_question(&self, id: Uuid) -> Result<Option<BankedQuestion>> {
+        self.db.set_banked_question_flag(id, None).await
+    }
+
+    pub async fn set_question_pinned(
+        &self,
+        id: Uuid,
+        pinned: bool,
+    ) -> Result<Option<BankedQuestion>> {
+        self.db.set_banked_question_pinned(id, pinned).await
+    }
+
+    /// Apply a reviewer's corrections to a banked question; unset fields are kept
+    pub async fn update_question(
+        &self,
+        id: Uuid,
+        request: UpdateQuestionRequest,
+    ) -> Result<Option<BankedQuestion>> {
+        let Some(existing) = self.db.get_banked_question(id).await? else {
+            return Ok(None);
+        };
+
+        let mut question = existing.question;
+        if let Some(text) = request.question {
+            question.question = text;
+        }
+        if let Some(options) = request.options {
+            question.options = Some(options);
+        }
+        if let Some(correct_answer) = request.correct_answer {
+            question.correct_answer = Some(correct_answer);
+        }
+        if let Some(rubric) = request.rubric {
+            question.rubric = Some(rubric);
+        }
+
+        info!(question_id = %id, card_id = %existing.card_id, "Updating banked question");
+        self.db.update_banked_question(id, &question).await
+    }
+
+    /// Store generated questions for reuse. Questions without a prompt version come
+    /// from the local fallback generator and are not worth keeping.
+    pub async fn bank_questions(&self, card: &Card, questions: &[QuizQuestion]) -> Result<()> {
+        let generated: Vec<QuizQuestion> = questions
+            .iter()
+            .filter(|q| q.prompt_version.is_some())
+            .cloned()
+            .collect();
+        if generated.is_empty() {
+            return Ok(());
+        }
+
+        self.db
+            .insert_banked_questions(card.id, &content_hash(&card.content), &generated)
+            .await
+    }
+
+    /// Count questions as asked so the least used ones are served next time
+    pub async fn record_question_use(&self, card: &Card, questions: &[QuizQuestion]) -> Result<()> {
+        let texts: Vec<&str> = questions.iter().map(|q| q.question.as_str()).collect();
+        self.db
+            .mark_banked_questions_used(card.id, &content_hash(&card.content), &texts)
+            .await
+    }
+
+    // Session answer operations
+    pub async fn record_session_answer(&self, answer: &SessionAnswer) -> Result<()> {
+        self.db.insert_session_answer(answer).await
+    }
+
+    pub async fn get_session_answers(&self, session_id: Uuid) -> Result<Vec<SessionAnswer>> {
+        self.db.get_session_answers(session_id).await
+    }
+
+    /// Record the rating the user gave a card against the suggestions made for its
+    /// answers in the session
+    pub async fn record_final_rating(
+        &self,
+        session_id: Uuid,
+        card_id: Uuid,
+        rating: i32,
+    ) -> Result<u64> {
+        let updated = self
+            .db
+            .set_session_final_rating(session_id, card_id, rating)
+            .await?;
+        debug!(
+            session_id = %session_id,
+            card_id = %card_id,
+            rating = rating,
+            answers = updated,
+            "Recorded final rating for session answers"
+        );
+        Ok(updated)
+    }
+
+    pub async fn calibration_stats(&self) -> Result<Vec<CalibrationStats>> {
+        let answers = self.db.get_rated_session_answers().await?;
+        Ok(calibration_stats(&answers))
+    }
+
+    /// Calibration used to adjust suggested ratings, or `None` when it is disabled
+    pub async fn rating_calibration(&self) -> Result<Option<Vec<CalibrationStats>>> {
+        if !self.rating_calibration {
+            return Ok(None);
+        }
+        self.calibration_stats().await.map(Some)
+    }
+
     // Topic operations
     pub async fn create_topic(&self, name: String, description: Option<String>) -> Result<Topic> {
         self.db.create_topic(name, description).await
@@ -233,6 +682,11 @@ impl CardService {
         self.db.get_all_topics().await
     }
 
+    #[allow(dead_code)]
+    pub async fn get_topic_names_for_card(&self, card_id: Uuid) -> Result<Vec<String>> {
+        self.db.get_topic_names_for_card(card_id).await
+    }
+
     pub async fn get_all_cards(&self) -> Result<Vec<Card>> {
         self.db.get_all_cards().await
     }
@@ -242,8 +696,24 @@ impl CardService {
         self.db.get_cards_due_for_review().await
     }
 
+    /// Cards that will become due within the next `hours`, including those already due
+    pub async fn get_cards_due_within(&self, hours: i64) -> Result<Vec<Card>> {
+        self.db
+            .get_cards_due_before(Utc::now() + chrono::Duration::hours(hours))
+            .await
+    }
+
+    /// Due cards ordered for review. Prerequisites come before the cards that depend on
+    /// them, and dependents of relearning cards are held back when postponement is on.
     pub async fn get_cards_due_optimized(&self) -> Result<Vec<Card>> {
         let mut cards = self.get_cards_due_for_review().await?;
+        let prerequisites = self.prerequisites().await?;
+
+        if self.postpone_lapsed_dependents {
+            cards = self
+                .without_lapsed_dependents(cards, &prerequisites)
+                .await?;
+        }
 
         if cards.len() <= 1 {
             return Ok(cards);
@@ -292,6 +762,7 @@ impl CardService {
             // Finally, sort by next_review (oldest first)
             a.next_review.cmp(&b.next_review)
         });
+        let cards = order_prerequisites_first(cards, &prerequisites);
 
         debug!(
             card_count = cards.len(),
@@ -303,6 +774,105 @@ impl CardService {
         Ok(cards)
     }
 
+    /// Prerequisite card ids for each card with prerequisite links
+    async fn prerequisites(&self) -> Result<HashMap<Uuid, Vec<Uuid>>> {
+        let mut prerequisites: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for link in self.db.get_all_backlinks().await? {
+            if link.relation == LinkRelation::Prerequisite {
+                prerequisites
+                    .entry(link.source_card_id)
+                    .or_default()
+                    .push(link.target_card_id);
+            }
+        }
+        Ok(prerequisites)
+    }
+
+    /// The prerequisites of the given cards, loaded together and keyed by id
+    async fn prerequisite_cards(
+        &self,
+        cards: &[Card],
+        prerequisites: &HashMap<Uuid, Vec<Uuid>>,
+    ) -> Result<HashMap<Uuid, Card>> {
+        let ids: Vec<Uuid> = cards
+            .iter()
+            .filter_map(|card| prerequisites.get(&card.id))
+            .flatten()
+            .copied()
+            .collect();
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        Ok(self
+            .db
+            .get_cards_by_ids(&ids)
+            .await?
+            .into_iter()
+            .map(|card| (card.id, card))
+            .collect())
+    }
+
+    /// The cards without those that have a prerequisite in relearning
+    async fn without_lapsed_dependents(
+        &self,
+        cards: Vec<Card>,
+        prerequisites: &HashMap<Uuid, Vec<Uuid>>,
+    ) -> Result<Vec<Card>> {
+        let prerequisite_cards = self.prerequisite_cards(&cards, prerequisites).await?;
+        let mut kept = Vec::new();
+        for card in cards {
+            let lapsed = prerequisites
+                .get(&card.id)
+                .into_iter()
+                .flatten()
+                .filter_map(|id| prerequisite_cards.get(id))
+                .find(|prerequisite| prerequisite.state == "Relearning");
+            match lapsed {
+                Some(prerequisite) => debug!(
+                    card_id = %card.id,
+                    prerequisite_id = %prerequisite.id,
+                    "Postponing card until its lapsed prerequisite is relearned"
+                ),
+                None => kept.push(card),
+            }
+        }
+        Ok(kept)
+    }
+
+    /// Cards whose prerequisites have fallen below the weak foundation threshold, with
+    /// those prerequisites
+    pub async fn weak_foundations(&self, cards: &[Card]) -> Result<Vec<WeakFoundation>> {
+        let prerequisites = self.prerequisites().await?;
+        let prerequisite_cards = self.prerequisite_cards(cards, &prerequisites).await?;
+        let now = Utc::now();
+        let mut warnings = Vec::new();
+        for card in cards {
+            let mut weak = Vec::new();
+            for id in prerequisites.get(&card.id).into_iter().flatten() {
+                let Some(prerequisite) = prerequisite_cards.get(id) else {
+                    continue;
+                };
+                let retrievability = self.scheduler.current_retrievability(prerequisite, now);
+                if retrievability < self.weak_foundation_threshold {
+                    weak.push(WeakPrerequisite {
+                        card_id: prerequisite.id,
+                        zettel_id: prerequisite.zettel_id.clone(),
+                        state: prerequisite.state.clone(),
+                        retrievability,
+                    });
+                }
+            }
+            if !weak.is_empty() {
+                warnings.push(WeakFoundation {
+                    card_id: card.id,
+                    zettel_id: card.zettel_id.clone(),
+                    prerequisites: weak,
+                });
+            }
+        }
+        Ok(warnings)
+    }
+
     pub async fn review_card(&self, card_id: Uuid, rating: i32) -> Result<Option<Card>> {
         let card = match self.db.get_card(card_id).await? {
             Some(card) => card,
@@ -350,24 +920,113 @@ impl CardService {
             None => return Ok(Vec::new()),
         };
 
-        if let Some(links_json) = card.links {
-            let link_ids: Vec<Uuid> = serde_json::from_str(&links_json)?;
-            let mut linked_cards = Vec::new();
+        let mut linked_cards = Vec::new();
+        for (link_id, _) in self.links_with_origin(card.id).await? {
+            if let Some(linked_card) = self.db.get_card(link_id).await? {
+                linked_cards.push(linked_card);
+            }
+        }
+        Ok(linked_cards)
+    }
+
+    /// The cards the card links to and where each link came from, in link order
+    async fn links_with_origin(&self, card_id: Uuid) -> Result<Vec<(Uuid, LinkOrigin)>> {
+        Ok(self
+            .db
+            .get_links_from(card_id)
+            .await?
+            .into_iter()
+            .map(|link| (link.target_card_id, link.origin))
+            .collect())
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_backlinks(&self, card_id: Uuid) -> Result<Vec<Card>> {
+        self.db.get_backlinks(card_id).await
+    }
 
-            for link_id in link_ids {
-                if let Some(linked_card) = self.db.get_card(link_id).await? {
-                    linked_cards.push(linked_card);
+    /// Linked cards in link order, with each link's relation and note
+    pub async fn get_typed_links(&self, card_id: Uuid) -> Result<Vec<LinkedCard>> {
+        let stored = self.db.get_links_from(card_id).await?;
+        Ok(self
+            .get_linked_cards(card_id)
+            .await?
+            .into_iter()
+            .map(|card| {
+                let link = stored.iter().find(|link| link.target_card_id == card.id);
+                LinkedCard {
+                    relation: link.map(|link| link.relation).unwrap_or_default(),
+                    note: link.and_then(|link| link.note.clone()),
+                    card,
                 }
+            })
+            .collect())
+    }
+
+    /// Cards linking to the card, with each link's relation and note
+    pub async fn get_typed_backlinks(&self, card_id: Uuid) -> Result<Vec<LinkedCard>> {
+        self.db.get_typed_backlinks(card_id).await
+    }
+
+    /// Every card and the links between them
+    pub async fn knowledge_graph(&self) -> Result<KnowledgeGraph> {
+        Ok(KnowledgeGraph::new(
+            &self.db.get_all_cards().await?,
+            &self.db.get_all_backlinks().await?,
+        ))
+    }
+
+    /// Cards named by `[[zettel_id]]` links in the content, in order of appearance and
+    /// without the card itself
+    pub async fn resolve_inline_links(&self, card_id: Uuid, content: &str) -> Result<Vec<Uuid>> {
+        let mut ids = Vec::new();
+        for target in wiki_links(content) {
+            if let Some(linked) = self.db.get_card_by_zettel_id(&target).await?
+                && linked.id != card_id
+                && !ids.contains(&linked.id)
+            {
+                ids.push(linked.id);
             }
+        }
+        Ok(ids)
+    }
 
-            Ok(linked_cards)
-        } else {
-            Ok(Vec::new())
+    /// `[[zettel_id]]` links in the content that don't name any card
+    pub async fn unresolved_inline_links(&self, content: &str) -> Result<Vec<String>> {
+        let mut unresolved = Vec::new();
+        for target in wiki_links(content) {
+            if self.db.get_card_by_zettel_id(&target).await?.is_none() {
+                unresolved.push(target);
+            }
         }
+        Ok(unresolved)
     }
 
-    pub async fn get_backlinks(&self, card_id: Uuid) -> Result<Vec<Card>> {
-        self.db.get_backlinks(card_id).await
+    /// Link cards whose content already mentions `[[zettel_id]]` of a card that was just
+    /// created or renamed, so links written before their target existed resolve
+    async fn link_waiting_references(&self, card: &Card) -> Result<()> {
+        for referencing in self
+            .db
+            .find_cards_referencing_zettel_id(&card.zettel_id)
+            .await?
+        {
+            let mut links = self.links_with_origin(referencing.id).await?;
+            if referencing.id == card.id
+                || links.iter().any(|(id, _)| *id == card.id)
+                || !wiki_links(&referencing.content).contains(&card.zettel_id)
+            {
+                continue;
+            }
+
+            links.push((card.id, LinkOrigin::Inline));
+            self.db.set_links(referencing.id, &links).await?;
+            debug!(
+                card_id = %referencing.id,
+                target_zettel_id = %card.zettel_id,
+                "Resolved inline link to new card"
+            );
+        }
+        Ok(())
     }
 
     pub async fn resolve_zettel_ids_to_uuids(&self, zettel_ids: &[String]) -> Result<Vec<Uuid>> {
@@ -394,10 +1053,135 @@ impl CardService {
     }
 }
 
+/// Explicit links followed by inline links that aren't already among them
+fn merge_links(explicit_links: Vec<Uuid>, inline_links: &[Uuid]) -> Vec<(Uuid, LinkOrigin)> {
+    let mut links: Vec<(Uuid, LinkOrigin)> = explicit_links
+        .into_iter()
+        .map(|id| (id, LinkOrigin::Explicit))
+        .collect();
+    for id in inline_links {
+        if !links.iter().any(|(linked, _)| linked == id) {
+            links.push((*id, LinkOrigin::Inline));
+        }
+    }
+    links
+}
+
+// Helper functions for smart card ordering
+
+/// The cards with each moved after any of its prerequisites in the list, otherwise in
+/// their given order. Prerequisite cycles are broken where they are first reached.
+pub fn order_prerequisites_first(
+    cards: Vec<Card>,
+    prerequisites: &HashMap<Uuid, Vec<Uuid>>,
+) -> Vec<Card> {
+    let positions: HashMap<Uuid, usize> = cards
+        .iter()
+        .enumerate()
+        .map(|(index, card)| (card.id, index))
+        .collect();
+    let no_prerequisites = Vec::new();
+
+    // Depth-first with an explicit stack of (card index, next prerequisite to visit), so
+    // a long prerequisite chain can't overflow the call stack
+    let mut visited = vec![false; cards.len()];
+    let mut order = Vec::with_capacity(cards.len());
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for start in 0..cards.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        stack.push((start, 0));
+        while let Some(top) = stack.last_mut() {
+            let (index, next) = *top;
+            top.1 += 1;
+            let card_prerequisites = prerequisites
+                .get(&cards[index].id)
+                .unwrap_or(&no_prerequisites);
+            match card_prerequisites.get(next) {
+                Some(id) => {
+                    if let Some(&position) = positions.get(id)
+                        && !visited[position]
+                    {
+                        visited[position] = true;
+                        stack.push((position, 0));
+                    }
+                }
+                None => {
+                    order.push(index);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    let mut cards: Vec<Option<Card>> = cards.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|index| cards[index].take())
+        .collect()
+}
+
+/// Stable hash tying banked questions to the exact card content they were generated from
+pub fn content_hash(content: &str) -> String {
+    // The same stable FNV-1a hash used for LLM fixture keys
+    crate::llm_fixtures::prompt_hash(None, content)
+}
+
+pub fn calculate_overdue_ratio(card: &Card, now: DateTime<Utc>) -> f64 {
+    if card.next_review <= now {
+        let overdue_duration = now.signed_duration_since(card.next_review).num_hours() as f64;
+        let total_interval = if let Some(last_reviewed) = card.last_reviewed {
+            card.next_review
+                .signed_duration_since(last_reviewed)
+                .num_hours() as f64
+        } else {
+            24.0 // Default to 1 day for new cards
+        };
+
+        if total_interval > 0.0 {
+            overdue_duration / total_interval
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    }
+}
+
+pub fn get_content_length_bucket(length: usize) -> u8 {
+    match length {
+        0..=100 => 0,    // Very short
+        101..=300 => 1,  // Short
+        301..=600 => 2,  // Medium
+        601..=1200 => 3, // Long
+        _ => 4,          // Very long
+    }
+}
+
+pub fn get_difficulty_bucket(difficulty: f64) -> u8 {
+    match difficulty {
+        d if d < 2.0 => 0, // Easy
+        d if d < 4.0 => 1, // Medium
+        d if d < 6.0 => 2, // Hard
+        _ => 3,            // Very hard
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::database::Database;
+    use std::collections::HashMap;
+
+    /// The card's linked card ids
+    fn parse_links(card: &Card) -> Vec<Uuid> {
+        card.links
+            .as_deref()
+            .and_then(|links| serde_json::from_str(links).ok())
+            .unwrap_or_default()
+    }
 
     async fn create_test_service() -> CardService {
         let db = Database::new("sqlite::memory:").await.unwrap();
@@ -679,6 +1463,222 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn create_generated_question(text: &str) -> QuizQuestion {
+        QuizQuestion {
+            question: text.to_string(),
+            question_type: "short_answer".to_string(),
+            options: None,
+            correct_answer: Some("answer".to_string()),
+            prompt_version: Some("quiz_generation@v1".to_string()),
+            rubric: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flagged_questions_are_skipped_and_pinned_ones_kept() {
+        let service = create_test_service().await;
+        let card = service
+            .create_card(CreateCardRequest {
+                zettel_id: "BANK-003".to_string(),
+                title: None,
+                content: "Feedback content".to_string(),
+                topic_ids: vec![],
+                links: None,
+            })
+            .await
+            .unwrap();
+        service
+            .bank_questions(
+                &card,
+                &[
+                    create_generated_question("Ambiguous"),
+                    create_generated_question("Keeper"),
+                    create_generated_question("Ordinary"),
+                ],
+            )
+            .await
+            .unwrap();
+        let ids: HashMap<String, Uuid> = service
+            .list_card_questions(&card)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|b| (b.question.question, b.id))
+            .collect();
+
+        let flagged = service
+            .flag_question(ids["Ambiguous"], "Two answers are correct")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            flagged.flag_reason.as_deref(),
+            Some("Two answers are correct")
+        );
+        service
+            .set_question_pinned(ids["Keeper"], true)
+            .await
+            .unwrap();
+
+        let served = service.get_banked_questions(&card, 10).await.unwrap();
+        let texts: Vec<_> = served.iter().map(|q| q.question.as_str()).collect();
+        assert_eq!(texts, vec!["Keeper", "Ordinary"]);
+
+        // Only the pinned question survives a content edit
+        let card = service
+            .update_card(
+                card.id,
+                UpdateCardRequest {
+                    zettel_id: None,
+                    title: None,
+                    content: Some("Rewritten feedback content".to_string()),
+                    topic_ids: None,
+                    links: None,
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let served = service.get_banked_questions(&card, 10).await.unwrap();
+        assert_eq!(served.len(), 1);
+        assert_eq!(served[0].question, "Keeper");
+
+        // Edits only replace the fields that were given
+        let edited = service
+            .update_question(
+                ids["Keeper"],
+                UpdateQuestionRequest {
+                    question: None,
+                    options: None,
+                    correct_answer: Some("Corrected".to_string()),
+                    rubric: None,
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(edited.question.question, "Keeper");
+        assert_eq!(edited.question.correct_answer.as_deref(), Some("Corrected"));
+        assert!(edited.edited_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_question_bank_rotates_away_from_last_session() {
+        let service = create_test_service().await;
+        let card = service
+            .create_card(CreateCardRequest {
+                zettel_id: "BANK-002".to_string(),
+                title: None,
+                content: "Rotation content".to_string(),
+                topic_ids: vec![],
+                links: None,
+            })
+            .await
+            .unwrap();
+
+        let mut multiple_choice = create_generated_question("Pick one");
+        multiple_choice.question_type = "multiple_choice".to_string();
+        service
+            .bank_questions(
+                &card,
+                &[
+                    create_generated_question("Asked last time"),
+                    create_generated_question("Short one"),
+                    create_generated_question("Short two"),
+                    multiple_choice,
+                ],
+            )
+            .await
+            .unwrap();
+        service
+            .record_question_use(&card, &[create_generated_question("Asked last time")])
+            .await
+            .unwrap();
+
+        // Last session's question is skipped and both question types are represented
+        let served = service.get_banked_questions(&card, 2).await.unwrap();
+        let texts: Vec<_> = served.iter().map(|q| q.question.as_str()).collect();
+        assert!(!texts.contains(&"Asked last time"));
+        assert!(texts.contains(&"Pick one"));
+
+        // It still fills the session when nothing else is left
+        let served = service.get_banked_questions(&card, 4).await.unwrap();
+        assert_eq!(served.len(), 4);
+        assert_eq!(served[3].question, "Asked last time");
+    }
+
+    #[tokio::test]
+    async fn test_question_bank_reuse_and_content_invalidation() {
+        let service = create_test_service().await;
+        let card = service
+            .create_card(CreateCardRequest {
+                zettel_id: "BANK-001".to_string(),
+                title: None,
+                content: "Original content".to_string(),
+                topic_ids: vec![],
+                links: None,
+            })
+            .await
+            .unwrap();
+
+        let mut local_fallback = create_generated_question("Local question");
+        local_fallback.prompt_version = None;
+        service
+            .bank_questions(
+                &card,
+                &[
+                    create_generated_question("First"),
+                    create_generated_question("Second"),
+                    local_fallback,
+                ],
+            )
+            .await
+            .unwrap();
+
+        // Local fallback questions are not banked
+        let banked = service.get_banked_questions(&card, 10).await.unwrap();
+        assert_eq!(banked.len(), 2);
+
+        // The least used question is served first
+        service
+            .record_question_use(&card, &[create_generated_question("First")])
+            .await
+            .unwrap();
+        let banked = service.get_banked_questions(&card, 1).await.unwrap();
+        assert_eq!(banked[0].question, "Second");
+
+        // A title-only edit keeps the bank; a content edit invalidates it
+        let update = |content: Option<&str>| UpdateCardRequest {
+            zettel_id: None,
+            title: Some("New title".to_string()),
+            content: content.map(str::to_string),
+            topic_ids: None,
+            links: None,
+        };
+        let card = service
+            .update_card(card.id, update(None))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            service.get_banked_questions(&card, 10).await.unwrap().len(),
+            2
+        );
+
+        let card = service
+            .update_card(card.id, update(Some("Rewritten content")))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            service
+                .get_banked_questions(&card, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
     #[tokio::test]
     async fn test_card_service_nonexistent_operations() {
         let service = create_test_service().await;
@@ -711,46 +1711,397 @@ mod tests {
         let linked = service.get_linked_cards(fake_id).await.unwrap();
         assert_eq!(linked.len(), 0);
     }
-}
 
-// Helper functions for smart card ordering
+    fn card_request(zettel_id: &str, content: &str) -> CreateCardRequest {
+        CreateCardRequest {
+            zettel_id: zettel_id.to_string(),
+            title: None,
+            content: content.to_string(),
+            topic_ids: vec![],
+            links: None,
+        }
+    }
 
-pub fn calculate_overdue_ratio(card: &Card, now: DateTime<Utc>) -> f64 {
-    if card.next_review <= now {
-        let overdue_duration = now.signed_duration_since(card.next_review).num_hours() as f64;
-        let total_interval = if let Some(last_reviewed) = card.last_reviewed {
-            card.next_review
-                .signed_duration_since(last_reviewed)
-                .num_hours() as f64
-        } else {
-            24.0 // Default to 1 day for new cards
-        };
+    #[tokio::test]
+    async fn test_inline_links_follow_content() {
+        let service = create_test_service().await;
+        let force = service
+            .create_card(card_request("PHYS-001", "F = ma"))
+            .await
+            .unwrap();
+        let energy = service
+            .create_card(card_request("PHYS-003", "E = mc^2"))
+            .await
+            .unwrap();
 
-        if total_interval > 0.0 {
-            overdue_duration / total_interval
-        } else {
-            0.0
-        }
-    } else {
-        0.0
+        // Inline links join explicit ones, and a missing target is reported
+        let momentum = service
+            .create_card(CreateCardRequest {
+                links: Some(vec![energy.id]),
+                ..card_request("PHYS-002", "See [[PHYS-001|Newton]] and [[PHYS-009]]")
+            })
+            .await
+            .unwrap();
+        assert_eq!(parse_links(&momentum), vec![energy.id, force.id]);
+        assert_eq!(
+            service
+                .unresolved_inline_links(&momentum.content)
+                .await
+                .unwrap(),
+            vec!["PHYS-009"]
+        );
+        assert_eq!(service.get_backlinks(force.id).await.unwrap().len(), 1);
+
+        // Removing the inline link drops it, but the explicit link stays
+        let updated = service
+            .update_card(
+                momentum.id,
+                UpdateCardRequest {
+                    zettel_id: None,
+                    title: None,
+                    content: Some("Momentum is conserved".to_string()),
+                    topic_ids: None,
+                    links: None,
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(parse_links(&updated), vec![energy.id]);
+        assert!(service.get_backlinks(force.id).await.unwrap().is_empty());
+        assert_eq!(service.get_backlinks(energy.id).await.unwrap().len(), 1);
     }
-}
 
-pub fn get_content_length_bucket(length: usize) -> u8 {
-    match length {
-        0..=100 => 0,    // Very short
-        101..=300 => 1,  // Short
-        301..=600 => 2,  // Medium
-        601..=1200 => 3, // Long
-        _ => 4,          // Very long
+    #[tokio::test]
+    async fn test_inline_links_resolve_when_target_appears() {
+        let service = create_test_service().await;
+        let early = service
+            .create_card(card_request("BIO-001", "Osmosis is a kind of [[BIO-002]]"))
+            .await
+            .unwrap();
+        assert_eq!(parse_links(&early), Vec::<Uuid>::new());
+
+        let diffusion = service
+            .create_card(card_request("BIO-002", "Particles spread out"))
+            .await
+            .unwrap();
+        let early = service.get_card(early.id).await.unwrap().unwrap();
+        assert_eq!(parse_links(&early), vec![diffusion.id]);
+        let backlinks = service.get_backlinks(diffusion.id).await.unwrap();
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].id, early.id);
+
+        // A rename that makes another card's link resolve links it too
+        let waiting = service
+            .create_card(card_request("BIO-004", "Compare [[BIO-003]]"))
+            .await
+            .unwrap();
+        let renamed = service
+            .update_card(
+                diffusion.id,
+                UpdateCardRequest {
+                    zettel_id: Some("BIO-003".to_string()),
+                    title: None,
+                    content: None,
+                    topic_ids: None,
+                    links: None,
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let waiting = service.get_card(waiting.id).await.unwrap().unwrap();
+        assert_eq!(parse_links(&waiting), vec![renamed.id]);
     }
-}
 
-pub fn get_difficulty_bucket(difficulty: f64) -> u8 {
-    match difficulty {
-        d if d < 2.0 => 0, // Easy
-        d if d < 4.0 => 1, // Medium
-        d if d < 6.0 => 2, // Hard
-        _ => 3,            // Very hard
+    #[tokio::test]
+    async fn test_failed_create_leaves_no_card() {
+        let service = create_test_service().await;
+        let force = service
+            .create_card(card_request("PHYS-001", "F = ma"))
+            .await
+            .unwrap();
+
+        // The topic doesn't exist, so the write fails after the card row is inserted
+        let result = service
+            .create_card(CreateCardRequest {
+                topic_ids: vec![Uuid::new_v4()],
+                links: Some(vec![force.id]),
+                ..card_request("PHYS-002", "Momentum")
+            })
+            .await;
+        assert!(result.is_err());
+        assert!(
+            service
+                .get_card_by_zettel_id("PHYS-002")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(service.get_backlinks(force.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_explicit_links_outlive_their_mentions() {
+        let service = create_test_service().await;
+        let force = service
+            .create_card(card_request("PHYS-001", "F = ma"))
+            .await
+            .unwrap();
+        let energy = service
+            .create_card(card_request("PHYS-003", "E = mc^2"))
+            .await
+            .unwrap();
+
+        // The explicit link to PHYS-001 is also mentioned, PHYS-003 only inline
+        let momentum = service
+            .create_card(CreateCardRequest {
+                links: Some(vec![force.id]),
+                ..card_request("PHYS-002", "From [[PHYS-001]] and [[PHYS-003]]")
+            })
+            .await
+            .unwrap();
+        let origins: Vec<_> = service
+            .db
+            .get_links_from(momentum.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|link| (link.target_card_id, link.origin))
+            .collect();
+        assert_eq!(
+            origins,
+            vec![
+                (force.id, LinkOrigin::Explicit),
+                (energy.id, LinkOrigin::Inline)
+            ]
+        );
+
+        // A later edit that drops both mentions keeps only the explicit link
+        let updated = service
+            .update_card(
+                momentum.id,
+                UpdateCardRequest {
+                    zettel_id: None,
+                    title: None,
+                    content: Some("Momentum is conserved".to_string()),
+                    topic_ids: None,
+                    links: None,
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(parse_links(&updated), vec![force.id]);
+        assert_eq!(service.get_backlinks(force.id).await.unwrap().len(), 1);
+        assert!(service.get_backlinks(energy.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rename_zettel_id_rewrites_references() {
+        let service = create_test_service().await;
+        let force = service
+            .create_card(card_request("PHYS-1", "F = ma, see [[PHYS-1#Units]]"))
+            .await
+            .unwrap();
+        let momentum = service
+            .create_card(card_request(
+                "PHYS-2",
+                "From [[PHYS-1|Newton]] and [[PHYS-1]], not PHYS-1 or [[PHYS-10]]",
+            ))
+            .await
+            .unwrap();
+        service
+            .create_card(card_request("PHYS-3", "Mentions PHYS-1 in passing"))
+            .await
+            .unwrap();
+
+        let preview = service
+            .rename_zettel_id(force.id, "MECH-1", true)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut rewritten: Vec<(String, usize)> = preview
+            .rewritten
+            .iter()
+            .map(|r| (r.zettel_id.clone(), r.occurrences))
+            .collect();
+        rewritten.sort();
+        assert_eq!(
+            rewritten,
+            vec![("PHYS-1".to_string(), 1), ("PHYS-2".to_string(), 2)]
+        );
+        let unchanged = service.get_card(momentum.id).await.unwrap().unwrap();
+        assert_eq!(unchanged.content, momentum.content);
+        assert!(
+            service
+                .get_card_by_zettel_id("MECH-1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let report = service
+            .rename_zettel_id(force.id, "MECH-1", false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.rewritten.len(), 2);
+        let momentum = service.get_card(momentum.id).await.unwrap().unwrap();
+        assert_eq!(
+            momentum.content,
+            "From [[MECH-1|Newton]] and [[MECH-1]], not PHYS-1 or [[PHYS-10]]"
+        );
+        let force = service.get_card(force.id).await.unwrap().unwrap();
+        assert_eq!(force.zettel_id, "MECH-1");
+        assert_eq!(force.content, "F = ma, see [[MECH-1#Units]]");
+
+        let taken = service.rename_zettel_id(force.id, "PHYS-2", false).await;
+        assert!(taken.unwrap_err().to_string().contains("already exists"));
+    }
+
+    #[tokio::test]
+    async fn test_typed_links_keep_their_relation() {
+        let service = create_test_service().await;
+        let calculus = service
+            .create_card(card_request("MATH-1", "Derivatives"))
+            .await
+            .unwrap();
+        service
+            .create_card(card_request("MATH-2", "Limits"))
+            .await
+            .unwrap();
+        let physics = service
+            .create_card_with_zettel_links(CreateCardWithZettelLinksRequest {
+                zettel_id: "PHYS-1".to_string(),
+                title: None,
+                content: "Velocity".to_string(),
+                topic_ids: vec![],
+                zettel_links: Some(vec!["MATH-2".to_string()]),
+                typed_links: Some(vec![TypedZettelLink {
+                    zettel_id: "MATH-1".to_string(),
+                    relation: LinkRelation::Prerequisite,
+                    note: Some("  Rates of change  ".to_string()),
+                }]),
+            })
+            .await
+            .unwrap();
+
+        let links = service.get_typed_links(physics.id).await.unwrap();
+        let summary: Vec<(&str, LinkRelation, Option<&str>)> = links
+            .iter()
+            .map(|link| {
+                (
+                    link.card.zettel_id.as_str(),
+                    link.relation,
+                    link.note.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("MATH-2", LinkRelation::SeeAlso, None),
+                (
+                    "MATH-1",
+                    LinkRelation::Prerequisite,
+                    Some("Rates of change")
+                ),
+            ]
+        );
+
+        // Plain zettel_links keep the relation of links that stay
+        let update = UpdateCardWithZettelLinksRequest {
+            zettel_id: None,
+            title: None,
+            content: None,
+            topic_ids: None,
+            zettel_links: Some(vec!["MATH-1".to_string()]),
+            typed_links: None,
+        };
+        service
+            .update_card_with_zettel_links(physics.id, update)
+            .await
+            .unwrap();
+        let backlinks = service.get_typed_backlinks(calculus.id).await.unwrap();
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].relation, LinkRelation::Prerequisite);
+        assert_eq!(service.get_typed_links(physics.id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_prerequisite_aware_due_ordering() {
+        let service = create_test_service().await;
+        let base = service
+            .create_card(card_request("BASE", &"Limits ".repeat(100)))
+            .await
+            .unwrap();
+        let dependent = service
+            .create_card_with_zettel_links(CreateCardWithZettelLinksRequest {
+                zettel_id: "DEP".to_string(),
+                title: None,
+                content: "Derivatives".to_string(),
+                topic_ids: vec![],
+                zettel_links: None,
+                typed_links: Some(vec![TypedZettelLink {
+                    zettel_id: "BASE".to_string(),
+                    relation: LinkRelation::Prerequisite,
+                    note: None,
+                }]),
+            })
+            .await
+            .unwrap();
+        service
+            .create_card(card_request("OTHER", "Vectors"))
+            .await
+            .unwrap();
+
+        // The long prerequisite would otherwise come last
+        let due = service.get_cards_due_optimized().await.unwrap();
+        let zettel_ids: Vec<&str> = due.iter().map(|card| card.zettel_id.as_str()).collect();
+        assert_eq!(zettel_ids, vec!["BASE", "DEP", "OTHER"]);
+
+        let cycle = HashMap::from([(base.id, vec![dependent.id]), (dependent.id, vec![base.id])]);
+        assert_eq!(order_prerequisites_first(due.clone(), &cycle).len(), 3);
+
+        // A chain far deeper than the call stack allows is ordered in reverse
+        let chain: Vec<Card> = (0..100_000)
+            .map(|_| Card {
+                id: Uuid::new_v4(),
+                ..base.clone()
+            })
+            .collect();
+        let chain_prerequisites: HashMap<Uuid, Vec<Uuid>> = chain
+            .windows(2)
+            .map(|pair| (pair[0].id, vec![pair[1].id]))
+            .collect();
+        let ordered = order_prerequisites_first(chain.clone(), &chain_prerequisites);
+        assert!(
+            ordered
+                .iter()
+                .rev()
+                .map(|card| card.id)
+                .eq(chain.iter().map(|card| card.id))
+        );
+
+        // A never-reviewed prerequisite is a weak foundation
+        let warnings = service.weak_foundations(&due).await.unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].zettel_id, "DEP");
+        assert_eq!(warnings[0].prerequisites[0].zettel_id, "BASE");
+
+        let mut base = service.get_card(base.id).await.unwrap().unwrap();
+        base.last_reviewed = Some(Utc::now());
+        base.stability = 10.0;
+        base.state = "Relearning".to_string();
+        service.db.update_card_after_review(&base).await.unwrap();
+        assert!(service.weak_foundations(&due).await.unwrap().is_empty());
+
+        // Dependents of a relearning card wait when postponement is on
+        assert_eq!(service.get_cards_due_optimized().await.unwrap().len(), 3);
+        let postponing = service.clone().with_lapsed_dependent_postponement(true);
+        let due = postponing.get_cards_due_optimized().await.unwrap();
+        let zettel_ids: Vec<&str> = due.iter().map(|card| card.zettel_id.as_str()).collect();
+        assert_eq!(zettel_ids, vec!["OTHER", "BASE"]);
     }
 }
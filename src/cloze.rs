@@ -0,0 +1,239 @@
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::sync::LazyLock;
+
+use crate::answer_text::normalize_answer;
+use crate::llm_service::GradingResult;
+use crate::models::QuizQuestion;
+
+pub const CLOZE_QUESTION_TYPE: &str = "cloze";
+
+/// Recorded as `served_by` for answers graded without an LLM
+pub const LOCAL_CLOZE_GRADER: &str = "local:cloze";
+
+// Separates the answers to a question with several blanks
+const ANSWER_SEPARATOR: &str = "; ";
+
+// {{c1::answer}} or {{c1::answer::hint}}
+static CLOZE_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{c(\d+)::(.*?)(?:::(.*?))?\}\}").expect("cloze pattern is valid")
+});
+
+/// One `{{cN::answer::hint}}` deletion in card content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClozeDeletion {
+    pub index: u32,
+    pub answer: String,
+    pub hint: Option<String>,
+}
+
+pub fn is_cloze(content: &str) -> bool {
+    CLOZE_PATTERN.is_match(content)
+}
+
+pub fn parse_deletions(content: &str) -> Vec<ClozeDeletion> {
+    CLOZE_PATTERN
+        .captures_iter(content)
+        .filter_map(|caps| {
+            Some(ClozeDeletion {
+                index: caps[1].parse().ok()?,
+                answer: caps[2].trim().to_string(),
+                hint: caps
+                    .get(3)
+                    .map(|hint| hint.as_str().trim().to_string())
+                    .filter(|hint| !hint.is_empty()),
+            })
+        })
+        .collect()
+}
+
+/// Card content with every deletion replaced by its answer, for display and prompts
+pub fn reveal(content: &str) -> Cow<'_, str> {
+    CLOZE_PATTERN.replace_all(content, "$2")
+}
+
+/// One fill-in-the-blank question per cloze index. Deletions with that index become
+/// blanks (showing their hint if any) and all other deletions are filled in.
+pub fn generate_cloze_questions(content: &str) -> Vec<QuizQuestion> {
+    let indices: BTreeSet<u32> = parse_deletions(content).iter().map(|d| d.index).collect();
+
+    indices
+        .into_iter()
+        .map(|index| {
+            let mut answers = Vec::new();
+            let text = CLOZE_PATTERN.replace_all(content, |caps: &regex::Captures| {
+                let deletion_index: u32 = caps[1].parse().unwrap_or(0);
+                let answer = caps[2].trim();
+                if deletion_index != index {
+                    return answer.to_string();
+                }
+
+                answers.push(answer.to_string());
+                match caps.get(3).map(|hint| hint.as_str().trim()) {
+                    Some(hint) if !hint.is_empty() => format!("[{}]", hint),
+                    _ => "[...]".to_string(),
+                }
+            });
+
+            QuizQuestion {
+                question: text.into_owned(),
+                question_type: CLOZE_QUESTION_TYPE.to_string(),
+                options: None,
+                correct_answer: Some(answers.join(ANSWER_SEPARATOR)),
+                prompt_version: None,
+                rubric: None,
+            }
+        })
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+enum BlankMatch {
+    Exact,
+    Typo, // One character off on a longer answer
+    Wrong,
+}
+
+fn match_blank(expected: &str, given: &str) -> BlankMatch {
+    let expected = normalize_answer(expected);
+    let given = normalize_answer(given);
+    if expected == given {
+        BlankMatch::Exact
+    } else if expected.chars().count() >= 5 && edit_distance(&expected, &given) <= 1 {
+        BlankMatch::Typo
+    } else {
+        BlankMatch::Wrong
+    }
+}
+
+/// Grade an answer to a cloze question by normalized comparison. Questions with several
+/// blanks take their answers in order, separated by semicolons or new lines. Commas are
+/// kept, since answers such as "1,000" or "Paris, France" contain them.
+pub fn grade_cloze_answer(question: &QuizQuestion, user_answer: &str) -> GradingResult {
+    let expected_answer = question.correct_answer.as_deref().unwrap_or_default();
+    let expected: Vec<&str> = expected_answer.split(ANSWER_SEPARATOR).collect();
+
+    let given: Vec<&str> = if expected.len() == 1 {
+        vec![user_answer]
+    } else {
+        user_answer
+            .split([';', '\n'])
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .collect()
+    };
+
+    let matches: Vec<BlankMatch> = expected
+        .iter()
+        .enumerate()
+        .map(|(i, blank)| match given.get(i) {
+            Some(answer) => match_blank(blank, answer),
+            None => BlankMatch::Wrong,
+        })
+        .collect();
+
+    let all_correct = matches.iter().all(|m| !matches!(m, BlankMatch::Wrong));
+    let any_typo = matches.iter().any(|m| matches!(m, BlankMatch::Typo));
+
+    let (feedback, suggested_rating) = if !all_correct {
+        (format!("Incorrect. The answer is: {}", expected_answer), 1)
+    } else if any_typo {
+        (
+            format!("Correct, but check the spelling: {}", expected_answer),
+            2,
+        )
+    } else {
+        ("Correct!".to_string(), 3)
+    };
+
+    GradingResult {
+        is_correct: all_correct,
+        feedback,
+        suggested_rating,
+        served_by: Some(LOCAL_CLOZE_GRADER.to_string()),
+        prompt_version: None,
+        score: None,
+        criterion_scores: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "The {{c1::mitochondria}} is the {{c2::powerhouse::role}} of the cell, and {{c1::ribosomes}} build proteins.";
+
+    #[test]
+    fn test_one_question_per_cloze_index() {
+        let questions = generate_cloze_questions(CONTENT);
+        assert_eq!(questions.len(), 2);
+
+        assert_eq!(
+            questions[0].question,
+            "The [...] is the powerhouse of the cell, and [...] build proteins."
+        );
+        assert_eq!(
+            questions[0].correct_answer.as_deref(),
+            Some("mitochondria; ribosomes")
+        );
+        assert_eq!(
+            questions[1].question,
+            "The mitochondria is the [role] of the cell, and ribosomes build proteins."
+        );
+        assert_eq!(
+            reveal(CONTENT),
+            "The mitochondria is the powerhouse of the cell, and ribosomes build proteins."
+        );
+        assert!(!is_cloze("Plain card content"));
+    }
+
+    #[test]
+    fn test_grading_normalizes_answers() {
+        let questions = generate_cloze_questions(CONTENT);
+
+        let result = grade_cloze_answer(&questions[1], "  The Powerhouse! ");
+        assert!(result.is_correct);
+        assert_eq!(result.suggested_rating, 3);
+
+        let result = grade_cloze_answer(&questions[1], "powerhose");
+        assert!(result.is_correct);
+        assert_eq!(result.suggested_rating, 2);
+
+        let result = grade_cloze_answer(&questions[0], "Mitochondria\nribosomes");
+        assert!(result.is_correct);
+
+        let result = grade_cloze_answer(&questions[0], "ribosomes; mitochondria");
+        assert!(!result.is_correct);
+        assert_eq!(result.suggested_rating, 1);
+        assert!(result.feedback.contains("mitochondria; ribosomes"));
+    }
+
+    #[test]
+    fn test_blank_answers_may_contain_commas() {
+        let content = "{{c1::Paris, France}} has {{c1::2,100,000}} residents.";
+        let question = generate_cloze_questions(content).remove(0);
+
+        let result = grade_cloze_answer(&question, "Paris, France; 2,100,000");
+        assert!(result.is_correct);
+        assert_eq!(result.suggested_rating, 3);
+
+        let result = grade_cloze_answer(&question, "Paris, 2,100,000");
+        assert!(!result.is_correct);
+    }
+}
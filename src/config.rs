@@ -3,7 +3,8 @@ use serde::Deserialize;
 use std::env;
 use tracing::{info, warn};
 
-use crate::llm_providers::LLMProviderType;
+use crate::llm_fixtures::FixtureMode;
+use crate::llm_providers::{LLMProviderConfig, LLMProviderType};
 
 // Import logging macros
 use crate::{log_system_event, log_validation};
@@ -15,6 +16,8 @@ pub struct Config {
     pub llm: LLMConfig,
     pub server: ServerConfig,
     pub logging: LoggingConfig,
+    pub pregeneration: PreGenerationConfig,
+    pub scheduling: SchedulingConfig,
 }
 
 /// Database connection configuration
@@ -30,6 +33,13 @@ pub struct LLMConfig {
     pub base_url: Option<String>,
     pub provider: LLMProviderType,
     pub model: Option<String>,
+    pub fallbacks: Vec<LLMProviderConfig>, // Tried in order when the primary provider fails
+    pub monthly_budget_usd: Option<f64>,   // LLM calls are refused once this is spent
+    pub prompt_templates_dir: Option<String>, // Overrides the built-in prompt templates
+    pub fixture_mode: Option<FixtureMode>, // Record or replay LLM responses as fixtures
+    pub fixture_dir: String,
+    pub rating_calibration: bool, // Adjust suggested ratings by how the user overrides them
+    pub linked_context: bool,     // Include linked cards and backlinks when generating questions
 }
 
 /// HTTP server configuration
@@ -39,6 +49,24 @@ pub struct ServerConfig {
     pub host: String,
 }
 
+/// Background question pre-generation and cache configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct PreGenerationConfig {
+    pub enabled: bool,
+    pub lookahead_hours: i64, // Pre-generate for cards due within this window
+    pub interval_secs: u64,
+    pub batch_size: usize,
+    pub cache_ttl_minutes: i64,
+    pub cache_max_size: usize,
+}
+
+/// Review ordering around prerequisite links
+#[derive(Debug, Clone, Deserialize)]
+pub struct SchedulingConfig {
+    pub postpone_lapsed_dependents: bool, // Hold back cards whose prerequisites are relearning
+    pub weak_foundation_threshold: f64,   // Warn when a prerequisite's retrievability is below this
+}
+
 /// Logging system configuration
 #[derive(Debug, Clone, Deserialize)]
 pub struct LoggingConfig {
@@ -60,12 +88,16 @@ impl Config {
         let llm_config = LLMConfig::from_env()?;
         let server_config = ServerConfig::from_env()?;
         let logging_config = LoggingConfig::from_env()?;
+        let pregeneration_config = PreGenerationConfig::from_env()?;
+        let scheduling_config = SchedulingConfig::from_env()?;
 
         let config = Config {
             database: database_config,
             llm: llm_config,
             server: server_config,
             logging: logging_config,
+            pregeneration: pregeneration_config,
+            scheduling: scheduling_config,
         };
 
         log_system_event!(config, "Configuration loaded successfully");
@@ -80,6 +112,14 @@ impl Config {
             database_url_masked = %mask_sensitive_data(&self.database.url),
             llm_provider = ?self.llm.provider,
             llm_model = ?self.llm.model,
+            llm_fallback_count = self.llm.fallbacks.len(),
+            llm_monthly_budget_usd = ?self.llm.monthly_budget_usd,
+            prompt_templates_dir = ?self.llm.prompt_templates_dir,
+            rating_calibration = self.llm.rating_calibration,
+            linked_context = self.llm.linked_context,
+            pregeneration_enabled = self.pregeneration.enabled,
+            pregeneration_lookahead_hours = self.pregeneration.lookahead_hours,
+            postpone_lapsed_dependents = self.scheduling.postpone_lapsed_dependents,
             server_address = %format!("{}:{}", self.server.host, self.server.port),
             log_level = %self.logging.level,
             "Configuration summary"
@@ -100,6 +140,12 @@ impl Config {
             return Err(anyhow!("Server port must be greater than 0"));
         }
 
+        if !(0.0..=1.0).contains(&self.scheduling.weak_foundation_threshold) {
+            return Err(anyhow!(
+                "SCHEDULING_WEAK_FOUNDATION_THRESHOLD must be between 0 and 1"
+            ));
+        }
+
         // Validate LLM API key presence
         if self.llm.api_key.is_empty() || self.llm.api_key == "your-api-key" {
             warn!("LLM API key appears to be placeholder or empty - LLM features may not work");
@@ -141,27 +187,136 @@ impl LLMConfig {
 
         let provider_str = env::var("LLM_PROVIDER").unwrap_or_else(|_| "openai".to_string());
 
-        let provider = match provider_str.to_lowercase().as_str() {
-            "gemini" | "google" => LLMProviderType::Gemini,
-            "openai" | "chatgpt" | "gpt" => LLMProviderType::OpenAI,
-            _ => {
-                info!(
-                    "Unknown LLM provider '{}', defaulting to OpenAI",
-                    provider_str
-                );
-                LLMProviderType::OpenAI
+        let provider = LLMProviderType::from_name(&provider_str).unwrap_or_else(|| {
+            info!(
+                "Unknown LLM provider '{}', defaulting to OpenAI",
+                provider_str
+            );
+            LLMProviderType::OpenAI
+        });
+
+        let model = env::var("LLM_MODEL").ok();
+
+        let fallbacks = match env::var("LLM_FALLBACK_PROVIDERS") {
+            Ok(spec) => parse_fallback_providers(&spec, |name| env::var(name).ok(), &api_key)?,
+            Err(_) => Vec::new(),
+        };
+
+        let monthly_budget_usd = match env::var("LLM_MONTHLY_BUDGET_USD") {
+            Ok(value) => Some(parse_budget(&value)?),
+            Err(_) => None,
+        };
+
+        let prompt_templates_dir = env::var("PROMPT_TEMPLATES_DIR")
+            .ok()
+            .filter(|dir| !dir.trim().is_empty());
+
+        let fixture_mode = match env::var("LLM_FIXTURE_MODE") {
+            Ok(value) if !value.trim().is_empty() => {
+                Some(FixtureMode::from_name(&value).ok_or_else(|| {
+                    anyhow!(
+                        "Invalid LLM_FIXTURE_MODE '{}': expected 'record' or 'replay'",
+                        value
+                    )
+                })?)
             }
+            _ => None,
         };
 
-        let model = env::var("LLM_MODEL").ok();
+        let fixture_dir =
+            env::var("LLM_FIXTURE_DIR").unwrap_or_else(|_| "tests/fixtures/llm".to_string());
+
+        let rating_calibration = env::var("LLM_RATING_CALIBRATION")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .unwrap_or(false);
+
+        let linked_context = env::var("LLM_LINKED_CONTEXT")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .unwrap_or(false);
 
         Ok(LLMConfig {
             api_key,
             base_url,
             provider,
             model,
+            fallbacks,
+            monthly_budget_usd,
+            prompt_templates_dir,
+            fixture_mode,
+            fixture_dir,
+            rating_calibration,
+            linked_context,
         })
     }
+
+    /// Full failover chain: the primary provider followed by any fallbacks
+    pub fn provider_chain(&self) -> Vec<LLMProviderConfig> {
+        let mut chain = vec![LLMProviderConfig {
+            provider: self.provider,
+            api_key: self.api_key.clone(),
+            base_url: self.base_url.clone(),
+            model: self.model.clone(),
+        }];
+        chain.extend(self.fallbacks.iter().cloned());
+        chain
+    }
+}
+
+/// Parse `LLM_FALLBACK_PROVIDERS`, a comma-separated list of `provider[:model]` entries.
+///
+/// Each fallback reads its key from `LLM_API_KEY_<PROVIDER>` (falling back to the primary
+/// key) and an optional base URL from `LLM_BASE_URL_<PROVIDER>`.
+fn parse_fallback_providers(
+    spec: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    default_api_key: &str,
+) -> Result<Vec<LLMProviderConfig>> {
+    let mut fallbacks = Vec::new();
+
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, model) = match entry.split_once(':') {
+            Some((name, model)) if !model.trim().is_empty() => {
+                (name.trim(), Some(model.trim().to_string()))
+            }
+            Some((name, _)) => (name.trim(), None),
+            None => (entry, None),
+        };
+
+        let provider = LLMProviderType::from_name(name).ok_or_else(|| {
+            anyhow!(
+                "Invalid LLM_FALLBACK_PROVIDERS entry '{}': unknown provider '{}'",
+                entry,
+                name
+            )
+        })?;
+
+        let suffix = match provider {
+            LLMProviderType::OpenAI => "OPENAI",
+            LLMProviderType::Gemini => "GEMINI",
+        };
+
+        fallbacks.push(LLMProviderConfig {
+            provider,
+            api_key: lookup(&format!("LLM_API_KEY_{}", suffix))
+                .unwrap_or_else(|| default_api_key.to_string()),
+            base_url: lookup(&format!("LLM_BASE_URL_{}", suffix)),
+            model,
+        });
+    }
+
+    Ok(fallbacks)
+}
+
+fn parse_budget(value: &str) -> Result<f64> {
+    match value.trim().parse::<f64>() {
+        Ok(budget) if budget.is_finite() && budget >= 0.0 => Ok(budget),
+        _ => Err(anyhow!(
+            "Invalid LLM_MONTHLY_BUDGET_USD value: '{}'. Must be a non-negative number",
+            value
+        )),
+    }
 }
 
 impl ServerConfig {
@@ -181,6 +336,60 @@ impl ServerConfig {
     }
 }
 
+impl PreGenerationConfig {
+    fn from_env() -> Result<Self> {
+        let enabled = env::var("PREGENERATION_ENABLED")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .unwrap_or(true);
+
+        Ok(PreGenerationConfig {
+            enabled,
+            lookahead_hours: parse_env_number("PREGENERATION_LOOKAHEAD_HOURS", 24)?,
+            interval_secs: parse_env_number("PREGENERATION_INTERVAL_SECS", 300)?,
+            batch_size: parse_env_number("PREGENERATION_BATCH_SIZE", 10)?,
+            cache_ttl_minutes: parse_env_number("QUESTION_CACHE_TTL_MINUTES", 24 * 60)?,
+            cache_max_size: parse_env_number("QUESTION_CACHE_MAX_SIZE", 1000)?,
+        })
+    }
+}
+
+impl SchedulingConfig {
+    fn from_env() -> Result<Self> {
+        let postpone_lapsed_dependents = env::var("SCHEDULING_POSTPONE_LAPSED_DEPENDENTS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .unwrap_or(false);
+
+        Ok(SchedulingConfig {
+            postpone_lapsed_dependents,
+            weak_foundation_threshold: parse_env_number(
+                "SCHEDULING_WEAK_FOUNDATION_THRESHOLD",
+                0.7,
+            )?,
+        })
+    }
+}
+
+/// Read a positive number from the environment, using `default` when it is unset
+fn parse_env_number<T>(name: &str, default: T) -> Result<T>
+where
+    T: std::str::FromStr + PartialOrd + Default,
+{
+    let Ok(value) = env::var(name) else {
+        return Ok(default);
+    };
+
+    match value.trim().parse::<T>() {
+        Ok(number) if number > T::default() => Ok(number),
+        _ => Err(anyhow!(
+            "Invalid {} value: '{}'. Must be a positive number",
+            name,
+            value
+        )),
+    }
+}
+
 impl LoggingConfig {
     fn from_env() -> Result<Self> {
         let level =
@@ -285,6 +494,75 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fallback_provider_parsing() {
+        let lookup = |name: &str| match name {
+            "LLM_API_KEY_GEMINI" => Some("gemini-key".to_string()),
+            "LLM_BASE_URL_OPENAI" => Some("http://localhost:11434/v1".to_string()),
+            _ => None,
+        };
+
+        let fallbacks =
+            parse_fallback_providers("gemini:gemini-1.5-pro, openai", lookup, "primary-key")
+                .unwrap();
+
+        assert_eq!(fallbacks.len(), 2);
+        assert_eq!(fallbacks[0].provider, LLMProviderType::Gemini);
+        assert_eq!(fallbacks[0].model.as_deref(), Some("gemini-1.5-pro"));
+        assert_eq!(fallbacks[0].api_key, "gemini-key");
+        assert_eq!(fallbacks[0].base_url, None);
+        assert_eq!(fallbacks[1].provider, LLMProviderType::OpenAI);
+        assert_eq!(fallbacks[1].model, None);
+        assert_eq!(fallbacks[1].api_key, "primary-key");
+        assert_eq!(
+            fallbacks[1].base_url.as_deref(),
+            Some("http://localhost:11434/v1")
+        );
+
+        assert!(
+            parse_fallback_providers("", lookup, "k")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(parse_fallback_providers("anthropic", lookup, "k").is_err());
+    }
+
+    #[test]
+    fn test_provider_chain_order() {
+        let config = LLMConfig {
+            api_key: "primary-key".to_string(),
+            base_url: None,
+            provider: LLMProviderType::OpenAI,
+            model: Some("gpt-4o-mini".to_string()),
+            fallbacks: vec![LLMProviderConfig {
+                provider: LLMProviderType::Gemini,
+                api_key: "gemini-key".to_string(),
+                base_url: None,
+                model: None,
+            }],
+            monthly_budget_usd: None,
+            prompt_templates_dir: None,
+            fixture_mode: None,
+            fixture_dir: "tests/fixtures/llm".to_string(),
+            rating_calibration: false,
+            linked_context: false,
+        };
+
+        let chain = config.provider_chain();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].provider, LLMProviderType::OpenAI);
+        assert_eq!(chain[0].model.as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(chain[1].provider, LLMProviderType::Gemini);
+    }
+
+    #[test]
+    fn test_monthly_budget_parsing() {
+        assert_eq!(parse_budget("25").unwrap(), 25.0);
+        assert_eq!(parse_budget(" 7.50 ").unwrap(), 7.5);
+        assert!(parse_budget("-1").is_err());
+        assert!(parse_budget("lots").is_err());
+    }
+
     #[test]
     fn test_config_validation() {
         // Test valid configuration
@@ -297,6 +575,13 @@ mod tests {
                 base_url: None,
                 provider: LLMProviderType::OpenAI,
                 model: None,
+                fallbacks: vec![],
+                monthly_budget_usd: None,
+                prompt_templates_dir: None,
+                fixture_mode: None,
+                fixture_dir: "tests/fixtures/llm".to_string(),
+                rating_calibration: false,
+                linked_context: false,
             },
             server: ServerConfig {
                 port: 3000,
@@ -308,6 +593,18 @@ mod tests {
                 console_enabled: true,
                 log_directory: "logs".to_string(),
             },
+            pregeneration: PreGenerationConfig {
+                enabled: false,
+                lookahead_hours: 24,
+                interval_secs: 300,
+                batch_size: 10,
+                cache_ttl_minutes: 60,
+                cache_max_size: 100,
+            },
+            scheduling: SchedulingConfig {
+                postpone_lapsed_dependents: false,
+                weak_foundation_threshold: 0.7,
+            },
         };
 
         assert!(config.validate().is_ok());
@@ -316,6 +613,35 @@ mod tests {
         let mut invalid_config = config.clone();
         invalid_config.server.port = 0;
         assert!(invalid_config.validate().is_err());
+
+        // Test retrievability thresholds outside 0 to 1
+        let mut invalid_config = config.clone();
+        invalid_config.scheduling.weak_foundation_threshold = 1.5;
+        assert!(invalid_config.validate().is_err());
+        invalid_config.scheduling.weak_foundation_threshold = -0.1;
+        assert!(invalid_config.validate().is_err());
+    }
+
+    #[test]
+    fn test_pregeneration_number_parsing() {
+        unsafe {
+            env::set_var("PREGENERATION_TEST_HOURS", "48");
+            env::set_var("PREGENERATION_TEST_ZERO", "0");
+        }
+        assert_eq!(
+            parse_env_number("PREGENERATION_TEST_HOURS", 24).unwrap(),
+            48
+        );
+        assert_eq!(
+            parse_env_number("PREGENERATION_TEST_UNSET", 24).unwrap(),
+            24
+        );
+        assert!(parse_env_number::<i64>("PREGENERATION_TEST_ZERO", 24).is_err());
+
+        unsafe {
+            env::remove_var("PREGENERATION_TEST_HOURS");
+            env::remove_var("PREGENERATION_TEST_ZERO");
+        }
     }
 
     #[test]
@@ -0,0 +1,480 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::card_service::CardService;
+use crate::database::NewCard;
+use crate::markdown::wiki_links;
+use crate::models::{Card, LinkOrigin, Topic};
+
+/// Which column holds each card field, by header name. Unmapped fields use the column
+/// named after the field, when there is one. Only content is required.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColumnMapping {
+    pub zettel_id: Option<String>,
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub topics: Option<String>,
+    pub links: Option<String>, // zettel_ids of linked cards
+}
+
+/// How to read and import a CSV or TSV file
+#[derive(Debug, Clone, Deserialize)]
+pub struct CsvImportOptions {
+    #[serde(default)]
+    pub mapping: ColumnMapping,
+    /// `,` or `\t`; guessed from the header line when missing
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// Separates topic names and links within a cell
+    #[serde(default = "default_list_separator")]
+    pub list_separator: char,
+    /// Rows without a zettel_id get `<prefix>-001`, `<prefix>-002`, ...
+    #[serde(default = "default_zettel_prefix")]
+    pub zettel_prefix: String,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+fn default_list_separator() -> char {
+    ';'
+}
+
+fn default_zettel_prefix() -> String {
+    "CSV".to_string()
+}
+
+impl Default for CsvImportOptions {
+    fn default() -> Self {
+        Self {
+            mapping: ColumnMapping::default(),
+            delimiter: None,
+            list_separator: default_list_separator(),
+            zettel_prefix: default_zettel_prefix(),
+            dry_run: false,
+        }
+    }
+}
+
+/// A CSV import over the API: the file's text plus the import options
+#[derive(Debug, Clone, Deserialize)]
+pub struct CsvImportRequest {
+    pub data: String,
+    #[serde(flatten)]
+    pub options: CsvImportOptions,
+}
+
+/// One data row with its fields picked out by the column mapping
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRow {
+    pub line: usize,
+    pub zettel_id: Option<String>,
+    pub title: Option<String>,
+    pub content: String,
+    pub topics: Vec<String>,
+    pub links: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RowError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// What an import did, or would do in a dry run. When any row has an error nothing is
+/// imported, and `created` lists the cards that would have been.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct CsvImportReport {
+    pub dry_run: bool,
+    pub rows: usize,
+    pub created: Vec<String>,
+    pub generated_zettel_ids: Vec<String>,
+    pub topics_created: Vec<String>,
+    pub errors: Vec<RowError>,
+}
+
+/// Tab when the header splits into more fields on tabs than on commas, otherwise comma
+fn detect_delimiter(text: &str) -> u8 {
+    let header_fields = |delimiter: u8| {
+        csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .from_reader(text.as_bytes())
+            .records()
+            .next()
+            .and_then(|record| record.ok())
+            .map_or(0, |record| record.len())
+    };
+    if header_fields(b'\t') > header_fields(b',') {
+        b'\t'
+    } else {
+        b','
+    }
+}
+
+/// Read the rows of a file whose first record is a header. Fails when a mapped
+/// column is missing from the header or no content column can be found. Records that
+/// can't be read, such as ones with more or fewer fields than the header, are returned
+/// as errors for their line.
+pub fn parse_rows(text: &str, options: &CsvImportOptions) -> Result<(Vec<CsvRow>, Vec<RowError>)> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let delimiter = match options.delimiter {
+        Some(delimiter) => u8::try_from(delimiter)
+            .map_err(|_| anyhow::anyhow!("Delimiter '{}' is not a single byte", delimiter))?,
+        None => detect_delimiter(text),
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(false)
+        .from_reader(text.as_bytes());
+    let header = reader.headers()?;
+    if header.iter().all(|h| h.trim().is_empty()) {
+        bail!("The file is empty");
+    }
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_string()).collect();
+
+    let column = |mapped: &Option<String>, field: &str| -> Result<Option<usize>> {
+        match mapped {
+            Some(name) => match header.iter().position(|h| h == name) {
+                Some(index) => Ok(Some(index)),
+                None => bail!("Column '{}' mapped to {} is not in the header", name, field),
+            },
+            None => Ok(header.iter().position(|h| h.eq_ignore_ascii_case(field))),
+        }
+    };
+    let mapping = &options.mapping;
+    let zettel_id_column = column(&mapping.zettel_id, "zettel_id")?;
+    let title_column = column(&mapping.title, "title")?;
+    let topics_column = column(&mapping.topics, "topics")?;
+    let links_column = column(&mapping.links, "links")?;
+    let Some(content_column) = column(&mapping.content, "content")? else {
+        bail!("No content column: map one or name a column 'content'");
+    };
+
+    let list = |value: Option<&str>| -> Vec<String> {
+        let mut items: Vec<String> = Vec::new();
+        for item in value.unwrap_or_default().split(options.list_separator) {
+            let item = item.trim();
+            if !item.is_empty() && !items.iter().any(|i| i == item) {
+                items.push(item.to_string());
+            }
+        }
+        items
+    };
+    let text_field = |record: &csv::StringRecord, column: Option<usize>| {
+        column
+            .and_then(|index| record.get(index))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = record_line(text, e.position());
+                let message = match e.kind() {
+                    csv::ErrorKind::UnequalLengths {
+                        expected_len, len, ..
+                    } => format!("Expected {} fields but found {}", expected_len, len),
+                    _ => e.to_string(),
+                };
+                errors.push(RowError { line, message });
+                continue;
+            }
+        };
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        rows.push(CsvRow {
+            line: record_line(text, record.position()),
+            zettel_id: text_field(&record, zettel_id_column),
+            title: text_field(&record, title_column),
+            content: text_field(&record, Some(content_column)).unwrap_or_default(),
+            topics: list(topics_column.and_then(|index| record.get(index))),
+            links: list(links_column.and_then(|index| record.get(index))),
+        });
+    }
+    Ok((rows, errors))
+}
+
+/// The line a record starts on. The reader places a record before the blank lines and
+/// line endings it skipped to reach it, so those are stepped over.
+fn record_line(text: &str, position: Option<&csv::Position>) -> usize {
+    let Some(position) = position else {
+        return 0;
+    };
+    let skipped = text
+        .as_bytes()
+        .get(position.byte() as usize..)
+        .unwrap_or_default()
+        .iter()
+        .take_while(|byte| matches!(byte, b'\r' | b'\n'))
+        .filter(|byte| **byte == b'\n')
+        .count();
+    position.line() as usize + skipped
+}
+
+/// Import rows as cards. Every row is checked first: content must be present, zettel_ids
+/// must be new and unique, and links must name an existing card or another row. When
+/// any row fails, a record could not be parsed, or in a dry run, nothing is written.
+/// Otherwise the cards and any missing topics are created in one transaction, and links
+/// may point at rows later in the file.
+pub async fn import_rows(
+    card_service: &CardService,
+    rows: &[CsvRow],
+    parse_errors: Vec<RowError>,
+    options: &CsvImportOptions,
+) -> Result<CsvImportReport> {
+    let mut report = CsvImportReport {
+        dry_run: options.dry_run,
+        rows: rows.len() + parse_errors.len(),
+        errors: parse_errors,
+        ..Default::default()
+    };
+
+    let existing: HashMap<String, Uuid> = card_service
+        .get_all_cards()
+        .await?
+        .into_iter()
+        .map(|card| (card.zettel_id, card.id))
+        .collect();
+    let mut topics: HashMap<String, Uuid> = card_service
+        .get_all_topics()
+        .await?
+        .into_iter()
+        .map(|topic| (topic.name, topic.id))
+        .collect();
+
+    // Explicit zettel_ids are claimed before any are generated, so a generated id
+    // never takes one that a later row asks for
+    let mut taken: HashSet<String> = existing.keys().cloned().collect();
+    let mut first_line: HashMap<&str, usize> = HashMap::new();
+    for row in rows {
+        let Some(zettel_id) = row.zettel_id.as_deref() else {
+            continue;
+        };
+        if let Some(line) = first_line.get(zettel_id) {
+            report.errors.push(RowError {
+                line: row.line,
+                message: format!("zettel_id '{}' is already used on line {}", zettel_id, line),
+            });
+        } else if existing.contains_key(zettel_id) {
+            report.errors.push(RowError {
+                line: row.line,
+                message: format!("A card with zettel_id '{}' already exists", zettel_id),
+            });
+        } else {
+            first_line.insert(zettel_id, row.line);
+        }
+        taken.insert(zettel_id.to_string());
+    }
+
+    let mut next_number = 1;
+    let mut zettel_ids = Vec::new();
+    for row in rows {
+        let zettel_id = match &row.zettel_id {
+            Some(zettel_id) => zettel_id.clone(),
+            None => {
+                let mut generated = format!("{}-{:03}", options.zettel_prefix, next_number);
+                while taken.contains(&generated) {
+                    next_number += 1;
+                    generated = format!("{}-{:03}", options.zettel_prefix, next_number);
+                }
+                taken.insert(generated.clone());
+                report.generated_zettel_ids.push(generated.clone());
+                generated
+            }
+        };
+        zettel_ids.push(zettel_id);
+    }
+
+    let imported: HashSet<&str> = zettel_ids.iter().map(String::as_str).collect();
+    for row in rows {
+        if row.content.is_empty() {
+            report.errors.push(RowError {
+                line: row.line,
+                message: "Content is empty".to_string(),
+            });
+        }
+        for link in &row.links {
+            if !existing.contains_key(link) && !imported.contains(link.as_str()) {
+                report.errors.push(RowError {
+                    line: row.line,
+                    message: format!("Link to unknown zettel_id '{}'", link),
+                });
+            }
+        }
+        for topic in &row.topics {
+            if !topics.contains_key(topic) && !report.topics_created.contains(topic) {
+                report.topics_created.push(topic.clone());
+            }
+        }
+    }
+    report.errors.sort_by_key(|error| error.line);
+    report.created = zettel_ids.clone();
+
+    if options.dry_run || !report.errors.is_empty() {
+        return Ok(report);
+    }
+
+    let new_topics: Vec<Topic> = report
+        .topics_created
+        .iter()
+        .map(|name| Topic {
+            id: Uuid::new_v4(),
+            name: name.clone(),
+            description: None,
+        })
+        .collect();
+    topics.extend(
+        new_topics
+            .iter()
+            .map(|topic| (topic.name.clone(), topic.id)),
+    );
+
+    // Every card gets its id up front, so links can point at rows later in the file
+    let ids: HashMap<&str, Uuid> = zettel_ids
+        .iter()
+        .map(|zettel_id| (zettel_id.as_str(), Uuid::new_v4()))
+        .collect();
+    let resolve = |zettel_id: &str| existing.get(zettel_id).or_else(|| ids.get(zettel_id));
+
+    let mut cards = Vec::new();
+    for (row, zettel_id) in rows.iter().zip(&zettel_ids) {
+        let id = ids[zettel_id.as_str()];
+
+        // Listed links are explicit, and [[zettel_id]]s in the content are inline links
+        let inline = wiki_links(&row.content);
+        let mut links: Vec<(Uuid, LinkOrigin)> = Vec::new();
+        let candidates = row
+            .links
+            .iter()
+            .map(|link| (link, LinkOrigin::Explicit))
+            .chain(inline.iter().map(|link| (link, LinkOrigin::Inline)));
+        for (link, origin) in candidates {
+            if let Some(&target) = resolve(link)
+                && target != id
+                && !links.iter().any(|(linked, _)| *linked == target)
+            {
+                links.push((target, origin));
+            }
+        }
+
+        cards.push(NewCard {
+            card: Card {
+                id,
+                ..Card::new(zettel_id.clone(), row.title.clone(), row.content.clone())
+            },
+            topic_ids: row.topics.iter().map(|topic| topics[topic]).collect(),
+            links,
+            link_relations: Vec::new(),
+        });
+    }
+    card_service.import_cards(&new_topics, &cards).await?;
+
+    info!(
+        rows = report.rows,
+        created = report.created.len(),
+        topics_created = report.topics_created.len(),
+        "CSV import finished"
+    );
+    Ok(report)
+}
+
+/// Parse and import a CSV or TSV file's text
+pub async fn import_csv(
+    card_service: &CardService,
+    text: &str,
+    options: &CsvImportOptions,
+) -> Result<CsvImportReport> {
+    let (rows, parse_errors) = parse_rows(text, options)?;
+    import_rows(card_service, &rows, parse_errors, options).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rows_handles_quotes_and_newlines() {
+        let text = "\u{feff}title,content\r\n\"Force, net\",\"Mass times\n\"\"acceleration\"\"\"\n\nMomentum,p = mv";
+        let (rows, errors) = parse_rows(text, &CsvImportOptions::default()).unwrap();
+        assert!(errors.is_empty());
+        let fields: Vec<(usize, Option<&str>, &str)> = rows
+            .iter()
+            .map(|row| (row.line, row.title.as_deref(), row.content.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (2, Some("Force, net"), "Mass times\n\"acceleration\""),
+                (5, Some("Momentum"), "p = mv"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ragged_records_are_row_errors() {
+        let text = "title\tcontent\nForce\tF = ma\textra\nMomentum\np = mv\tp\tq\nEnergy\tE = mc^2";
+        let (rows, errors) = parse_rows(text, &CsvImportOptions::default()).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].line, rows[0].content.as_str()), (5, "E = mc^2"));
+        assert_eq!(
+            errors,
+            vec![
+                RowError {
+                    line: 2,
+                    message: "Expected 2 fields but found 3".to_string()
+                },
+                RowError {
+                    line: 3,
+                    message: "Expected 2 fields but found 1".to_string()
+                },
+                RowError {
+                    line: 4,
+                    message: "Expected 2 fields but found 3".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rows_applies_mapping() {
+        let text =
+            "Term\tDefinition\tTags\nForce\tF = ma\tphysics; mechanics;physics\nMomentum\t\t";
+        let options = CsvImportOptions {
+            mapping: ColumnMapping {
+                title: Some("Term".to_string()),
+                content: Some("Definition".to_string()),
+                topics: Some("Tags".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (rows, errors) = parse_rows(text, &options).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].title.as_deref(), Some("Force"));
+        assert_eq!(rows[0].content, "F = ma");
+        assert_eq!(rows[0].topics, vec!["physics", "mechanics"]);
+        assert_eq!(rows[0].zettel_id, None);
+        assert_eq!((rows[1].line, rows[1].content.as_str()), (3, ""));
+
+        let missing = CsvImportOptions {
+            mapping: ColumnMapping {
+                content: Some("Back".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let error = parse_rows(text, &missing).unwrap_err();
+        assert!(error.to_string().contains("'Back'"));
+        assert!(parse_rows("Term,Notes\nx,y", &CsvImportOptions::default()).is_err());
+    }
+}
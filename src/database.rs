@@ -1,6 +1,7 @@
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::models::*;
@@ -10,6 +11,24 @@ pub struct Database {
     pub pool: SqlitePool,
 }
 
+/// The writes of one card edit, saved together by `Database::save_card_update`
+pub struct CardUpdate<'a> {
+    pub card: &'a Card,                // zettel_id, title and content to save
+    pub content_hash: Option<&'a str>, // Set when the content changed
+    pub links: Option<&'a [(Uuid, LinkOrigin)]>, // Replaces the card's links when set
+    pub link_relations: &'a [(Uuid, LinkRelation, Option<String>)],
+    pub topic_ids: Option<&'a [Uuid]>, // Replaces the card's topics when set
+    pub rewritten_contents: &'a [(Uuid, String, String)], // (card id, content, content hash)
+}
+
+/// A card to insert with `Database::insert_cards`, with its topics and links
+pub struct NewCard {
+    pub card: Card,
+    pub topic_ids: Vec<Uuid>,
+    pub links: Vec<(Uuid, LinkOrigin)>,
+    pub link_relations: Vec<(Uuid, LinkRelation, Option<String>)>,
+}
+
 impl Database {
     pub async fn new(database_url: &str) -> Result<Self> {
         let pool = SqlitePool::connect(database_url).await?;
@@ -104,6 +123,9 @@ impl Database {
             CREATE TABLE IF NOT EXISTS backlinks (
                 source_card_id TEXT NOT NULL,
                 target_card_id TEXT NOT NULL,
+                relation TEXT NOT NULL DEFAULT 'see-also',
+                note TEXT,
+                origin TEXT NOT NULL DEFAULT 'explicit',
                 PRIMARY KEY (source_card_id, target_card_id),
                 FOREIGN KEY (source_card_id) REFERENCES cards(id) ON DELETE CASCADE,
                 FOREIGN KEY (target_card_id) REFERENCES cards(id) ON DELETE CASCADE
@@ -113,10 +135,112 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
+        // Add link relations to existing backlinks; earlier links are plain see-also links
+        for column in ["relation TEXT NOT NULL DEFAULT 'see-also'", "note TEXT"] {
+            sqlx::query(&format!("ALTER TABLE backlinks ADD COLUMN {}", column))
+                .execute(&self.pool)
+                .await
+                .ok(); // Ignore error if column already exists
+        }
+
+        // Earlier links don't say whether they came from the content, so they are kept
+        // as explicit links rather than dropped with a mention
+        sqlx::query("ALTER TABLE backlinks ADD COLUMN origin TEXT NOT NULL DEFAULT 'explicit'")
+            .execute(&self.pool)
+            .await
+            .ok(); // Ignore error if column already exists
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS llm_usage (
+                id TEXT PRIMARY KEY,
+                created_at TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                operation TEXT NOT NULL,
+                prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                completion_tokens INTEGER NOT NULL DEFAULT 0,
+                latency_ms INTEGER NOT NULL DEFAULT 0,
+                success INTEGER NOT NULL DEFAULT 1,
+                estimated_cost_usd REAL NOT NULL DEFAULT 0.0
+            );
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_llm_usage_created_at ON llm_usage(created_at)")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS question_bank (
+                id TEXT PRIMARY KEY,
+                card_id TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                question TEXT NOT NULL,
+                question_type TEXT NOT NULL,
+                options TEXT,
+                correct_answer TEXT,
+                prompt_version TEXT,
+                created_at TEXT NOT NULL,
+                last_used_at TEXT,
+                times_used INTEGER NOT NULL DEFAULT 0,
+                pinned INTEGER NOT NULL DEFAULT 0,
+                flag_reason TEXT,
+                flagged_at TEXT,
+                edited_at TEXT,
+                rubric TEXT,
+                UNIQUE (card_id, content_hash, question),
+                FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE
+            );
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_question_bank_card ON question_bank(card_id, content_hash)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_answers (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                card_id TEXT NOT NULL,
+                question_index INTEGER NOT NULL,
+                question TEXT NOT NULL,
+                user_answer TEXT NOT NULL,
+                is_correct INTEGER NOT NULL,
+                feedback TEXT NOT NULL,
+                suggested_rating INTEGER NOT NULL,
+                score REAL,
+                criterion_scores TEXT,
+                served_by TEXT,
+                final_rating INTEGER,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE
+            );
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_session_answers_session ON session_answers(session_id)",
+        )
+        .execute(&self.pool)
+        .await?;
+
         Ok(())
     }
 
     // Card operations
+    #[allow(dead_code)]
     pub async fn create_card(&self, request: CreateCardRequest) -> Result<Card> {
         let card_id = Uuid::new_v4();
         let now = Utc::now();
@@ -191,6 +315,63 @@ impl Database {
         Ok(card)
     }
 
+    /// Insert new topics and cards, with the cards' topics, links and link relations, in
+    /// one transaction. Links may point at existing cards or at any card in the batch.
+    pub async fn insert_cards(&self, topics: &[Topic], cards: &[NewCard]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for topic in topics {
+            sqlx::query("INSERT INTO topics (id, name, description) VALUES (?1, ?2, ?3)")
+                .bind(topic.id.to_string())
+                .bind(&topic.name)
+                .bind(&topic.description)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // Every card is inserted before any links, so links can point forward in the batch
+        for new in cards {
+            let card = &new.card;
+            sqlx::query(
+                r#"
+                INSERT INTO cards (id, zettel_id, title, content, creation_date, last_reviewed, next_review,
+                                 difficulty, stability, retrievability, reps, lapses, state, links)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, NULL)
+                "#,
+            )
+            .bind(card.id.to_string())
+            .bind(&card.zettel_id)
+            .bind(&card.title)
+            .bind(&card.content)
+            .bind(card.creation_date.to_rfc3339())
+            .bind(card.last_reviewed.map(|d| d.to_rfc3339()))
+            .bind(card.next_review.to_rfc3339())
+            .bind(card.difficulty)
+            .bind(card.stability)
+            .bind(card.retrievability)
+            .bind(card.reps)
+            .bind(card.lapses)
+            .bind(&card.state)
+            .execute(&mut *tx)
+            .await?;
+            write_card_topics(&mut tx, card.id, &new.topic_ids).await?;
+        }
+        for new in cards {
+            if new.links.is_empty() && new.link_relations.is_empty() {
+                continue;
+            }
+            write_links(&mut tx, new.card.id, &new.links).await?;
+            for (target, relation, note) in &new.link_relations {
+                write_link_relation(&mut tx, new.card.id, *target, *relation, note.as_deref())
+                    .await?;
+            }
+            sync_links_column(&mut tx, &new.card.id.to_string()).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
     fn map_row_to_card(&self, row: sqlx::sqlite::SqliteRow) -> Result<Card> {
         Ok(Card {
             id: Uuid::parse_str(&row.get::<String, _>("id"))?,
@@ -233,6 +414,19 @@ impl Database {
         }
     }
 
+    /// The cards with the given ids that exist, in one query
+    pub async fn get_cards_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Card>> {
+        let ids: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+        let rows = sqlx::query("SELECT * FROM cards WHERE id IN (SELECT value FROM json_each(?1))")
+            .bind(serde_json::to_string(&ids)?)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter()
+            .map(|row| self.map_row_to_card(row))
+            .collect()
+    }
+
     pub async fn get_all_cards(&self) -> Result<Vec<Card>> {
         let rows = sqlx::query("SELECT * FROM cards ORDER BY creation_date DESC")
             .fetch_all(&self.pool)
@@ -242,10 +436,14 @@ impl Database {
     }
 
     pub async fn get_cards_due_for_review(&self) -> Result<Vec<Card>> {
-        let now = Utc::now().to_rfc3339();
+        self.get_cards_due_before(Utc::now()).await
+    }
+
+    /// Cards whose next review falls at or before `until`, soonest first
+    pub async fn get_cards_due_before(&self, until: DateTime<Utc>) -> Result<Vec<Card>> {
         let rows =
             sqlx::query("SELECT * FROM cards WHERE next_review <= ?1 ORDER BY next_review ASC")
-                .bind(now)
+                .bind(until.to_rfc3339())
                 .fetch_all(&self.pool)
                 .await?;
 
@@ -317,6 +515,50 @@ impl Database {
         Ok(topics)
     }
 
+    pub async fn get_topic_names_for_card(&self, card_id: Uuid) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT t.name FROM topics t
+            INNER JOIN card_topics ct ON t.id = ct.topic_id
+            WHERE ct.card_id = ?1
+            ORDER BY t.name
+            "#,
+        )
+        .bind(card_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| row.get("name")).collect())
+    }
+
+    /// Replace the card's topics
+    #[allow(dead_code)]
+    pub async fn set_card_topics(&self, card_id: Uuid, topic_ids: &[Uuid]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        write_card_topics(&mut tx, card_id, topic_ids).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Every card to topic assignment
+    #[allow(dead_code)]
+    pub async fn get_all_card_topics(&self) -> Result<Vec<CardTopic>> {
+        let rows =
+            sqlx::query("SELECT card_id, topic_id FROM card_topics ORDER BY card_id, topic_id")
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut card_topics = Vec::new();
+        for row in rows {
+            card_topics.push(CardTopic {
+                card_id: Uuid::parse_str(&row.get::<String, _>("card_id"))?,
+                topic_id: Uuid::parse_str(&row.get::<String, _>("topic_id"))?,
+            });
+        }
+
+        Ok(card_topics)
+    }
+
     // Review operations
     pub async fn create_review(
         &self,
@@ -349,15 +591,124 @@ impl Database {
         Ok(review)
     }
 
+    /// Store a review as it happened, such as one imported from another app
+    #[allow(dead_code)]
+    pub async fn insert_review(&self, review: &Review) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO reviews (id, card_id, review_date, rating, interval, ease_factor) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
+        )
+        .bind(review.id.to_string())
+        .bind(review.card_id.to_string())
+        .bind(review.review_date.to_rfc3339())
+        .bind(review.rating)
+        .bind(review.interval)
+        .bind(review.ease_factor)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The card's reviews, oldest first
+    #[allow(dead_code)]
+    pub async fn get_reviews_for_card(&self, card_id: Uuid) -> Result<Vec<Review>> {
+        let rows = sqlx::query("SELECT * FROM reviews WHERE card_id = ?1 ORDER BY review_date")
+            .bind(card_id.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter().map(Self::map_row_to_review).collect()
+    }
+
+    /// Every review, oldest first
+    #[allow(dead_code)]
+    pub async fn get_all_reviews(&self) -> Result<Vec<Review>> {
+        let rows = sqlx::query("SELECT * FROM reviews ORDER BY review_date")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter().map(Self::map_row_to_review).collect()
+    }
+
+    fn map_row_to_review(row: sqlx::sqlite::SqliteRow) -> Result<Review> {
+        Ok(Review {
+            id: Uuid::parse_str(&row.get::<String, _>("id"))?,
+            card_id: Uuid::parse_str(&row.get::<String, _>("card_id"))?,
+            review_date: DateTime::parse_from_rfc3339(&row.get::<String, _>("review_date"))?
+                .with_timezone(&Utc),
+            rating: row.get("rating"),
+            interval: row.get("interval"),
+            ease_factor: row.get("ease_factor"),
+        })
+    }
+
     pub async fn delete_card(&self, id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let linking: Vec<String> = sqlx::query_scalar(
+            "SELECT source_card_id FROM backlinks WHERE target_card_id = ?1 AND source_card_id != ?1",
+        )
+        .bind(id.to_string())
+        .fetch_all(&mut *tx)
+        .await?;
         let result = sqlx::query("DELETE FROM cards WHERE id = ?1")
             .bind(id.to_string())
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
+        // The deleted card's links went with it, so the cards that linked to it are
+        // left with their remaining links
+        for source_card_id in &linking {
+            sync_links_column(&mut tx, source_card_id).await?;
+        }
 
+        tx.commit().await?;
         Ok(result.rows_affected() > 0)
     }
 
+    /// Save an edited card together with everything the edit changes, in one
+    /// transaction. Returns the card's derived `links` column.
+    pub async fn save_card_update(&self, update: &CardUpdate<'_>) -> Result<Option<String>> {
+        let mut tx = self.pool.begin().await?;
+        let card_id = update.card.id.to_string();
+
+        // Other cards' content first, so a failure leaves the renamed card untouched
+        write_rewritten_contents(&mut tx, update.rewritten_contents).await?;
+
+        sqlx::query("UPDATE cards SET zettel_id = ?1, title = ?2, content = ?3 WHERE id = ?4")
+            .bind(&update.card.zettel_id)
+            .bind(&update.card.title)
+            .bind(&update.card.content)
+            .bind(&card_id)
+            .execute(&mut *tx)
+            .await?;
+        if let Some(content_hash) = update.content_hash {
+            sqlx::query(
+                "DELETE FROM question_bank WHERE card_id = ?1 AND content_hash != ?2 AND pinned = 0",
+            )
+            .bind(&card_id)
+            .bind(content_hash)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if let Some(links) = update.links {
+            write_links(&mut tx, update.card.id, links).await?;
+        }
+        for (target, relation, note) in update.link_relations {
+            write_link_relation(&mut tx, update.card.id, *target, *relation, note.as_deref())
+                .await?;
+        }
+        let links = sync_links_column(&mut tx, &card_id).await?;
+
+        if let Some(topic_ids) = update.topic_ids {
+            write_card_topics(&mut tx, update.card.id, topic_ids).await?;
+        }
+
+        tx.commit().await?;
+        Ok(links)
+    }
+
+    #[allow(dead_code)]
     pub async fn update_card_content(&self, card: &Card) -> Result<()> {
         sqlx::query(
             r#"
@@ -409,44 +760,68 @@ impl Database {
     }
 
     // Backlinks operations
+    // The backlinks table holds every link between cards. A card's `links` column is
+    // derived from it, in link order, whenever the card's links change.
+
+    /// Replace the card's links with links to the given cards, in order, keeping the
+    /// relation and note of links that stay. Cards that don't exist are skipped. Returns
+    /// the card's derived `links` column.
+    pub async fn set_links(
+        &self,
+        source_card_id: Uuid,
+        links: &[(Uuid, LinkOrigin)],
+    ) -> Result<Option<String>> {
+        let mut tx = self.pool.begin().await?;
+        write_links(&mut tx, source_card_id, links).await?;
+        let links = sync_links_column(&mut tx, &source_card_id.to_string()).await?;
+        tx.commit().await?;
+        Ok(links)
+    }
+
+    /// Add a link from one card to another, if the target exists
+    #[allow(dead_code)]
     pub async fn create_backlinks(
         &self,
         source_card_id: Uuid,
         target_card_ids: &[Uuid],
     ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
         for target_card_id in target_card_ids {
             sqlx::query(
-                "INSERT OR IGNORE INTO backlinks (source_card_id, target_card_id) VALUES (?1, ?2)",
+                "INSERT OR IGNORE INTO backlinks (source_card_id, target_card_id) SELECT ?1, id FROM cards WHERE id = ?2",
             )
             .bind(source_card_id.to_string())
             .bind(target_card_id.to_string())
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
         }
+        sync_links_column(&mut tx, &source_card_id.to_string()).await?;
+        tx.commit().await?;
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub async fn remove_backlinks(
         &self,
         source_card_id: Uuid,
         target_card_ids: &[Uuid],
     ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
         for target_card_id in target_card_ids {
             sqlx::query("DELETE FROM backlinks WHERE source_card_id = ?1 AND target_card_id = ?2")
                 .bind(source_card_id.to_string())
                 .bind(target_card_id.to_string())
-                .execute(&self.pool)
+                .execute(&mut *tx)
                 .await?;
         }
+        sync_links_column(&mut tx, &source_card_id.to_string()).await?;
+        tx.commit().await?;
         Ok(())
     }
 
     #[allow(dead_code)]
     pub async fn remove_all_backlinks_from_source(&self, source_card_id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM backlinks WHERE source_card_id = ?1")
-            .bind(source_card_id.to_string())
-            .execute(&self.pool)
-            .await?;
+        self.set_links(source_card_id, &[]).await?;
         Ok(())
     }
 
@@ -466,16 +841,98 @@ impl Database {
         self.rows_to_cards(rows)
     }
 
+    /// Cards linking to the card, with each link's relation and note
+    pub async fn get_typed_backlinks(&self, target_card_id: Uuid) -> Result<Vec<LinkedCard>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT c.*, b.relation, b.note FROM cards c
+            INNER JOIN backlinks b ON c.id = b.source_card_id
+            WHERE b.target_card_id = ?1
+            ORDER BY c.creation_date DESC
+            "#,
+        )
+        .bind(target_card_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut linked = Vec::new();
+        for row in rows {
+            let relation = parse_relation(&row.get::<String, _>("relation"))?;
+            let note = row.get("note");
+            linked.push(LinkedCard {
+                card: self.map_row_to_card(row)?,
+                relation,
+                note,
+            });
+        }
+        Ok(linked)
+    }
+
+    /// The card's links, in order
+    pub async fn get_links_from(&self, source_card_id: Uuid) -> Result<Vec<Backlink>> {
+        let rows = sqlx::query(
+            "SELECT source_card_id, target_card_id, relation, note, origin FROM backlinks WHERE source_card_id = ?1 ORDER BY rowid",
+        )
+        .bind(source_card_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::map_row_to_backlink).collect()
+    }
+
+    /// Every link, grouped by source card and in link order within each card
+    pub async fn get_all_backlinks(&self) -> Result<Vec<Backlink>> {
+        let rows = sqlx::query(
+            "SELECT source_card_id, target_card_id, relation, note, origin FROM backlinks ORDER BY source_card_id, rowid",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::map_row_to_backlink).collect()
+    }
+
+    fn map_row_to_backlink(row: sqlx::sqlite::SqliteRow) -> Result<Backlink> {
+        Ok(Backlink {
+            source_card_id: Uuid::parse_str(&row.get::<String, _>("source_card_id"))?,
+            target_card_id: Uuid::parse_str(&row.get::<String, _>("target_card_id"))?,
+            relation: parse_relation(&row.get::<String, _>("relation"))?,
+            note: row.get("note"),
+            origin: LinkOrigin::parse(&row.get::<String, _>("origin")).unwrap_or_default(),
+        })
+    }
+
+    /// Set the relation and note of a link, adding the link when the card doesn't have
+    /// it yet. Fails when the target card doesn't exist.
+    #[allow(dead_code)]
+    pub async fn set_link_relation(
+        &self,
+        source_card_id: Uuid,
+        target_card_id: Uuid,
+        relation: LinkRelation,
+        note: Option<&str>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        write_link_relation(&mut tx, source_card_id, target_card_id, relation, note).await?;
+        sync_links_column(&mut tx, &source_card_id.to_string()).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
     pub async fn update_backlinks(
         &self,
         source_card_id: Uuid,
         old_target_ids: &[Uuid],
         new_target_ids: &[Uuid],
     ) -> Result<()> {
-        // Remove old backlinks
-        if !old_target_ids.is_empty() {
-            self.remove_backlinks(source_card_id, old_target_ids)
-                .await?;
+        // Remove old backlinks; links that are kept keep their relation and note
+        let removed: Vec<Uuid> = old_target_ids
+            .iter()
+            .copied()
+            .filter(|id| !new_target_ids.contains(id))
+            .collect();
+        if !removed.is_empty() {
+            self.remove_backlinks(source_card_id, &removed).await?;
         }
 
         // Add new backlinks
@@ -489,22 +946,18 @@ impl Database {
 
     #[allow(dead_code)]
     pub async fn get_cards_linking_to(&self, target_card_id: Uuid) -> Result<Vec<Card>> {
-        let rows = sqlx::query("SELECT * FROM cards WHERE links IS NOT NULL AND links != '[]'")
-            .fetch_all(&self.pool)
-            .await?;
-
-        let mut linking_cards = Vec::new();
-        for row in rows {
-            if let Some(links_json) = row.get::<Option<String>, _>("links") {
-                if let Ok(link_ids) = serde_json::from_str::<Vec<Uuid>>(&links_json) {
-                    if link_ids.contains(&target_card_id) {
-                        linking_cards.push(self.map_row_to_card(row)?);
-                    }
-                }
-            }
-        }
+        let rows = sqlx::query(
+            r#"
+            SELECT c.* FROM cards c
+            INNER JOIN backlinks b ON c.id = b.source_card_id
+            WHERE b.target_card_id = ?1
+            "#,
+        )
+        .bind(target_card_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(linking_cards)
+        self.rows_to_cards(rows)
     }
 
     pub async fn find_cards_referencing_zettel_id(&self, zettel_id: &str) -> Result<Vec<Card>> {
@@ -517,6 +970,557 @@ impl Database {
 
         self.rows_to_cards(rows)
     }
+
+    // LLM usage operations
+    pub async fn record_llm_usage(&self, record: &LLMUsageRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO llm_usage (id, created_at, provider, model, operation, prompt_tokens,
+                                   completion_tokens, latency_ms, success, estimated_cost_usd)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#,
+        )
+        .bind(record.id.to_string())
+        .bind(record.created_at.to_rfc3339())
+        .bind(&record.provider)
+        .bind(&record.model)
+        .bind(&record.operation)
+        .bind(record.prompt_tokens)
+        .bind(record.completion_tokens)
+        .bind(record.latency_ms)
+        .bind(record.success)
+        .bind(record.estimated_cost_usd)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_daily_llm_usage(&self, since: DateTime<Utc>) -> Result<Vec<DailyLLMUsage>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT substr(created_at, 1, 10) AS date, provider, model,
+                   COUNT(*) AS calls,
+                   SUM(CASE WHEN success THEN 0 ELSE 1 END) AS failed_calls,
+                   SUM(prompt_tokens) AS prompt_tokens,
+                   SUM(completion_tokens) AS completion_tokens,
+                   SUM(estimated_cost_usd) AS estimated_cost_usd
+            FROM llm_usage
+            WHERE created_at >= ?1
+            GROUP BY date, provider, model
+            ORDER BY date DESC, provider, model
+            "#,
+        )
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DailyLLMUsage {
+                date: row.get("date"),
+                provider: row.get("provider"),
+                model: row.get("model"),
+                calls: row.get("calls"),
+                failed_calls: row.get("failed_calls"),
+                prompt_tokens: row.get("prompt_tokens"),
+                completion_tokens: row.get("completion_tokens"),
+                estimated_cost_usd: row.get("estimated_cost_usd"),
+            })
+            .collect())
+    }
+
+    pub async fn get_llm_cost_since(&self, since: DateTime<Utc>) -> Result<f64> {
+        let row = sqlx::query(
+            "SELECT COALESCE(SUM(estimated_cost_usd), 0.0) AS total FROM llm_usage WHERE created_at >= ?1",
+        )
+        .bind(since.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.get("total"))
+    }
+
+    // Question bank operations
+    pub async fn insert_banked_questions(
+        &self,
+        card_id: Uuid,
+        content_hash: &str,
+        questions: &[QuizQuestion],
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        for question in questions {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO question_bank (id, card_id, content_hash, question, question_type,
+                                                     options, correct_answer, prompt_version, created_at, rubric)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(card_id.to_string())
+            .bind(content_hash)
+            .bind(&question.question)
+            .bind(&question.question_type)
+            .bind(question.options.as_ref().map(serde_json::to_string).transpose()?)
+            .bind(&question.correct_answer)
+            .bind(&question.prompt_version)
+            .bind(&now)
+            .bind(question.rubric.as_ref().map(serde_json::to_string).transpose()?)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Banked questions for the given card content plus the card's pinned questions,
+    /// least used first. Flagged questions are included.
+    pub async fn get_banked_questions(
+        &self,
+        card_id: Uuid,
+        content_hash: &str,
+    ) -> Result<Vec<BankedQuestion>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM question_bank
+            WHERE card_id = ?1 AND (content_hash = ?2 OR pinned = 1)
+            ORDER BY times_used ASC, last_used_at ASC, created_at ASC
+            "#,
+        )
+        .bind(card_id.to_string())
+        .bind(content_hash)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| self.map_row_to_banked_question(row))
+            .collect()
+    }
+
+    fn map_row_to_banked_question(&self, row: sqlx::sqlite::SqliteRow) -> Result<BankedQuestion> {
+        let parse_date = |value: String| -> Result<DateTime<Utc>> {
+            Ok(DateTime::parse_from_rfc3339(&value)?.with_timezone(&Utc))
+        };
+
+        Ok(BankedQuestion {
+            id: Uuid::parse_str(&row.get::<String, _>("id"))?,
+            card_id: Uuid::parse_str(&row.get::<String, _>("card_id"))?,
+            content_hash: row.get("content_hash"),
+            question: QuizQuestion {
+                question: row.get("question"),
+                question_type: row.get("question_type"),
+                options: row
+                    .get::<Option<String>, _>("options")
+                    .map(|json| serde_json::from_str(&json))
+                    .transpose()?,
+                correct_answer: row.get("correct_answer"),
+                prompt_version: row.get("prompt_version"),
+                rubric: row
+                    .get::<Option<String>, _>("rubric")
+                    .map(|json| serde_json::from_str(&json))
+                    .transpose()?,
+            },
+            created_at: parse_date(row.get("created_at"))?,
+            last_used_at: row
+                .get::<Option<String>, _>("last_used_at")
+                .map(parse_date)
+                .transpose()?,
+            times_used: row.get("times_used"),
+            pinned: row.get::<i64, _>("pinned") != 0,
+            flag_reason: row.get("flag_reason"),
+            flagged_at: row
+                .get::<Option<String>, _>("flagged_at")
+                .map(parse_date)
+                .transpose()?,
+            edited_at: row
+                .get::<Option<String>, _>("edited_at")
+                .map(parse_date)
+                .transpose()?,
+        })
+    }
+
+    pub async fn get_banked_question(&self, id: Uuid) -> Result<Option<BankedQuestion>> {
+        let row = sqlx::query("SELECT * FROM question_bank WHERE id = ?1")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| self.map_row_to_banked_question(row))
+            .transpose()
+    }
+
+    /// Flag a banked question with a reason, or clear its flag with `None`
+    pub async fn set_banked_question_flag(
+        &self,
+        id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<Option<BankedQuestion>> {
+        let flagged_at = reason.map(|_| Utc::now().to_rfc3339());
+        sqlx::query("UPDATE question_bank SET flag_reason = ?1, flagged_at = ?2 WHERE id = ?3")
+            .bind(reason)
+            .bind(flagged_at)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        self.get_banked_question(id).await
+    }
+
+    pub async fn set_banked_question_pinned(
+        &self,
+        id: Uuid,
+        pinned: bool,
+    ) -> Result<Option<BankedQuestion>> {
+        sqlx::query("UPDATE question_bank SET pinned = ?1 WHERE id = ?2")
+            .bind(pinned as i64)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        self.get_banked_question(id).await
+    }
+
+    /// Replace a banked question's text, options, answer and rubric. Editing fixes whatever
+    /// the question was flagged for, so the flag is cleared.
+    pub async fn update_banked_question(
+        &self,
+        id: Uuid,
+        question: &QuizQuestion,
+    ) -> Result<Option<BankedQuestion>> {
+        sqlx::query(
+            r#"
+            UPDATE question_bank
+            SET question = ?1, options = ?2, correct_answer = ?3, rubric = ?4, edited_at = ?5,
+                flag_reason = NULL, flagged_at = NULL
+            WHERE id = ?6
+            "#,
+        )
+        .bind(&question.question)
+        .bind(
+            question
+                .options
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+        )
+        .bind(&question.correct_answer)
+        .bind(
+            question
+                .rubric
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        self.get_banked_question(id).await
+    }
+
+    pub async fn mark_banked_questions_used(
+        &self,
+        card_id: Uuid,
+        content_hash: &str,
+        question_texts: &[&str],
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        for question in question_texts {
+            sqlx::query(
+                r#"
+                UPDATE question_bank SET times_used = times_used + 1, last_used_at = ?1
+                WHERE card_id = ?2 AND (content_hash = ?3 OR pinned = 1) AND question = ?4
+                "#,
+            )
+            .bind(&now)
+            .bind(card_id.to_string())
+            .bind(content_hash)
+            .bind(question)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove unpinned questions generated from content other than the card's current content
+    #[allow(dead_code)]
+    pub async fn delete_stale_banked_questions(
+        &self,
+        card_id: Uuid,
+        current_content_hash: &str,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM question_bank WHERE card_id = ?1 AND content_hash != ?2 AND pinned = 0",
+        )
+        .bind(card_id.to_string())
+        .bind(current_content_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Session answer operations
+    pub async fn insert_session_answer(&self, answer: &SessionAnswer) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO session_answers (id, session_id, card_id, question_index, question, user_answer,
+                                         is_correct, feedback, suggested_rating, score, criterion_scores,
+                                         served_by, final_rating, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+            "#,
+        )
+        .bind(answer.id.to_string())
+        .bind(answer.session_id.to_string())
+        .bind(answer.card_id.to_string())
+        .bind(answer.question_index as i64)
+        .bind(serde_json::to_string(&answer.question)?)
+        .bind(&answer.user_answer)
+        .bind(answer.is_correct)
+        .bind(&answer.feedback)
+        .bind(answer.suggested_rating)
+        .bind(answer.score)
+        .bind(
+            answer
+                .criterion_scores
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+        )
+        .bind(&answer.served_by)
+        .bind(answer.final_rating)
+        .bind(answer.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Answers graded in a session, in the order they were given
+    pub async fn get_session_answers(&self, session_id: Uuid) -> Result<Vec<SessionAnswer>> {
+        let rows = sqlx::query(
+            "SELECT * FROM session_answers WHERE session_id = ?1 ORDER BY created_at ASC, rowid ASC",
+        )
+        .bind(session_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| self.map_row_to_session_answer(row))
+            .collect()
+    }
+
+    /// Session answers whose card has since been given a final rating
+    pub async fn get_rated_session_answers(&self) -> Result<Vec<SessionAnswer>> {
+        let rows = sqlx::query(
+            "SELECT * FROM session_answers WHERE final_rating IS NOT NULL ORDER BY created_at ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| self.map_row_to_session_answer(row))
+            .collect()
+    }
+
+    /// Record the rating a card was given on every answer for it in the session
+    pub async fn set_session_final_rating(
+        &self,
+        session_id: Uuid,
+        card_id: Uuid,
+        rating: i32,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE session_answers SET final_rating = ?1 WHERE session_id = ?2 AND card_id = ?3",
+        )
+        .bind(rating)
+        .bind(session_id.to_string())
+        .bind(card_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    fn map_row_to_session_answer(&self, row: sqlx::sqlite::SqliteRow) -> Result<SessionAnswer> {
+        Ok(SessionAnswer {
+            id: Uuid::parse_str(&row.get::<String, _>("id"))?,
+            session_id: Uuid::parse_str(&row.get::<String, _>("session_id"))?,
+            card_id: Uuid::parse_str(&row.get::<String, _>("card_id"))?,
+            question_index: row.get::<i64, _>("question_index") as usize,
+            question: serde_json::from_str(&row.get::<String, _>("question"))?,
+            user_answer: row.get("user_answer"),
+            is_correct: row.get("is_correct"),
+            feedback: row.get("feedback"),
+            suggested_rating: row.get("suggested_rating"),
+            score: row.get("score"),
+            criterion_scores: row
+                .get::<Option<String>, _>("criterion_scores")
+                .map(|json| serde_json::from_str(&json))
+                .transpose()?,
+            served_by: row.get("served_by"),
+            final_rating: row.get("final_rating"),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
+                .with_timezone(&Utc),
+        })
+    }
+}
+
+/// Replace a card's topics
+async fn write_card_topics(
+    conn: &mut sqlx::SqliteConnection,
+    card_id: Uuid,
+    topic_ids: &[Uuid],
+) -> Result<()> {
+    sqlx::query("DELETE FROM card_topics WHERE card_id = ?1")
+        .bind(card_id.to_string())
+        .execute(&mut *conn)
+        .await?;
+
+    for topic_id in topic_ids {
+        sqlx::query("INSERT OR IGNORE INTO card_topics (card_id, topic_id) VALUES (?1, ?2)")
+            .bind(card_id.to_string())
+            .bind(topic_id.to_string())
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Replace a card's rows in the backlinks table, leaving its `links` column to
+/// `sync_links_column`. The rows are inserted again in the given order, since the
+/// column follows rowid order, and links that stay keep their relation and note.
+async fn write_links(
+    conn: &mut sqlx::SqliteConnection,
+    source_card_id: Uuid,
+    links: &[(Uuid, LinkOrigin)],
+) -> Result<()> {
+    let source = source_card_id.to_string();
+    let mut current: HashMap<String, (String, Option<String>)> = HashMap::new();
+    for row in sqlx::query(
+        "SELECT target_card_id, relation, note FROM backlinks WHERE source_card_id = ?1",
+    )
+    .bind(&source)
+    .fetch_all(&mut *conn)
+    .await?
+    {
+        current.insert(
+            row.get("target_card_id"),
+            (row.get("relation"), row.get("note")),
+        );
+    }
+
+    sqlx::query("DELETE FROM backlinks WHERE source_card_id = ?1")
+        .bind(&source)
+        .execute(&mut *conn)
+        .await?;
+    for (target, origin) in links {
+        let target = target.to_string();
+        let (relation, note) = current
+            .remove(&target)
+            .unwrap_or_else(|| (LinkRelation::default().as_str().to_string(), None));
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO backlinks (source_card_id, target_card_id, relation, note, origin)
+            SELECT ?1, id, ?3, ?4, ?5 FROM cards WHERE id = ?2
+            "#,
+        )
+        .bind(&source)
+        .bind(&target)
+        .bind(relation)
+        .bind(note)
+        .bind(origin.as_str())
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Upsert the relation and note of a link, failing when the target card doesn't exist
+async fn write_link_relation(
+    conn: &mut sqlx::SqliteConnection,
+    source_card_id: Uuid,
+    target_card_id: Uuid,
+    relation: LinkRelation,
+    note: Option<&str>,
+) -> Result<()> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO backlinks (source_card_id, target_card_id, relation, note)
+        SELECT ?1, id, ?3, ?4 FROM cards WHERE id = ?2
+        ON CONFLICT (source_card_id, target_card_id)
+        DO UPDATE SET relation = excluded.relation, note = excluded.note
+        "#,
+    )
+    .bind(source_card_id.to_string())
+    .bind(target_card_id.to_string())
+    .bind(relation.as_str())
+    .bind(note)
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(anyhow::anyhow!(
+            "Cannot link to card {}: it does not exist",
+            target_card_id
+        ));
+    }
+    Ok(())
+}
+
+/// Replace the content of cards whose references were rewritten, dropping their banked
+/// questions for the old content
+async fn write_rewritten_contents(
+    conn: &mut sqlx::SqliteConnection,
+    rewritten_contents: &[(Uuid, String, String)],
+) -> Result<()> {
+    for (id, content, content_hash) in rewritten_contents {
+        sqlx::query("UPDATE cards SET content = ?1 WHERE id = ?2")
+            .bind(content)
+            .bind(id.to_string())
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(
+            "DELETE FROM question_bank WHERE card_id = ?1 AND content_hash != ?2 AND pinned = 0",
+        )
+        .bind(id.to_string())
+        .bind(content_hash)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Rewrite a card's `links` column from its rows in the backlinks table, returning it
+pub(crate) async fn sync_links_column(
+    conn: &mut sqlx::SqliteConnection,
+    card_id: &str,
+) -> Result<Option<String>> {
+    sqlx::query(
+        r#"
+        UPDATE cards SET links = (
+            SELECT json_group_array(target_card_id) FROM (
+                SELECT target_card_id FROM backlinks WHERE source_card_id = ?1 ORDER BY rowid
+            )
+        )
+        WHERE id = ?1
+        "#,
+    )
+    .bind(card_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(sqlx::query_scalar("SELECT links FROM cards WHERE id = ?1")
+        .bind(card_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .flatten())
+}
+
+fn parse_relation(value: &str) -> Result<LinkRelation> {
+    LinkRelation::parse(value).ok_or_else(|| anyhow::anyhow!("Unknown link relation '{}'", value))
 }
 
 #[cfg(test)]
@@ -791,4 +1795,74 @@ mod tests {
         let backlinks = db.get_backlinks(card_b.id).await.unwrap();
         assert_eq!(backlinks.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_links_column_follows_backlinks() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let mut cards = Vec::new();
+        for zettel_id in ["EDGE-A", "EDGE-B", "EDGE-C"] {
+            cards.push(
+                db.create_card(CreateCardRequest {
+                    zettel_id: zettel_id.to_string(),
+                    title: None,
+                    content: zettel_id.to_string(),
+                    topic_ids: vec![],
+                    links: None,
+                })
+                .await
+                .unwrap(),
+            );
+        }
+        let (a, b, c) = (&cards[0], &cards[1], &cards[2]);
+        let stored_links = |links: Option<String>| -> Vec<Uuid> {
+            serde_json::from_str(&links.unwrap_or_default()).unwrap_or_default()
+        };
+
+        // Links to cards that don't exist are not stored
+        let links = db
+            .set_links(
+                a.id,
+                &[
+                    (c.id, LinkOrigin::Explicit),
+                    (Uuid::new_v4(), LinkOrigin::Explicit),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(stored_links(links), vec![c.id]);
+
+        // Setting a relation adds a missing link and keeps the link order
+        db.set_link_relation(a.id, b.id, LinkRelation::Prerequisite, Some("basics"))
+            .await
+            .unwrap();
+        let card = db.get_card(a.id).await.unwrap().unwrap();
+        assert_eq!(stored_links(card.links), vec![c.id, b.id]);
+        let from_a = db.get_links_from(a.id).await.unwrap();
+        assert_eq!(from_a[1].relation, LinkRelation::Prerequisite);
+        assert_eq!(from_a[1].note.as_deref(), Some("basics"));
+        assert!(
+            db.set_link_relation(a.id, Uuid::new_v4(), LinkRelation::SeeAlso, None)
+                .await
+                .is_err()
+        );
+
+        // Reordering links changes the stored order, and links keep their relation
+        let links = db
+            .set_links(
+                a.id,
+                &[(b.id, LinkOrigin::Explicit), (c.id, LinkOrigin::Explicit)],
+            )
+            .await
+            .unwrap();
+        assert_eq!(stored_links(links), vec![b.id, c.id]);
+        let from_a = db.get_links_from(a.id).await.unwrap();
+        assert_eq!(from_a[0].target_card_id, b.id);
+        assert_eq!(from_a[0].relation, LinkRelation::Prerequisite);
+        assert_eq!(from_a[0].note.as_deref(), Some("basics"));
+
+        // Deleting a linked card removes it from the links of the cards linking to it
+        db.delete_card(c.id).await.unwrap();
+        let card = db.get_card(a.id).await.unwrap().unwrap();
+        assert_eq!(stored_links(card.links), vec![b.id]);
+    }
 }
@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod efficiency_tests {
     use crate::card_service::CardService;
     use crate::database::Database;
@@ -100,9 +101,12 @@ mod efficiency_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("A subset of AI that learns from data".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             user_answer: "ML learns from data".to_string(),
             card_content: "Machine learning is a subset of AI".to_string(),
+            card_id: None,
         }];
 
         // Test batch grading - should handle the structure correctly
@@ -111,12 +115,12 @@ mod efficiency_tests {
         // Due to fake API, this will likely fail but should handle gracefully
         assert!(result.is_ok() || result.is_err()); // Both outcomes are acceptable for this test
 
-        if let Ok(results) = result {
-            if !results.is_empty() {
-                assert_eq!(results.len(), 1);
-                assert!(!results[0].feedback.is_empty());
-                assert!(results[0].suggested_rating >= 1 && results[0].suggested_rating <= 4);
-            }
+        if let Ok(results) = result
+            && !results.is_empty()
+        {
+            assert_eq!(results.len(), 1);
+            assert!(!results[0].feedback.is_empty());
+            assert!(results[0].suggested_rating >= 1 && results[0].suggested_rating <= 4);
         }
     }
 
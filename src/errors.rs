@@ -63,6 +63,7 @@ impl ErrorContext {
 
 impl ApiError {
     /// Convert API error to HTTP response with consistent structure and logging
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_response_with_context(
         self,
         context: ErrorContext,
@@ -173,7 +174,7 @@ impl ApiError {
     }
 
     /// Simple conversion without context (for backward compatibility)
-    #[allow(dead_code)]
+    #[allow(dead_code, clippy::wrong_self_convention)]
     pub fn to_response(self) -> (StatusCode, Json<ApiResponse<()>>) {
         let context = ErrorContext::new("unknown", "resource");
         self.to_response_with_context(context)
@@ -184,35 +185,41 @@ impl ApiError {
 #[macro_export]
 macro_rules! api_error {
     (not_found, $operation:expr, $resource_type:expr, $id:expr) => {
-        crate::errors::ApiError::NotFound(format!("{} with id '{}' not found", $resource_type, $id))
-            .to_response_with_context(
-                crate::errors::ErrorContext::new($operation, $resource_type).with_id($id),
-            )
+        $crate::errors::ApiError::NotFound(format!(
+            "{} with id '{}' not found",
+            $resource_type, $id
+        ))
+        .to_response_with_context(
+            $crate::errors::ErrorContext::new($operation, $resource_type).with_id($id),
+        )
     };
 
     (validation, $operation:expr, $resource_type:expr, $message:expr) => {
-        crate::errors::ApiError::ValidationError($message.to_string())
-            .to_response_with_context(crate::errors::ErrorContext::new($operation, $resource_type))
+        $crate::errors::ApiError::ValidationError($message.to_string()).to_response_with_context(
+            $crate::errors::ErrorContext::new($operation, $resource_type),
+        )
     };
 
     (duplicate, $operation:expr, $resource_type:expr, $id:expr) => {
-        crate::errors::ApiError::DuplicateResource(format!(
+        $crate::errors::ApiError::DuplicateResource(format!(
             "{} with id '{}' already exists",
             $resource_type, $id
         ))
         .to_response_with_context(
-            crate::errors::ErrorContext::new($operation, $resource_type).with_id($id),
+            $crate::errors::ErrorContext::new($operation, $resource_type).with_id($id),
         )
     };
 
     (database, $operation:expr, $resource_type:expr, $error:expr) => {
-        crate::errors::ApiError::DatabaseError($error)
-            .to_response_with_context(crate::errors::ErrorContext::new($operation, $resource_type))
+        $crate::errors::ApiError::DatabaseError($error).to_response_with_context(
+            $crate::errors::ErrorContext::new($operation, $resource_type),
+        )
     };
 
     (llm, $operation:expr, $resource_type:expr, $error:expr) => {
-        crate::errors::ApiError::LLMError($error.to_string())
-            .to_response_with_context(crate::errors::ErrorContext::new($operation, $resource_type))
+        $crate::errors::ApiError::LLMError($error.to_string()).to_response_with_context(
+            $crate::errors::ErrorContext::new($operation, $resource_type),
+        )
     };
 }
 
@@ -229,14 +236,14 @@ pub fn classify_database_error(error: &anyhow::Error) -> ApiError {
 
     if error_str.contains("already exists") || error_str.contains("unique constraint") {
         // Extract the relevant part of the error message
-        if let Some(start) = error_str.find("'") {
-            if let Some(end) = error_str[start + 1..].find("'") {
-                let identifier = &error_str[start + 1..start + 1 + end];
-                return ApiError::DuplicateResource(format!(
-                    "Resource '{}' already exists",
-                    identifier
-                ));
-            }
+        if let Some(start) = error_str.find("'")
+            && let Some(end) = error_str[start + 1..].find("'")
+        {
+            let identifier = &error_str[start + 1..start + 1 + end];
+            return ApiError::DuplicateResource(format!(
+                "Resource '{}' already exists",
+                identifier
+            ));
         }
         ApiError::DuplicateResource("Resource already exists".to_string())
     } else if error_str.contains("not found") || error_str.contains("no rows") {
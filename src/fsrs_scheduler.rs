@@ -22,6 +22,12 @@ pub struct FSRSScheduler {
     initial_ease: f64,
 }
 
+impl Default for FSRSScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FSRSScheduler {
     pub fn new() -> Self {
         Self { initial_ease: 2.5 }
@@ -226,6 +232,18 @@ impl FSRSScheduler {
         }
     }
 
+    /// Estimated chance of recalling the card now, from its stability and the time since
+    /// its last review. Cards never reviewed have nothing to recall yet.
+    pub fn current_retrievability(&self, card: &Card, now: DateTime<Utc>) -> f64 {
+        match card.last_reviewed {
+            Some(last) if card.stability > 0.0 => {
+                let elapsed_days = (now - last).num_seconds().max(0) as f64 / 86400.0;
+                self.calculate_retrievability(card.stability, elapsed_days)
+            }
+            _ => 0.0,
+        }
+    }
+
     fn calculate_retrievability(&self, stability: f64, elapsed_days: f64) -> f64 {
         if elapsed_days == 0.0 {
             1.0
@@ -311,6 +329,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_rating_to_int_conversion() {
         // Remove this test as the rating_to_int method doesn't exist
         // The conversion is handled internally by the FSRS library
@@ -431,4 +450,17 @@ mod tests {
         assert!(retrievability < 1.0);
         assert!(retrievability > 0.0);
     }
+
+    #[test]
+    fn test_current_retrievability() {
+        let scheduler = FSRSScheduler::new();
+        let now = Utc::now();
+        let mut card = create_test_card();
+        assert_eq!(scheduler.current_retrievability(&card, now), 0.0);
+
+        card.last_reviewed = Some(now - Duration::days(2));
+        card.stability = 2.0;
+        let retrievability = scheduler.current_retrievability(&card, now);
+        assert!((retrievability - (-1.0f64).exp()).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,406 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use uuid::Uuid;
+
+use crate::models::{Backlink, Card, LinkRelation};
+
+/// A card as a graph node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: Uuid,
+    pub zettel_id: String,
+    pub title: Option<String>,
+    pub state: String,
+}
+
+/// A link from one card to another
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: Uuid,
+    pub target: Uuid,
+    #[serde(default)]
+    pub relation: LinkRelation,
+}
+
+/// Cards and the links between them. Links to cards that no longer exist are left out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Export formats for visualization tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    #[default]
+    Json,
+    GraphMl,
+    Dot,
+}
+
+impl KnowledgeGraph {
+    /// Nodes in zettel_id order, edges in the order of each card's links
+    pub fn new(cards: &[Card], links: &[Backlink]) -> Self {
+        let mut cards: Vec<&Card> = cards.iter().collect();
+        cards.sort_by(|a, b| a.zettel_id.cmp(&b.zettel_id));
+        let ids: HashSet<Uuid> = cards.iter().map(|card| card.id).collect();
+
+        let mut links_by_source: HashMap<Uuid, Vec<&Backlink>> = HashMap::new();
+        for link in links {
+            links_by_source
+                .entry(link.source_card_id)
+                .or_default()
+                .push(link);
+        }
+
+        let mut graph = KnowledgeGraph::default();
+        let mut seen = HashSet::new();
+        for card in cards {
+            graph.nodes.push(GraphNode {
+                id: card.id,
+                zettel_id: card.zettel_id.clone(),
+                title: card.title.clone(),
+                state: card.state.clone(),
+            });
+
+            for link in links_by_source.get(&card.id).into_iter().flatten() {
+                let edge = GraphEdge {
+                    source: card.id,
+                    target: link.target_card_id,
+                    relation: link.relation,
+                };
+                if edge.target != card.id
+                    && ids.contains(&edge.target)
+                    && seen.insert((edge.source, edge.target))
+                {
+                    graph.edges.push(edge);
+                }
+            }
+        }
+        graph
+    }
+
+    pub fn contains(&self, id: Uuid) -> bool {
+        self.nodes.iter().any(|node| node.id == id)
+    }
+
+    /// Linked cards in either direction, in edge order
+    fn neighbors(&self) -> HashMap<Uuid, Vec<Uuid>> {
+        let mut neighbors: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for edge in &self.edges {
+            neighbors.entry(edge.source).or_default().push(edge.target);
+            neighbors.entry(edge.target).or_default().push(edge.source);
+        }
+        neighbors
+    }
+
+    /// Hops from the card to every card within `depth`, following links both ways
+    fn distances(&self, center: Uuid, depth: usize) -> HashMap<Uuid, usize> {
+        let neighbors = self.neighbors();
+        let mut distances = HashMap::from([(center, 0)]);
+        let mut queue = VecDeque::from([center]);
+        while let Some(id) = queue.pop_front() {
+            let distance = distances[&id];
+            if distance == depth {
+                continue;
+            }
+            for next in neighbors.get(&id).into_iter().flatten() {
+                if !distances.contains_key(next) {
+                    distances.insert(*next, distance + 1);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        distances
+    }
+
+    /// The cards within `depth` links of the card, following links both ways, and the
+    /// links between them. Empty when the card isn't in the graph.
+    pub fn neighborhood(&self, center: Uuid, depth: usize) -> KnowledgeGraph {
+        if !self.contains(center) {
+            return KnowledgeGraph::default();
+        }
+        let included = self.distances(center, depth);
+        KnowledgeGraph {
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|node| included.contains_key(&node.id))
+                .cloned()
+                .collect(),
+            edges: self
+                .edges
+                .iter()
+                .filter(|edge| {
+                    included.contains_key(&edge.source) && included.contains_key(&edge.target)
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// The fewest links from one card to another, as the cards along the way including
+    /// both ends. Links may be followed against their direction.
+    pub fn shortest_path(&self, from: Uuid, to: Uuid) -> Option<Vec<GraphNode>> {
+        if !self.contains(from) || !self.contains(to) {
+            return None;
+        }
+
+        let neighbors = self.neighbors();
+        let mut previous: HashMap<Uuid, Uuid> = HashMap::new();
+        let mut visited = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(id) = queue.pop_front() {
+            if id == to {
+                break;
+            }
+            for next in neighbors.get(&id).into_iter().flatten() {
+                if visited.insert(*next) {
+                    previous.insert(*next, id);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        if !visited.contains(&to) {
+            return None;
+        }
+
+        let mut path = vec![to];
+        while let Some(id) = previous.get(path.last().expect("path is not empty")) {
+            path.push(*id);
+        }
+        path.reverse();
+        Some(
+            path.into_iter()
+                .filter_map(|id| self.nodes.iter().find(|node| node.id == id).cloned())
+                .collect(),
+        )
+    }
+
+    /// Cards with no links in or out
+    pub fn orphans(&self) -> Vec<GraphNode> {
+        let linked: HashSet<Uuid> = self
+            .edges
+            .iter()
+            .flat_map(|edge| [edge.source, edge.target])
+            .collect();
+        self.nodes
+            .iter()
+            .filter(|node| !linked.contains(&node.id))
+            .cloned()
+            .collect()
+    }
+
+    /// GraphML for tools such as Gephi, Cytoscape and yEd, with zettel_id, title and
+    /// state as node attributes and relation as an edge attribute
+    pub fn to_graphml(&self) -> String {
+        let mut output = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n  \
+             <key id=\"zettel_id\" for=\"node\" attr.name=\"zettel_id\" attr.type=\"string\"/>\n  \
+             <key id=\"title\" for=\"node\" attr.name=\"title\" attr.type=\"string\"/>\n  \
+             <key id=\"state\" for=\"node\" attr.name=\"state\" attr.type=\"string\"/>\n  \
+             <key id=\"relation\" for=\"edge\" attr.name=\"relation\" attr.type=\"string\"/>\n  \
+             <graph id=\"cards\" edgedefault=\"directed\">\n",
+        );
+        for node in &self.nodes {
+            output.push_str(&format!("    <node id=\"{}\">\n", node.id));
+            output.push_str(&format!(
+                "      <data key=\"zettel_id\">{}</data>\n",
+                escape_xml(&node.zettel_id)
+            ));
+            if let Some(title) = &node.title {
+                output.push_str(&format!(
+                    "      <data key=\"title\">{}</data>\n",
+                    escape_xml(title)
+                ));
+            }
+            output.push_str(&format!(
+                "      <data key=\"state\">{}</data>\n",
+                escape_xml(&node.state)
+            ));
+            output.push_str("    </node>\n");
+        }
+        for edge in &self.edges {
+            output.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\">\n      \
+                 <data key=\"relation\">{}</data>\n    </edge>\n",
+                edge.source,
+                edge.target,
+                edge.relation.as_str()
+            ));
+        }
+        output.push_str("  </graph>\n</graphml>\n");
+        output
+    }
+
+    /// Graphviz DOT, with cards labelled by title or zettel_id and links labelled by
+    /// relation unless they are plain see-also links
+    pub fn to_dot(&self) -> String {
+        let mut output = String::from("digraph cards {\n");
+        for node in &self.nodes {
+            let label = node.title.as_deref().unwrap_or(&node.zettel_id);
+            output.push_str(&format!(
+                "  \"{}\" [label=\"{}\", tooltip=\"{}\"];\n",
+                node.id,
+                escape_dot(label),
+                escape_dot(&node.zettel_id)
+            ));
+        }
+        for edge in &self.edges {
+            match edge.relation {
+                LinkRelation::SeeAlso => {
+                    output.push_str(&format!("  \"{}\" -> \"{}\";\n", edge.source, edge.target))
+                }
+                relation => output.push_str(&format!(
+                    "  \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                    edge.source,
+                    edge.target,
+                    relation.as_str()
+                )),
+            }
+        }
+        output.push_str("}\n");
+        output
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::LinkOrigin;
+    use chrono::Utc;
+
+    fn card(zettel_id: &str) -> Card {
+        Card {
+            id: Uuid::new_v4(),
+            zettel_id: zettel_id.to_string(),
+            title: Some(format!("Card \"{}\"", zettel_id)),
+            content: String::new(),
+            creation_date: Utc::now(),
+            last_reviewed: None,
+            next_review: Utc::now(),
+            difficulty: 0.0,
+            stability: 0.0,
+            retrievability: 1.0,
+            reps: 0,
+            lapses: 0,
+            state: "New".to_string(),
+            links: None,
+        }
+    }
+
+    fn link(source: &Card, target: Uuid, relation: LinkRelation) -> Backlink {
+        Backlink {
+            source_card_id: source.id,
+            target_card_id: target,
+            relation,
+            note: None,
+            origin: LinkOrigin::Explicit,
+        }
+    }
+
+    /// a -> b -> c <- d, e alone, and a dangling link from e's neighbor f
+    fn sample_with(b_to_c: LinkRelation) -> (Vec<Card>, KnowledgeGraph) {
+        let cards: Vec<Card> = ["a", "b", "c", "d", "e", "f"].map(card).into();
+        let [a, b, c, d, _, f] = &cards[..] else {
+            unreachable!()
+        };
+        let links = [
+            link(a, b.id, LinkRelation::SeeAlso),
+            link(a, b.id, LinkRelation::SeeAlso),
+            link(b, c.id, b_to_c),
+            link(d, c.id, LinkRelation::SeeAlso),
+            link(f, Uuid::new_v4(), LinkRelation::SeeAlso),
+        ];
+        let graph = KnowledgeGraph::new(&cards, &links);
+        (cards, graph)
+    }
+
+    fn sample() -> (Vec<Card>, KnowledgeGraph) {
+        sample_with(LinkRelation::SeeAlso)
+    }
+
+    fn zettel_ids(nodes: &[GraphNode]) -> Vec<&str> {
+        nodes.iter().map(|node| node.zettel_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_neighborhood_follows_links_both_ways() {
+        let (cards, graph) = sample();
+        assert_eq!(graph.edges.len(), 3);
+
+        let around_b = graph.neighborhood(cards[1].id, 1);
+        assert_eq!(zettel_ids(&around_b.nodes), vec!["a", "b", "c"]);
+        assert_eq!(around_b.edges.len(), 2);
+
+        let around_a = graph.neighborhood(cards[0].id, 3);
+        assert_eq!(zettel_ids(&around_a.nodes), vec!["a", "b", "c", "d"]);
+        assert!(graph.neighborhood(Uuid::new_v4(), 2).nodes.is_empty());
+    }
+
+    #[test]
+    fn test_shortest_path_and_orphans() {
+        let (cards, graph) = sample();
+        let path = graph.shortest_path(cards[0].id, cards[3].id).unwrap();
+        assert_eq!(zettel_ids(&path), vec!["a", "b", "c", "d"]);
+        assert_eq!(
+            zettel_ids(&graph.shortest_path(cards[2].id, cards[2].id).unwrap()),
+            vec!["c"]
+        );
+        assert!(graph.shortest_path(cards[0].id, cards[4].id).is_none());
+
+        assert_eq!(zettel_ids(&graph.orphans()), vec!["e", "f"]);
+    }
+
+    #[test]
+    fn test_exports_escape_labels() {
+        let (cards, graph) = sample();
+        let graph = graph.neighborhood(cards[0].id, 1);
+
+        let graphml = graph.to_graphml();
+        assert!(graphml.contains("<data key=\"title\">Card &quot;a&quot;</data>"));
+        assert!(graphml.contains(&format!(
+            "<edge source=\"{}\" target=\"{}\">\n      <data key=\"relation\">see-also</data>",
+            cards[0].id, cards[1].id
+        )));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph cards {\n"));
+        assert!(dot.contains("[label=\"Card \\\"a\\\"\", tooltip=\"a\"];"));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\";", cards[0].id, cards[1].id)));
+    }
+
+    #[test]
+    fn test_edges_carry_link_relations() {
+        let (cards, graph) = sample_with(LinkRelation::Prerequisite);
+
+        let relations: Vec<LinkRelation> = graph.edges.iter().map(|edge| edge.relation).collect();
+        assert_eq!(
+            relations,
+            vec![
+                LinkRelation::SeeAlso,
+                LinkRelation::Prerequisite,
+                LinkRelation::SeeAlso
+            ]
+        );
+        assert!(graph.to_dot().contains(&format!(
+            "\"{}\" -> \"{}\" [label=\"prerequisite\"];",
+            cards[1].id, cards[2].id
+        )));
+    }
+}
@@ -1,31 +1,63 @@
+pub mod anki;
+pub mod answer_text;
 pub mod api;
+pub mod backup;
+pub mod calibration;
 pub mod card_service;
+pub mod cloze;
+pub mod csv_import;
 pub mod database;
 mod efficiency_tests;
 pub mod errors;
 pub mod fsrs_scheduler;
+pub mod knowledge_graph;
+pub mod llm_fixtures;
 pub mod llm_providers;
 pub mod llm_service;
+pub mod llm_usage;
+pub mod local_grading;
 pub mod logging;
+pub mod markdown;
 pub mod models;
-
-#[cfg(test)]
-mod tests {
-    mod batch_grading_integration_test;
-    mod batch_grading_service_test;
-    mod batch_grading_test;
-    mod concurrent_processing_integration_test;
-    mod parallel_grading_phase2_test;
-    mod session_answer_test;
-}
+pub mod prompt_templates;
+pub mod question_cache;
+pub mod question_variety;
+pub mod rubric;
+pub mod token_budget;
+pub mod vault_export;
+pub mod vault_import;
+pub mod zip_archive;
 
 pub use card_service::CardService;
 pub use database::Database;
 pub use errors::*;
 pub use fsrs_scheduler::FSRSScheduler;
-pub use llm_providers::{JsonResponseParser, LLMProvider, LLMProviderFactory, LLMProviderType};
+pub use llm_fixtures::{FixtureMode, FixtureProvider};
+pub use llm_providers::{
+    JsonResponseParser, LLMProvider, LLMProviderConfig, LLMProviderFactory, LLMProviderType,
+};
 
 // Backward compatibility alias for tests - this should be removed in a future refactor
 pub use llm_providers::LLMProviderType as LegacyLLMProvider;
 pub use llm_service::LLMService;
+pub use llm_usage::{LLMOperation, UsageTracker};
 pub use models::*;
+
+#[cfg(test)]
+mod tests {
+    mod batch_grading_integration_test;
+    mod batch_grading_service_test;
+    mod batch_grading_test;
+    mod cloze_session_test;
+    mod concurrent_processing_integration_test;
+    mod llm_usage_test;
+    mod parallel_grading_phase2_test;
+    mod prompt_template_test;
+    mod provider_failover_test;
+    mod question_cache_test;
+    mod question_feedback_test;
+    mod rating_calibration_test;
+    mod rubric_grading_test;
+    mod session_answer_test;
+    mod streaming_grading_test;
+}
@@ -0,0 +1,196 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::{debug, info};
+
+use crate::llm_providers::{LLMCompletion, LLMProvider, TokenUsage};
+
+/// Whether a fixture provider calls through and saves responses, or serves saved ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FixtureMode {
+    Record,
+    Replay,
+}
+
+impl FixtureMode {
+    /// Parse a mode name as used in configuration ("record" or "replay")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "record" => Some(FixtureMode::Record),
+            "replay" => Some(FixtureMode::Replay),
+            _ => None,
+        }
+    }
+}
+
+/// One recorded request/response pair, stored as `<key>.json` in the fixture directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LLMFixture {
+    pub key: String,
+    pub provider: String,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    pub prompt: String,
+    pub response: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+}
+
+/// Stable fixture key for a request: 64-bit FNV-1a over the system message and prompt.
+///
+/// `std`'s `DefaultHasher` is not guaranteed to be stable across releases, so it can't
+/// be used for file names that are committed to the repository.
+pub fn prompt_hash(system_message: Option<&str>, prompt: &str) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    let system = system_message.unwrap_or("");
+    // Length-prefix the system message so ("ab", "c") and ("a", "bc") differ
+    let bytes = (system.len() as u64)
+        .to_le_bytes()
+        .into_iter()
+        .chain(system.bytes())
+        .chain(prompt.bytes());
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Provider that records real responses to fixture files or replays them offline
+#[derive(Debug, Clone)]
+pub struct FixtureProvider {
+    dir: PathBuf,
+    inner: Option<Box<LLMProvider>>, // The real provider when recording; None replays
+}
+
+impl FixtureProvider {
+    /// Forward every request to `inner` and save the response under `dir`
+    pub fn record(inner: LLMProvider, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            inner: Some(Box::new(inner)),
+        }
+    }
+
+    /// Serve responses from `dir` without any network access
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            inner: None,
+        }
+    }
+
+    pub async fn make_request(
+        &self,
+        system_message: Option<&str>,
+        prompt: &str,
+    ) -> Result<LLMCompletion> {
+        let key = prompt_hash(system_message, prompt);
+
+        match &self.inner {
+            Some(inner) => {
+                let completion = inner
+                    .make_request_with_usage(system_message, prompt)
+                    .await?;
+                let fixture = LLMFixture {
+                    key: key.clone(),
+                    provider: inner.provider_name().to_string(),
+                    model: inner.model_name().to_string(),
+                    system: system_message.map(str::to_string),
+                    prompt: prompt.to_string(),
+                    response: completion.text.clone(),
+                    usage: completion.usage,
+                };
+                self.save(&fixture).await?;
+                info!(
+                    fixture_key = %key,
+                    provider = %fixture.provider,
+                    "Recorded LLM fixture"
+                );
+                Ok(completion)
+            }
+            None => {
+                let fixture = self.load(&key).await?;
+                debug!(fixture_key = %key, "Replaying LLM fixture");
+                Ok(LLMCompletion {
+                    text: fixture.response,
+                    usage: fixture.usage,
+                })
+            }
+        }
+    }
+
+    async fn load(&self, key: &str) -> Result<LLMFixture> {
+        let path = self.fixture_path(key);
+        let contents = tokio::fs::read_to_string(&path).await.with_context(|| {
+            format!(
+                "No LLM fixture recorded for prompt hash {} (expected {}); \
+                 record it with LLM_FIXTURE_MODE=record",
+                key,
+                path.display()
+            )
+        })?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid LLM fixture file {}", path.display()))
+    }
+
+    async fn save(&self, fixture: &LLMFixture) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| {
+                format!("Failed to create fixture directory {}", self.dir.display())
+            })?;
+
+        // Write to a temporary file first so concurrent recordings never leave a torn fixture
+        let path = self.fixture_path(&fixture.key);
+        let tmp_path = path.with_extension(format!("json.{}.tmp", uuid::Uuid::new_v4()));
+        tokio::fs::write(&tmp_path, serde_json::to_string_pretty(fixture)? + "\n").await?;
+        tokio::fs::rename(&tmp_path, &path)
+            .await
+            .with_context(|| format!("Failed to write LLM fixture {}", path.display()))
+    }
+
+    fn fixture_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    pub fn provider_name(&self) -> &'static str {
+        "Fixture"
+    }
+
+    pub fn model_name(&self) -> &str {
+        match &self.inner {
+            Some(inner) => inner.model_name(),
+            None => "replay",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_hash_is_stable_and_separates_system_message() {
+        // Pinned so that a change to the hashing invalidates fixtures loudly, not silently
+        assert_eq!(prompt_hash(None, "prompt"), "ff088fd1bd269d1b");
+        assert_eq!(prompt_hash(None, "prompt"), prompt_hash(Some(""), "prompt"));
+        assert_ne!(prompt_hash(Some("ab"), "c"), prompt_hash(Some("a"), "bc"));
+        assert_ne!(prompt_hash(None, "prompt"), prompt_hash(None, "prompt "));
+        assert_eq!(prompt_hash(None, "prompt").len(), 16);
+    }
+
+    #[test]
+    fn test_fixture_mode_parsing() {
+        assert_eq!(FixtureMode::from_name("Record"), Some(FixtureMode::Record));
+        assert_eq!(
+            FixtureMode::from_name(" replay "),
+            Some(FixtureMode::Replay)
+        );
+        assert_eq!(FixtureMode::from_name("live"), None);
+    }
+}
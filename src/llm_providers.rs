@@ -1,8 +1,13 @@
 use anyhow::Result;
+use futures_util::{Stream, StreamExt, stream};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::pin::Pin;
 use tracing::{error, info};
 
+use crate::llm_fixtures::FixtureProvider;
+
 /// Common message structure for LLM requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
@@ -16,19 +21,97 @@ pub struct LLMMessage {
 pub enum LLMProvider {
     OpenAI(OpenAIProvider),
     Gemini(GeminiProvider),
+    Fixture(FixtureProvider), // Records or replays responses keyed by prompt hash
     #[cfg(test)]
     #[allow(dead_code)]
     Mock(MockProvider),
 }
 
+/// Token counts reported by the provider for a single request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+/// Response text together with provider-reported token usage
+#[derive(Debug, Clone)]
+pub struct LLMCompletion {
+    pub text: String,
+    pub usage: Option<TokenUsage>,
+}
+
+/// One increment of a streamed response. Providers may report usage more than
+/// once (Gemini sends running totals); the last report wins.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LLMStreamChunk {
+    Text(String),
+    Usage(TokenUsage),
+}
+
+/// Stream of response increments; errors after the stream opens arrive as items
+pub type LLMStream = Pin<Box<dyn Stream<Item = Result<LLMStreamChunk>> + Send>>;
+
 impl LLMProvider {
     /// Make a request to the LLM provider with optional system message
+    #[allow(dead_code)]
     pub async fn make_request(&self, system_message: Option<&str>, prompt: &str) -> Result<String> {
+        Ok(self
+            .make_request_with_usage(system_message, prompt)
+            .await?
+            .text)
+    }
+
+    /// Make a request and return the response along with its token usage
+    pub async fn make_request_with_usage(
+        &self,
+        system_message: Option<&str>,
+        prompt: &str,
+    ) -> Result<LLMCompletion> {
         match self {
             LLMProvider::OpenAI(provider) => provider.make_request(system_message, prompt).await,
             LLMProvider::Gemini(provider) => provider.make_request(system_message, prompt).await,
+            LLMProvider::Fixture(provider) => {
+                Box::pin(provider.make_request(system_message, prompt)).await
+            }
             #[cfg(test)]
-            LLMProvider::Mock(provider) => provider.make_request(system_message, prompt).await,
+            LLMProvider::Mock(provider) => {
+                let text = provider.make_request(system_message, prompt).await?;
+                // Rough 4-characters-per-token estimate so usage accounting can be tested
+                let usage = TokenUsage {
+                    prompt_tokens: (prompt.len() / 4) as u32,
+                    completion_tokens: (text.len() / 4) as u32,
+                };
+                Ok(LLMCompletion {
+                    text,
+                    usage: Some(usage),
+                })
+            }
+        }
+    }
+
+    /// Open a streaming request. Connection and HTTP status errors are returned
+    /// directly so callers can fail over before any output has been produced.
+    pub async fn make_request_stream(
+        &self,
+        system_message: Option<&str>,
+        prompt: &str,
+    ) -> Result<LLMStream> {
+        match self {
+            LLMProvider::OpenAI(provider) => {
+                provider.make_request_stream(system_message, prompt).await
+            }
+            LLMProvider::Gemini(provider) => {
+                provider.make_request_stream(system_message, prompt).await
+            }
+            // Recording and replaying work on whole responses, which are then re-chunked
+            LLMProvider::Fixture(_) => Ok(stream_completion(
+                self.make_request_with_usage(system_message, prompt).await?,
+            )),
+            #[cfg(test)]
+            LLMProvider::Mock(_) => Ok(stream_completion(
+                self.make_request_with_usage(system_message, prompt).await?,
+            )),
         }
     }
 
@@ -38,6 +121,7 @@ impl LLMProvider {
         match self {
             LLMProvider::OpenAI(provider) => provider.provider_name(),
             LLMProvider::Gemini(provider) => provider.provider_name(),
+            LLMProvider::Fixture(provider) => provider.provider_name(),
             #[cfg(test)]
             LLMProvider::Mock(provider) => provider.provider_name(),
         }
@@ -49,6 +133,7 @@ impl LLMProvider {
         match self {
             LLMProvider::OpenAI(provider) => provider.model_name(),
             LLMProvider::Gemini(provider) => provider.model_name(),
+            LLMProvider::Fixture(provider) => provider.model_name(),
             #[cfg(test)]
             LLMProvider::Mock(provider) => provider.model_name(),
         }
@@ -69,6 +154,15 @@ pub struct OpenAIProvider {
 struct OpenAIRequest {
     model: String,
     messages: Vec<OpenAIMessage>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stream_options: Option<OpenAIStreamOptions>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIStreamOptions {
+    include_usage: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +202,24 @@ struct OpenAIUsage {
     total_tokens: u32,
 }
 
+/// One `data:` event of a streamed Chat Completions response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIStreamEvent {
+    #[serde(default)]
+    choices: Vec<OpenAIStreamChoice>,
+    usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIStreamChoice {
+    delta: OpenAIStreamDelta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIStreamDelta {
+    content: Option<String>,
+}
+
 impl OpenAIProvider {
     pub fn new(api_key: String, base_url: Option<String>, model: Option<String>) -> Self {
         Self {
@@ -120,26 +232,12 @@ impl OpenAIProvider {
 }
 
 impl OpenAIProvider {
-    pub async fn make_request(&self, system_message: Option<&str>, prompt: &str) -> Result<String> {
-        // Build messages array for Chat Completions API
-        let mut messages = Vec::new();
-
-        if let Some(sys_msg) = system_message {
-            messages.push(OpenAIMessage {
-                role: "system".to_string(),
-                content: sys_msg.to_string(),
-            });
-        }
-
-        messages.push(OpenAIMessage {
-            role: "user".to_string(),
-            content: prompt.to_string(),
-        });
-
-        let request_body = OpenAIRequest {
-            model: self.model.clone(),
-            messages,
-        };
+    pub async fn make_request(
+        &self,
+        system_message: Option<&str>,
+        prompt: &str,
+    ) -> Result<LLMCompletion> {
+        let request_body = self.build_request(system_message, prompt, false);
 
         info!(
             provider = self.provider_name(),
@@ -151,7 +249,7 @@ impl OpenAIProvider {
 
         let response = self
             .client
-            .post(&format!("{}/chat/completions", self.base_url))
+            .post(format!("{}/chat/completions", self.base_url))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
             .json(&request_body)
@@ -180,14 +278,98 @@ impl OpenAIProvider {
         }
 
         let response_content = openai_response.choices[0].message.content.clone();
+        let usage = openai_response.usage.map(|usage| TokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        });
         info!(
             provider = self.provider_name(),
             response_length = response_content.len(),
             response_id = %openai_response.id,
+            prompt_tokens = usage.map(|u| u.prompt_tokens),
+            completion_tokens = usage.map(|u| u.completion_tokens),
             "Successfully received LLM response from Chat Completions API"
         );
 
-        Ok(response_content)
+        Ok(LLMCompletion {
+            text: response_content,
+            usage,
+        })
+    }
+
+    /// Stream a Chat Completions response using server-sent events
+    pub async fn make_request_stream(
+        &self,
+        system_message: Option<&str>,
+        prompt: &str,
+    ) -> Result<LLMStream> {
+        let request_body = self.build_request(system_message, prompt, true);
+
+        info!(
+            provider = self.provider_name(),
+            model = %self.model,
+            base_url = %self.base_url,
+            prompt_length = prompt.len(),
+            "Making streaming LLM request"
+        );
+
+        let response = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .header("Accept", "text/event-stream")
+            .json(&request_body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            error!(
+                provider = self.provider_name(),
+                status = %status,
+                error = %error_text,
+                "LLM API streaming request failed"
+            );
+            return Err(anyhow::anyhow!("OpenAI API request failed: {}", error_text));
+        }
+
+        Ok(parse_sse_stream(response, parse_openai_stream_event))
+    }
+
+    fn build_request(
+        &self,
+        system_message: Option<&str>,
+        prompt: &str,
+        stream: bool,
+    ) -> OpenAIRequest {
+        // Build messages array for Chat Completions API
+        let mut messages = Vec::new();
+
+        if let Some(sys_msg) = system_message {
+            messages.push(OpenAIMessage {
+                role: "system".to_string(),
+                content: sys_msg.to_string(),
+            });
+        }
+
+        messages.push(OpenAIMessage {
+            role: "user".to_string(),
+            content: prompt.to_string(),
+        });
+
+        OpenAIRequest {
+            model: self.model.clone(),
+            messages,
+            stream,
+            stream_options: stream.then_some(OpenAIStreamOptions {
+                include_usage: true,
+            }),
+        }
     }
 
     pub fn provider_name(&self) -> &'static str {
@@ -219,6 +401,7 @@ struct GeminiRequest {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct GeminiContent {
+    #[serde(default)]
     parts: Vec<GeminiPart>,
 }
 
@@ -240,7 +423,18 @@ struct GeminiGenerationConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct GeminiResponse {
+    #[serde(default)]
     candidates: Vec<GeminiCandidate>,
+    #[serde(rename = "usageMetadata")]
+    usage_metadata: Option<GeminiUsageMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GeminiUsageMetadata {
+    #[serde(rename = "promptTokenCount", default)]
+    prompt_token_count: u32,
+    #[serde(rename = "candidatesTokenCount", default)]
+    candidates_token_count: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -261,23 +455,12 @@ impl GeminiProvider {
 }
 
 impl GeminiProvider {
-    pub async fn make_request(&self, system_message: Option<&str>, prompt: &str) -> Result<String> {
-        let full_prompt = match system_message {
-            Some(sys_msg) => format!("{}\n\n{}", sys_msg, prompt),
-            None => prompt.to_string(),
-        };
-
-        let request_body = GeminiRequest {
-            contents: vec![GeminiContent {
-                parts: vec![GeminiPart { text: full_prompt }],
-            }],
-            generation_config: GeminiGenerationConfig {
-                temperature: 0.7,
-                top_k: 40,
-                top_p: 0.9,
-                max_output_tokens: 2048,
-            },
-        };
+    pub async fn make_request(
+        &self,
+        system_message: Option<&str>,
+        prompt: &str,
+    ) -> Result<LLMCompletion> {
+        let request_body = Self::build_request(system_message, prompt);
 
         let url = format!(
             "{}/models/{}:generateContent?key={}",
@@ -326,13 +509,88 @@ impl GeminiProvider {
         }
 
         let response_content = gemini_response.candidates[0].content.parts[0].text.clone();
+        let usage = gemini_response.usage_metadata.map(|usage| TokenUsage {
+            prompt_tokens: usage.prompt_token_count,
+            completion_tokens: usage.candidates_token_count,
+        });
         info!(
             provider = self.provider_name(),
             response_length = response_content.len(),
+            prompt_tokens = usage.map(|u| u.prompt_tokens),
+            completion_tokens = usage.map(|u| u.completion_tokens),
             "Successfully received LLM response"
         );
 
-        Ok(response_content)
+        Ok(LLMCompletion {
+            text: response_content,
+            usage,
+        })
+    }
+
+    /// Stream a response from the `streamGenerateContent` endpoint using server-sent events
+    pub async fn make_request_stream(
+        &self,
+        system_message: Option<&str>,
+        prompt: &str,
+    ) -> Result<LLMStream> {
+        let request_body = Self::build_request(system_message, prompt);
+
+        let url = format!(
+            "{}/models/{}:streamGenerateContent?alt=sse&key={}",
+            self.base_url, self.model, self.api_key
+        );
+
+        info!(
+            provider = self.provider_name(),
+            model = %self.model,
+            base_url = %self.base_url,
+            prompt_length = prompt.len(),
+            "Making streaming LLM request"
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .json(&request_body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            error!(
+                provider = self.provider_name(),
+                status = %status,
+                error = %error_text,
+                "LLM API streaming request failed"
+            );
+            return Err(anyhow::anyhow!("Gemini API request failed: {}", error_text));
+        }
+
+        Ok(parse_sse_stream(response, parse_gemini_stream_event))
+    }
+
+    fn build_request(system_message: Option<&str>, prompt: &str) -> GeminiRequest {
+        let full_prompt = match system_message {
+            Some(sys_msg) => format!("{}\n\n{}", sys_msg, prompt),
+            None => prompt.to_string(),
+        };
+
+        GeminiRequest {
+            contents: vec![GeminiContent {
+                parts: vec![GeminiPart { text: full_prompt }],
+            }],
+            generation_config: GeminiGenerationConfig {
+                temperature: 0.7,
+                top_k: 40,
+                top_p: 0.9,
+                max_output_tokens: 2048,
+            },
+        }
     }
 
     pub fn provider_name(&self) -> &'static str {
@@ -345,8 +603,134 @@ impl GeminiProvider {
     }
 }
 
+/// Splits a server-sent-events byte stream into the payloads of its `data:` lines.
+/// Lines are split on `\n` bytes, so multi-byte characters spanning network
+/// chunks are reassembled before decoding.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    /// Feed raw bytes and return every complete `data:` payload
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut payloads = Vec::new();
+        while let Some(newline) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            if let Some(payload) = Self::data_payload(&line) {
+                payloads.push(payload);
+            }
+        }
+        payloads
+    }
+
+    /// Flush a trailing line that was not newline-terminated
+    pub fn finish(&mut self) -> Option<String> {
+        let line = std::mem::take(&mut self.buffer);
+        Self::data_payload(&line)
+    }
+
+    fn data_payload(line: &[u8]) -> Option<String> {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim_end_matches(['\r', '\n']);
+        line.strip_prefix("data:")
+            .map(|data| data.strip_prefix(' ').unwrap_or(data).to_string())
+    }
+}
+
+/// Turn an SSE HTTP response into a stream of chunks using a provider-specific event parser
+fn parse_sse_stream(
+    response: reqwest::Response,
+    parse_event: fn(&str) -> Result<Vec<LLMStreamChunk>>,
+) -> LLMStream {
+    let payloads = stream::unfold(
+        (Some(response), SseDecoder::default(), VecDeque::new()),
+        |(mut response, mut decoder, mut pending)| async move {
+            loop {
+                if let Some(payload) = pending.pop_front() {
+                    return Some((Ok(payload), (response, decoder, pending)));
+                }
+
+                let body = response.as_mut()?;
+                match body.chunk().await {
+                    Ok(Some(bytes)) => pending.extend(decoder.push(&bytes)),
+                    Ok(None) => {
+                        response = None;
+                        pending.extend(decoder.finish());
+                    }
+                    Err(e) => {
+                        return Some((Err(anyhow::Error::from(e)), (None, decoder, pending)));
+                    }
+                }
+            }
+        },
+    );
+
+    Box::pin(payloads.flat_map(move |payload| {
+        let chunks: Vec<Result<LLMStreamChunk>> = match payload.and_then(|p| parse_event(&p)) {
+            Ok(chunks) => chunks.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        stream::iter(chunks)
+    }))
+}
+
+fn parse_openai_stream_event(payload: &str) -> Result<Vec<LLMStreamChunk>> {
+    let payload = payload.trim();
+    if payload.is_empty() || payload == "[DONE]" {
+        return Ok(Vec::new());
+    }
+
+    let event: OpenAIStreamEvent = serde_json::from_str(payload)
+        .map_err(|e| anyhow::anyhow!("Failed to parse OpenAI stream event: {}", e))?;
+
+    let mut chunks: Vec<LLMStreamChunk> = event
+        .choices
+        .into_iter()
+        .filter_map(|choice| choice.delta.content)
+        .filter(|text| !text.is_empty())
+        .map(LLMStreamChunk::Text)
+        .collect();
+    if let Some(usage) = event.usage {
+        chunks.push(LLMStreamChunk::Usage(TokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        }));
+    }
+    Ok(chunks)
+}
+
+fn parse_gemini_stream_event(payload: &str) -> Result<Vec<LLMStreamChunk>> {
+    let payload = payload.trim();
+    if payload.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let event: GeminiResponse = serde_json::from_str(payload)
+        .map_err(|e| anyhow::anyhow!("Failed to parse Gemini stream event: {}", e))?;
+
+    let mut chunks: Vec<LLMStreamChunk> = event
+        .candidates
+        .into_iter()
+        .take(1)
+        .flat_map(|candidate| candidate.content.parts)
+        .map(|part| part.text)
+        .filter(|text| !text.is_empty())
+        .map(LLMStreamChunk::Text)
+        .collect();
+    if let Some(usage) = event.usage_metadata {
+        chunks.push(LLMStreamChunk::Usage(TokenUsage {
+            prompt_tokens: usage.prompt_token_count,
+            completion_tokens: usage.candidates_token_count,
+        }));
+    }
+    Ok(chunks)
+}
+
 /// Centralized JSON response parser with robust extraction logic
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct JsonResponseParser;
 
 impl JsonResponseParser {
@@ -357,43 +741,41 @@ impl JsonResponseParser {
     /// Extract JSON from LLM responses that might be wrapped in markdown or other formatting
     pub fn extract_json_from_response(content: &str) -> String {
         // Try to find JSON within markdown code blocks
-        if let Some(start) = content.find("```json") {
-            if let Some(end) = content[start + 7..].find("```") {
-                let json_start = start + 7;
-                let json_end = json_start + end;
-                return content[json_start..json_end].trim().to_string();
-            }
+        if let Some(start) = content.find("```json")
+            && let Some(end) = content[start + 7..].find("```")
+        {
+            let json_start = start + 7;
+            let json_end = json_start + end;
+            return content[json_start..json_end].trim().to_string();
         }
 
         // Try to find JSON within plain code blocks
-        if let Some(start) = content.find("```") {
-            if let Some(end) = content[start + 3..].find("```") {
-                let json_start = start + 3;
-                let json_end = json_start + end;
-                let potential_json = content[json_start..json_end].trim();
-                // Check if it looks like JSON
-                if potential_json.starts_with('{') || potential_json.starts_with('[') {
-                    return potential_json.to_string();
-                }
+        if let Some(start) = content.find("```")
+            && let Some(end) = content[start + 3..].find("```")
+        {
+            let json_start = start + 3;
+            let json_end = json_start + end;
+            let potential_json = content[json_start..json_end].trim();
+            // Check if it looks like JSON
+            if potential_json.starts_with('{') || potential_json.starts_with('[') {
+                return potential_json.to_string();
             }
         }
 
         // Try to find standalone JSON objects
-        if let Some(start) = content.find('{') {
-            if let Some(end) = content.rfind('}') {
-                if end > start {
-                    return content[start..=end].to_string();
-                }
-            }
+        if let Some(start) = content.find('{')
+            && let Some(end) = content.rfind('}')
+            && end > start
+        {
+            return content[start..=end].to_string();
         }
 
         // Try to find standalone JSON arrays
-        if let Some(start) = content.find('[') {
-            if let Some(end) = content.rfind(']') {
-                if end > start {
-                    return content[start..=end].to_string();
-                }
-            }
+        if let Some(start) = content.find('[')
+            && let Some(end) = content.rfind(']')
+            && end > start
+        {
+            return content[start..=end].to_string();
         }
 
         // Return original content if no JSON extraction patterns match
@@ -411,6 +793,150 @@ impl JsonResponseParser {
     }
 }
 
+/// Incrementally decodes the value of one JSON string field (e.g. `"feedback"`)
+/// from a response that is still streaming in, so it can be shown before the
+/// full JSON document is available.
+#[derive(Debug)]
+pub struct StreamingJsonFieldExtractor {
+    key_pattern: String,
+    buffer: String,
+    value_start: Option<usize>,
+    cursor: usize,
+    complete: bool,
+}
+
+impl StreamingJsonFieldExtractor {
+    pub fn new(field: &str) -> Self {
+        Self {
+            key_pattern: format!("\"{}\"", field),
+            buffer: String::new(),
+            value_start: None,
+            cursor: 0,
+            complete: false,
+        }
+    }
+
+    /// Append raw response text and return any newly decoded characters of the field value
+    pub fn push(&mut self, delta: &str) -> Option<String> {
+        self.buffer.push_str(delta);
+        if self.complete {
+            return None;
+        }
+
+        if self.value_start.is_none() {
+            self.value_start = self.find_value_start();
+            self.cursor = self.value_start?;
+        }
+
+        let mut decoded = String::new();
+        let bytes = self.buffer.as_bytes();
+        while self.cursor < bytes.len() {
+            match bytes[self.cursor] {
+                b'"' => {
+                    self.complete = true;
+                    break;
+                }
+                b'\\' => match self.decode_escape(self.cursor) {
+                    Some((ch, consumed)) => {
+                        decoded.push(ch);
+                        self.cursor += consumed;
+                    }
+                    // Escape sequence split across chunks; wait for more input
+                    None => break,
+                },
+                _ => {
+                    let ch = self.buffer[self.cursor..]
+                        .chars()
+                        .next()
+                        .expect("cursor is on a char boundary");
+                    decoded.push(ch);
+                    self.cursor += ch.len_utf8();
+                }
+            }
+        }
+
+        (!decoded.is_empty()).then_some(decoded)
+    }
+
+    /// Everything pushed so far
+    pub fn buffer(&self) -> &str {
+        &self.buffer
+    }
+
+    fn find_value_start(&self) -> Option<usize> {
+        let mut search_from = 0;
+        while let Some(found) = self.buffer[search_from..].find(&self.key_pattern) {
+            let after_key = search_from + found + self.key_pattern.len();
+            let rest = &self.buffer[after_key..];
+            let trimmed = rest.trim_start();
+            if let Some(after_colon) = trimmed.strip_prefix(':') {
+                // None while the value has not arrived yet or is not a string
+                let inner = after_colon.trim_start().strip_prefix('"')?;
+                return Some(self.buffer.len() - inner.len());
+            }
+            if !trimmed.is_empty() {
+                // Matched the name inside another string; keep looking
+                search_from = after_key;
+                continue;
+            }
+            return None;
+        }
+        None
+    }
+
+    /// Decode the escape at `start`, returning the character and bytes consumed
+    fn decode_escape(&self, start: usize) -> Option<(char, usize)> {
+        let rest = &self.buffer[start..];
+        let escaped = rest.chars().nth(1)?;
+        let simple = match escaped {
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            '/' => Some('/'),
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            'b' => Some('\u{8}'),
+            'f' => Some('\u{c}'),
+            'u' => None,
+            other => return Some((other, 1 + other.len_utf8())),
+        };
+        if let Some(ch) = simple {
+            return Some((ch, 2));
+        }
+
+        let high = u32::from_str_radix(rest.get(2..6)?, 16).ok()?;
+        if (0xD800..0xDC00).contains(&high) {
+            // Surrogate pair: need the following \uXXXX as well
+            if rest.get(6..8)? != "\\u" {
+                return Some((char::REPLACEMENT_CHARACTER, 6));
+            }
+            let low = u32::from_str_radix(rest.get(8..12)?, 16).ok()?;
+            let code = 0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+            return Some((
+                char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER),
+                12,
+            ));
+        }
+        Some((
+            char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER),
+            6,
+        ))
+    }
+}
+
+/// Replay a completed response as a stream of small text chunks followed by usage
+fn stream_completion(completion: LLMCompletion) -> LLMStream {
+    let chars: Vec<char> = completion.text.chars().collect();
+    let mut chunks: Vec<Result<LLMStreamChunk>> = chars
+        .chunks(8)
+        .map(|chunk| Ok(LLMStreamChunk::Text(chunk.iter().collect())))
+        .collect();
+    if let Some(usage) = completion.usage {
+        chunks.push(Ok(LLMStreamChunk::Usage(usage)));
+    }
+    Box::pin(stream::iter(chunks))
+}
+
 /// Factory for creating LLM providers based on provider type
 pub struct LLMProviderFactory;
 
@@ -420,6 +946,26 @@ pub enum LLMProviderType {
     Gemini,
 }
 
+impl LLMProviderType {
+    /// Parse a provider name as used in configuration (e.g. "openai", "gemini")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "gemini" | "google" => Some(LLMProviderType::Gemini),
+            "openai" | "chatgpt" | "gpt" => Some(LLMProviderType::OpenAI),
+            _ => None,
+        }
+    }
+}
+
+/// Connection settings for one provider in an ordered failover chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LLMProviderConfig {
+    pub provider: LLMProviderType,
+    pub api_key: String,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+}
+
 impl LLMProviderFactory {
     /// Create a new LLM provider instance based on provider type
     pub fn create_provider(
@@ -437,6 +983,21 @@ impl LLMProviderFactory {
             }
         }
     }
+
+    /// Create providers for a failover chain, preserving the configured order
+    pub fn create_provider_chain(configs: &[LLMProviderConfig]) -> Vec<LLMProvider> {
+        configs
+            .iter()
+            .map(|config| {
+                Self::create_provider(
+                    config.provider,
+                    config.api_key.clone(),
+                    config.base_url.clone(),
+                    config.model.clone(),
+                )
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -446,6 +1007,8 @@ pub struct MockProvider {
     correct_answers: bool,
     batch_fails: bool,
     mixed_mode: bool,
+    unavailable: bool,
+    short_batches: bool, // Batch grading replies leave out the last answer
 }
 
 #[cfg(test)]
@@ -456,6 +1019,8 @@ impl MockProvider {
             correct_answers,
             batch_fails,
             mixed_mode: false,
+            unavailable: false,
+            short_batches: false,
         }
     }
 
@@ -466,6 +1031,32 @@ impl MockProvider {
             correct_answers: false, // Will be ignored in mixed mode
             batch_fails: false,
             mixed_mode: true,
+            unavailable: false,
+            short_batches: false,
+        }
+    }
+
+    /// Create a mock provider that fails every request, simulating an outage
+    #[allow(dead_code)]
+    pub fn new_unavailable() -> Self {
+        Self {
+            correct_answers: false,
+            batch_fails: false,
+            mixed_mode: false,
+            unavailable: true,
+            short_batches: false,
+        }
+    }
+
+    /// Create a mock provider whose batch grading replies skip the last answer
+    #[allow(dead_code)]
+    pub fn new_short_batches() -> Self {
+        Self {
+            correct_answers: true,
+            batch_fails: false,
+            mixed_mode: false,
+            unavailable: false,
+            short_batches: true,
         }
     }
 
@@ -474,6 +1065,20 @@ impl MockProvider {
         self.mixed_mode
     }
 
+    /// Criteria listed under "Rubric:" in a grading prompt
+    fn rubric_criteria(prompt: &str) -> Vec<String> {
+        prompt
+            .split_once("Rubric:\n")
+            .map(|(_, rest)| {
+                rest.lines()
+                    .take_while(|line| line.starts_with("- "))
+                    .filter_map(|line| line[2..].rsplit_once(" (weight "))
+                    .map(|(criterion, _)| criterion.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     pub async fn make_request(
         &self,
         _system_message: Option<&str>,
@@ -482,6 +1087,10 @@ impl MockProvider {
         // Simulate network delay
         tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
 
+        if self.unavailable {
+            return Err(anyhow::anyhow!("Mock provider unavailable"));
+        }
+
         // Check if this is a batch grading request
         if prompt.contains("Grade the following quiz answers") {
             if self.batch_fails {
@@ -491,7 +1100,27 @@ impl MockProvider {
 
             let mut results = Vec::new();
 
-            if self.is_mixed_mode() {
+            if self.short_batches {
+                let answer_count = prompt
+                    .lines()
+                    .filter(|line| {
+                        line.split_once(". Card Content:")
+                            .is_some_and(|(number, _)| number.parse::<usize>().is_ok())
+                    })
+                    .count();
+                for i in 1..answer_count {
+                    results.push(serde_json::json!({
+                        "question_id": i.to_string(),
+                        "is_correct": true,
+                        "feedback": "Graded in batch.",
+                        "suggested_rating": 4
+                    }));
+                }
+                return Ok(format!(
+                    "```json\n{}\n```",
+                    serde_json::to_string(&results)?
+                ));
+            } else if self.is_mixed_mode() {
                 // Mixed mode: always return exactly 2 results, first correct, second incorrect
                 results.push(serde_json::json!({
                     "question_id": "1",
@@ -545,7 +1174,7 @@ impl MockProvider {
                     self.correct_answers
                 };
 
-                let result = serde_json::json!({
+                let mut result = serde_json::json!({
                     "is_correct": is_correct,
                     "feedback": if is_correct {
                         "Correct answer!"
@@ -554,6 +1183,20 @@ impl MockProvider {
                     },
                     "suggested_rating": if is_correct { 4 } else { 2 }
                 });
+
+                // Incorrect answers only cover the first rubric criterion
+                let criteria = Self::rubric_criteria(prompt);
+                if !criteria.is_empty() {
+                    let scores: Vec<serde_json::Value> = criteria
+                        .iter()
+                        .enumerate()
+                        .map(|(i, criterion)| {
+                            let score = if is_correct || i == 0 { 1.0 } else { 0.0 };
+                            serde_json::json!({ "criterion": criterion, "score": score })
+                        })
+                        .collect();
+                    result["criterion_scores"] = serde_json::Value::Array(scores);
+                }
                 Ok(serde_json::to_string(&result)?)
             } else {
                 // Quiz generation
@@ -582,6 +1225,103 @@ impl MockProvider {
     }
 
     pub fn model_name(&self) -> &str {
-        "mock-model"
+        if self.unavailable {
+            "mock-unavailable"
+        } else {
+            "mock-model"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_decoder_handles_split_lines_and_utf8() {
+        let mut decoder = SseDecoder::default();
+        let event = "data: {\"text\":\"caf\u{e9}\"}\n\n".as_bytes();
+        let split = event.iter().position(|b| *b == 0xC3).unwrap() + 1;
+
+        assert!(decoder.push(&event[..split]).is_empty());
+        assert_eq!(decoder.push(&event[split..]), vec!["{\"text\":\"café\"}"]);
+
+        assert!(
+            decoder
+                .push(b": keep-alive\r\nevent: message\r\n")
+                .is_empty()
+        );
+        assert!(decoder.push(b"data: [DONE]").is_empty());
+        assert_eq!(decoder.finish().as_deref(), Some("[DONE]"));
+    }
+
+    #[test]
+    fn test_openai_stream_event_parsing() {
+        let chunks = parse_openai_stream_event(
+            r#"{"choices":[{"index":0,"delta":{"content":"Hel"}}],"usage":null}"#,
+        )
+        .unwrap();
+        assert_eq!(chunks, vec![LLMStreamChunk::Text("Hel".to_string())]);
+
+        let chunks = parse_openai_stream_event(
+            r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":3,"total_tokens":15}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            chunks,
+            vec![LLMStreamChunk::Usage(TokenUsage {
+                prompt_tokens: 12,
+                completion_tokens: 3
+            })]
+        );
+
+        assert!(parse_openai_stream_event("[DONE]").unwrap().is_empty());
+        assert!(parse_openai_stream_event("{not json").is_err());
+    }
+
+    #[test]
+    fn test_gemini_stream_event_parsing() {
+        let chunks = parse_gemini_stream_event(
+            r#"{"candidates":[{"content":{"parts":[{"text":"Hi"}],"role":"model"}}],"usageMetadata":{"promptTokenCount":5,"candidatesTokenCount":1}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                LLMStreamChunk::Text("Hi".to_string()),
+                LLMStreamChunk::Usage(TokenUsage {
+                    prompt_tokens: 5,
+                    completion_tokens: 1
+                })
+            ]
+        );
+    }
+
+    #[test]
+    fn test_streaming_field_extractor() {
+        let mut extractor = StreamingJsonFieldExtractor::new("feedback");
+        let response = r#"```json
+{"is_correct": true, "feedback": "Use \"quotes\" \u00e9\n\ud83d\ude00 done", "suggested_rating": 3}
+```"#;
+
+        let mut streamed = String::new();
+        for ch in response.chars() {
+            if let Some(text) = extractor.push(&ch.to_string()) {
+                streamed.push_str(&text);
+            }
+        }
+
+        assert_eq!(streamed, "Use \"quotes\" é\n😀 done");
+        assert_eq!(extractor.buffer(), response);
+    }
+
+    #[test]
+    fn test_streaming_field_extractor_ignores_key_inside_other_values() {
+        let mut extractor = StreamingJsonFieldExtractor::new("feedback");
+        assert_eq!(extractor.push(r#"{"note": "no \"feedback\" here", "#), None);
+        assert_eq!(
+            extractor.push(r#""feedback": "Good"}"#).as_deref(),
+            Some("Good")
+        );
     }
 }
@@ -1,11 +1,31 @@
 use anyhow::Result;
-use futures_util::future;
+use futures_util::{StreamExt, future};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use crate::llm_providers::{JsonResponseParser, LLMProvider, LLMProviderFactory, LLMProviderType};
-use crate::models::{BatchGradingRequest, BatchGradingResult, Card, QuizQuestion};
+use crate::card_service::content_hash;
+use crate::cloze::{generate_cloze_questions, is_cloze, reveal};
+use crate::database::Database;
+use crate::llm_fixtures::{FixtureMode, FixtureProvider};
+use crate::llm_providers::{
+    JsonResponseParser, LLMProvider, LLMProviderConfig, LLMProviderFactory, LLMProviderType,
+    LLMStreamChunk, StreamingJsonFieldExtractor,
+};
+use crate::llm_usage::{LLMOperation, UsageTracker};
+use crate::local_grading::grade_locally;
+use crate::models::{BatchGradingRequest, BatchGradingResult, Card, CriterionScore, QuizQuestion};
+use crate::prompt_templates::{PromptKind, PromptLibrary};
+use crate::question_variety::{
+    QuestionHistory, format_flagged_questions, format_question_history, remove_repeated_questions,
+};
+use crate::rubric::{
+    PASSING_SCORE, apply_partial_credit, format_rubric, partial_credit, score_to_rating,
+};
+use crate::token_budget::{TokenBudget, estimate_tokens, pack_by_tokens, truncate_to_tokens};
 
 use serde::{Deserialize, Serialize};
 
@@ -24,12 +44,139 @@ pub struct GradingResult {
     pub is_correct: bool,
     pub feedback: String,
     pub suggested_rating: i32, // 1-4 for FSRS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub served_by: Option<String>, // "provider:model" that produced this grade
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>, // Grading template used, e.g. "grading@v1"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>, // Partial credit from 0.0 to 1.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub criterion_scores: Option<Vec<CriterionScore>>,
+}
+
+impl From<BatchGradingResult> for GradingResult {
+    fn from(result: BatchGradingResult) -> Self {
+        GradingResult {
+            is_correct: result.is_correct,
+            feedback: result.feedback,
+            suggested_rating: result.suggested_rating,
+            served_by: result.served_by,
+            prompt_version: result.prompt_version,
+            score: result.score,
+            criterion_scores: result.criterion_scores,
+        }
+    }
+}
+
+/// Raw LLM response text together with the provider that served it
+struct ServedResponse {
+    text: String,
+    served_by: String,
+}
+
+/// A prompt rendered from a template, with the version to record on its output
+struct RenderedPrompt {
+    prompt: String,
+    system: Option<String>,
+    version: String,
+}
+
+/// Each card's content is truncated so that at least this many cards fit in one batch prompt
+const MIN_CARDS_PER_BATCH: usize = 2;
+
+/// Rough size of the questions generated for one card, reserved for the reply
+const GENERATION_OUTPUT_TOKENS_PER_CARD: usize = 400;
+
+/// Rough size of one batch grading result, reserved for the reply
+const GRADING_OUTPUT_TOKENS_PER_ANSWER: usize = 150;
+
+/// Most of a card's content shown next to each answer in a batch grading prompt
+const GRADING_CONTENT_TOKEN_LIMIT: usize = 1_000;
+
+/// A card prepared for a batch generation prompt
+struct BatchCard {
+    card: Card,
+    history: QuestionHistory,
+    summary: String, // Everything after "Card N: " in the prompt
+}
+
+/// Per-card values available to prompt templates
+struct CardPromptContext {
+    topics: Vec<String>,
+    linked_cards: String,
+    backlinks: String,
+    question_history: QuestionHistory,
+}
+
+impl Default for CardPromptContext {
+    fn default() -> Self {
+        Self {
+            topics: Vec::new(),
+            linked_cards: "none".to_string(),
+            backlinks: "none".to_string(),
+            question_history: QuestionHistory::default(),
+        }
+    }
+}
+
+/// Cards a card links to and cards that link to it
+#[derive(Default)]
+struct RelatedCards {
+    linked: Vec<Card>,
+    backlinks: Vec<Card>,
+}
+
+impl RelatedCards {
+    fn is_empty(&self) -> bool {
+        self.linked.is_empty() && self.backlinks.is_empty()
+    }
+}
+
+/// Generated questions that test how a card relates to a linked card or backlink
+pub const CONNECTION_QUESTION_TYPE: &str = "connection";
+
+/// Linked cards and backlinks summarized in a prompt, in each direction
+const MAX_RELATED_CARDS: usize = 5;
+
+/// Content tokens kept from each related card's summary
+const RELATED_CARD_TOKENS: usize = 80;
+
+/// One line per related card. Summaries give the title and the start of the content;
+/// without them only the title, or failing that the first 80 characters, is shown.
+fn format_related_cards(cards: &[Card], summaries: bool) -> String {
+    if cards.is_empty() {
+        return "none".to_string();
+    }
+
+    cards
+        .iter()
+        .take(MAX_RELATED_CARDS)
+        .map(|card| {
+            let content = reveal(&card.content);
+            let description = match (&card.title, summaries) {
+                (Some(title), true) => format!(
+                    "{} - {}",
+                    title,
+                    truncate_to_tokens(&content, RELATED_CARD_TOKENS)
+                ),
+                (None, true) => truncate_to_tokens(&content, RELATED_CARD_TOKENS).into_owned(),
+                (Some(title), false) => title.clone(),
+                (None, false) => content.chars().take(80).collect(),
+            };
+            format!("- {}: {}", card.zettel_id, description)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 #[derive(Clone)]
 pub struct LLMService {
-    provider: LLMProvider,
+    providers: Vec<LLMProvider>, // Ordered failover chain, primary first
     json_parser: JsonResponseParser,
+    usage_tracker: Option<UsageTracker>,
+    prompts: Arc<PromptLibrary>,
+    card_context: Option<Database>, // Looks up topics and linked cards for prompts
+    linked_context: bool,           // Summarize linked cards and backlinks for connection questions
 }
 
 impl LLMService {
@@ -47,8 +194,30 @@ impl LLMService {
         let provider = LLMProviderFactory::create_provider(provider_type, api_key, base_url, model);
 
         Self {
-            provider,
+            providers: vec![provider],
             json_parser: JsonResponseParser,
+            usage_tracker: None,
+            prompts: Arc::new(PromptLibrary::builtin()),
+            card_context: None,
+            linked_context: false,
+        }
+    }
+
+    /// Create a service that fails over across the given providers in order
+    pub fn new_with_providers(configs: &[LLMProviderConfig]) -> Self {
+        let providers = LLMProviderFactory::create_provider_chain(configs);
+        assert!(
+            !providers.is_empty(),
+            "LLM provider chain must contain at least one provider"
+        );
+
+        Self {
+            providers,
+            json_parser: JsonResponseParser,
+            usage_tracker: None,
+            prompts: Arc::new(PromptLibrary::builtin()),
+            card_context: None,
+            linked_context: false,
         }
     }
 
@@ -62,29 +231,251 @@ impl LLMService {
         )
     }
 
+    /// Record token usage for every call and enforce the tracker's monthly budget
+    pub fn with_usage_tracker(mut self, tracker: UsageTracker) -> Self {
+        self.usage_tracker = Some(tracker);
+        self
+    }
+
+    pub fn usage_tracker(&self) -> Option<&UsageTracker> {
+        self.usage_tracker.as_ref()
+    }
+
+    /// Record every provider's responses to `dir`, or replace the whole chain with
+    /// offline replay of previously recorded responses
+    pub fn with_fixtures(mut self, mode: FixtureMode, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        self.providers = match mode {
+            FixtureMode::Record => self
+                .providers
+                .into_iter()
+                .map(|provider| LLMProvider::Fixture(FixtureProvider::record(provider, &dir)))
+                .collect(),
+            FixtureMode::Replay => vec![LLMProvider::Fixture(FixtureProvider::replay(dir))],
+        };
+        self
+    }
+
+    /// Use deployment-specific prompt templates instead of the built-in ones
+    pub fn with_prompt_library(mut self, prompts: PromptLibrary) -> Self {
+        self.prompts = Arc::new(prompts);
+        self
+    }
+
+    /// Look up card topics and linked cards so templates can use them and
+    /// topic-specific templates can be selected
+    pub fn with_card_context(mut self, db: Database) -> Self {
+        self.card_context = Some(db);
+        self
+    }
+
+    /// Give generation prompts summaries of a card's linked cards and backlinks, and ask
+    /// for a connection question relating the card to one of them. Needs card context.
+    pub fn with_linked_context(mut self, enabled: bool) -> Self {
+        self.linked_context = enabled;
+        self
+    }
+
+    async fn card_topics(&self, card_id: Uuid) -> Vec<String> {
+        let Some(db) = &self.card_context else {
+            return Vec::new();
+        };
+
+        db.get_topic_names_for_card(card_id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(card_id = %card_id, error = %e, "Failed to load card topics for prompt");
+                Vec::new()
+            })
+    }
+
+    async fn card_prompt_context(&self, card: &Card) -> CardPromptContext {
+        if self.card_context.is_none() {
+            return CardPromptContext::default();
+        }
+
+        let related = self.related_cards(card).await;
+        CardPromptContext {
+            topics: self.card_topics(card.id).await,
+            linked_cards: format_related_cards(&related.linked, self.linked_context),
+            backlinks: format_related_cards(&related.backlinks, self.linked_context),
+            question_history: self.card_question_history(card).await,
+        }
+    }
+
+    /// The card's linked cards, plus its backlinks when linked context is enabled
+    async fn related_cards(&self, card: &Card) -> RelatedCards {
+        let Some(db) = &self.card_context else {
+            return RelatedCards::default();
+        };
+
+        let mut related = RelatedCards::default();
+        let links = db.get_links_from(card.id).await.unwrap_or_else(|e| {
+            warn!(card_id = %card.id, error = %e, "Failed to load links for prompt");
+            Vec::new()
+        });
+        for link in links {
+            if let Ok(Some(linked_card)) = db.get_card(link.target_card_id).await {
+                related.linked.push(linked_card);
+            }
+        }
+
+        if self.linked_context {
+            related.backlinks = db.get_backlinks(card.id).await.unwrap_or_else(|e| {
+                warn!(card_id = %card.id, error = %e, "Failed to load backlinks for prompt");
+                Vec::new()
+            });
+        }
+        related
+    }
+
+    /// The card's banked questions for its current content, most recently asked first
+    async fn card_question_history(&self, card: &Card) -> QuestionHistory {
+        let Some(db) = &self.card_context else {
+            return QuestionHistory::default();
+        };
+
+        match db
+            .get_banked_questions(card.id, &content_hash(&card.content))
+            .await
+        {
+            Ok(banked) => QuestionHistory::from_banked(banked),
+            Err(e) => {
+                warn!(card_id = %card.id, error = %e, "Failed to load question history for prompt");
+                QuestionHistory::default()
+            }
+        }
+    }
+
+    fn render_prompt(
+        &self,
+        kind: PromptKind,
+        topics: &[String],
+        variables: &[(&str, &str)],
+    ) -> RenderedPrompt {
+        let template = self.prompts.select(kind, topics);
+        let topic_list = if topics.is_empty() {
+            "none".to_string()
+        } else {
+            topics.join(", ")
+        };
+        let mut all_variables = variables.to_vec();
+        all_variables.push(("topics", topic_list.as_str()));
+
+        RenderedPrompt {
+            prompt: template.render(&all_variables),
+            system: template.system.clone(),
+            version: template.version_id(),
+        }
+    }
+
     #[allow(dead_code)]
-    async fn make_llm_request(&self, prompt: &str) -> Result<String> {
-        self.make_llm_request_with_system(None, prompt).await
+    async fn make_llm_request(&self, prompt: &str, operation: LLMOperation) -> Result<String> {
+        Ok(self
+            .make_llm_request_with_system(None, prompt, operation)
+            .await?
+            .text)
     }
 
+    /// Send the request to each provider in the chain until one succeeds
     async fn make_llm_request_with_system(
         &self,
         system_message: Option<&str>,
         prompt: &str,
-    ) -> Result<String> {
-        self.provider.make_request(system_message, prompt).await
+        operation: LLMOperation,
+    ) -> Result<ServedResponse> {
+        if let Some(tracker) = &self.usage_tracker {
+            tracker.check_budget().await?;
+        }
+
+        let mut last_error = None;
+
+        for (attempt, provider) in self.providers.iter().enumerate() {
+            let started = Instant::now();
+            let result = provider
+                .make_request_with_usage(system_message, prompt)
+                .await;
+
+            if let Some(tracker) = &self.usage_tracker {
+                tracker
+                    .record(
+                        provider.provider_name(),
+                        provider.model_name(),
+                        operation,
+                        result.as_ref().ok().and_then(|c| c.usage),
+                        started.elapsed().as_millis() as u64,
+                        result.is_ok(),
+                    )
+                    .await;
+            }
+
+            match result {
+                Ok(completion) => {
+                    let text = completion.text;
+                    let served_by =
+                        format!("{}:{}", provider.provider_name(), provider.model_name());
+                    if attempt > 0 {
+                        info!(
+                            served_by = %served_by,
+                            attempt = attempt + 1,
+                            "LLM request served by failover provider"
+                        );
+                    } else {
+                        debug!(served_by = %served_by, "LLM request served by primary provider");
+                    }
+                    return Ok(ServedResponse { text, served_by });
+                }
+                Err(e) => {
+                    warn!(
+                        provider = provider.provider_name(),
+                        model = %provider.model_name(),
+                        attempt = attempt + 1,
+                        remaining_providers = self.providers.len() - attempt - 1,
+                        error = %e,
+                        "LLM provider request failed"
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        let error = last_error.unwrap_or_else(|| anyhow::anyhow!("No LLM providers configured"));
+        if self.providers.len() > 1 {
+            Err(anyhow::anyhow!(
+                "All {} LLM providers failed, last error: {}",
+                self.providers.len(),
+                error
+            ))
+        } else {
+            Err(error)
+        }
     }
 
     /// Get the provider name for logging and testing
     #[allow(dead_code)]
     pub fn provider_name(&self) -> &'static str {
-        self.provider.provider_name()
+        self.providers[0].provider_name()
     }
 
     /// Get the model name being used
     #[allow(dead_code)]
     pub fn model_name(&self) -> &str {
-        self.provider.model_name()
+        self.providers[0].model_name()
+    }
+
+    /// Token budget that every provider in the failover chain can serve, so a request
+    /// that fails over still fits the fallback model
+    fn token_budget(&self) -> TokenBudget {
+        TokenBudget::for_models(self.providers.iter().map(|p| p.model_name()))
+    }
+
+    /// Names of all providers in the failover chain, in order
+    #[allow(dead_code)]
+    pub fn provider_chain(&self) -> Vec<String> {
+        self.providers
+            .iter()
+            .map(|p| format!("{}:{}", p.provider_name(), p.model_name()))
+            .collect()
     }
 
     #[cfg(test)]
@@ -114,8 +505,66 @@ impl LLMService {
         let json_parser = JsonResponseParser::new();
 
         Self {
-            provider,
+            providers: vec![provider],
             json_parser,
+            usage_tracker: None,
+            prompts: Arc::new(PromptLibrary::builtin()),
+            card_context: None,
+            linked_context: false,
+        }
+    }
+
+    /// Mock service whose batch grading replies leave out the last answer of each prompt
+    #[cfg(test)]
+    #[allow(dead_code)]
+    pub fn new_mock_with_short_batches() -> Self {
+        use crate::llm_providers::MockProvider;
+
+        Self {
+            providers: vec![LLMProvider::Mock(MockProvider::new_short_batches())],
+            json_parser: JsonResponseParser::new(),
+            usage_tracker: None,
+            prompts: Arc::new(PromptLibrary::builtin()),
+            card_context: None,
+            linked_context: false,
+        }
+    }
+
+    /// Mock service whose primary provider is down, forcing failover to a healthy mock
+    #[cfg(test)]
+    #[allow(dead_code)]
+    pub fn new_mock_with_failover() -> Self {
+        use crate::llm_providers::MockProvider;
+
+        Self {
+            providers: vec![
+                LLMProvider::Mock(MockProvider::new_unavailable()),
+                LLMProvider::Mock(MockProvider::new(true, false)),
+            ],
+            json_parser: JsonResponseParser::new(),
+            usage_tracker: None,
+            prompts: Arc::new(PromptLibrary::builtin()),
+            card_context: None,
+            linked_context: false,
+        }
+    }
+
+    /// Mock service where every provider in the chain is down
+    #[cfg(test)]
+    #[allow(dead_code)]
+    pub fn new_mock_all_unavailable() -> Self {
+        use crate::llm_providers::MockProvider;
+
+        Self {
+            providers: vec![
+                LLMProvider::Mock(MockProvider::new_unavailable()),
+                LLMProvider::Mock(MockProvider::new_unavailable()),
+            ],
+            json_parser: JsonResponseParser::new(),
+            usage_tracker: None,
+            prompts: Arc::new(PromptLibrary::builtin()),
+            card_context: None,
+            linked_context: false,
         }
     }
 
@@ -128,8 +577,12 @@ impl LLMService {
         let json_parser = JsonResponseParser::new();
 
         Self {
-            provider,
+            providers: vec![provider],
             json_parser,
+            usage_tracker: None,
+            prompts: Arc::new(PromptLibrary::builtin()),
+            card_context: None,
+            linked_context: false,
         }
     }
 
@@ -141,40 +594,73 @@ impl LLMService {
             "Generating quiz questions for card"
         );
 
-        let prompt = format!(
-            r#"Based on the following learning card content, generate 2-3 quiz questions to test understanding.
-            The questions should be varied in type (multiple choice, fill in the blank, short answer, or problem-solving).
-
-            Card Content:
-            {}
-
-            Please respond with a JSON object in this exact format:
-            {{
-                "questions": [
-                    {{
-                        "question": "Question text here",
-                        "question_type": "multiple_choice|short_answer|problem_solving",
-                        "options": ["Option text 1", "Option text 2", "Option text 3", "Option text 4"] or null,
-                        "correct_answer": "Correct answer or option letter"
-                    }}
-                ]
-            }}
-
-            Guidelines:
-            - Questions should be geared towards helping with spaced repetition: focused, precise, consistent, tractable, and effortful
-            - For multiple_choice, provide 4 option texts WITHOUT any letter prefixes (A., B., etc.) - just the option content
-            - The frontend will automatically add A., B., C., D. prefixes when displaying
-            - For short_answer, provide the expected answer
-            - For problem_solving, provide the solution approach
-            - Make questions challenging but fair
-            - The questions should emphasize practical, real world applications of the concepts, particurlarly when applied to machine learning or computer science.
-            - Ensure questions test key concepts from the card"#,
-            card.content
+        if is_cloze(&card.content) {
+            debug!(card_id = %card.id, "Generating cloze questions locally");
+            return Ok(generate_cloze_questions(&card.content));
+        }
+
+        let mut context = self.card_prompt_context(card).await;
+        let questions = self.request_quiz_questions(card, &context).await?;
+
+        let mut avoid = context.question_history.all_questions();
+        let (accepted, rejected) = remove_repeated_questions(questions, &avoid);
+        if !accepted.is_empty() {
+            if !rejected.is_empty() {
+                debug!(
+                    card_id = %card.id,
+                    rejected_count = rejected.len(),
+                    "Dropped generated questions that repeat earlier ones"
+                );
+            }
+            return Ok(accepted);
+        }
+
+        // Every question repeated an earlier one: ask once more with the repeats added
+        warn!(
+            card_id = %card.id,
+            rejected_count = rejected.len(),
+            "All generated questions repeat earlier ones, retrying generation"
         );
+        avoid.extend(rejected.iter().cloned());
+        context.question_history.asked.extend(rejected);
+        let retry = self.request_quiz_questions(card, &context).await?;
+        let (accepted, _) = remove_repeated_questions(retry.clone(), &avoid);
+        if accepted.is_empty() {
+            warn!(card_id = %card.id, "Generator kept repeating earlier questions, using its last attempt");
+            return Ok(retry);
+        }
+        Ok(accepted)
+    }
 
-        let system_message = "You are a university professor. Always respond with valid JSON in the requested format.";
-        let response_text = self
-            .make_llm_request_with_system(Some(system_message), &prompt)
+    async fn request_quiz_questions(
+        &self,
+        card: &Card,
+        context: &CardPromptContext,
+    ) -> Result<Vec<QuizQuestion>> {
+        let question_history = format_question_history(&context.question_history.asked);
+        let flagged_questions = format_flagged_questions(&context.question_history.flagged);
+        let rendered = self.render_prompt(
+            PromptKind::QuizGeneration,
+            &context.topics,
+            &[
+                ("card_content", card.content.as_str()),
+                ("zettel_id", card.zettel_id.as_str()),
+                ("linked_cards", context.linked_cards.as_str()),
+                ("backlinks", context.backlinks.as_str()),
+                ("question_history", question_history.as_str()),
+                ("flagged_questions", flagged_questions.as_str()),
+            ],
+        );
+
+        let ServedResponse {
+            text: response_text,
+            served_by,
+        } = self
+            .make_llm_request_with_system(
+                rendered.system.as_deref(),
+                &rendered.prompt,
+                LLMOperation::Generate,
+            )
             .await?;
 
         {
@@ -199,9 +685,15 @@ impl LLMService {
                     info!(
                         card_id = %card.id,
                         question_count = generated_quiz.questions.len(),
+                        served_by = %served_by,
+                        prompt_version = %rendered.version,
                         "Successfully generated quiz questions"
                     );
-                    Ok(generated_quiz.questions)
+                    let mut questions = generated_quiz.questions;
+                    for question in &mut questions {
+                        question.prompt_version = Some(rendered.version.clone());
+                    }
+                    Ok(questions)
                 }
                 Err(e) => {
                     error!(
@@ -224,80 +716,161 @@ impl LLMService {
             return Ok(HashMap::new());
         }
 
-        info!(
-            card_count = cards.len(),
-            card_ids = ?cards.iter().map(|c| c.id).collect::<Vec<_>>(),
-            "Generating batch quiz questions for multiple cards"
+        // Cloze cards carry their own questions and never reach the LLM
+        let mut result = HashMap::new();
+        let (cloze_cards, cards): (Vec<&Card>, Vec<&Card>) =
+            cards.iter().partition(|card| is_cloze(&card.content));
+        for card in cloze_cards {
+            result.insert(card.id, generate_cloze_questions(&card.content));
+        }
+
+        // Cards whose topics select different templates are generated in separate batches
+        let mut groups: Vec<(String, Vec<String>, Vec<Card>)> = Vec::new();
+        for card in cards {
+            let topics = self.card_topics(card.id).await;
+            let version = self
+                .prompts
+                .select(PromptKind::BatchQuizGeneration, &topics)
+                .version_id();
+            match groups.iter_mut().find(|(v, _, _)| *v == version) {
+                Some((_, _, group)) => group.push(card.clone()),
+                None => groups.push((version, topics, vec![card.clone()])),
+            }
+        }
+
+        let budget = self.token_budget();
+        for (_, topics, group) in groups {
+            // The template itself and the reply to every card share the context window
+            let overhead = self.prompt_overhead(PromptKind::BatchQuizGeneration, &topics);
+            let available = budget.context_window.saturating_sub(overhead);
+            let content_limit =
+                (available / MIN_CARDS_PER_BATCH).saturating_sub(GENERATION_OUTPUT_TOKENS_PER_CARD);
+
+            let mut prepared = Vec::with_capacity(group.len());
+            for card in group {
+                prepared.push(self.prepare_batch_card(card, content_limit).await);
+            }
+
+            let batches = pack_by_tokens(
+                prepared,
+                |c| estimate_tokens(&c.summary) + GENERATION_OUTPUT_TOKENS_PER_CARD,
+                available,
+                budget.max_output_tokens / GENERATION_OUTPUT_TOKENS_PER_CARD,
+            );
+            if batches.len() > 1 {
+                info!(
+                    batch_count = batches.len(),
+                    model = %self.model_name(),
+                    "Split quiz generation into batches that fit the model's context window"
+                );
+            }
+            for batch in batches {
+                result.extend(self.generate_quiz_batch(batch, &topics).await?);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Estimated tokens a template takes up before any per-card text is added
+    fn prompt_overhead(&self, kind: PromptKind, topics: &[String]) -> usize {
+        let empty: Vec<(&str, &str)> = kind.variables().iter().map(|&v| (v, "")).collect();
+        let rendered = self.render_prompt(kind, topics, &empty);
+        estimate_tokens(&rendered.prompt) + rendered.system.as_deref().map_or(0, estimate_tokens)
+    }
+
+    /// Describe a card for a batch generation prompt, with its content cut to
+    /// `content_limit` tokens and its question history attached
+    async fn prepare_batch_card(&self, card: Card, content_limit: usize) -> BatchCard {
+        let history = self.card_question_history(&card).await;
+        let content = truncate_to_tokens(&card.content, content_limit);
+        if content.len() < card.content.len() {
+            warn!(
+                card_id = %card.id,
+                content_tokens = estimate_tokens(&card.content),
+                content_limit = content_limit,
+                "Card content truncated to fit the batch prompt"
+            );
+        }
+
+        let mut summary = format!(
+            "ID={}, Zettel_ID={}, Content={}",
+            card.id, card.zettel_id, content
         );
+        if self.linked_context {
+            let related = self.related_cards(&card).await;
+            if !related.linked.is_empty() {
+                summary.push_str("\nLinked cards:\n");
+                summary.push_str(&format_related_cards(&related.linked, true));
+            }
+            if !related.backlinks.is_empty() {
+                summary.push_str("\nCards linking here:\n");
+                summary.push_str(&format_related_cards(&related.backlinks, true));
+            }
+        }
+        if !history.asked.is_empty() {
+            summary.push_str("\nExisting questions:\n");
+            summary.push_str(&format_question_history(&history.asked));
+        }
+        if !history.flagged.is_empty() {
+            summary.push_str("\nFlagged by reviewers:\n");
+            summary.push_str(&format_flagged_questions(&history.flagged));
+        }
+
+        BatchCard {
+            card,
+            history,
+            summary,
+        }
+    }
 
-        // Create card summaries for the prompt
-        let card_summaries = cards
+    async fn generate_quiz_batch(
+        &self,
+        batch: Vec<BatchCard>,
+        topics: &[String],
+    ) -> Result<HashMap<Uuid, Vec<QuizQuestion>>> {
+        let card_summaries = batch
             .iter()
             .enumerate()
-            .map(|(i, card)| {
-                format!(
-                    "Card {}: ID={}, Zettel_ID={}, Content={}",
-                    i + 1,
-                    card.id,
-                    card.zettel_id,
-                    // Truncate content to avoid overly long prompts
-                    if card.content.len() > 500 {
-                        format!("{}...", &card.content[..500])
-                    } else {
-                        card.content.clone()
-                    }
-                )
-            })
+            .map(|(i, c)| format!("Card {}: {}", i + 1, c.summary))
             .collect::<Vec<_>>()
             .join("\n\n");
+        let mut cards = Vec::with_capacity(batch.len());
+        let mut histories = HashMap::new();
+        for c in batch {
+            histories.insert(c.card.id, c.history);
+            cards.push(c.card);
+        }
+
+        info!(
+            card_count = cards.len(),
+            card_ids = ?cards.iter().map(|c| c.id).collect::<Vec<_>>(),
+            "Generating batch quiz questions for multiple cards"
+        );
 
-        let prompt = format!(
-            r#"Generate 2-3 quiz questions for each of the following learning cards. The questions should be varied in type (multiple choice, short answer, or problem-solving) and test key concepts.
-
-Cards:
-{}
-
-Please respond with a JSON object in this exact format:
-{{
-    "results": {{
-        "{}": [
-            {{
-                "question": "Question text here",
-                "question_type": "multiple_choice|short_answer|problem_solving",
-                "options": ["Option text 1", "Option text 2", "Option text 3", "Option text 4"] or null,
-                "correct_answer": "Correct answer or option letter"
-            }}
-        ],
-        "{}": [
-            {{
-                "question": "Question text here",
-                "question_type": "multiple_choice|short_answer|problem_solving", 
-                "options": ["Option text 1", "Option text 2", "Option text 3", "Option text 4"] or null,
-                "correct_answer": "Correct answer or option letter"
-            }}
-        ]
-    }}
-}}
-
-Guidelines:
-- For multiple_choice, provide 4 option texts WITHOUT any letter prefixes (A., B., etc.) - just the option content
-- The frontend will automatically add A., B., C., D. prefixes when displaying
-- For short_answer, provide the expected answer
-- For problem_solving, provide the solution approach
-- Make questions challenging but fair
-- Ensure questions test key concepts from each card
-- Use the exact card IDs provided above as keys in the results object"#,
-            card_summaries,
-            cards.first().map(|c| c.id.to_string()).unwrap_or_default(),
-            cards
-                .get(1)
-                .map(|c| c.id.to_string())
-                .unwrap_or(cards.first().map(|c| c.id.to_string()).unwrap_or_default())
+        let first_card_id = cards.first().map(|c| c.id.to_string()).unwrap_or_default();
+        let second_card_id = cards
+            .get(1)
+            .map(|c| c.id.to_string())
+            .unwrap_or_else(|| first_card_id.clone());
+        let rendered = self.render_prompt(
+            PromptKind::BatchQuizGeneration,
+            topics,
+            &[
+                ("cards", card_summaries.as_str()),
+                ("first_card_id", first_card_id.as_str()),
+                ("second_card_id", second_card_id.as_str()),
+            ],
         );
 
-        let system_message = "You are a university professor creating quiz questions. Always respond with valid JSON in the exact requested format. Use the provided card IDs as keys.";
-        let response_text = self
-            .make_llm_request_with_system(Some(system_message), &prompt)
+        let ServedResponse {
+            text: response_text,
+            served_by,
+        } = self
+            .make_llm_request_with_system(
+                rendered.system.as_deref(),
+                &rendered.prompt,
+                LLMOperation::BatchGenerate,
+            )
             .await?;
 
         {
@@ -321,9 +894,23 @@ Guidelines:
                 Ok(batch_quiz) => {
                     // Convert string keys to UUIDs
                     let mut result = HashMap::new();
-                    for (card_id_str, questions) in batch_quiz.results {
+                    let mut repeated_cards = Vec::new();
+                    for (card_id_str, mut questions) in batch_quiz.results {
                         if let Ok(card_id) = Uuid::parse_str(&card_id_str) {
-                            result.insert(card_id, questions);
+                            for question in &mut questions {
+                                question.prompt_version = Some(rendered.version.clone());
+                            }
+                            let avoid = histories
+                                .get(&card_id)
+                                .map(QuestionHistory::all_questions)
+                                .unwrap_or_default();
+                            let (accepted, _) = remove_repeated_questions(questions, &avoid);
+                            if accepted.is_empty() {
+                                repeated_cards
+                                    .extend(cards.iter().find(|c| c.id == card_id).cloned());
+                            } else {
+                                result.insert(card_id, accepted);
+                            }
                         } else {
                             warn!(
                                 card_id_str = %card_id_str,
@@ -336,8 +923,22 @@ Guidelines:
                         card_count = cards.len(),
                         generated_count = result.len(),
                         total_questions = result.values().map(|q| q.len()).sum::<usize>(),
+                        served_by = %served_by,
+                        prompt_version = %rendered.version,
                         "Successfully generated batch quiz questions"
                     );
+
+                    // Cards whose questions all repeated earlier ones get a dedicated retry
+                    if !repeated_cards.is_empty() {
+                        warn!(
+                            card_count = repeated_cards.len(),
+                            "Batch questions repeated earlier ones, regenerating individually"
+                        );
+                        result.extend(
+                            self.fallback_to_individual_generation(&repeated_cards)
+                                .await?,
+                        );
+                    }
                     Ok(result)
                 }
                 Err(e) => {
@@ -348,7 +949,7 @@ Guidelines:
                         "Failed to parse batch quiz generation JSON response"
                     );
                     // Fallback to individual generation
-                    self.fallback_to_individual_generation(cards).await
+                    self.fallback_to_individual_generation(&cards).await
                 }
             }
         }
@@ -410,100 +1011,238 @@ Guidelines:
             user_answer = %user_answer,
             "Grading quiz answer"
         );
-        let prompt = format!(
-            r#"Grade the following quiz answer based on semantic understanding and conceptual accuracy, not just literal text matching.
-
-            Card Content:
-            {}
-
-            Question: {}
-            Question Type: {}
-            Correct Answer: {}
-            User's Answer: {}
-
-            GRADING PRINCIPLES:
-            - Accept semantically equivalent answers (synonyms, paraphrasing, different valid explanations)
-            - For multiple choice: Accept the correct option letter OR the full option text
-            - For numerical answers: Accept equivalent forms (0.5 = 1/2 = 50%)
-            - For short answers: Focus on key concepts rather than exact wording
-            - Consider context from the card content when evaluating answers
-            - Give credit for partially correct answers that show understanding
-
-            EXAMPLES OF EQUIVALENT ANSWERS:
-            - "Quick" = "Fast" = "Rapid" (synonyms)
-            - "Machine Learning" = "ML" (abbreviations)  
-            - "Because it increases efficiency" = "It makes things more efficient" (paraphrasing)
-            - "Option A" = "A" = "[Full text of option A]" (multiple choice formats)
-
-            GRADING CRITERIA:
-            - CORRECT (is_correct: true): Answer demonstrates understanding of key concepts, even if wording differs
-            - INCORRECT (is_correct: false): Answer shows fundamental misunderstanding or is completely wrong
-
-            Please respond with a JSON object in this exact format:
-            {{
-                "is_correct": true|false,
-                "feedback": "Specific feedback explaining the evaluation, mentioning what was correct/incorrect and providing the complete correct information",
-                "suggested_rating": 1|2|3|4
-            }}
-
-            Rating Guidelines (be generous for conceptually correct answers):
-            - 1 (Again): Fundamentally wrong or no understanding demonstrated
-            - 2 (Hard): Shows some understanding but with significant conceptual errors
-            - 3 (Good): Correct understanding with minor wording differences or small omissions
-            - 4 (Easy): Perfect or excellent answer with clear mastery
-
-            Focus on conceptual understanding rather than exact text matching. When in doubt between correct/incorrect, lean toward giving credit if the core concept is understood."#,
-            card.content,
-            question.question,
-            question.question_type,
-            question.correct_answer.as_deref().unwrap_or("N/A"),
-            user_answer
-        );
+        if let Some(result) = grade_locally(question, user_answer) {
+            return Ok(result);
+        }
 
-        let system_message = "You are an expert teacher focused on fair, understanding-based grading. Prioritize semantic meaning over exact text matching. Accept equivalent answers that demonstrate understanding. Always respond with valid JSON in the requested format.";
-        let response_text = self
-            .make_llm_request_with_system(Some(system_message), &prompt)
+        let rendered = self.grading_prompt(card, question, user_answer).await;
+        let ServedResponse {
+            text: response_text,
+            served_by,
+        } = self
+            .make_llm_request_with_system(
+                rendered.system.as_deref(),
+                &rendered.prompt,
+                LLMOperation::Grade,
+            )
             .await?;
 
-        {
-            debug!(
-                card_id = %card.id,
-                response_content = %response_text,
-                "Raw LLM response for answer grading"
-            );
+        self.parse_grading_response(card, question, &response_text, served_by, rendered.version)
+    }
 
-            let json_content = JsonResponseParser::extract_json_from_response(&response_text);
-            debug!(
-                card_id = %card.id,
-                extracted_json = %json_content,
-                "Extracted JSON from grading response"
-            );
+    /// Grade an answer while streaming the feedback text to `on_feedback` as it is
+    /// generated. The full response is still parsed into a `GradingResult` at the end.
+    pub async fn grade_answer_streaming<F>(
+        &self,
+        card: &Card,
+        question: &QuizQuestion,
+        user_answer: &str,
+        mut on_feedback: F,
+    ) -> Result<GradingResult>
+    where
+        F: FnMut(&str) + Send,
+    {
+        info!(
+            card_id = %card.id,
+            card_zettel_id = %card.zettel_id,
+            question_type = %question.question_type,
+            "Grading quiz answer with streaming feedback"
+        );
+        if let Some(result) = grade_locally(question, user_answer) {
+            on_feedback(&result.feedback);
+            return Ok(result);
+        }
 
-            match self
-                .json_parser
-                .parse_json_response::<GradingResult>(&response_text)
-            {
-                Ok(grading_result) => {
-                    info!(
-                        card_id = %card.id,
-                        is_correct = grading_result.is_correct,
-                        suggested_rating = grading_result.suggested_rating,
-                        feedback = %grading_result.feedback.chars().take(100).collect::<String>(),
-                        "Successfully graded quiz answer"
+        let rendered = self.grading_prompt(card, question, user_answer).await;
+
+        if let Some(tracker) = &self.usage_tracker {
+            tracker.check_budget().await?;
+        }
+
+        let mut last_error = None;
+
+        for (attempt, provider) in self.providers.iter().enumerate() {
+            let started = Instant::now();
+            let mut usage = None;
+            let mut extractor = StreamingJsonFieldExtractor::new("feedback");
+            let mut emitted_feedback = false;
+
+            let outcome: Result<()> = async {
+                let mut stream = provider
+                    .make_request_stream(rendered.system.as_deref(), &rendered.prompt)
+                    .await?;
+                while let Some(chunk) = stream.next().await {
+                    match chunk? {
+                        LLMStreamChunk::Text(text) => {
+                            if let Some(feedback) = extractor.push(&text) {
+                                emitted_feedback = true;
+                                on_feedback(&feedback);
+                            }
+                        }
+                        LLMStreamChunk::Usage(reported) => usage = Some(reported),
+                    }
+                }
+                Ok(())
+            }
+            .await;
+
+            if let Some(tracker) = &self.usage_tracker {
+                tracker
+                    .record(
+                        provider.provider_name(),
+                        provider.model_name(),
+                        LLMOperation::Grade,
+                        usage,
+                        started.elapsed().as_millis() as u64,
+                        outcome.is_ok(),
+                    )
+                    .await;
+            }
+
+            match outcome {
+                Ok(()) => {
+                    let served_by =
+                        format!("{}:{}", provider.provider_name(), provider.model_name());
+                    debug!(
+                        served_by = %served_by,
+                        attempt = attempt + 1,
+                        "Streaming grading response completed"
+                    );
+                    return self.parse_grading_response(
+                        card,
+                        question,
+                        extractor.buffer(),
+                        served_by,
+                        rendered.version,
                     );
-                    Ok(grading_result)
                 }
                 Err(e) => {
-                    error!(
-                        card_id = %card.id,
+                    warn!(
+                        provider = provider.provider_name(),
+                        model = %provider.model_name(),
+                        attempt = attempt + 1,
+                        emitted_feedback = emitted_feedback,
                         error = %e,
-                        json_content = %json_content,
-                        "Failed to parse grading JSON response"
+                        "Streaming LLM request failed"
                     );
-                    Err(anyhow::anyhow!("Failed to parse grading JSON: {}", e))
+                    // Feedback already shown to the user cannot be replaced by another provider's
+                    if emitted_feedback {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
                 }
             }
         }
+
+        let error = last_error.unwrap_or_else(|| anyhow::anyhow!("No LLM providers configured"));
+        if self.providers.len() > 1 {
+            Err(anyhow::anyhow!(
+                "All {} LLM providers failed, last error: {}",
+                self.providers.len(),
+                error
+            ))
+        } else {
+            Err(error)
+        }
+    }
+
+    async fn grading_prompt(
+        &self,
+        card: &Card,
+        question: &QuizQuestion,
+        user_answer: &str,
+    ) -> RenderedPrompt {
+        let topics = self.card_topics(card.id).await;
+        let mut card_content = reveal(&card.content);
+        // Connection questions are answered from the related cards as well
+        if question.question_type == CONNECTION_QUESTION_TYPE {
+            let related = self.related_cards(card).await;
+            if !related.is_empty() {
+                let mut with_related = card_content.into_owned();
+                for (heading, cards) in [
+                    ("Linked cards", &related.linked),
+                    ("Cards linking here", &related.backlinks),
+                ] {
+                    if !cards.is_empty() {
+                        with_related.push_str(&format!(
+                            "\n\n{}:\n{}",
+                            heading,
+                            format_related_cards(cards, true)
+                        ));
+                    }
+                }
+                card_content = with_related.into();
+            }
+        }
+        let rubric = format_rubric(question.rubric.as_deref());
+        self.render_prompt(
+            PromptKind::Grading,
+            &topics,
+            &[
+                ("card_content", card_content.as_ref()),
+                ("question", question.question.as_str()),
+                ("question_type", question.question_type.as_str()),
+                (
+                    "correct_answer",
+                    question.correct_answer.as_deref().unwrap_or("N/A"),
+                ),
+                ("user_answer", user_answer),
+                ("rubric", rubric.as_str()),
+            ],
+        )
+    }
+
+    fn parse_grading_response(
+        &self,
+        card: &Card,
+        question: &QuizQuestion,
+        response_text: &str,
+        served_by: String,
+        prompt_version: String,
+    ) -> Result<GradingResult> {
+        debug!(
+            card_id = %card.id,
+            response_content = %response_text,
+            "Raw LLM response for answer grading"
+        );
+
+        let json_content = JsonResponseParser::extract_json_from_response(response_text);
+        debug!(
+            card_id = %card.id,
+            extracted_json = %json_content,
+            "Extracted JSON from grading response"
+        );
+
+        match self
+            .json_parser
+            .parse_json_response::<GradingResult>(response_text)
+        {
+            Ok(mut grading_result) => {
+                apply_partial_credit(&mut grading_result, question.rubric.as_deref());
+                info!(
+                    card_id = %card.id,
+                    is_correct = grading_result.is_correct,
+                    suggested_rating = grading_result.suggested_rating,
+                    score = ?grading_result.score,
+                    feedback = %grading_result.feedback.chars().take(100).collect::<String>(),
+                    served_by = %served_by,
+                    prompt_version = %prompt_version,
+                    "Successfully graded quiz answer"
+                );
+                grading_result.served_by = Some(served_by);
+                grading_result.prompt_version = Some(prompt_version);
+                Ok(grading_result)
+            }
+            Err(e) => {
+                error!(
+                    card_id = %card.id,
+                    error = %e,
+                    json_content = %json_content,
+                    "Failed to parse grading JSON response"
+                );
+                Err(anyhow::anyhow!("Failed to parse grading JSON: {}", e))
+            }
+        }
     }
 
     // Alternative method for local/offline LLM integration
@@ -522,6 +1261,8 @@ Guidelines:
             question_type: "short_answer".to_string(),
             options: None,
             correct_answer: Some("Based on the card content".to_string()),
+            prompt_version: None,
+            rubric: None,
         }])
     }
 
@@ -539,82 +1280,161 @@ Guidelines:
             "Grading batch of quiz answers"
         );
 
-        let questions_and_answers = grading_requests.iter().enumerate().map(|(i, req)| {
-            format!(
-                "{}. Card Content: {}\n   Question: {}\n   Question Type: {}\n   Correct Answer: {}\n   User Answer: {}",
-                i + 1,
-                // Truncate card content for prompt efficiency
-                if req.card_content.len() > 300 {
-                    format!("{}...", &req.card_content[..300])
-                } else {
-                    req.card_content.clone()
-                },
-                req.question.question,
-                req.question.question_type,
-                req.question.correct_answer.as_deref().unwrap_or("N/A"),
-                req.user_answer
-            )
-        }).collect::<Vec<_>>().join("\n\n");
-
-        let prompt = format!(
-            r#"Grade the following quiz answers based on semantic understanding and conceptual accuracy, not just literal text matching.
-
-Questions and Answers:
-{}
-
-GRADING PRINCIPLES:
-- Accept semantically equivalent answers (synonyms, paraphrasing, different valid explanations)
-- For multiple choice: Accept the correct option letter OR the full option text
-- For numerical answers: Accept equivalent forms (0.5 = 1/2 = 50%)
-- For short answers: Focus on key concepts rather than exact wording
-- Consider context from the card content when evaluating answers
-- Give credit for partially correct answers that show understanding
-
-EXAMPLES OF EQUIVALENT ANSWERS:
-- "Quick" = "Fast" = "Rapid" (synonyms)
-- "Machine Learning" = "ML" (abbreviations)
-- "Because it increases efficiency" = "It makes things more efficient" (paraphrasing)
-- "Option A" = "A" = "[Full text of option A]" (multiple choice formats)
-
-Please respond with a JSON array in this exact format:
-[
-    {{
-        "question_id": "1",
-        "is_correct": true|false,
-        "feedback": "Specific feedback explaining the evaluation, mentioning what was correct/incorrect",
-        "suggested_rating": 1|2|3|4
-    }},
-    {{
-        "question_id": "2",
-        "is_correct": true|false,
-        "feedback": "Specific feedback...",
-        "suggested_rating": 1|2|3|4
-    }}
-]
-
-Rating Guidelines (be generous for conceptually correct answers):
-- 1 (Again): Fundamentally wrong or no understanding demonstrated
-- 2 (Hard): Shows some understanding but with significant conceptual errors
-- 3 (Good): Correct understanding with minor wording differences or small omissions
-- 4 (Easy): Perfect or excellent answer with clear mastery
-
-Focus on conceptual understanding rather than exact text matching."#,
-            questions_and_answers
+        // Answers that can be graded locally are kept out of the prompt and merged back
+        // into their original positions afterwards
+        let local: Vec<Option<GradingResult>> = grading_requests
+            .iter()
+            .map(|req| grade_locally(&req.question, &req.user_answer))
+            .collect();
+        if local.iter().any(Option::is_some) {
+            let (remaining_indices, remaining): (Vec<usize>, Vec<BatchGradingRequest>) =
+                grading_requests
+                    .iter()
+                    .zip(&local)
+                    .enumerate()
+                    .filter(|(_, (_, graded))| graded.is_none())
+                    .map(|(i, (req, _))| (i, req.clone()))
+                    .unzip();
+            // The LLM numbers the remaining answers 1..n; match its results by that
+            // number so a skipped answer is graded individually rather than lost
+            let llm_results = Box::pin(self.grade_batch_answers(&remaining)).await?;
+            let llm_results = self.align_batch_results(llm_results, &remaining).await?;
+
+            let mut merged: Vec<Option<BatchGradingResult>> = local
+                .into_iter()
+                .map(|graded| {
+                    graded.map(|graded| BatchGradingResult {
+                        question_id: String::new(),
+                        is_correct: graded.is_correct,
+                        feedback: graded.feedback,
+                        suggested_rating: graded.suggested_rating,
+                        served_by: graded.served_by,
+                        prompt_version: graded.prompt_version,
+                        score: graded.score,
+                        criterion_scores: graded.criterion_scores,
+                    })
+                })
+                .collect();
+            for (i, result) in remaining_indices.into_iter().zip(llm_results) {
+                merged[i] = Some(result);
+            }
+
+            return merged
+                .into_iter()
+                .enumerate()
+                .map(|(i, result)| {
+                    let mut result =
+                        result.ok_or_else(|| anyhow::anyhow!("Answer {} was not graded", i + 1))?;
+                    result.question_id = (i + 1).to_string();
+                    Ok(result)
+                })
+                .collect();
+        }
+
+        // Batches are graded with one prompt, so topic templates apply only when
+        // every answer belongs to the same card
+        let first_card_id = grading_requests[0].card_id;
+        let topics = match first_card_id {
+            Some(card_id) if grading_requests.iter().all(|r| r.card_id == first_card_id) => {
+                self.card_topics(card_id).await
+            }
+            _ => Vec::new(),
+        };
+
+        let budget = self.token_budget();
+        let overhead = self.prompt_overhead(PromptKind::BatchGrading, &topics);
+        let entries: Vec<(&BatchGradingRequest, String)> = grading_requests
+            .iter()
+            .map(|req| {
+                let mut entry = format!(
+                    "Card Content: {}\n   Question: {}\n   Question Type: {}\n   Correct Answer: {}\n   User Answer: {}",
+                    truncate_to_tokens(&req.card_content, GRADING_CONTENT_TOKEN_LIMIT),
+                    req.question.question,
+                    req.question.question_type,
+                    req.question.correct_answer.as_deref().unwrap_or("N/A"),
+                    req.user_answer
+                );
+                if let Some(rubric) = req.question.rubric.as_deref() {
+                    entry.push_str(&format!(
+                        "\n   Rubric:\n{}",
+                        format_rubric(Some(rubric))
+                    ));
+                }
+                (req, entry)
+            })
+            .collect();
+        let chunks = pack_by_tokens(
+            entries,
+            |(_, entry)| estimate_tokens(entry) + GRADING_OUTPUT_TOKENS_PER_ANSWER,
+            budget.context_window.saturating_sub(overhead),
+            budget.max_output_tokens / GRADING_OUTPUT_TOKENS_PER_ANSWER,
+        );
+        if chunks.len() == 1 {
+            let chunk = chunks.into_iter().next().unwrap_or_default();
+            return self.grade_answer_chunk(chunk, &topics).await;
+        }
+
+        info!(
+            request_count = grading_requests.len(),
+            chunk_count = chunks.len(),
+            model = %self.model_name(),
+            "Split batch grading into chunks that fit the model's context window"
+        );
+        let mut results = Vec::with_capacity(grading_requests.len());
+        let mut offset = 0;
+        for chunk in chunks {
+            // Question IDs are numbered per prompt; renumber them across the whole batch
+            let entry_count = chunk.len();
+            for mut result in self.grade_answer_chunk(chunk, &topics).await? {
+                if let Ok(number) = result.question_id.parse::<usize>() {
+                    result.question_id = (number + offset).to_string();
+                }
+                results.push(result);
+            }
+            offset += entry_count;
+        }
+        Ok(results)
+    }
+
+    /// Grade answers with a single batch grading prompt
+    async fn grade_answer_chunk(
+        &self,
+        entries: Vec<(&BatchGradingRequest, String)>,
+        topics: &[String],
+    ) -> Result<Vec<BatchGradingResult>> {
+        let questions_and_answers = entries
+            .iter()
+            .enumerate()
+            .map(|(i, (_, entry))| format!("{}. {}", i + 1, entry))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let grading_requests: Vec<BatchGradingRequest> =
+            entries.into_iter().map(|(req, _)| req.clone()).collect();
+        let rendered = self.render_prompt(
+            PromptKind::BatchGrading,
+            topics,
+            &[("questions_and_answers", questions_and_answers.as_str())],
         );
 
-        let system_message = "You are an expert teacher focused on fair, understanding-based grading. Prioritize semantic meaning over exact text matching. Accept equivalent answers that demonstrate understanding. Always respond with valid JSON array in the requested format.";
-        let response_text = match self
-            .make_llm_request_with_system(Some(system_message), &prompt)
+        let ServedResponse {
+            text: response_text,
+            served_by,
+        } = match self
+            .make_llm_request_with_system(
+                rendered.system.as_deref(),
+                &rendered.prompt,
+                LLMOperation::BatchGrade,
+            )
             .await
         {
-            Ok(text) => text,
+            Ok(response) => response,
             Err(e) => {
                 error!(
                     request_count = grading_requests.len(),
                     error = %e,
                     "Batch grading LLM request failed, falling back to individual grading"
                 );
-                return self.fallback_to_individual_grading(grading_requests).await;
+                return self.fallback_to_individual_grading(&grading_requests).await;
             }
         };
 
@@ -635,13 +1455,33 @@ Focus on conceptual understanding rather than exact text matching."#,
             .json_parser
             .parse_json_response::<Vec<BatchGradingResult>>(&response_text)
         {
-            Ok(results) => {
+            Ok(mut results) => {
                 info!(
                     request_count = grading_requests.len(),
                     result_count = results.len(),
+                    served_by = %served_by,
+                    prompt_version = %rendered.version,
                     "Successfully graded batch answers"
                 );
-                Ok(results)
+                for result in &mut results {
+                    result.served_by = Some(served_by.clone());
+                    result.prompt_version = Some(rendered.version.clone());
+
+                    let rubric = result
+                        .question_id
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|number| grading_requests.get(number.wrapping_sub(1)))
+                        .and_then(|req| req.question.rubric.as_deref());
+                    if let Some(score) =
+                        partial_credit(rubric, result.score, result.criterion_scores.as_deref())
+                    {
+                        result.score = Some(score);
+                        result.is_correct = score >= PASSING_SCORE;
+                        result.suggested_rating = score_to_rating(score);
+                    }
+                }
+                self.align_batch_results(results, &grading_requests).await
             }
             Err(e) => {
                 error!(
@@ -651,9 +1491,59 @@ Focus on conceptual understanding rather than exact text matching."#,
                     "Failed to parse batch grading JSON response"
                 );
                 // Fallback to individual grading
-                self.fallback_to_individual_grading(grading_requests).await
+                self.fallback_to_individual_grading(&grading_requests).await
+            }
+        }
+    }
+
+    /// One result per answer, in prompt order. Results whose question_id matches no
+    /// answer, or one already graded, are logged and dropped; answers the LLM skipped
+    /// are graded individually.
+    async fn align_batch_results(
+        &self,
+        results: Vec<BatchGradingResult>,
+        grading_requests: &[BatchGradingRequest],
+    ) -> Result<Vec<BatchGradingResult>> {
+        let mut aligned: Vec<Option<BatchGradingResult>> =
+            grading_requests.iter().map(|_| None).collect();
+        for result in results {
+            let slot = result
+                .question_id
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|number| aligned.get_mut(number.wrapping_sub(1)))
+                .filter(|slot| slot.is_none());
+            match slot {
+                Some(slot) => *slot = Some(result),
+                None => warn!(
+                    question_id = %result.question_id,
+                    request_count = grading_requests.len(),
+                    "Discarding batch grading result that matches no answer"
+                ),
+            }
+        }
+
+        let missing: Vec<usize> = (0..aligned.len())
+            .filter(|&i| aligned[i].is_none())
+            .collect();
+        if !missing.is_empty() {
+            warn!(
+                request_count = grading_requests.len(),
+                missing_count = missing.len(),
+                "Batch grading response skipped answers, grading them individually"
+            );
+            let skipped: Vec<BatchGradingRequest> = missing
+                .iter()
+                .map(|&i| grading_requests[i].clone())
+                .collect();
+            let graded = self.fallback_to_individual_grading(&skipped).await?;
+            for (i, mut result) in missing.into_iter().zip(graded) {
+                result.question_id = (i + 1).to_string();
+                aligned[i] = Some(result);
             }
         }
+        Ok(aligned.into_iter().flatten().collect())
     }
 
     #[allow(dead_code)]
@@ -670,7 +1560,7 @@ Focus on conceptual understanding rather than exact text matching."#,
         for (i, req) in grading_requests.iter().enumerate() {
             // Create a temporary card for the grading call
             let temp_card = Card {
-                id: Uuid::new_v4(),
+                id: req.card_id.unwrap_or_else(Uuid::new_v4),
                 zettel_id: format!("temp-{}", i),
                 title: None,
                 content: req.card_content.clone(),
@@ -696,6 +1586,10 @@ Focus on conceptual understanding rather than exact text matching."#,
                         is_correct: grading_result.is_correct,
                         feedback: grading_result.feedback,
                         suggested_rating: grading_result.suggested_rating,
+                        served_by: grading_result.served_by,
+                        prompt_version: grading_result.prompt_version,
+                        score: grading_result.score,
+                        criterion_scores: grading_result.criterion_scores,
                     });
                 }
                 Err(e) => {
@@ -711,6 +1605,10 @@ Focus on conceptual understanding rather than exact text matching."#,
                         feedback: "Unable to grade this answer due to technical issues."
                             .to_string(),
                         suggested_rating: 2,
+                        served_by: None,
+                        prompt_version: None,
+                        score: None,
+                        criterion_scores: None,
                     });
                 }
             }
@@ -775,6 +1673,10 @@ Focus on conceptual understanding rather than exact text matching."#,
                                 is_correct: grading_result.is_correct,
                                 feedback: grading_result.feedback,
                                 suggested_rating: grading_result.suggested_rating,
+                                served_by: grading_result.served_by,
+                                prompt_version: grading_result.prompt_version,
+                                score: grading_result.score,
+                                criterion_scores: grading_result.criterion_scores,
                             },
                             duration,
                         ))
@@ -832,6 +1734,10 @@ Focus on conceptual understanding rather than exact text matching."#,
                     is_correct: false,
                     feedback: "Unable to grade this answer due to technical issues.".to_string(),
                     suggested_rating: 2,
+                    served_by: None,
+                    prompt_version: None,
+                    score: None,
+                    criterion_scores: None,
                 },
             ));
         }
@@ -909,6 +1815,7 @@ Focus on conceptual understanding rather than exact text matching."#,
                     card_content: card.content.clone(),
                     question: question.clone(),
                     user_answer: answer.clone(),
+                    card_id: Some(card.id),
                 })
                 .collect();
 
@@ -950,6 +1857,10 @@ Focus on conceptual understanding rather than exact text matching."#,
                         is_correct: grading_result.is_correct,
                         feedback: grading_result.feedback,
                         suggested_rating: grading_result.suggested_rating,
+                        served_by: grading_result.served_by,
+                        prompt_version: grading_result.prompt_version,
+                        score: grading_result.score,
+                        criterion_scores: grading_result.criterion_scores,
                     });
                 }
                 Err(e) => {
@@ -965,6 +1876,10 @@ Focus on conceptual understanding rather than exact text matching."#,
                         feedback: "Unable to grade this answer due to technical issues."
                             .to_string(),
                         suggested_rating: 2,
+                        served_by: None,
+                        prompt_version: None,
+                        score: None,
+                        criterion_scores: None,
                     });
                 }
             }
@@ -985,3 +1900,102 @@ Focus on conceptual understanding rather than exact text matching."#,
         Ok((results, "sequential_fallback".to_string(), fallback_reason))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card_service::CardService;
+    use crate::models::CreateCardRequest;
+
+    async fn create_card(
+        card_service: &CardService,
+        zettel_id: &str,
+        title: Option<&str>,
+        links: Option<Vec<Uuid>>,
+    ) -> Card {
+        card_service
+            .create_card(CreateCardRequest {
+                zettel_id: zettel_id.to_string(),
+                title: title.map(str::to_string),
+                content: format!("{} explains one step of photosynthesis.", zettel_id),
+                topic_ids: vec![],
+                links,
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_linked_context_summarizes_links_and_backlinks() {
+        let db = Database::new_in_memory().await.unwrap();
+        let card_service = CardService::new(db.clone());
+        let light = create_card(&card_service, "LINK-001", Some("Light reactions"), None).await;
+        let calvin = create_card(
+            &card_service,
+            "LINK-002",
+            Some("Calvin cycle"),
+            Some(vec![light.id]),
+        )
+        .await;
+        let light = card_service.get_card(light.id).await.unwrap().unwrap();
+
+        // Without linked context only titles of outgoing links are listed
+        let plain = LLMService::new_mock().with_card_context(db.clone());
+        let context = plain.card_prompt_context(&calvin).await;
+        assert_eq!(context.linked_cards, "- LINK-001: Light reactions");
+        assert_eq!(plain.card_prompt_context(&light).await.backlinks, "none");
+
+        let linked = plain.with_linked_context(true);
+        let context = linked.card_prompt_context(&calvin).await;
+        assert_eq!(
+            context.linked_cards,
+            "- LINK-001: Light reactions - LINK-001 explains one step of photosynthesis."
+        );
+        let context = linked.card_prompt_context(&light).await;
+        assert_eq!(context.linked_cards, "none");
+        assert_eq!(
+            context.backlinks,
+            "- LINK-002: Calvin cycle - LINK-002 explains one step of photosynthesis."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connection_grading_prompt_includes_related_cards() {
+        let db = Database::new_in_memory().await.unwrap();
+        let card_service = CardService::new(db.clone());
+        let light = create_card(&card_service, "LINK-003", Some("Light reactions"), None).await;
+        let calvin = create_card(
+            &card_service,
+            "LINK-004",
+            Some("Calvin cycle"),
+            Some(vec![light.id]),
+        )
+        .await;
+        let llm_service = LLMService::new_mock()
+            .with_card_context(db)
+            .with_linked_context(true);
+
+        let mut question = QuizQuestion {
+            question: "How does the Calvin cycle depend on the light reactions?".to_string(),
+            question_type: CONNECTION_QUESTION_TYPE.to_string(),
+            options: None,
+            correct_answer: Some("It uses their ATP and NADPH".to_string()),
+            prompt_version: None,
+            rubric: None,
+        };
+        let prompt = llm_service
+            .grading_prompt(&calvin, &question, "It needs ATP")
+            .await;
+        assert!(
+            prompt
+                .prompt
+                .contains("Linked cards:\n- LINK-003: Light reactions")
+        );
+
+        question.question_type = "short_answer".to_string();
+        let prompt = llm_service
+            .grading_prompt(&calvin, &question, "It needs ATP")
+            .await;
+        assert!(!prompt.prompt.contains("Linked cards:"));
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::llm_providers::TokenUsage;
+use crate::models::{LLMUsageRecord, LLMUsageSummary};
+
+/// The kind of work an LLM call was made for, recorded with each usage row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LLMOperation {
+    Generate,
+    BatchGenerate,
+    Grade,
+    BatchGrade,
+}
+
+impl LLMOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LLMOperation::Generate => "generate",
+            LLMOperation::BatchGenerate => "batch_generate",
+            LLMOperation::Grade => "grade",
+            LLMOperation::BatchGrade => "batch_grade",
+        }
+    }
+}
+
+/// Published list prices in USD per million (prompt, completion) tokens.
+/// More specific model prefixes must come before shorter ones.
+const MODEL_PRICING: &[(&str, f64, f64)] = &[
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-5", 1.25, 10.00),
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+    ("gemini-2.0-flash", 0.10, 0.40),
+    ("gemini-1.5-flash", 0.075, 0.30),
+    ("gemini-1.5-pro", 1.25, 5.00),
+];
+
+/// Estimate the cost of a call from its token usage; unknown models are priced at zero
+pub fn estimate_cost_usd(model: &str, usage: TokenUsage) -> f64 {
+    let model = model.to_lowercase();
+    match MODEL_PRICING
+        .iter()
+        .find(|(prefix, _, _)| model.starts_with(prefix))
+    {
+        Some((_, prompt_price, completion_price)) => {
+            (usage.prompt_tokens as f64 * prompt_price
+                + usage.completion_tokens as f64 * completion_price)
+                / 1_000_000.0
+        }
+        None => 0.0,
+    }
+}
+
+/// Records per-call LLM usage to SQLite and enforces an optional monthly budget
+#[derive(Clone)]
+pub struct UsageTracker {
+    db: Database,
+    monthly_budget_usd: Option<f64>,
+}
+
+impl UsageTracker {
+    pub fn new(db: Database, monthly_budget_usd: Option<f64>) -> Self {
+        Self {
+            db,
+            monthly_budget_usd,
+        }
+    }
+
+    /// Record a single provider call. Failures are logged rather than propagated so
+    /// that accounting problems never break quiz generation or grading.
+    pub async fn record(
+        &self,
+        provider: &str,
+        model: &str,
+        operation: LLMOperation,
+        usage: Option<TokenUsage>,
+        latency_ms: u64,
+        success: bool,
+    ) {
+        let usage = usage.unwrap_or_default();
+        let record = LLMUsageRecord {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            operation: operation.as_str().to_string(),
+            prompt_tokens: usage.prompt_tokens as i64,
+            completion_tokens: usage.completion_tokens as i64,
+            latency_ms: latency_ms as i64,
+            success,
+            estimated_cost_usd: estimate_cost_usd(model, usage),
+        };
+
+        if let Err(e) = self.db.record_llm_usage(&record).await {
+            error!(
+                provider = %provider,
+                model = %model,
+                operation = operation.as_str(),
+                error = %e,
+                "Failed to record LLM usage"
+            );
+        }
+    }
+
+    /// Estimated spend since the start of the current UTC month
+    pub async fn month_to_date_cost(&self) -> Result<f64> {
+        self.db.get_llm_cost_since(start_of_month(Utc::now())).await
+    }
+
+    /// Return an error if the monthly budget is set and already spent
+    pub async fn check_budget(&self) -> Result<()> {
+        let Some(budget) = self.monthly_budget_usd else {
+            return Ok(());
+        };
+
+        let spent = self.month_to_date_cost().await?;
+        if spent >= budget {
+            warn!(
+                month_to_date_cost_usd = spent,
+                monthly_budget_usd = budget,
+                "Monthly LLM budget exhausted, blocking LLM call"
+            );
+            return Err(anyhow::anyhow!(
+                "Monthly LLM budget exceeded: ${:.4} spent of ${:.2}",
+                spent,
+                budget
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Daily token and cost totals per provider/model for the last `days` days
+    pub async fn summary(&self, days: i64) -> Result<LLMUsageSummary> {
+        let now = Utc::now();
+        let since = (now - Duration::days(days.max(1) - 1))
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_utc();
+
+        Ok(LLMUsageSummary {
+            daily: self.db.get_daily_llm_usage(since).await?,
+            month_to_date_cost_usd: self.month_to_date_cost().await?,
+            monthly_budget_usd: self.monthly_budget_usd,
+        })
+    }
+}
+
+fn start_of_month(now: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .single()
+        .expect("first of month is a valid date")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn create_test_tracker(budget: Option<f64>) -> UsageTracker {
+        let db = Database::new_in_memory().await.unwrap();
+        UsageTracker::new(db, budget)
+    }
+
+    #[test]
+    fn test_cost_estimation() {
+        let usage = TokenUsage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 1_000_000,
+        };
+
+        assert!((estimate_cost_usd("gpt-4o-mini", usage) - 0.75).abs() < 1e-9);
+        assert!((estimate_cost_usd("gpt-4o", usage) - 12.5).abs() < 1e-9);
+        assert!((estimate_cost_usd("gemini-2.0-flash-exp", usage) - 0.5).abs() < 1e-9);
+        assert_eq!(estimate_cost_usd("llama3", usage), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_record_and_summarize_usage() {
+        let tracker = create_test_tracker(None).await;
+        let usage = TokenUsage {
+            prompt_tokens: 1000,
+            completion_tokens: 500,
+        };
+
+        tracker
+            .record(
+                "OpenAI",
+                "gpt-4o-mini",
+                LLMOperation::Generate,
+                Some(usage),
+                120,
+                true,
+            )
+            .await;
+        tracker
+            .record(
+                "OpenAI",
+                "gpt-4o-mini",
+                LLMOperation::Grade,
+                Some(usage),
+                80,
+                true,
+            )
+            .await;
+        tracker
+            .record(
+                "Gemini",
+                "gemini-1.5-pro",
+                LLMOperation::Grade,
+                None,
+                30,
+                false,
+            )
+            .await;
+
+        let summary = tracker.summary(7).await.unwrap();
+        assert_eq!(summary.daily.len(), 2);
+
+        let openai = summary
+            .daily
+            .iter()
+            .find(|d| d.provider == "OpenAI")
+            .unwrap();
+        assert_eq!(openai.calls, 2);
+        assert_eq!(openai.prompt_tokens, 2000);
+        assert_eq!(openai.completion_tokens, 1000);
+
+        let gemini = summary
+            .daily
+            .iter()
+            .find(|d| d.provider == "Gemini")
+            .unwrap();
+        assert_eq!(gemini.failed_calls, 1);
+        assert_eq!(gemini.estimated_cost_usd, 0.0);
+
+        assert!(summary.month_to_date_cost_usd > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_budget_blocks_calls_when_exceeded() {
+        let tracker = create_test_tracker(Some(0.001)).await;
+        assert!(tracker.check_budget().await.is_ok());
+
+        tracker
+            .record(
+                "OpenAI",
+                "gpt-4o",
+                LLMOperation::BatchGrade,
+                Some(TokenUsage {
+                    prompt_tokens: 10_000,
+                    completion_tokens: 1_000,
+                }),
+                200,
+                true,
+            )
+            .await;
+
+        let result = tracker.check_budget().await;
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Monthly LLM budget exceeded")
+        );
+    }
+}
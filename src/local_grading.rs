@@ -0,0 +1,365 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::answer_text::normalize_answer;
+use crate::cloze::{CLOZE_QUESTION_TYPE, grade_cloze_answer};
+use crate::llm_service::GradingResult;
+use crate::models::QuizQuestion;
+
+/// Recorded as `served_by` for answers graded by the rules in this module
+pub const LOCAL_RULES_GRADER: &str = "local:rules";
+
+/// Slack for floating point error when comparing numeric answers
+const NUMERIC_ABSOLUTE_TOLERANCE: f64 = 1e-9;
+
+// "B", "(b)", "B)", "option B"
+static CHOICE_LETTER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:option\s+)?\(?([a-z])\)?[.):]?$").expect("choice letter pattern is valid")
+});
+
+// "B) Option text", "b. Option text"
+static CHOICE_PREFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^\(?([a-z])[.):]\s+(.+)$").expect("choice prefix pattern is valid")
+});
+
+// A number or fraction, an optional percent sign, then an optional unit
+static NUMERIC_ANSWER: LazyLock<Regex> = LazyLock::new(|| {
+    let number = r"[-+]?(?:\d{1,3}(?:,\d{3})+(?:\.\d+)?|\d+(?:\.\d*)?|\.\d+)(?:[eE][-+]?\d+)?";
+    Regex::new(&format!(
+        r"^({number})(?:\s*/\s*({number}))?\s*(%)?\s*([^\d]*?)\.?$"
+    ))
+    .expect("numeric answer pattern is valid")
+});
+
+/// Grade an answer without an LLM when the result is certain: cloze deletions, multiple
+/// choice picks, numeric answers and exact matches. Returns `None` when the answer needs
+/// judgement, such as a paraphrased short answer.
+pub fn grade_locally(question: &QuizQuestion, user_answer: &str) -> Option<GradingResult> {
+    if question.question_type == CLOZE_QUESTION_TYPE {
+        return Some(grade_cloze_answer(question, user_answer));
+    }
+    let correct_answer = question.correct_answer.as_deref()?;
+
+    if question.question_type == "multiple_choice"
+        && let Some(options) = question.options.as_deref()
+    {
+        return grade_multiple_choice(options, correct_answer, user_answer);
+    }
+
+    if let (Some(expected), Some(given)) = (
+        NumericAnswer::parse(correct_answer),
+        NumericAnswer::parse(user_answer),
+    ) {
+        return grade_numeric(&expected, &given, correct_answer);
+    }
+
+    let expected = normalize_answer(correct_answer);
+    (!expected.is_empty() && expected == normalize_answer(user_answer))
+        .then(|| local_result(true, "Correct!".to_string()))
+}
+
+fn local_result(is_correct: bool, feedback: String) -> GradingResult {
+    GradingResult {
+        is_correct,
+        feedback,
+        suggested_rating: if is_correct { 3 } else { 1 },
+        served_by: Some(LOCAL_RULES_GRADER.to_string()),
+        prompt_version: None,
+        score: None,
+        criterion_scores: None,
+    }
+}
+
+fn option_letter(index: usize) -> char {
+    (b'A' + index as u8) as char
+}
+
+/// Option text without a letter prefix the generator may have added
+fn option_text(option: &str) -> &str {
+    CHOICE_PREFIX
+        .captures(option.trim())
+        .and_then(|caps| caps.get(2))
+        .map_or(option.trim(), |text| text.as_str())
+}
+
+/// Which option an answer picks, by letter, by letter and text, or by text alone
+fn resolve_choice(answer: &str, options: &[String]) -> Option<usize> {
+    let answer = answer.trim();
+    let letter_index = |letter: &str| {
+        let index = (letter.to_ascii_uppercase().as_bytes()[0] - b'A') as usize;
+        (index < options.len()).then_some(index)
+    };
+    let by_text = |text: &str| {
+        let text = normalize_answer(text);
+        let mut matching = options
+            .iter()
+            .enumerate()
+            .filter(|(_, option)| normalize_answer(option_text(option)) == text);
+        match (matching.next(), matching.next()) {
+            (Some((index, _)), None) => Some(index),
+            _ => None,
+        }
+    };
+
+    if let Some(caps) = CHOICE_LETTER.captures(answer) {
+        return letter_index(&caps[1]);
+    }
+    if let Some(caps) = CHOICE_PREFIX.captures(answer) {
+        let index = letter_index(&caps[1]);
+        let text_index = by_text(&caps[2]);
+        return match (index, text_index) {
+            (Some(index), Some(text_index)) if index != text_index => None,
+            (index, text_index) => text_index.or(index),
+        };
+    }
+    by_text(answer)
+}
+
+fn grade_multiple_choice(
+    options: &[String],
+    correct_answer: &str,
+    user_answer: &str,
+) -> Option<GradingResult> {
+    let expected = resolve_choice(correct_answer, options)?;
+    let given = resolve_choice(user_answer, options)?;
+    let answer = format!(
+        "{}) {}",
+        option_letter(expected),
+        option_text(&options[expected])
+    );
+
+    Some(if given == expected {
+        local_result(true, format!("Correct! The answer is {}.", answer))
+    } else {
+        local_result(
+            false,
+            format!(
+                "Incorrect. You chose {}, but the correct answer is {}.",
+                option_letter(given),
+                answer
+            ),
+        )
+    })
+}
+
+/// A numeric answer such as "0.25", "1/4", "25%" or "9.8 m/s"
+#[derive(Debug, Clone, PartialEq)]
+struct NumericAnswer {
+    value: f64,
+    precision: Option<f64>, // Half a unit in the last written digit; None for fractions
+    percent: bool,
+    unit: String,
+}
+
+impl NumericAnswer {
+    fn parse(answer: &str) -> Option<Self> {
+        let caps = NUMERIC_ANSWER.captures(answer.trim())?;
+        let number = |text: &str| text.replace(',', "").parse::<f64>().ok();
+
+        let mut value = number(&caps[1])?;
+        let mut precision = Some(half_unit_in_last_digit(&caps[1]));
+        if let Some(denominator) = caps.get(2) {
+            let denominator = number(denominator.as_str())?;
+            if denominator == 0.0 {
+                return None;
+            }
+            value /= denominator;
+            precision = None;
+        }
+
+        Some(NumericAnswer {
+            value,
+            precision,
+            percent: caps.get(3).is_some(),
+            unit: normalize_answer(caps.get(4).map_or("", |unit| unit.as_str())),
+        })
+    }
+
+    /// Values the answer may stand for, with their precision: "25%" is 0.25, but may
+    /// also mean plain 25
+    fn candidates(&self) -> Vec<(f64, Option<f64>)> {
+        if self.percent {
+            vec![
+                (self.value / 100.0, self.precision.map(|p| p / 100.0)),
+                (self.value, self.precision),
+            ]
+        } else {
+            vec![(self.value, self.precision)]
+        }
+    }
+}
+
+/// Half a unit in the last digit a number is written with: 0.05 for "9.8", 0.5 for
+/// "1945" and 50 for "1.2e3"
+fn half_unit_in_last_digit(number: &str) -> f64 {
+    let number = number.replace(',', "");
+    let (mantissa, exponent) = match number.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().unwrap_or(0)),
+        None => (number.as_str(), 0),
+    };
+    let decimals = mantissa
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len() as i32);
+    0.5 * 10f64.powi(exponent - decimals)
+}
+
+fn grade_numeric(
+    expected: &NumericAnswer,
+    given: &NumericAnswer,
+    correct_answer: &str,
+) -> Option<GradingResult> {
+    // A different unit needs conversion or judgement
+    if !given.unit.is_empty() && given.unit != expected.unit {
+        return None;
+    }
+
+    // The answer may be off by half a unit in the expected answer's last digit, so
+    // "1940" is wrong for 1945 but "9.81" is right for 9.8. An exact fraction is held to
+    // the precision the answer itself is written with.
+    let close = |(g, given_precision): (f64, Option<f64>),
+                 (e, expected_precision): (f64, Option<f64>)| {
+        let tolerance = expected_precision.or(given_precision).unwrap_or(0.0);
+        (g - e).abs() <= tolerance + NUMERIC_ABSOLUTE_TOLERANCE
+    };
+    // Percentages only count at face value when exactly one side is written as one
+    let is_correct = if expected.percent == given.percent {
+        close(
+            (given.value, given.precision),
+            (expected.value, expected.precision),
+        )
+    } else {
+        expected
+            .candidates()
+            .into_iter()
+            .any(|e| given.candidates().into_iter().any(|g| close(g, e)))
+    };
+
+    Some(if is_correct {
+        local_result(true, format!("Correct! The answer is {}.", correct_answer))
+    } else {
+        local_result(
+            false,
+            format!("Incorrect. The correct answer is {}.", correct_answer),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_service::LLMService;
+    use crate::models::Card;
+
+    fn question(question_type: &str, options: Option<&[&str]>, answer: &str) -> QuizQuestion {
+        QuizQuestion {
+            question: "Question".to_string(),
+            question_type: question_type.to_string(),
+            options: options.map(|options| options.iter().map(|o| o.to_string()).collect()),
+            correct_answer: Some(answer.to_string()),
+            prompt_version: None,
+            rubric: None,
+        }
+    }
+
+    fn grade(question: &QuizQuestion, answer: &str) -> Option<bool> {
+        grade_locally(question, answer).map(|result| result.is_correct)
+    }
+
+    #[test]
+    fn test_multiple_choice_by_letter_or_text() {
+        let options = ["Carbon dioxide", "Water", "Glucose", "ATP"];
+        let by_letter = question("multiple_choice", Some(&options), "B");
+
+        assert_eq!(grade(&by_letter, "b"), Some(true));
+        assert_eq!(grade(&by_letter, "(B)"), Some(true));
+        assert_eq!(grade(&by_letter, "B) Water"), Some(true));
+        assert_eq!(grade(&by_letter, "water"), Some(true));
+        assert_eq!(grade(&by_letter, "A"), Some(false));
+        // Letter and text disagree, or the text matches no option
+        assert_eq!(grade(&by_letter, "A) Water"), None);
+        assert_eq!(grade(&by_letter, "H2O"), None);
+
+        let prefixed = ["A) Option 1", "B) Option 2"];
+        let by_text = question("multiple_choice", Some(&prefixed), "A) Option 1");
+        assert_eq!(grade(&by_text, "A"), Some(true));
+        assert_eq!(grade(&by_text, "option 2"), Some(false));
+    }
+
+    #[test]
+    fn test_numeric_tolerance_and_equivalent_forms() {
+        let quarter = question("short_answer", None, "0.25");
+        assert_eq!(grade(&quarter, "1/4"), Some(true));
+        assert_eq!(grade(&quarter, "25%"), Some(true));
+        assert_eq!(grade(&quarter, "0.2501"), Some(true));
+        assert_eq!(grade(&quarter, "0.3"), Some(false));
+
+        let percent = question("short_answer", None, "25%");
+        assert_eq!(grade(&percent, "25"), Some(true));
+        assert_eq!(grade(&percent, "0.25"), Some(true));
+
+        let speed = question("problem_solving", None, "9.8 m/s");
+        assert_eq!(grade(&speed, "9.81 m/s"), Some(true));
+        assert_eq!(grade(&speed, "9.8"), Some(true));
+        assert_eq!(grade(&speed, "35.3 km/h"), None);
+
+        let large = question("short_answer", None, "1,000,000");
+        assert_eq!(grade(&large, "1e6"), Some(true));
+
+        let third = question("short_answer", None, "1/3");
+        assert_eq!(grade(&third, "0.333"), Some(true));
+        assert_eq!(grade(&third, "0.4"), Some(false));
+    }
+
+    #[test]
+    fn test_whole_numbers_are_compared_exactly() {
+        let year = question("short_answer", None, "1945");
+        assert_eq!(grade(&year, "1940"), Some(false));
+        assert_eq!(grade(&year, "1945"), Some(true));
+        assert_eq!(grade(&year, "1945.0"), Some(true));
+
+        let count = question("short_answer", None, "1000");
+        assert_eq!(grade(&count, "996"), Some(false));
+    }
+
+    #[test]
+    fn test_short_answers_only_graded_on_exact_match() {
+        let capital = question("short_answer", None, "The Calvin cycle");
+        assert_eq!(grade(&capital, "calvin cycle."), Some(true));
+        assert_eq!(grade(&capital, "The cycle named after Melvin Calvin"), None);
+    }
+
+    #[tokio::test]
+    async fn test_grade_answer_skips_llm_when_conclusive() {
+        let llm_service = LLMService::new_mock_all_unavailable();
+        let card = Card {
+            id: uuid::Uuid::new_v4(),
+            zettel_id: "LOCAL-001".to_string(),
+            title: None,
+            content: "Photosynthesis splits water.".to_string(),
+            creation_date: chrono::Utc::now(),
+            last_reviewed: None,
+            next_review: chrono::Utc::now(),
+            difficulty: 0.0,
+            stability: 0.0,
+            retrievability: 0.0,
+            reps: 0,
+            lapses: 0,
+            state: "New".to_string(),
+            links: None,
+        };
+        let choice = question("multiple_choice", Some(&["Carbon dioxide", "Water"]), "B");
+
+        let result = llm_service.grade_answer(&card, &choice, "B").await.unwrap();
+        assert!(result.is_correct);
+        assert_eq!(result.served_by.as_deref(), Some(LOCAL_RULES_GRADER));
+
+        // Inconclusive answers still go to the (here unavailable) LLM
+        let open = question("short_answer", None, "Water is split");
+        assert!(
+            llm_service
+                .grade_answer(&card, &open, "H2O gets broken up")
+                .await
+                .is_err()
+        );
+    }
+}
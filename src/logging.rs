@@ -1,12 +1,12 @@
 // Macros file - tracing macros are imported within the macro definitions
 
-/// Standardized logging macros for consistent field names and message patterns across the application
-///
-/// These macros ensure:
-/// - Consistent field naming conventions
-/// - Appropriate logging levels for different scenarios
-/// - Structured logging with context
-/// - Consistent message formatting
+// Standardized logging macros for consistent field names and message patterns across the application
+//
+// These macros ensure:
+// - Consistent field naming conventions
+// - Appropriate logging levels for different scenarios
+// - Structured logging with context
+// - Consistent message formatting
 
 // ============================================================================
 // API Operation Logging Macros
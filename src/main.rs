@@ -1,18 +1,33 @@
+mod answer_text;
 mod api;
+mod calibration;
 mod card_service;
+mod cloze;
 mod config;
+mod csv_import;
 mod database;
 mod errors;
 mod fsrs_scheduler;
+mod knowledge_graph;
+mod llm_fixtures;
 mod llm_providers;
 mod llm_service;
+mod llm_usage;
+mod local_grading;
 mod logging;
+mod markdown;
 mod models;
+mod prompt_templates;
+mod question_cache;
+mod question_variety;
+mod rubric;
+mod token_budget;
 
 use anyhow::Result;
 use axum::{Router, http::StatusCode, response::Html, routing::get};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::fs;
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
@@ -25,6 +40,9 @@ use crate::{
     config::Config,
     database::Database,
     llm_service::LLMService,
+    llm_usage::UsageTracker,
+    prompt_templates::PromptLibrary,
+    question_cache::{PreGenerationQueue, PreGenerationWorker, QuestionCache},
 };
 
 #[tokio::main]
@@ -54,30 +72,69 @@ async fn main() -> Result<()> {
     );
 
     // Initialize services
-    let card_service = CardService::new(db);
+    let card_service = CardService::new(db.clone())
+        .with_rating_calibration(config.llm.rating_calibration)
+        .with_lapsed_dependent_postponement(config.scheduling.postpone_lapsed_dependents)
+        .with_weak_foundation_threshold(config.scheduling.weak_foundation_threshold);
+
+    let prompt_library = match &config.llm.prompt_templates_dir {
+        Some(dir) => PromptLibrary::load_dir(dir)?,
+        None => PromptLibrary::builtin(),
+    };
 
-    let llm_service = LLMService::new_with_provider(
-        config.llm.api_key.clone(),
-        config.llm.base_url.clone(),
-        config.llm.provider,
-        config.llm.model.clone(),
-    );
+    let mut llm_service = LLMService::new_with_providers(&config.llm.provider_chain());
+    if let Some(mode) = config.llm.fixture_mode {
+        llm_service = llm_service.with_fixtures(mode, &config.llm.fixture_dir);
+    }
+    let llm_service = llm_service
+        .with_usage_tracker(UsageTracker::new(db.clone(), config.llm.monthly_budget_usd))
+        .with_prompt_library(prompt_library)
+        .with_card_context(db)
+        .with_linked_context(config.llm.linked_context);
 
     log_system_event!(
         startup,
         component = "llm_service",
         format!(
-            "LLM service initialized with provider: {:?}",
-            config.llm.provider
+            "LLM service initialized with provider chain: {}",
+            llm_service.provider_chain().join(" -> ")
         )
         .as_str()
     );
 
+    let question_cache = QuestionCache::new(
+        config.pregeneration.cache_max_size,
+        config.pregeneration.cache_ttl_minutes,
+    );
+
+    if config.pregeneration.enabled {
+        PreGenerationWorker::new(
+            card_service.clone(),
+            llm_service.clone(),
+            question_cache.clone(),
+            PreGenerationQueue::new(config.pregeneration.cache_max_size),
+            config.pregeneration.lookahead_hours,
+            config.pregeneration.batch_size,
+        )
+        .spawn(Duration::from_secs(config.pregeneration.interval_secs));
+
+        log_system_event!(
+            startup,
+            component = "question_pregeneration",
+            format!(
+                "Question pre-generation worker started for cards due within {} hours",
+                config.pregeneration.lookahead_hours
+            )
+            .as_str()
+        );
+    }
+
     // Create application state
     let state = AppState {
         card_service,
         llm_service,
         review_sessions: Arc::new(Mutex::new(HashMap::new())),
+        question_cache,
     };
 
     // Build the application router
@@ -0,0 +1,349 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+// "# Header", "## Header", ...
+#[allow(dead_code)]
+static HEADER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(#{1,6})\s+(.+)$").expect("header pattern is valid"));
+
+// "[[target]]", "[[target|alias]]", "[[target#heading]]" and "![[embed]]", capturing
+// the target, the heading or block reference, and the alias
+static WIKI_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\[\[([^\[\]|#^]*)([#^][^\[\]|]*)?(?:\|([^\[\]]*))?\]\]")
+        .expect("wiki link pattern is valid")
+});
+
+// "#tag" or "#nested/tag", but not headings, "C#" or URL fragments
+#[allow(dead_code)]
+static INLINE_TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|\s)#([\p{L}_][\p{L}\p{N}_/-]*)").expect("inline tag pattern is valid")
+});
+
+/// Split a leading markdown header off the content, returning the title and the rest
+#[allow(dead_code)]
+pub fn extract_markdown_header(content: &str) -> Option<(String, String)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let first_line = lines.first()?.trim();
+
+    let captures = HEADER.captures(first_line)?;
+    let title = captures.get(2)?.as_str().trim().to_string();
+    if title.is_empty() {
+        return None;
+    }
+
+    // Remove the header line from content
+    let remaining_lines = if lines.len() > 1 {
+        lines[1..].join("\n").trim_start().to_string()
+    } else {
+        String::new()
+    };
+
+    Some((title, remaining_lines))
+}
+
+/// The fields of a note's YAML front matter that cards use
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrontMatter {
+    pub zettel_id: Option<String>,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+}
+
+/// Split YAML front matter between `---` lines off the start of a note. Only flat keys
+/// with scalar values, inline `[a, b]` lists or `- item` lists are understood.
+#[allow(dead_code)]
+pub fn split_front_matter(text: &str) -> (Option<FrontMatter>, &str) {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return (None, text);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let body = &rest[offset + line.len()..];
+            return (Some(parse_front_matter(&rest[..offset])), body);
+        }
+        offset += line.len();
+    }
+    (None, text)
+}
+
+#[allow(dead_code)]
+fn parse_front_matter(yaml: &str) -> FrontMatter {
+    let mut front_matter = FrontMatter::default();
+    // The list key whose "- item" lines follow
+    let mut current_list: Option<&str> = None;
+
+    for line in yaml.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            let list = match current_list {
+                Some("tags") => &mut front_matter.tags,
+                Some("aliases") => &mut front_matter.aliases,
+                _ => continue,
+            };
+            list.push(unquote(item).to_string());
+            continue;
+        }
+
+        current_list = None;
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_lowercase().as_str() {
+            "zettel_id" | "id" if !value.is_empty() => {
+                front_matter.zettel_id = Some(unquote(value).to_string())
+            }
+            "title" if !value.is_empty() => front_matter.title = Some(unquote(value).to_string()),
+            "tags" | "tag" | "topics" => {
+                front_matter.tags.extend(scalar_list(value));
+                current_list = Some("tags");
+            }
+            "aliases" | "alias" => {
+                front_matter.aliases.extend(scalar_list(value));
+                current_list = Some("aliases");
+            }
+            _ => {}
+        }
+    }
+
+    front_matter
+}
+
+/// Items of an inline YAML list, or of a comma or space separated scalar
+#[allow(dead_code)]
+fn scalar_list(value: &str) -> Vec<String> {
+    let inner = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    let separator = if inner.contains(',') { ',' } else { ' ' };
+    inner
+        .split(separator)
+        .map(unquote)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[allow(dead_code)]
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+        .trim()
+}
+
+/// One `[[wiki link]]` in content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WikiLink<'a> {
+    /// The whole link, brackets included
+    pub text: &'a str,
+    /// The linked note, trimmed
+    pub target: &'a str,
+    /// A `#heading` or `^block` reference, with its marker
+    pub heading: Option<&'a str>,
+    pub alias: Option<&'a str>,
+}
+
+impl<'a> WikiLink<'a> {
+    fn from_captures(captures: &regex::Captures<'a>) -> Self {
+        WikiLink {
+            text: captures.get(0).map_or("", |m| m.as_str()),
+            target: captures.get(1).map_or("", |m| m.as_str()).trim(),
+            heading: captures.get(2).map(|m| m.as_str()),
+            alias: captures.get(3).map(|m| m.as_str()),
+        }
+    }
+}
+
+/// Every `[[wiki link]]` in content, in order, including ones with an empty target
+pub fn parse_wiki_links(content: &str) -> impl Iterator<Item = WikiLink<'_>> {
+    WIKI_LINK
+        .captures_iter(content)
+        .map(|captures| WikiLink::from_captures(&captures))
+}
+
+/// Replace every `[[wiki link]]` in content with what `replace` returns for it
+pub fn replace_wiki_links(content: &str, mut replace: impl FnMut(&WikiLink) -> String) -> String {
+    WIKI_LINK
+        .replace_all(content, |captures: &regex::Captures| {
+            replace(&WikiLink::from_captures(captures))
+        })
+        .into_owned()
+}
+
+/// Targets of `[[wiki links]]` in order of first appearance, without headings or aliases
+pub fn wiki_links(content: &str) -> Vec<String> {
+    let mut targets: Vec<String> = Vec::new();
+    for link in parse_wiki_links(content) {
+        if !link.target.is_empty() && !targets.iter().any(|t| t == link.target) {
+            targets.push(link.target.to_string());
+        }
+    }
+    targets
+}
+
+/// Point `[[old]]` wiki links at `new`, keeping any heading or alias. Returns the new
+/// content and the number of links rewritten. Plain-text mentions are left alone.
+pub fn rewrite_wiki_links(content: &str, old: &str, new: &str) -> (String, usize) {
+    let mut count = 0;
+    let rewritten = replace_wiki_links(content, |link| {
+        if link.target != old {
+            return link.text.to_string();
+        }
+        count += 1;
+        // The target is the first text after the brackets, so this keeps its spacing
+        link.text.replacen(link.target, new, 1)
+    });
+    (rewritten, count)
+}
+
+/// Inline `#tags` outside fenced code blocks, without the leading `#`
+#[allow(dead_code)]
+pub fn inline_tags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut in_code_block = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        for captures in INLINE_TAG.captures_iter(line) {
+            let tag = &captures[1];
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_markdown_header() {
+        // Test basic header extraction
+        let content = "# This is a title\n\nThis is the content.";
+        let result = extract_markdown_header(content);
+        assert!(result.is_some());
+        let (title, new_content) = result.unwrap();
+        assert_eq!(title, "This is a title");
+        assert_eq!(new_content, "This is the content.");
+
+        // Test with different header levels
+        let content = "## Section Header\nContent here";
+        let result = extract_markdown_header(content);
+        assert!(result.is_some());
+        let (title, new_content) = result.unwrap();
+        assert_eq!(title, "Section Header");
+        assert_eq!(new_content, "Content here");
+
+        // Test with no header
+        let content = "Just regular content\nNo header here";
+        let result = extract_markdown_header(content);
+        assert!(result.is_none());
+
+        // Test with empty header
+        let content = "# \nContent";
+        let result = extract_markdown_header(content);
+        assert!(result.is_none());
+
+        // Test with only header
+        let content = "# Just a title";
+        let result = extract_markdown_header(content);
+        assert!(result.is_some());
+        let (title, new_content) = result.unwrap();
+        assert_eq!(title, "Just a title");
+        assert_eq!(new_content, "");
+    }
+
+    #[test]
+    fn test_split_front_matter() {
+        let note = "---\nid: \"202401011200\"\ntitle: Osmosis\ntags: [biology, cells]\naliases:\n  - Water transport\n---\n# Osmosis\nBody";
+        let (front_matter, body) = split_front_matter(note);
+        assert_eq!(
+            front_matter,
+            Some(FrontMatter {
+                zettel_id: Some("202401011200".to_string()),
+                title: Some("Osmosis".to_string()),
+                tags: vec!["biology".to_string(), "cells".to_string()],
+                aliases: vec!["Water transport".to_string()],
+            })
+        );
+        assert_eq!(body, "# Osmosis\nBody");
+
+        let block_tags = "---\ntags:\n- physics\n- \"mechanics\"\n---\nBody";
+        let (front_matter, _) = split_front_matter(block_tags);
+        assert_eq!(front_matter.unwrap().tags, vec!["physics", "mechanics"]);
+
+        // An unterminated block is left as content
+        assert_eq!(
+            split_front_matter("---\ntitle: x\nBody"),
+            (None, "---\ntitle: x\nBody")
+        );
+        assert_eq!(split_front_matter("Body"), (None, "Body"));
+    }
+
+    #[test]
+    fn test_wiki_links_and_inline_tags() {
+        let content = "See [[Osmosis]], [[Diffusion|diffusing]] and [[Osmosis#Pressure]].\n![[diagram.png]] #biology #cells/membrane\n```\n#not-a-tag\n```\nC# and https://example.com/#anchor";
+        assert_eq!(
+            wiki_links(content),
+            vec!["Osmosis", "Diffusion", "diagram.png"]
+        );
+        assert_eq!(inline_tags(content), vec!["biology", "cells/membrane"]);
+    }
+
+    #[test]
+    fn test_rewrite_wiki_links() {
+        let content = "[[PHYS-1]], [[PHYS-1|Newton]], ![[PHYS-1#Laws]], [[PHYS-10]] and PHYS-1";
+        assert_eq!(
+            rewrite_wiki_links(content, "PHYS-1", "MECH-1"),
+            (
+                "[[MECH-1]], [[MECH-1|Newton]], ![[MECH-1#Laws]], [[PHYS-10]] and PHYS-1"
+                    .to_string(),
+                3
+            )
+        );
+        assert_eq!(
+            rewrite_wiki_links("[[a.b]] [[axb]]", "a.b", "c"),
+            ("[[c]] [[axb]]".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn test_parse_wiki_links_keeps_heading_and_alias() {
+        let links: Vec<WikiLink> =
+            parse_wiki_links("[[ Osmosis#Pressure|osmotic pressure ]] and [[Diffusion]]").collect();
+        assert_eq!(links[0].target, "Osmosis");
+        assert_eq!(links[0].heading, Some("#Pressure"));
+        assert_eq!(links[0].alias, Some("osmotic pressure "));
+        assert_eq!(links[1].text, "[[Diffusion]]");
+        assert_eq!((links[1].heading, links[1].alias), (None, None));
+
+        let replaced = replace_wiki_links("[[A|first]] [[B]]", |link| {
+            link.alias.unwrap_or(link.target).to_string()
+        });
+        assert_eq!(replaced, "first B");
+    }
+}
@@ -22,6 +22,29 @@ pub struct Card {
     pub links: Option<String>, // JSON array of linked card IDs
 }
 
+impl Card {
+    /// A card that has never been reviewed and is due now
+    pub fn new(zettel_id: String, title: Option<String>, content: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            zettel_id,
+            title,
+            content,
+            creation_date: now,
+            last_reviewed: None,
+            next_review: now,
+            difficulty: 0.0,
+            stability: 0.0,
+            retrievability: 0.0,
+            reps: 0,
+            lapses: 0,
+            state: "New".to_string(),
+            links: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Topic {
     pub id: Uuid,
@@ -36,6 +59,120 @@ pub struct CardTopic {
     pub topic_id: Uuid,
 }
 
+/// A link from one card to another, stored from the target's side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub struct Backlink {
+    pub source_card_id: Uuid,
+    pub target_card_id: Uuid,
+    #[serde(default)]
+    pub relation: LinkRelation,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub origin: LinkOrigin,
+}
+
+/// Where a link came from: listed explicitly, or written as `[[zettel_id]]` in the
+/// linking card's content. Inline links are dropped when the content stops naming them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkOrigin {
+    #[default]
+    Explicit,
+    Inline,
+}
+
+impl LinkOrigin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkOrigin::Explicit => "explicit",
+            LinkOrigin::Inline => "inline",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "explicit" => Some(LinkOrigin::Explicit),
+            "inline" => Some(LinkOrigin::Inline),
+            _ => None,
+        }
+    }
+}
+
+/// What a link says about its target, read from the linking card: the target supports
+/// or contradicts it, it is an example of the target, or the target is a prerequisite
+/// for understanding it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkRelation {
+    Supports,
+    Contradicts,
+    ExampleOf,
+    Prerequisite,
+    #[default]
+    SeeAlso,
+}
+
+impl LinkRelation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkRelation::Supports => "supports",
+            LinkRelation::Contradicts => "contradicts",
+            LinkRelation::ExampleOf => "example-of",
+            LinkRelation::Prerequisite => "prerequisite",
+            LinkRelation::SeeAlso => "see-also",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "supports" => Some(LinkRelation::Supports),
+            "contradicts" => Some(LinkRelation::Contradicts),
+            "example-of" => Some(LinkRelation::ExampleOf),
+            "prerequisite" => Some(LinkRelation::Prerequisite),
+            "see-also" => Some(LinkRelation::SeeAlso),
+            _ => None,
+        }
+    }
+}
+
+/// A link to another card by zettel_id, with its relation and an optional note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypedZettelLink {
+    pub zettel_id: String,
+    #[serde(default)]
+    pub relation: LinkRelation,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// A card at the other end of a link, with the link's relation and note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedCard {
+    #[serde(flatten)]
+    pub card: Card,
+    pub relation: LinkRelation,
+    pub note: Option<String>,
+}
+
+/// A due card whose prerequisites are likely forgotten
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeakFoundation {
+    pub card_id: Uuid,
+    pub zettel_id: String,
+    pub prerequisites: Vec<WeakPrerequisite>,
+}
+
+/// A prerequisite below the retrievability threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeakPrerequisite {
+    pub card_id: Uuid,
+    pub zettel_id: String,
+    pub state: String,
+    pub retrievability: f64, // Estimated for now, not as of the last review
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Review {
     pub id: Uuid,
@@ -55,6 +192,17 @@ pub struct CreateCardRequest {
     pub links: Option<Vec<Uuid>>,
 }
 
+/// A card as returned after it is saved, with the `[[zettel_id]]` links in its content
+/// that don't name any card yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedCard {
+    #[serde(flatten)]
+    pub card: Card,
+    pub unresolved_links: Vec<String>,
+    #[serde(default)]
+    pub rewritten_references: Vec<RewrittenReference>, // When a rename rewrote other cards
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateCardWithZettelLinksRequest {
     pub zettel_id: String,
@@ -62,6 +210,8 @@ pub struct CreateCardWithZettelLinksRequest {
     pub content: String,
     pub topic_ids: Vec<Uuid>,
     pub zettel_links: Option<Vec<String>>,
+    #[serde(default)]
+    pub typed_links: Option<Vec<TypedZettelLink>>, // Links with a relation and note
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,6 +230,8 @@ pub struct UpdateCardWithZettelLinksRequest {
     pub content: Option<String>,
     pub topic_ids: Option<Vec<Uuid>>,
     pub zettel_links: Option<Vec<String>>,
+    #[serde(default)]
+    pub typed_links: Option<Vec<TypedZettelLink>>, // Links with a relation and note
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +240,26 @@ pub struct QuizQuestion {
     pub question_type: String, // "multiple_choice", "short_answer", "problem_solving"
     pub options: Option<Vec<String>>, // For multiple choice
     pub correct_answer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>, // Template that generated this question, e.g. "quiz_generation@v1"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rubric: Option<Vec<RubricCriterion>>, // Key points a full answer covers
+}
+
+/// One key point a good answer covers, weighted relative to the others
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RubricCriterion {
+    pub criterion: String,
+    pub weight: f64,
+}
+
+/// How well an answer covered one rubric criterion, from 0.0 to 1.0
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriterionScore {
+    pub criterion: String,
+    pub score: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,6 +282,76 @@ pub struct ReviewSession {
     pub created_at: DateTime<Utc>,
 }
 
+/// A generated question kept for reuse, tied to the card content it was generated from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BankedQuestion {
+    pub id: Uuid,
+    pub card_id: Uuid,
+    pub content_hash: String,
+    pub question: QuizQuestion,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub times_used: i64,
+    pub pinned: bool, // Always served and kept when the card's content changes
+    pub flag_reason: Option<String>, // Set when a reviewer reports the question as wrong or ambiguous
+    pub flagged_at: Option<DateTime<Utc>>,
+    pub edited_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameZettelIdRequest {
+    pub zettel_id: String,
+    #[serde(default)]
+    pub preview: bool, // Report what would be rewritten without changing anything
+}
+
+/// A card whose `[[zettel_id]]` links were pointed at a renamed card
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewrittenReference {
+    pub card_id: Uuid,
+    pub zettel_id: String,
+    pub occurrences: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZettelRenameReport {
+    pub card_id: Uuid,
+    pub old_zettel_id: String,
+    pub new_zettel_id: String,
+    pub preview: bool,
+    pub rewritten: Vec<RewrittenReference>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlagQuestionRequest {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateQuestionRequest {
+    pub question: Option<String>,
+    pub options: Option<Vec<String>>,
+    pub correct_answer: Option<String>,
+    pub rubric: Option<Vec<RubricCriterion>>,
+}
+
+/// Questions generated ahead of a review session, valid until `expires_at`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedQuestions {
+    pub card_id: Uuid,
+    pub questions: Vec<QuizQuestion>,
+    pub generated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// How urgently a card's questions should be pre-generated
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreGenerationPriority {
+    Immediate,  // Already due
+    NextCard,   // Coming up next in an open session
+    Background, // Due later within the lookahead window
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuizAnswerWithContext {
     pub question_index: usize,
@@ -121,6 +363,8 @@ pub struct BatchGradingRequest {
     pub question: QuizQuestion,
     pub user_answer: String,
     pub card_content: String,
+    #[serde(default)]
+    pub card_id: Option<Uuid>, // Used to pick topic-specific grading prompts
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -135,4 +379,64 @@ pub struct BatchGradingResult {
     pub is_correct: bool,
     pub feedback: String,
     pub suggested_rating: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub served_by: Option<String>, // "provider:model" that produced this grade
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>, // Grading template used, e.g. "batch_grading@v1"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>, // Partial credit from 0.0 to 1.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub criterion_scores: Option<Vec<CriterionScore>>,
+}
+
+/// A graded answer given during a review session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionAnswer {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub card_id: Uuid,
+    pub question_index: usize,
+    pub question: QuizQuestion,
+    pub user_answer: String,
+    pub is_correct: bool,
+    pub feedback: String,
+    pub suggested_rating: i32,
+    pub score: Option<f64>,
+    pub criterion_scores: Option<Vec<CriterionScore>>,
+    pub served_by: Option<String>,
+    pub final_rating: Option<i32>, // Rating the user gave the card when finishing it
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LLMUsageRecord {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    pub operation: String, // "generate", "batch_generate", "grade", "batch_grade"
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub latency_ms: i64,
+    pub success: bool,
+    pub estimated_cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyLLMUsage {
+    pub date: String, // YYYY-MM-DD (UTC)
+    pub provider: String,
+    pub model: String,
+    pub calls: i64,
+    pub failed_calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub estimated_cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LLMUsageSummary {
+    pub daily: Vec<DailyLLMUsage>,
+    pub month_to_date_cost_usd: f64,
+    pub monthly_budget_usd: Option<f64>,
 }
@@ -0,0 +1,420 @@
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// The prompts the LLM service sends; each maps to `<name>.md` in a templates directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PromptKind {
+    QuizGeneration,
+    BatchQuizGeneration,
+    Grading,
+    BatchGrading,
+}
+
+impl PromptKind {
+    pub const ALL: [PromptKind; 4] = [
+        PromptKind::QuizGeneration,
+        PromptKind::BatchQuizGeneration,
+        PromptKind::Grading,
+        PromptKind::BatchGrading,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PromptKind::QuizGeneration => "quiz_generation",
+            PromptKind::BatchQuizGeneration => "batch_quiz_generation",
+            PromptKind::Grading => "grading",
+            PromptKind::BatchGrading => "batch_grading",
+        }
+    }
+
+    /// Variables a template of this kind may reference as `{{name}}`
+    pub fn variables(&self) -> &'static [&'static str] {
+        match self {
+            PromptKind::QuizGeneration => &[
+                "card_content",
+                "zettel_id",
+                "topics",
+                "linked_cards",
+                "backlinks",
+                "question_history",
+                "flagged_questions",
+            ],
+            PromptKind::BatchQuizGeneration => {
+                &["cards", "first_card_id", "second_card_id", "topics"]
+            }
+            PromptKind::Grading => &[
+                "card_content",
+                "question",
+                "question_type",
+                "correct_answer",
+                "user_answer",
+                "rubric",
+                "topics",
+            ],
+            PromptKind::BatchGrading => &["questions_and_answers", "topics"],
+        }
+    }
+
+    fn builtin_source(&self) -> &'static str {
+        match self {
+            PromptKind::QuizGeneration => include_str!("../prompts/quiz_generation.md"),
+            PromptKind::BatchQuizGeneration => include_str!("../prompts/batch_quiz_generation.md"),
+            PromptKind::Grading => include_str!("../prompts/grading.md"),
+            PromptKind::BatchGrading => include_str!("../prompts/batch_grading.md"),
+        }
+    }
+}
+
+/// A prompt template parsed from a file with `version` and `system` front matter:
+///
+/// ```text
+/// ---
+/// version: v2
+/// system: You are a university professor...
+/// ---
+/// Prompt body with {{card_content}} variables
+/// ```
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    pub kind: PromptKind,
+    pub version: String,
+    pub system: Option<String>,
+    pub body: String,
+    pub topic: Option<String>,
+}
+
+impl PromptTemplate {
+    pub fn parse(kind: PromptKind, source: &str) -> Result<Self> {
+        let source = source.trim_start_matches('\u{feff}');
+        let rest = source.strip_prefix("---").ok_or_else(|| {
+            anyhow!(
+                "{} template must start with '---' front matter",
+                kind.name()
+            )
+        })?;
+        let end = rest.find("\n---").ok_or_else(|| {
+            anyhow!(
+                "{} template front matter is missing its closing '---'",
+                kind.name()
+            )
+        })?;
+        let front_matter = &rest[..end];
+        let body = rest[end + 4..].trim_start_matches(['\r', '\n']).trim_end();
+
+        let mut version = None;
+        let mut system = None;
+        for line in front_matter
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+        {
+            let (key, value) = line.split_once(':').ok_or_else(|| {
+                anyhow!(
+                    "Invalid front matter line in {} template: '{}'",
+                    kind.name(),
+                    line
+                )
+            })?;
+            match key.trim() {
+                "version" => version = Some(value.trim().to_string()),
+                "system" => system = Some(value.trim().to_string()),
+                other => {
+                    return Err(anyhow!(
+                        "Unknown front matter key '{}' in {} template",
+                        other,
+                        kind.name()
+                    ));
+                }
+            }
+        }
+
+        let version = version
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| anyhow!("{} template is missing a version", kind.name()))?;
+
+        for variable in Self::referenced_variables(body) {
+            if !kind.variables().contains(&variable) {
+                return Err(anyhow!(
+                    "{} template references unknown variable '{{{{{}}}}}'; available: {}",
+                    kind.name(),
+                    variable,
+                    kind.variables().join(", ")
+                ));
+            }
+        }
+
+        Ok(Self {
+            kind,
+            version,
+            system: system.filter(|s| !s.is_empty()),
+            body: body.to_string(),
+            topic: None,
+        })
+    }
+
+    /// Identifier recorded with generated questions and grades, e.g. `grading@v1`
+    /// or `grading/physics@v2` for a topic override
+    pub fn version_id(&self) -> String {
+        match &self.topic {
+            Some(topic) => format!("{}/{}@{}", self.kind.name(), topic, self.version),
+            None => format!("{}@{}", self.kind.name(), self.version),
+        }
+    }
+
+    /// Substitute `{{name}}` placeholders; variables not supplied render as empty
+    pub fn render(&self, variables: &[(&str, &str)]) -> String {
+        let mut rendered = String::with_capacity(self.body.len());
+        let mut rest = self.body.as_str();
+
+        while let Some(start) = rest.find("{{") {
+            rendered.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            match after.find("}}") {
+                Some(end) => {
+                    let name = after[..end].trim();
+                    if let Some((_, value)) = variables.iter().find(|(key, _)| *key == name) {
+                        rendered.push_str(value);
+                    }
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    rendered.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+
+    fn referenced_variables(body: &str) -> Vec<&str> {
+        let mut variables = Vec::new();
+        let mut rest = body;
+        while let Some(start) = rest.find("{{") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else { break };
+            variables.push(after[..end].trim());
+            rest = &after[end + 2..];
+        }
+        variables
+    }
+}
+
+/// Prompt templates for a deployment: built-in defaults, optionally replaced from a
+/// directory, plus per-topic overrides from `<dir>/topics/<topic>/<name>.md`
+#[derive(Debug, Clone)]
+pub struct PromptLibrary {
+    defaults: HashMap<PromptKind, PromptTemplate>,
+    topic_overrides: HashMap<String, HashMap<PromptKind, PromptTemplate>>,
+}
+
+impl Default for PromptLibrary {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl PromptLibrary {
+    /// Templates compiled into the binary from the repository's `prompts/` directory
+    pub fn builtin() -> Self {
+        let defaults = PromptKind::ALL
+            .iter()
+            .map(|kind| {
+                let template = PromptTemplate::parse(*kind, kind.builtin_source())
+                    .expect("built-in prompt templates are valid");
+                (*kind, template)
+            })
+            .collect();
+
+        Self {
+            defaults,
+            topic_overrides: HashMap::new(),
+        }
+    }
+
+    /// Load templates from a directory. Missing files fall back to the built-in templates.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        if !dir.is_dir() {
+            return Err(anyhow!(
+                "Prompt templates directory '{}' does not exist",
+                dir.display()
+            ));
+        }
+
+        let mut library = Self::builtin();
+        library.defaults.extend(Self::load_templates(dir, None)?);
+
+        let topics_dir = dir.join("topics");
+        if topics_dir.is_dir() {
+            for entry in fs::read_dir(&topics_dir)? {
+                let entry = entry?;
+                if !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                let topic = entry.file_name().to_string_lossy().to_string();
+                let templates = Self::load_templates(&entry.path(), Some(&topic))?;
+                if !templates.is_empty() {
+                    library
+                        .topic_overrides
+                        .insert(normalize_topic(&topic), templates);
+                }
+            }
+        }
+
+        info!(
+            prompt_dir = %dir.display(),
+            versions = ?PromptKind::ALL.iter().map(|k| library.defaults[k].version_id()).collect::<Vec<_>>(),
+            topic_override_count = library.topic_overrides.len(),
+            "Loaded prompt templates"
+        );
+
+        Ok(library)
+    }
+
+    fn load_templates(
+        dir: &Path,
+        topic: Option<&str>,
+    ) -> Result<HashMap<PromptKind, PromptTemplate>> {
+        let mut templates = HashMap::new();
+        for kind in PromptKind::ALL {
+            let path = dir.join(format!("{}.md", kind.name()));
+            if !path.is_file() {
+                continue;
+            }
+            let source = fs::read_to_string(&path)?;
+            let mut template = PromptTemplate::parse(kind, &source)
+                .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+            template.topic = topic.map(str::to_string);
+            debug!(path = %path.display(), version = %template.version_id(), "Loaded prompt template");
+            templates.insert(kind, template);
+        }
+        Ok(templates)
+    }
+
+    /// Pick the template for a card: the first of its topics with an override wins
+    pub fn select(&self, kind: PromptKind, topics: &[String]) -> &PromptTemplate {
+        topics
+            .iter()
+            .filter_map(|topic| self.topic_overrides.get(&normalize_topic(topic)))
+            .find_map(|templates| templates.get(&kind))
+            .unwrap_or(&self.defaults[&kind])
+    }
+}
+
+/// Topic directory names match topic names case-insensitively, with spaces as `-` or `_`
+fn normalize_topic(topic: &str) -> String {
+    topic
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c == ' ' || c == '_' { '-' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_templates_parse_and_render() {
+        let library = PromptLibrary::builtin();
+        let template = library.select(PromptKind::Grading, &[]);
+
+        assert_eq!(template.version_id(), "grading@v2");
+        assert!(template.system.is_some());
+
+        let prompt = template.render(&[
+            ("card_content", "Water boils at 100°C at sea level."),
+            ("question", "At what temperature does water boil?"),
+            ("question_type", "short_answer"),
+            ("correct_answer", "100°C"),
+            ("user_answer", "100 degrees"),
+        ]);
+        assert!(prompt.starts_with("Grade the following quiz answer"));
+        assert!(prompt.contains("User's Answer: 100 degrees"));
+        assert!(!prompt.contains("{{"));
+        assert!(prompt.contains("\"is_correct\": true|false"));
+    }
+
+    #[test]
+    fn test_default_generation_prompt_has_no_domain_bias() {
+        let library = PromptLibrary::builtin();
+        let prompt = library
+            .select(PromptKind::QuizGeneration, &[])
+            .render(&[("card_content", "The French Revolution began in 1789.")]);
+
+        assert!(!prompt.to_lowercase().contains("machine learning"));
+        assert!(!prompt.to_lowercase().contains("computer science"));
+    }
+
+    #[test]
+    fn test_template_validation() {
+        assert!(PromptTemplate::parse(PromptKind::Grading, "no front matter").is_err());
+        assert!(PromptTemplate::parse(PromptKind::Grading, "---\nsystem: hi\n---\nbody").is_err());
+
+        let error = PromptTemplate::parse(
+            PromptKind::Grading,
+            "---\nversion: v9\n---\nGrade {{card_contents}}",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("card_contents"), "{}", error);
+
+        let template = PromptTemplate::parse(
+            PromptKind::Grading,
+            "---\nversion: v9\n---\n\nGrade {{ user_answer }}!",
+        )
+        .unwrap();
+        assert_eq!(template.system, None);
+        assert_eq!(template.render(&[("user_answer", "42")]), "Grade 42!");
+    }
+
+    #[test]
+    fn test_load_dir_with_topic_overrides() {
+        let dir = std::env::temp_dir().join(format!("prompt-templates-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("topics").join("Organic_Chemistry")).unwrap();
+        fs::write(
+            dir.join("grading.md"),
+            "---\nversion: strict-1\nsystem: Grade strictly.\n---\nGrade the following quiz answer: {{user_answer}}",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("topics")
+                .join("Organic_Chemistry")
+                .join("quiz_generation.md"),
+            "---\nversion: chem-1\n---\nAsk about mechanisms in {{card_content}}",
+        )
+        .unwrap();
+
+        let library = PromptLibrary::load_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            library.select(PromptKind::Grading, &[]).version_id(),
+            "grading@strict-1"
+        );
+        assert_eq!(
+            library.select(PromptKind::BatchGrading, &[]).version_id(),
+            "batch_grading@v2"
+        );
+
+        let topics = vec!["History".to_string(), "organic chemistry".to_string()];
+        assert_eq!(
+            library
+                .select(PromptKind::QuizGeneration, &topics)
+                .version_id(),
+            "quiz_generation/Organic_Chemistry@chem-1"
+        );
+        // Kinds without a topic override use the deployment default
+        assert_eq!(
+            library.select(PromptKind::Grading, &topics).version_id(),
+            "grading@strict-1"
+        );
+
+        assert!(PromptLibrary::load_dir("/nonexistent/prompts").is_err());
+    }
+}
@@ -1,11 +1,15 @@
+use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{debug, info, warn};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use crate::models::{CachedQuestions, QuizQuestion, PreGenerationPriority};
+use crate::card_service::{CardService, QUESTIONS_PER_REVIEW};
+use crate::llm_service::LLMService;
+use crate::models::{CachedQuestions, PreGenerationPriority, QuizQuestion};
 
 /// In-memory question cache with TTL support and intelligent eviction
 #[derive(Debug, Clone)]
@@ -28,7 +32,7 @@ impl QuestionCache {
     pub async fn cache_questions(&self, card_id: Uuid, questions: Vec<QuizQuestion>) {
         let now = Utc::now();
         let expires_at = now + Duration::minutes(self.default_ttl_minutes);
-        
+
         let cached_questions = CachedQuestions {
             card_id,
             questions,
@@ -37,44 +41,55 @@ impl QuestionCache {
         };
 
         let mut cache = self.cache.write().await;
-        
+
         // Clean up expired entries before adding new one
         self.cleanup_expired_entries(&mut cache, now).await;
-        
+
         // If we're at capacity, remove oldest entry
         if cache.len() >= self.max_size {
             self.evict_oldest(&mut cache).await;
         }
-        
+
         cache.insert(card_id, cached_questions);
-        
-        debug!("Cached questions for card {}, cache size: {}", card_id, cache.len());
+
+        debug!(
+            "Cached questions for card {}, cache size: {}",
+            card_id,
+            cache.len()
+        );
     }
 
-    /// Retrieve questions from cache if available and not expired
-    pub async fn get_questions(&self, card_id: Uuid) -> Option<Vec<QuizQuestion>> {
+    /// Take a card's questions out of the cache if available and not expired. Each set
+    /// of pre-generated questions is served to one session only.
+    pub async fn take_questions(&self, card_id: Uuid) -> Option<Vec<QuizQuestion>> {
         let mut cache = self.cache.write().await;
         let now = Utc::now();
-        
-        if let Some(cached) = cache.get(&card_id) {
+
+        if let Some(cached) = cache.remove(&card_id) {
             if cached.expires_at > now {
                 debug!("Cache hit for card {}", card_id);
-                return Some(cached.questions.clone());
-            } else {
-                debug!("Cache expired for card {}, removing", card_id);
-                cache.remove(&card_id);
+                return Some(cached.questions);
             }
+            debug!("Cache expired for card {}, removing", card_id);
         }
-        
+
         debug!("Cache miss for card {}", card_id);
         None
     }
 
+    /// Drop a card's questions, e.g. after its content changed or it was reviewed
+    pub async fn invalidate(&self, card_id: Uuid) {
+        let mut cache = self.cache.write().await;
+        if cache.remove(&card_id).is_some() {
+            debug!("Invalidated cached questions for card {}", card_id);
+        }
+    }
+
     /// Check if questions are cached and not expired
     pub async fn has_cached_questions(&self, card_id: Uuid) -> bool {
         let cache = self.cache.read().await;
         let now = Utc::now();
-        
+
         if let Some(cached) = cache.get(&card_id) {
             cached.expires_at > now
         } else {
@@ -86,12 +101,13 @@ impl QuestionCache {
     pub async fn get_stats(&self) -> CacheStats {
         let cache = self.cache.read().await;
         let now = Utc::now();
-        
+
         let total_entries = cache.len();
-        let expired_entries = cache.values()
+        let expired_entries = cache
+            .values()
             .filter(|cached| cached.expires_at <= now)
             .count();
-        
+
         CacheStats {
             total_entries,
             expired_entries,
@@ -109,6 +125,7 @@ impl QuestionCache {
     }
 
     /// Clear all cached questions
+    #[allow(dead_code)]
     pub async fn clear(&self) {
         let mut cache = self.cache.write().await;
         cache.clear();
@@ -116,12 +133,17 @@ impl QuestionCache {
     }
 
     /// Private helper to remove expired entries
-    async fn cleanup_expired_entries(&self, cache: &mut HashMap<Uuid, CachedQuestions>, now: DateTime<Utc>) {
-        let expired_keys: Vec<Uuid> = cache.iter()
+    async fn cleanup_expired_entries(
+        &self,
+        cache: &mut HashMap<Uuid, CachedQuestions>,
+        now: DateTime<Utc>,
+    ) {
+        let expired_keys: Vec<Uuid> = cache
+            .iter()
             .filter(|(_, cached)| cached.expires_at <= now)
             .map(|(key, _)| *key)
             .collect();
-        
+
         for key in expired_keys {
             cache.remove(&key);
             debug!("Removed expired cache entry for card {}", key);
@@ -130,9 +152,11 @@ impl QuestionCache {
 
     /// Private helper to evict oldest entry when at capacity
     async fn evict_oldest(&self, cache: &mut HashMap<Uuid, CachedQuestions>) {
-        if let Some(oldest_key) = cache.iter()
+        if let Some(oldest_key) = cache
+            .iter()
             .min_by_key(|(_, cached)| cached.generated_at)
-            .map(|(key, _)| *key) {
+            .map(|(key, _)| *key)
+        {
             cache.remove(&oldest_key);
             debug!("Evicted oldest cache entry for card {}", oldest_key);
         }
@@ -140,6 +164,7 @@ impl QuestionCache {
 }
 
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct CacheStats {
     pub total_entries: usize,
     pub expired_entries: usize,
@@ -173,13 +198,13 @@ impl PreGenerationQueue {
     /// Add card to pre-generation queue
     pub async fn enqueue(&self, card_id: Uuid, priority: PreGenerationPriority) {
         let mut queue = self.queue.write().await;
-        
+
         // Check if already queued
         if queue.iter().any(|task| task.card_id == card_id) {
             debug!("Card {} already in pre-generation queue", card_id);
             return;
         }
-        
+
         // If at capacity, remove lowest priority task
         if queue.len() >= self.max_queue_size {
             queue.sort_by_key(|task| match task.priority {
@@ -190,32 +215,35 @@ impl PreGenerationQueue {
             queue.pop(); // Remove lowest priority (Background)
             debug!("Pre-generation queue at capacity, removed background task");
         }
-        
+
         let task = PreGenerationTask {
             card_id,
             priority: priority.clone(),
             queued_at: Utc::now(),
         };
-        
+
         queue.push(task);
-        debug!("Added card {} to pre-generation queue with priority {:?}", card_id, priority);
+        debug!(
+            "Added card {} to pre-generation queue with priority {:?}",
+            card_id, priority
+        );
     }
 
     /// Get next task to process (highest priority first)
     pub async fn dequeue(&self) -> Option<PreGenerationTask> {
         let mut queue = self.queue.write().await;
-        
+
         if queue.is_empty() {
             return None;
         }
-        
+
         // Sort by priority (Immediate = 0, NextCard = 1, Background = 2)
         queue.sort_by_key(|task| match task.priority {
             PreGenerationPriority::Immediate => 0,
             PreGenerationPriority::NextCard => 1,
             PreGenerationPriority::Background => 2,
         });
-        
+
         let task = queue.remove(0);
         debug!("Dequeued card {} from pre-generation queue", task.card_id);
         Some(task)
@@ -226,4 +254,241 @@ impl PreGenerationQueue {
         let queue = self.queue.read().await;
         queue.len()
     }
-}
\ No newline at end of file
+}
+
+/// Background worker that fills the question cache for cards coming due soon,
+/// so review sessions can open without waiting on an LLM round-trip
+#[derive(Clone)]
+pub struct PreGenerationWorker {
+    card_service: CardService,
+    llm_service: LLMService,
+    cache: QuestionCache,
+    queue: PreGenerationQueue,
+    lookahead_hours: i64,
+    batch_size: usize,
+}
+
+impl PreGenerationWorker {
+    pub fn new(
+        card_service: CardService,
+        llm_service: LLMService,
+        cache: QuestionCache,
+        queue: PreGenerationQueue,
+        lookahead_hours: i64,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            card_service,
+            llm_service,
+            cache,
+            queue,
+            lookahead_hours,
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    /// Queue every card due within the lookahead window that has no cached questions
+    pub async fn enqueue_due_cards(&self) -> Result<usize> {
+        let now = Utc::now();
+        let cards = self
+            .card_service
+            .get_cards_due_within(self.lookahead_hours)
+            .await?;
+
+        let mut queued = 0;
+        for card in cards {
+            if self.cache.has_cached_questions(card.id).await {
+                continue;
+            }
+
+            let priority = if card.next_review <= now {
+                PreGenerationPriority::Immediate
+            } else {
+                PreGenerationPriority::Background
+            };
+            self.queue.enqueue(card.id, priority).await;
+            queued += 1;
+        }
+
+        Ok(queued)
+    }
+
+    /// Generate and cache questions for up to `batch_size` queued cards.
+    /// Returns the number of cards whose questions were cached.
+    pub async fn process_batch(&self) -> Result<usize> {
+        let mut cards = Vec::new();
+        let mut served_from_bank = 0;
+        while cards.len() < self.batch_size {
+            let Some(task) = self.queue.dequeue().await else {
+                break;
+            };
+
+            debug!(
+                card_id = %task.card_id,
+                priority = ?task.priority,
+                queued_ms = (Utc::now() - task.queued_at).num_milliseconds(),
+                "Pre-generating questions for queued card"
+            );
+
+            // Skip cards deleted or already cached since they were queued
+            if self.cache.has_cached_questions(task.card_id).await {
+                continue;
+            }
+            let Some(card) = self.card_service.get_card(task.card_id).await? else {
+                continue;
+            };
+
+            // Banked questions for unchanged content need no LLM call
+            let banked = self
+                .card_service
+                .get_banked_questions(&card, QUESTIONS_PER_REVIEW)
+                .await?;
+            if banked.is_empty() {
+                cards.push(card);
+            } else {
+                self.cache.cache_questions(card.id, banked).await;
+                served_from_bank += 1;
+            }
+        }
+
+        if cards.is_empty() {
+            return Ok(served_from_bank);
+        }
+
+        // Failed cards are picked up again on the next pass; sessions still generate
+        // questions inline for anything not cached, so there is no local fallback here
+        let generated = match self.llm_service.generate_batch_quiz_questions(&cards).await {
+            Ok(generated) => generated,
+            Err(e) => {
+                warn!(
+                    card_count = cards.len(),
+                    error = %e,
+                    "Question pre-generation failed for batch"
+                );
+                return Ok(served_from_bank);
+            }
+        };
+
+        let mut cached = served_from_bank;
+        for card in &cards {
+            let Some(questions) = generated.get(&card.id).filter(|q| !q.is_empty()) else {
+                continue;
+            };
+            if let Err(e) = self.card_service.bank_questions(card, questions).await {
+                warn!(card_id = %card.id, error = %e, "Failed to bank pre-generated questions");
+            }
+            self.cache.cache_questions(card.id, questions.clone()).await;
+            cached += 1;
+        }
+
+        Ok(cached)
+    }
+
+    /// Refresh the queue from the due-card window and drain it
+    pub async fn run_once(&self) -> Result<usize> {
+        self.cache.cleanup().await;
+        let queued = self.enqueue_due_cards().await?;
+
+        let mut cached = 0;
+        while self.queue.size().await > 0 {
+            cached += self.process_batch().await?;
+        }
+
+        if queued > 0 {
+            let stats = self.cache.get_stats().await;
+            info!(
+                queued = queued,
+                cached = cached,
+                cache_entries = stats.active_entries,
+                cache_max_size = stats.max_size,
+                "Question pre-generation pass completed"
+            );
+        }
+
+        Ok(cached)
+    }
+
+    /// Run a pre-generation pass every `interval` until the runtime shuts down
+    pub fn spawn(self, interval: std::time::Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_once().await {
+                    error!(error = %e, "Question pre-generation pass failed");
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_question(text: &str) -> QuizQuestion {
+        QuizQuestion {
+            question: text.to_string(),
+            question_type: "short_answer".to_string(),
+            options: None,
+            correct_answer: Some("answer".to_string()),
+            prompt_version: None,
+            rubric: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_expiry_eviction_and_invalidation() {
+        let cache = QuestionCache::new(2, 60);
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        cache
+            .cache_questions(a, vec![create_test_question("a")])
+            .await;
+        cache
+            .cache_questions(b, vec![create_test_question("b")])
+            .await;
+        cache
+            .cache_questions(c, vec![create_test_question("c")])
+            .await;
+
+        // At capacity the oldest entry is evicted
+        assert!(cache.take_questions(a).await.is_none());
+        assert_eq!(cache.take_questions(c).await.unwrap()[0].question, "c");
+
+        // Questions are served once, so a second lookup misses
+        assert!(cache.take_questions(c).await.is_none());
+
+        cache.invalidate(b).await;
+        assert!(!cache.has_cached_questions(b).await);
+
+        let expired = QuestionCache::new(10, 0);
+        expired
+            .cache_questions(a, vec![create_test_question("a")])
+            .await;
+        assert!(expired.take_questions(a).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_queue_dequeues_by_priority_without_duplicates() {
+        let queue = PreGenerationQueue::new(10);
+        let (background, immediate) = (Uuid::new_v4(), Uuid::new_v4());
+
+        queue
+            .enqueue(background, PreGenerationPriority::Background)
+            .await;
+        queue
+            .enqueue(immediate, PreGenerationPriority::Immediate)
+            .await;
+        queue
+            .enqueue(immediate, PreGenerationPriority::Immediate)
+            .await;
+
+        assert_eq!(queue.size().await, 2);
+        assert_eq!(queue.dequeue().await.unwrap().card_id, immediate);
+        assert_eq!(queue.dequeue().await.unwrap().card_id, background);
+        assert!(queue.dequeue().await.is_none());
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashSet;
+
+use crate::models::{BankedQuestion, QuizQuestion};
+
+/// Questions at least this similar to an earlier one count as repeats
+pub const SIMILARITY_THRESHOLD: f64 = 0.6;
+
+/// How many of a card's existing questions are shown to the generator
+pub const QUESTION_HISTORY_LIMIT: usize = 10;
+
+// Words that carry no meaning about what a question tests
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "can", "does", "for", "from", "how", "in",
+    "is", "it", "its", "of", "on", "or", "that", "the", "this", "to", "what", "when", "where",
+    "which", "who", "why", "with",
+];
+
+fn significant_words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Jaccard similarity of the significant words of two questions, from 0.0 to 1.0
+pub fn question_similarity(a: &str, b: &str) -> f64 {
+    let a_words = significant_words(a);
+    let b_words = significant_words(b);
+    if a_words.is_empty() && b_words.is_empty() {
+        return 1.0;
+    }
+
+    let shared = a_words.intersection(&b_words).count();
+    let total = a_words.union(&b_words).count();
+    shared as f64 / total as f64
+}
+
+/// Highest similarity between `question` and any question in `history`
+pub fn max_similarity(question: &QuizQuestion, history: &[QuizQuestion]) -> f64 {
+    history
+        .iter()
+        .map(|past| question_similarity(&question.question, &past.question))
+        .fold(0.0, f64::max)
+}
+
+/// Split generated questions into those that are new and those that repeat the
+/// history or an earlier candidate in the same batch
+pub fn remove_repeated_questions(
+    candidates: Vec<QuizQuestion>,
+    history: &[QuizQuestion],
+) -> (Vec<QuizQuestion>, Vec<QuizQuestion>) {
+    let mut accepted: Vec<QuizQuestion> = Vec::new();
+    let mut rejected = Vec::new();
+
+    for candidate in candidates {
+        let repeats_history = max_similarity(&candidate, history) >= SIMILARITY_THRESHOLD;
+        let repeats_batch = max_similarity(&candidate, &accepted) >= SIMILARITY_THRESHOLD;
+        if repeats_history || repeats_batch {
+            rejected.push(candidate);
+        } else {
+            accepted.push(candidate);
+        }
+    }
+
+    (accepted, rejected)
+}
+
+/// Pick up to `limit` questions, preferring ones whose type hasn't been picked yet
+/// while otherwise keeping the given (least used first) order
+pub fn select_varied(questions: Vec<QuizQuestion>, limit: usize) -> Vec<QuizQuestion> {
+    let mut selected = Vec::new();
+    let mut remaining = Vec::new();
+    let mut types = HashSet::new();
+
+    for question in questions {
+        if selected.len() < limit && types.insert(question.question_type.clone()) {
+            selected.push(question);
+        } else {
+            remaining.push(question);
+        }
+    }
+
+    let missing = limit.saturating_sub(selected.len());
+    selected.extend(remaining.into_iter().take(missing));
+    selected
+}
+
+/// A card's earlier questions, shown to the generator and used to reject repeats
+#[derive(Debug, Clone, Default)]
+pub struct QuestionHistory {
+    pub asked: Vec<QuizQuestion>,
+    pub flagged: Vec<BankedQuestion>, // Reported by reviewers, used as negative examples
+}
+
+impl QuestionHistory {
+    /// Split banked questions into flagged ones and the rest, keeping the most
+    /// recently asked of each
+    pub fn from_banked(mut banked: Vec<BankedQuestion>) -> Self {
+        // Never-asked questions (None) sort after every asked one
+        banked.sort_by_key(|b| std::cmp::Reverse(b.last_used_at));
+        let (flagged, asked): (Vec<_>, Vec<_>) =
+            banked.into_iter().partition(|b| b.flag_reason.is_some());
+
+        Self {
+            asked: asked
+                .into_iter()
+                .take(QUESTION_HISTORY_LIMIT)
+                .map(|b| b.question)
+                .collect(),
+            flagged: flagged.into_iter().take(QUESTION_HISTORY_LIMIT).collect(),
+        }
+    }
+
+    /// Every question a newly generated one must not repeat
+    pub fn all_questions(&self) -> Vec<QuizQuestion> {
+        self.asked
+            .iter()
+            .cloned()
+            .chain(self.flagged.iter().map(|b| b.question.clone()))
+            .collect()
+    }
+}
+
+/// Render question history for a generation prompt
+pub fn format_question_history(history: &[QuizQuestion]) -> String {
+    if history.is_empty() {
+        return "none".to_string();
+    }
+
+    history
+        .iter()
+        .map(|q| format!("- [{}] {}", q.question_type, q.question))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render flagged questions and the reasons they were flagged for a generation prompt
+pub fn format_flagged_questions(flagged: &[BankedQuestion]) -> String {
+    if flagged.is_empty() {
+        return "none".to_string();
+    }
+
+    flagged
+        .iter()
+        .map(|b| {
+            format!(
+                "- [{}] {} (flagged: {})",
+                b.question.question_type,
+                b.question.question,
+                b.flag_reason.as_deref().unwrap_or("no reason given")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question(text: &str, question_type: &str) -> QuizQuestion {
+        QuizQuestion {
+            question: text.to_string(),
+            question_type: question_type.to_string(),
+            options: None,
+            correct_answer: None,
+            prompt_version: None,
+            rubric: None,
+        }
+    }
+
+    #[test]
+    fn test_similarity_ignores_case_punctuation_and_stop_words() {
+        assert_eq!(
+            question_similarity(
+                "What is the capital of France?",
+                "capital of france — what is it"
+            ),
+            1.0
+        );
+        assert!(
+            question_similarity(
+                "What is the capital of France?",
+                "Which river flows through Paris?"
+            ) < SIMILARITY_THRESHOLD
+        );
+    }
+
+    #[test]
+    fn test_repeats_of_history_and_batch_are_rejected() {
+        let history = vec![question("Define photosynthesis", "short_answer")];
+        let candidates = vec![
+            question("Define photosynthesis.", "short_answer"),
+            question("Which gas does photosynthesis release?", "short_answer"),
+            question(
+                "Which gas does photosynthesis release into the air?",
+                "multiple_choice",
+            ),
+        ];
+
+        let (accepted, rejected) = remove_repeated_questions(candidates, &history);
+        assert_eq!(accepted.len(), 1);
+        assert_eq!(rejected.len(), 2);
+        assert_eq!(
+            accepted[0].question,
+            "Which gas does photosynthesis release?"
+        );
+    }
+
+    #[test]
+    fn test_select_varied_prefers_distinct_types() {
+        let questions = vec![
+            question("One", "short_answer"),
+            question("Two", "short_answer"),
+            question("Three", "multiple_choice"),
+        ];
+
+        let selected = select_varied(questions, 2);
+        let types: Vec<_> = selected.iter().map(|q| q.question_type.as_str()).collect();
+        assert_eq!(types, vec!["short_answer", "multiple_choice"]);
+    }
+
+    #[test]
+    fn test_history_separates_flagged_questions() {
+        let banked = |text: &str, flag_reason: Option<&str>| BankedQuestion {
+            id: uuid::Uuid::new_v4(),
+            card_id: uuid::Uuid::nil(),
+            content_hash: String::new(),
+            question: question(text, "short_answer"),
+            created_at: chrono::Utc::now(),
+            last_used_at: None,
+            times_used: 0,
+            pinned: false,
+            flag_reason: flag_reason.map(str::to_string),
+            flagged_at: None,
+            edited_at: None,
+        };
+
+        let history = QuestionHistory::from_banked(vec![
+            banked("Fine question", None),
+            banked("Broken question", Some("No correct option")),
+        ]);
+        assert_eq!(history.asked.len(), 1);
+        assert_eq!(history.all_questions().len(), 2);
+        assert_eq!(
+            format_flagged_questions(&history.flagged),
+            "- [short_answer] Broken question (flagged: No correct option)"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::answer_text::normalize_answer;
+use crate::llm_service::GradingResult;
+use crate::models::{CriterionScore, RubricCriterion};
+
+/// Answers scoring at least this much count as correct
+pub const PASSING_SCORE: f64 = 0.6;
+
+/// FSRS rating for a 0.0-1.0 partial-credit score
+pub fn score_to_rating(score: f64) -> i32 {
+    match score {
+        s if s < 0.3 => 1,           // Again
+        s if s < PASSING_SCORE => 2, // Hard, with partial recall
+        s if s < 0.9 => 3,           // Good
+        _ => 4,                      // Easy
+    }
+}
+
+/// Weighted average of the criterion scores, using the question's own weights. Criteria
+/// the grader skipped score zero; returns `None` when no criterion was scored at all.
+pub fn weighted_score(rubric: &[RubricCriterion], scores: &[CriterionScore]) -> Option<f64> {
+    let total_weight: f64 = rubric.iter().map(|c| c.weight.max(0.0)).sum();
+    if total_weight <= 0.0 {
+        return None;
+    }
+
+    let mut matched = false;
+    let weighted: f64 = rubric
+        .iter()
+        .map(|criterion| {
+            let name = normalize_answer(&criterion.criterion);
+            let score = scores
+                .iter()
+                .find(|s| normalize_answer(&s.criterion) == name)
+                .map(|s| s.score.clamp(0.0, 1.0));
+            matched |= score.is_some();
+            criterion.weight.max(0.0) * score.unwrap_or(0.0)
+        })
+        .sum();
+
+    matched.then_some(weighted / total_weight)
+}
+
+/// Partial credit for a graded answer. The question's rubric weights take precedence
+/// over an overall score reported by the grader.
+pub fn partial_credit(
+    rubric: Option<&[RubricCriterion]>,
+    score: Option<f64>,
+    criterion_scores: Option<&[CriterionScore]>,
+) -> Option<f64> {
+    let from_criteria = match (rubric, criterion_scores) {
+        (Some(rubric), Some(scores)) => weighted_score(rubric, scores),
+        _ => None,
+    };
+    from_criteria.or(score).map(|score| score.clamp(0.0, 1.0))
+}
+
+/// Recompute correctness and the suggested rating from partial credit, when there is any
+pub fn apply_partial_credit(result: &mut GradingResult, rubric: Option<&[RubricCriterion]>) {
+    let Some(score) = partial_credit(rubric, result.score, result.criterion_scores.as_deref())
+    else {
+        return;
+    };
+
+    result.score = Some(score);
+    result.is_correct = score >= PASSING_SCORE;
+    result.suggested_rating = score_to_rating(score);
+}
+
+/// Render a rubric for a grading prompt
+pub fn format_rubric(rubric: Option<&[RubricCriterion]>) -> String {
+    match rubric {
+        Some(rubric) if !rubric.is_empty() => rubric
+            .iter()
+            .map(|c| format!("- {} (weight {})", c.criterion, c.weight))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => "none".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rubric() -> Vec<RubricCriterion> {
+        vec![
+            RubricCriterion {
+                criterion: "Names the Calvin cycle".to_string(),
+                weight: 0.25,
+            },
+            RubricCriterion {
+                criterion: "Explains that it uses ATP and NADPH".to_string(),
+                weight: 0.75,
+            },
+        ]
+    }
+
+    fn score(criterion: &str, score: f64) -> CriterionScore {
+        CriterionScore {
+            criterion: criterion.to_string(),
+            score,
+            feedback: None,
+        }
+    }
+
+    #[test]
+    fn test_weighted_score_uses_rubric_weights() {
+        let scores = vec![
+            score("names the Calvin cycle.", 1.0),
+            score("Explains that it uses ATP and NADPH", 0.5),
+        ];
+        assert_eq!(weighted_score(&rubric(), &scores), Some(0.625));
+
+        // Skipped criteria count as zero; unknown ones are ignored
+        let scores = vec![score("Names the Calvin cycle", 1.0), score("Other", 1.0)];
+        assert_eq!(weighted_score(&rubric(), &scores), Some(0.25));
+        assert_eq!(weighted_score(&rubric(), &[score("Other", 1.0)]), None);
+    }
+
+    #[test]
+    fn test_partial_credit_sets_rating_and_correctness() {
+        let mut result = GradingResult {
+            is_correct: true,
+            feedback: "Mostly right".to_string(),
+            suggested_rating: 4,
+            served_by: None,
+            prompt_version: None,
+            score: Some(0.9),
+            criterion_scores: Some(vec![score("Names the Calvin cycle", 1.0)]),
+        };
+
+        apply_partial_credit(&mut result, Some(&rubric()));
+        assert_eq!(result.score, Some(0.25));
+        assert!(!result.is_correct);
+        assert_eq!(result.suggested_rating, 1);
+
+        // Without a rubric the grader's overall score is used
+        let mut result = GradingResult {
+            criterion_scores: None,
+            ..result
+        };
+        result.score = Some(0.7);
+        apply_partial_credit(&mut result, None);
+        assert!(result.is_correct);
+        assert_eq!(result.suggested_rating, 3);
+    }
+}
@@ -1,6 +1,11 @@
+#![allow(clippy::useless_vec)]
+
 #[cfg(test)]
 mod batch_grading_integration_tests {
-    use crate::{api::*, card_service::CardService, llm_service::LLMService, models::*};
+    use crate::{
+        api::*, card_service::CardService, llm_service::LLMService, models::*,
+        question_cache::QuestionCache,
+    };
     use axum::{
         Router,
         body::Body,
@@ -23,6 +28,7 @@ mod batch_grading_integration_tests {
             card_service: card_service.clone(),
             llm_service,
             review_sessions: review_sessions.clone(),
+            question_cache: QuestionCache::new(100, 60),
         };
 
         let app = create_app(app_state);
@@ -48,6 +54,8 @@ mod batch_grading_integration_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Testing batch grading flow".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "Which testing approach is being demonstrated?".to_string(),
@@ -58,12 +66,16 @@ mod batch_grading_integration_tests {
                     "C) End-to-end testing".to_string(),
                 ]),
                 correct_answer: Some("A) Integration testing".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "What is the expected improvement from batch processing?".to_string(),
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Reduced API calls and latency".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "How many network requests should batch processing make?".to_string(),
@@ -74,6 +86,8 @@ mod batch_grading_integration_tests {
                     "C) Multiple parallel requests".to_string(),
                 ]),
                 correct_answer: Some("B) One per card".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
         ];
 
@@ -138,7 +152,7 @@ mod batch_grading_integration_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -172,7 +186,7 @@ mod batch_grading_integration_tests {
             assert!(result["suggested_rating"].is_number());
 
             let rating = result["suggested_rating"].as_i64().unwrap();
-            assert!(rating >= 1 && rating <= 4);
+            assert!((1..=4).contains(&rating));
         }
 
         // Performance check: should be faster than sequential calls would be
@@ -185,7 +199,7 @@ mod batch_grading_integration_tests {
     async fn test_batch_vs_sequential_performance_comparison() {
         let (app, session_id, card_id, _questions) = setup_integration_test().await;
 
-        let user_answers = vec![
+        let user_answers = [
             "Answer 1".to_string(),
             "Answer 2".to_string(),
             "Answer 3".to_string(),
@@ -210,7 +224,7 @@ mod batch_grading_integration_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -237,7 +251,7 @@ mod batch_grading_integration_tests {
                 .oneshot(
                     Request::builder()
                         .method("POST")
-                        .uri(&format!(
+                        .uri(format!(
                             "/api/review/session/{}/answer/{}",
                             session_id, card_id
                         ))
@@ -298,7 +312,7 @@ mod batch_grading_integration_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -339,7 +353,7 @@ mod batch_grading_integration_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -364,7 +378,7 @@ mod batch_grading_integration_tests {
 
         // All results should have proper structure despite large content
         for result in results {
-            assert!(result["feedback"].as_str().unwrap().len() > 0);
+            assert!(!result["feedback"].as_str().unwrap().is_empty());
             assert!(result["suggested_rating"].as_i64().unwrap() >= 1);
         }
     }
@@ -394,7 +408,7 @@ mod batch_grading_integration_tests {
                     .oneshot(
                         Request::builder()
                             .method("POST")
-                            .uri(&format!(
+                            .uri(format!(
                                 "/api/review/session/{}/answers/{}/batch",
                                 session_id_clone, card_id_clone
                             ))
@@ -441,7 +455,7 @@ mod batch_grading_integration_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -459,7 +473,7 @@ mod batch_grading_integration_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -497,7 +511,7 @@ mod batch_grading_integration_tests {
         // Step 1: Start review session (this should already be done in setup)
 
         // Step 2: Submit batch answers
-        let user_answers = vec![
+        let user_answers = [
             "Testing batch grading flow".to_string(),
             "A) Integration testing".to_string(),
             "Reduced API calls and latency".to_string(),
@@ -520,7 +534,7 @@ mod batch_grading_integration_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -552,7 +566,7 @@ mod batch_grading_integration_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!("/api/cards/{}/review", card_id))
+                    .uri(format!("/api/cards/{}/review", card_id))
                     .header("content-type", "application/json")
                     .body(Body::from(format!(r#"{{"rating": {}}}"#, avg_rating)))
                     .unwrap(),
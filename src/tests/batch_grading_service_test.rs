@@ -26,22 +26,26 @@ mod batch_grading_service_tests {
             links: None,
         };
 
-        let questions = vec![
+        let questions = [
             QuizQuestion {
                 question: "What is this about?".to_string(),
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Test content".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "Choose correct option".to_string(),
                 question_type: "multiple_choice".to_string(),
                 options: Some(vec!["A) Correct".to_string(), "B) Wrong".to_string()]),
                 correct_answer: Some("A) Correct".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
         ];
 
-        let user_answers = vec!["Test content".to_string(), "A) Correct".to_string()];
+        let user_answers = ["Test content".to_string(), "A) Correct".to_string()];
 
         // Build batch grading requests
         let batch_requests: Vec<BatchGradingRequest> = questions
@@ -51,6 +55,7 @@ mod batch_grading_service_tests {
                 card_content: card.content.clone(),
                 question: question.clone(),
                 user_answer: answer.clone(),
+                card_id: None,
             })
             .collect();
 
@@ -74,8 +79,11 @@ mod batch_grading_service_tests {
                     question_type: "short_answer".to_string(),
                     options: None,
                     correct_answer: Some("42".to_string()),
+                    prompt_version: None,
+                    rubric: None,
                 },
                 user_answer: "42".to_string(),
+                card_id: None,
             },
             BatchGradingRequest {
                 card_content: "Test card content".to_string(),
@@ -84,8 +92,11 @@ mod batch_grading_service_tests {
                     question_type: "multiple_choice".to_string(),
                     options: Some(vec!["A) Right".to_string(), "B) Wrong".to_string()]),
                     correct_answer: Some("A) Right".to_string()),
+                    prompt_version: None,
+                    rubric: None,
                 },
                 user_answer: "A) Right".to_string(),
+                card_id: None,
             },
         ];
 
@@ -99,7 +110,7 @@ mod batch_grading_service_tests {
         // Verify each result has required fields
         for (i, result) in results.iter().enumerate() {
             assert_eq!(result.question_id, (i + 1).to_string());
-            assert!(result.feedback.len() > 0);
+            assert!(!result.feedback.is_empty());
             assert!(result.suggested_rating >= 1 && result.suggested_rating <= 4);
             // Mock service should return correct for exact matches
             assert!(result.is_correct);
@@ -130,8 +141,11 @@ mod batch_grading_service_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Single answer".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             user_answer: "Single answer".to_string(),
+            card_id: None,
         }];
 
         let results = llm_service
@@ -142,7 +156,7 @@ mod batch_grading_service_tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].question_id, "1");
         assert!(results[0].is_correct);
-        assert!(results[0].feedback.len() > 0);
+        assert!(!results[0].feedback.is_empty());
     }
 
     #[tokio::test]
@@ -157,8 +171,11 @@ mod batch_grading_service_tests {
                     question_type: "short_answer".to_string(),
                     options: None,
                     correct_answer: Some("Short answer".to_string()),
+                    prompt_version: None,
+                    rubric: None,
                 },
                 user_answer: "Short answer".to_string(),
+                card_id: None,
             },
             BatchGradingRequest {
                 card_content: "Mixed content".to_string(),
@@ -167,8 +184,11 @@ mod batch_grading_service_tests {
                     question_type: "multiple_choice".to_string(),
                     options: Some(vec!["A) Option 1".to_string(), "B) Option 2".to_string()]),
                     correct_answer: Some("A) Option 1".to_string()),
+                    prompt_version: None,
+                    rubric: None,
                 },
                 user_answer: "A".to_string(), // Test abbreviated answer
+                card_id: None,
             },
         ];
 
@@ -198,8 +218,11 @@ mod batch_grading_service_tests {
                     question_type: "short_answer".to_string(),
                     options: None,
                     correct_answer: Some("Correct answer".to_string()),
+                    prompt_version: None,
+                    rubric: None,
                 },
                 user_answer: "Wrong answer".to_string(),
+                card_id: None,
             },
             BatchGradingRequest {
                 card_content: "Test content".to_string(),
@@ -208,8 +231,11 @@ mod batch_grading_service_tests {
                     question_type: "multiple_choice".to_string(),
                     options: Some(vec!["A) Right".to_string(), "B) Wrong".to_string()]),
                     correct_answer: Some("A) Right".to_string()),
+                    prompt_version: None,
+                    rubric: None,
                 },
                 user_answer: "B) Wrong".to_string(),
+                card_id: None,
             },
         ];
 
@@ -224,7 +250,7 @@ mod batch_grading_service_tests {
         for result in results {
             assert!(!result.is_correct);
             assert!(result.suggested_rating >= 1 && result.suggested_rating <= 2); // Should be low for incorrect
-            assert!(result.feedback.len() > 0);
+            assert!(!result.feedback.is_empty());
         }
     }
 
@@ -240,8 +266,11 @@ mod batch_grading_service_tests {
                     question_type: "short_answer".to_string(),
                     options: None,
                     correct_answer: Some("First answer".to_string()),
+                    prompt_version: None,
+                    rubric: None,
                 },
                 user_answer: "First answer".to_string(),
+                card_id: None,
             },
             BatchGradingRequest {
                 card_content: "Content 2".to_string(),
@@ -250,8 +279,11 @@ mod batch_grading_service_tests {
                     question_type: "short_answer".to_string(),
                     options: None,
                     correct_answer: Some("Second answer".to_string()),
+                    prompt_version: None,
+                    rubric: None,
                 },
                 user_answer: "Second answer".to_string(),
+                card_id: None,
             },
             BatchGradingRequest {
                 card_content: "Content 3".to_string(),
@@ -260,8 +292,11 @@ mod batch_grading_service_tests {
                     question_type: "short_answer".to_string(),
                     options: None,
                     correct_answer: Some("Third answer".to_string()),
+                    prompt_version: None,
+                    rubric: None,
                 },
                 user_answer: "Third answer".to_string(),
+                card_id: None,
             },
         ];
 
@@ -292,8 +327,11 @@ mod batch_grading_service_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Answer".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             user_answer: "Answer".to_string(),
+            card_id: None,
         }];
 
         // This should not fail due to content length
@@ -303,7 +341,7 @@ mod batch_grading_service_tests {
             .unwrap();
 
         assert_eq!(results.len(), 1);
-        assert!(results[0].feedback.len() > 0);
+        assert!(!results[0].feedback.is_empty());
     }
 
     #[tokio::test]
@@ -317,8 +355,11 @@ mod batch_grading_service_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Fallback answer".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             user_answer: "Fallback answer".to_string(),
+            card_id: None,
         }];
 
         // Should fall back to individual grading
@@ -330,7 +371,7 @@ mod batch_grading_service_tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].question_id, "1");
         // Fallback should still provide valid results
-        assert!(results[0].feedback.len() > 0);
+        assert!(!results[0].feedback.is_empty());
     }
 
     #[tokio::test]
@@ -346,8 +387,11 @@ mod batch_grading_service_tests {
                     question_type: "short_answer".to_string(),
                     options: None,
                     correct_answer: Some(format!("Answer {}", i)),
+                    prompt_version: None,
+                    rubric: None,
                 },
                 user_answer: format!("Answer {}", i),
+                card_id: None,
             })
             .collect();
 
@@ -361,7 +405,168 @@ mod batch_grading_service_tests {
         for (i, result) in results.iter().enumerate() {
             assert_eq!(result.question_id, (i + 1).to_string());
             assert!(result.is_correct);
-            assert!(result.feedback.len() > 0);
+            assert!(!result.feedback.is_empty());
         }
     }
+
+    #[tokio::test]
+    async fn test_batch_grading_splits_multibyte_content_beyond_context_window() {
+        let llm_service = LLMService::new_mock();
+
+        // Multi-byte content used to panic when cut at a fixed byte offset, and these
+        // answers together exceed the mock model's context window
+        let content = "Équation de Schrödinger: $i\\hbar \\partial_t \\psi = H \\psi$ — 量子力学. "
+            .repeat(80);
+        let batch_requests: Vec<BatchGradingRequest> = (0..30)
+            .map(|i| BatchGradingRequest {
+                card_content: content.clone(),
+                question: QuizQuestion {
+                    question: format!("Question {}", i),
+                    question_type: "short_answer".to_string(),
+                    options: None,
+                    correct_answer: Some(format!("Answer {}", i)),
+                    prompt_version: None,
+                    rubric: None,
+                },
+                user_answer: format!("Answer {}", i),
+                card_id: None,
+            })
+            .collect();
+
+        let results = llm_service
+            .grade_batch_answers(&batch_requests)
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 30);
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.question_id, (i + 1).to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_grading_short_chunks_keep_answer_numbering() {
+        let llm_service = LLMService::new_mock_with_short_batches();
+
+        // Enough content to split the batch into several prompts, each of whose replies
+        // leaves out its last answer
+        let content = "Équation de Schrödinger: $i\\hbar \\partial_t \\psi = H \\psi$ — 量子力学. "
+            .repeat(80);
+        let batch_requests: Vec<BatchGradingRequest> = (0..30)
+            .map(|i| BatchGradingRequest {
+                card_content: content.clone(),
+                question: QuizQuestion {
+                    question: format!("Question {}", i),
+                    question_type: "short_answer".to_string(),
+                    options: None,
+                    correct_answer: Some(format!("Answer {}", i)),
+                    prompt_version: None,
+                    rubric: None,
+                },
+                user_answer: format!("My answer {}", i),
+                card_id: None,
+            })
+            .collect();
+
+        let results = llm_service
+            .grade_batch_answers(&batch_requests)
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 30);
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.question_id, (i + 1).to_string());
+        }
+        let individually_graded = results
+            .iter()
+            .filter(|r| r.feedback != "Graded in batch.")
+            .count();
+        assert!(individually_graded >= 2, "batch should span several chunks");
+        assert!(individually_graded < results.len() / 2);
+        assert_eq!(results.last().unwrap().feedback, "Correct answer!");
+    }
+
+    #[tokio::test]
+    async fn test_batch_grading_matches_llm_grades_to_their_answers() {
+        let llm_service = LLMService::new_mock_with_short_batches();
+        let request = |correct: &str, answer: &str| BatchGradingRequest {
+            card_content: "Photosynthesis turns light into chemical energy.".to_string(),
+            question: QuizQuestion {
+                question: format!("What is {}?", correct),
+                question_type: "short_answer".to_string(),
+                options: None,
+                correct_answer: Some(correct.to_string()),
+                prompt_version: None,
+                rubric: None,
+            },
+            user_answer: answer.to_string(),
+            card_id: None,
+        };
+
+        // The reply for the three LLM-graded answers leaves out the last one
+        let batch_requests = vec![
+            request("Chlorophyll", "The green pigment"),
+            request("Glucose", "glucose"),
+            request("Carbon dioxide", "What we breathe out"),
+            request("Oxygen", "The gas we breathe"),
+        ];
+
+        let results = llm_service
+            .grade_batch_answers(&batch_requests)
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 4);
+        let ids: Vec<&str> = results.iter().map(|r| r.question_id.as_str()).collect();
+        assert_eq!(ids, ["1", "2", "3", "4"]);
+        assert_eq!(results[0].feedback, "Graded in batch.");
+        assert_eq!(
+            results[1].served_by.as_deref(),
+            Some(crate::local_grading::LOCAL_RULES_GRADER)
+        );
+        assert_eq!(results[2].feedback, "Graded in batch.");
+        assert_eq!(results[3].feedback, "Correct answer!");
+    }
+
+    #[tokio::test]
+    async fn test_batch_grading_merges_local_cloze_grades_in_order() {
+        let llm_service = LLMService::new_mock();
+        let content = "The {{c1::Calvin}} cycle fixes carbon.";
+        let cloze = crate::cloze::generate_cloze_questions(content).remove(0);
+        let request = |question: QuizQuestion, answer: &str| BatchGradingRequest {
+            question,
+            user_answer: answer.to_string(),
+            card_content: content.to_string(),
+            card_id: None,
+        };
+
+        let batch_requests = vec![
+            request(
+                QuizQuestion {
+                    question: "What does the cycle fix?".to_string(),
+                    question_type: "short_answer".to_string(),
+                    options: None,
+                    correct_answer: Some("Carbon".to_string()),
+                    prompt_version: None,
+                    rubric: None,
+                },
+                "Carbon",
+            ),
+            request(cloze.clone(), "Krebs"),
+            request(cloze, "calvin"),
+        ];
+
+        let results = llm_service
+            .grade_batch_answers(&batch_requests)
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 3);
+        let ids: Vec<_> = results.iter().map(|r| r.question_id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+        assert_ne!(results[0].served_by.as_deref(), Some("local:cloze"));
+        assert_eq!(results[1].served_by.as_deref(), Some("local:cloze"));
+        assert!(!results[1].is_correct);
+        assert!(results[2].is_correct);
+    }
 }
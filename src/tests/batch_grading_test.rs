@@ -1,6 +1,9 @@
 #[cfg(test)]
 mod batch_grading_tests {
-    use crate::{api::*, card_service::CardService, llm_service::LLMService, models::*};
+    use crate::{
+        api::*, card_service::CardService, llm_service::LLMService, models::*,
+        question_cache::QuestionCache,
+    };
     use axum::{
         Router,
         body::Body,
@@ -30,6 +33,7 @@ mod batch_grading_tests {
             card_service: card_service.clone(),
             llm_service,
             review_sessions: review_sessions.clone(),
+            question_cache: QuestionCache::new(100, 60),
         };
 
         let app = create_app(app_state);
@@ -54,12 +58,16 @@ mod batch_grading_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Test content".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "Choose the correct option".to_string(),
                 question_type: "multiple_choice".to_string(),
                 options: Some(vec!["A) Option 1".to_string(), "B) Option 2".to_string()]),
                 correct_answer: Some("A) Option 1".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
         ];
 
@@ -114,7 +122,7 @@ mod batch_grading_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -140,14 +148,14 @@ mod batch_grading_tests {
         // Check first question result
         assert_eq!(results[0]["question_id"], "1");
         assert!(results[0]["is_correct"].as_bool().unwrap());
-        assert!(results[0]["feedback"].as_str().unwrap().len() > 0);
+        assert!(!results[0]["feedback"].as_str().unwrap().is_empty());
         assert!(results[0]["suggested_rating"].as_i64().unwrap() >= 1);
         assert!(results[0]["suggested_rating"].as_i64().unwrap() <= 4);
 
         // Check second question result
         assert_eq!(results[1]["question_id"], "2");
         assert!(results[1]["is_correct"].as_bool().unwrap());
-        assert!(results[1]["feedback"].as_str().unwrap().len() > 0);
+        assert!(!results[1]["feedback"].as_str().unwrap().is_empty());
         assert!(results[1]["suggested_rating"].as_i64().unwrap() >= 1);
         assert!(results[1]["suggested_rating"].as_i64().unwrap() <= 4);
     }
@@ -168,7 +176,7 @@ mod batch_grading_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         invalid_session_id, card_id
                     ))
@@ -198,7 +206,7 @@ mod batch_grading_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, invalid_card_id
                     ))
@@ -227,7 +235,7 @@ mod batch_grading_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -251,7 +259,7 @@ mod batch_grading_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -286,7 +294,7 @@ mod batch_grading_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -338,7 +346,7 @@ mod batch_grading_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -381,7 +389,7 @@ mod batch_grading_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -413,7 +421,7 @@ mod batch_grading_tests {
 
             // Verify suggested_rating is in valid range
             let rating = result["suggested_rating"].as_i64().unwrap();
-            assert!(rating >= 1 && rating <= 4);
+            assert!((1..=4).contains(&rating));
         }
     }
 
@@ -438,7 +446,7 @@ mod batch_grading_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
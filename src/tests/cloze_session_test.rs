@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod cloze_session_tests {
+    use crate::{
+        api::*, card_service::CardService, llm_service::LLMService, models::*,
+        question_cache::QuestionCache,
+    };
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    async fn response_json(response: axum::response::Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cloze_card_reviews_without_llm() {
+        let card_service = CardService::new_in_memory().await.unwrap();
+        let card = card_service
+            .create_card(CreateCardRequest {
+                zettel_id: "CLOZE-001".to_string(),
+                title: None,
+                content: "Water boils at {{c1::100}} degrees Celsius at sea level.".to_string(),
+                topic_ids: vec![],
+                links: None,
+            })
+            .await
+            .unwrap();
+
+        // Every provider is down, so any LLM call would fail the session
+        let app = create_app(AppState {
+            card_service: card_service.clone(),
+            llm_service: LLMService::new_mock_all_unavailable(),
+            review_sessions: Arc::new(Mutex::new(HashMap::new())),
+            question_cache: QuestionCache::new(100, 60),
+        });
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/review/session/start")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = response_json(response).await;
+        let session_id = json["data"]["session_id"].as_str().unwrap().to_string();
+        let questions = &json["data"]["questions"][card.id.to_string()];
+        assert_eq!(questions[0]["question_type"], "cloze");
+        assert_eq!(
+            questions[0]["question"],
+            "Water boils at [...] degrees Celsius at sea level."
+        );
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!(
+                        "/api/review/session/{}/answer/{}",
+                        session_id, card.id
+                    ))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"question_index": 0, "answer": " 100 "}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = response_json(response).await;
+        assert_eq!(json["data"]["is_correct"], true);
+        assert_eq!(json["data"]["rating"], 3);
+    }
+}
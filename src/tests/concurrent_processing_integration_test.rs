@@ -1,3 +1,5 @@
+#![allow(clippy::await_holding_lock)]
+
 #[cfg(test)]
 mod concurrent_processing_integration_tests {
     use crate::{
@@ -147,6 +149,8 @@ mod concurrent_processing_integration_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Multiple tasks executing at the same time".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "Which is faster for independent tasks?".to_string(),
@@ -157,6 +161,8 @@ mod concurrent_processing_integration_tests {
                     "C) Both are the same".to_string(),
                 ]),
                 correct_answer: Some("B) Concurrent processing".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "What is a potential downside of concurrency?".to_string(),
@@ -165,6 +171,8 @@ mod concurrent_processing_integration_tests {
                 correct_answer: Some(
                     "Increased complexity and potential race conditions".to_string(),
                 ),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "Which Rust construct spawns concurrent tasks?".to_string(),
@@ -176,12 +184,16 @@ mod concurrent_processing_integration_tests {
                     "D) futures::join".to_string(),
                 ]),
                 correct_answer: Some("B) tokio::spawn".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "How does join_all work in concurrent processing?".to_string(),
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("It waits for all concurrent tasks to complete".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
         ]
     }
@@ -228,8 +240,8 @@ mod concurrent_processing_integration_tests {
             assert_eq!(seq_idx, conc_idx);
             assert_eq!(seq_result.is_correct, conc_result.is_correct);
             // Feedback might vary slightly due to mock randomization, so we don't assert equality
-            assert!(seq_result.feedback.len() > 0);
-            assert!(conc_result.feedback.len() > 0);
+            assert!(!seq_result.feedback.is_empty());
+            assert!(!conc_result.feedback.is_empty());
             assert!(seq_result.suggested_rating >= 1 && seq_result.suggested_rating <= 4);
             assert!(conc_result.suggested_rating >= 1 && conc_result.suggested_rating <= 4);
         }
@@ -252,7 +264,7 @@ mod concurrent_processing_integration_tests {
         let service = ConcurrentGradingService::new(max_concurrent);
         let card = create_test_card();
         let questions = create_test_questions();
-        let answers = vec!["Answer"; 5]
+        let answers = ["Answer"; 5]
             .iter()
             .map(|s| s.to_string())
             .collect::<Vec<_>>();
@@ -277,7 +289,7 @@ mod concurrent_processing_integration_tests {
 
         // All tasks should complete successfully despite limitation
         for (idx, result) in &results {
-            assert!(result.feedback.len() > 0);
+            assert!(!result.feedback.is_empty());
             assert!(result.suggested_rating >= 1 && result.suggested_rating <= 4);
             assert!(*idx < 5); // Valid index
         }
@@ -295,9 +307,11 @@ mod concurrent_processing_integration_tests {
             question_type: "invalid_type".to_string(),
             options: None,
             correct_answer: None,
+            prompt_version: None,
+            rubric: None,
         });
 
-        let answers = vec!["Answer"; 6]
+        let answers = ["Answer"; 6]
             .iter()
             .map(|s| s.to_string())
             .collect::<Vec<_>>();
@@ -368,6 +382,8 @@ mod concurrent_processing_integration_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some(format!("Answer {}", i + 1)),
+                prompt_version: None,
+                rubric: None,
             })
             .collect();
 
@@ -387,7 +403,7 @@ mod concurrent_processing_integration_tests {
         // Verify all results are valid
         for (idx, result) in results.iter() {
             assert!(*idx < 20);
-            assert!(result.feedback.len() > 0);
+            assert!(!result.feedback.is_empty());
             assert!(result.suggested_rating >= 1 && result.suggested_rating <= 4);
         }
 
@@ -409,7 +425,7 @@ mod concurrent_processing_integration_tests {
         // Run multiple concurrent processing sessions to test resource cleanup
         for session in 0..5 {
             let questions = create_test_questions();
-            let answers = vec!["Test answer"; 5]
+            let answers = ["Test answer"; 5]
                 .iter()
                 .map(|s| s.to_string())
                 .collect::<Vec<_>>();
@@ -449,24 +465,32 @@ mod concurrent_processing_integration_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Short answer".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "Multiple choice question".to_string(),
                 question_type: "multiple_choice".to_string(),
                 options: Some(vec!["A) Option 1".to_string(), "B) Option 2".to_string()]),
                 correct_answer: Some("A) Option 1".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "True/false question".to_string(),
                 question_type: "true_false".to_string(),
                 options: Some(vec!["True".to_string(), "False".to_string()]),
                 correct_answer: Some("True".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
             QuizQuestion {
                 question: "Essay question".to_string(),
                 question_type: "essay".to_string(),
                 options: None,
                 correct_answer: Some("Detailed essay response".to_string()),
+                prompt_version: None,
+                rubric: None,
             },
         ];
 
@@ -490,7 +514,7 @@ mod concurrent_processing_integration_tests {
         // Verify each question type was processed correctly
         for (idx, result) in results.iter() {
             assert!(*idx < 4);
-            assert!(result.feedback.len() > 0);
+            assert!(!result.feedback.is_empty());
             assert!(result.suggested_rating >= 1 && result.suggested_rating <= 4);
 
             // All should be correct with our mock answers
@@ -512,6 +536,8 @@ mod concurrent_processing_integration_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some(format!("Answer {}", i + 1)),
+                prompt_version: None,
+                rubric: None,
             })
             .collect();
 
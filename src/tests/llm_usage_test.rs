@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod llm_usage_tests {
+    use crate::{
+        api::*, card_service::CardService, database::Database, llm_service::LLMService,
+        llm_usage::UsageTracker, models::*, question_cache::QuestionCache,
+    };
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn create_test_card() -> Card {
+        Card {
+            id: Uuid::new_v4(),
+            zettel_id: "USAGE-001".to_string(),
+            title: None,
+            content: "Token usage is recorded for every LLM call.".to_string(),
+            creation_date: Utc::now(),
+            last_reviewed: None,
+            next_review: Utc::now(),
+            difficulty: 0.0,
+            stability: 0.0,
+            retrievability: 0.0,
+            reps: 0,
+            lapses: 0,
+            state: "New".to_string(),
+            links: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover_attempts_are_recorded_per_provider() {
+        let db = Database::new_in_memory().await.unwrap();
+        let llm_service = LLMService::new_mock_with_failover()
+            .with_usage_tracker(UsageTracker::new(db.clone(), None));
+
+        llm_service
+            .generate_quiz_questions(&create_test_card())
+            .await
+            .unwrap();
+
+        let summary = llm_service
+            .usage_tracker()
+            .unwrap()
+            .summary(1)
+            .await
+            .unwrap();
+        assert_eq!(summary.daily.len(), 2);
+
+        let failed = summary
+            .daily
+            .iter()
+            .find(|d| d.model == "mock-unavailable")
+            .unwrap();
+        assert_eq!(failed.calls, 1);
+        assert_eq!(failed.failed_calls, 1);
+
+        let served = summary
+            .daily
+            .iter()
+            .find(|d| d.model == "mock-model")
+            .unwrap();
+        assert_eq!(served.failed_calls, 0);
+        assert!(served.prompt_tokens > 0);
+        assert!(served.completion_tokens > 0);
+    }
+
+    #[tokio::test]
+    async fn test_zero_budget_blocks_llm_calls() {
+        let db = Database::new_in_memory().await.unwrap();
+        let llm_service =
+            LLMService::new_mock().with_usage_tracker(UsageTracker::new(db.clone(), Some(0.0)));
+
+        let error = llm_service
+            .generate_quiz_questions(&create_test_card())
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("Monthly LLM budget exceeded"), "{}", error);
+        let summary = llm_service
+            .usage_tracker()
+            .unwrap()
+            .summary(1)
+            .await
+            .unwrap();
+        assert!(summary.daily.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_usage_endpoint() {
+        let db = Database::new_in_memory().await.unwrap();
+        let llm_service =
+            LLMService::new_mock().with_usage_tracker(UsageTracker::new(db.clone(), Some(10.0)));
+        llm_service
+            .generate_quiz_questions(&create_test_card())
+            .await
+            .unwrap();
+
+        let app = create_app(AppState {
+            card_service: CardService::new(db),
+            llm_service,
+            review_sessions: Arc::new(Mutex::new(HashMap::new())),
+            question_cache: QuestionCache::new(100, 60),
+        });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/llm/usage?days=7")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json_response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json_response["success"], true);
+        assert_eq!(json_response["data"]["monthly_budget_usd"], 10.0);
+        assert_eq!(json_response["data"]["daily"][0]["calls"], 1);
+        assert_eq!(json_response["data"]["daily"][0]["model"], "mock-model");
+    }
+
+    #[tokio::test]
+    async fn test_usage_endpoint_without_tracking() {
+        let app = create_app(AppState {
+            card_service: CardService::new_in_memory().await.unwrap(),
+            llm_service: LLMService::new_mock(),
+            review_sessions: Arc::new(Mutex::new(HashMap::new())),
+            question_cache: QuestionCache::new(100, 60),
+        });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/llm/usage")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
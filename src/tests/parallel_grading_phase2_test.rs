@@ -1,6 +1,9 @@
 #[cfg(test)]
 mod parallel_grading_phase2_tests {
-    use crate::{api::*, card_service::CardService, llm_service::LLMService, models::*};
+    use crate::{
+        api::*, card_service::CardService, llm_service::LLMService, models::*,
+        question_cache::QuestionCache,
+    };
     use axum::{
         Router,
         body::Body,
@@ -23,6 +26,7 @@ mod parallel_grading_phase2_tests {
             card_service: card_service.clone(),
             llm_service,
             review_sessions: review_sessions.clone(),
+            question_cache: QuestionCache::new(100, 60),
         };
 
         let app = create_app(app_state);
@@ -46,6 +50,7 @@ mod parallel_grading_phase2_tests {
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Concurrent execution reduces total processing time".to_string()),
+                prompt_version: None, rubric: None,
             },
             QuizQuestion {
                 question: "Which concurrency model does Rust use?".to_string(),
@@ -57,12 +62,14 @@ mod parallel_grading_phase2_tests {
                     "D) Traditional threading".to_string(),
                 ]),
                 correct_answer: Some("C) Async/await with tokio".to_string()),
+                prompt_version: None, rubric: None,
             },
             QuizQuestion {
                 question: "What is the difference between concurrency and parallelism?".to_string(),
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("Concurrency is about dealing with lots of things at once, parallelism is about doing lots of things at once".to_string()),
+                prompt_version: None, rubric: None,
             },
             QuizQuestion {
                 question: "Which Rust feature enables safe parallel processing?".to_string(),
@@ -74,12 +81,14 @@ mod parallel_grading_phase2_tests {
                     "D) All of the above".to_string(),
                 ]),
                 correct_answer: Some("D) All of the above".to_string()),
+                prompt_version: None, rubric: None,
             },
             QuizQuestion {
                 question: "How does tokio::spawn enable parallelism?".to_string(),
                 question_type: "short_answer".to_string(),
                 options: None,
                 correct_answer: Some("It schedules async tasks across multiple threads".to_string()),
+                prompt_version: None, rubric: None,
             },
         ];
 
@@ -133,7 +142,7 @@ mod parallel_grading_phase2_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/parallel",
                         session_id, card_id
                     ))
@@ -188,7 +197,7 @@ mod parallel_grading_phase2_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/batch",
                         session_id, card_id
                     ))
@@ -212,7 +221,7 @@ mod parallel_grading_phase2_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/parallel",
                         session_id, card_id
                     ))
@@ -274,7 +283,7 @@ mod parallel_grading_phase2_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/parallel",
                         session_id, card_id
                     ))
@@ -340,7 +349,7 @@ mod parallel_grading_phase2_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/parallel",
                         session_id, card_id
                     ))
@@ -396,7 +405,7 @@ mod parallel_grading_phase2_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/parallel",
                         session_id, card_id
                     ))
@@ -459,7 +468,7 @@ mod parallel_grading_phase2_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/parallel",
                         session_id, card_id
                     ))
@@ -513,7 +522,7 @@ mod parallel_grading_phase2_tests {
                 .oneshot(
                     Request::builder()
                         .method("POST")
-                        .uri(&format!(
+                        .uri(format!(
                             "/api/review/session/{}/answers/{}/parallel",
                             session_id, card_id
                         ))
@@ -558,7 +567,7 @@ mod parallel_grading_phase2_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/parallel",
                         session_id, card_id
                     ))
@@ -617,7 +626,7 @@ mod parallel_grading_phase2_tests {
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(&format!(
+                    .uri(format!(
                         "/api/review/session/{}/answers/{}/parallel",
                         session_id, card_id
                     ))
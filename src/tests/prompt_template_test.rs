@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod prompt_template_tests {
+    use crate::{
+        card_service::CardService, database::Database, llm_service::LLMService, models::*,
+        prompt_templates::PromptLibrary,
+    };
+    use std::fs;
+
+    fn create_request(zettel_id: &str, topic_ids: Vec<uuid::Uuid>) -> CreateCardRequest {
+        CreateCardRequest {
+            zettel_id: zettel_id.to_string(),
+            title: None,
+            content: "Newton's second law relates force, mass and acceleration.".to_string(),
+            topic_ids,
+            links: None,
+        }
+    }
+
+    fn create_test_question() -> QuizQuestion {
+        QuizQuestion {
+            question: "State Newton's second law.".to_string(),
+            question_type: "short_answer".to_string(),
+            options: None,
+            correct_answer: Some("F = ma".to_string()),
+            prompt_version: Some("quiz_generation@v1".to_string()),
+            rubric: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generated_questions_and_grades_record_prompt_version() {
+        let card_service = CardService::new_in_memory().await.unwrap();
+        let card = card_service
+            .create_card(create_request("PROMPT-001", vec![]))
+            .await
+            .unwrap();
+        let llm_service = LLMService::new_mock();
+
+        let questions = llm_service.generate_quiz_questions(&card).await.unwrap();
+        assert!(!questions.is_empty());
+        assert!(
+            questions
+                .iter()
+                .all(|q| q.prompt_version.as_deref() == Some("quiz_generation@v5"))
+        );
+
+        let grade = llm_service
+            .grade_answer(
+                &card,
+                &create_test_question(),
+                "Force is mass times acceleration",
+            )
+            .await
+            .unwrap();
+        assert_eq!(grade.prompt_version.as_deref(), Some("grading@v2"));
+    }
+
+    #[tokio::test]
+    async fn test_topic_templates_override_deployment_defaults() {
+        let dir = std::env::temp_dir().join(format!("prompt-topics-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("topics").join("physics")).unwrap();
+        fs::write(
+            dir.join("topics").join("physics").join("quiz_generation.md"),
+            "---\nversion: physics-1\nsystem: You write physics problems.\n---\nWrite problems about {{card_content}} ({{topics}})",
+        )
+        .unwrap();
+        let library = PromptLibrary::load_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let db = Database::new_in_memory().await.unwrap();
+        let card_service = CardService::new(db.clone());
+        let physics = card_service
+            .create_topic("Physics".to_string(), None)
+            .await
+            .unwrap();
+        let physics_card = card_service
+            .create_card(create_request("PROMPT-002", vec![physics.id]))
+            .await
+            .unwrap();
+        let plain_card = card_service
+            .create_card(create_request("PROMPT-003", vec![]))
+            .await
+            .unwrap();
+
+        let llm_service = LLMService::new_mock()
+            .with_prompt_library(library)
+            .with_card_context(db);
+
+        let questions = llm_service
+            .generate_quiz_questions(&physics_card)
+            .await
+            .unwrap();
+        assert_eq!(
+            questions[0].prompt_version.as_deref(),
+            Some("quiz_generation/physics@physics-1")
+        );
+
+        let questions = llm_service
+            .generate_quiz_questions(&plain_card)
+            .await
+            .unwrap();
+        assert_eq!(
+            questions[0].prompt_version.as_deref(),
+            Some("quiz_generation@v5")
+        );
+
+        // Grading has no physics override, so the default template is used
+        let grade = llm_service
+            .grade_answer(
+                &physics_card,
+                &create_test_question(),
+                "Force is mass times acceleration",
+            )
+            .await
+            .unwrap();
+        assert_eq!(grade.prompt_version.as_deref(), Some("grading@v2"));
+    }
+
+    #[tokio::test]
+    async fn test_generation_drops_questions_already_in_the_bank() {
+        let db = Database::new_in_memory().await.unwrap();
+        let card_service = CardService::new(db.clone());
+        let card = card_service
+            .create_card(create_request("PROMPT-004", vec![]))
+            .await
+            .unwrap();
+        let llm_service = LLMService::new_mock().with_card_context(db);
+
+        let mut banked = create_test_question();
+        banked.question = "What is the main concept?".to_string();
+        card_service
+            .bank_questions(&card, std::slice::from_ref(&banked))
+            .await
+            .unwrap();
+
+        let questions = llm_service.generate_quiz_questions(&card).await.unwrap();
+        assert_eq!(questions.len(), 1);
+        assert_eq!(questions[0].question, "Choose the correct option");
+
+        // When every attempt repeats the bank, the last attempt is used rather than nothing
+        let mut banked_choice = create_test_question();
+        banked_choice.question = "Choose the correct option".to_string();
+        card_service
+            .bank_questions(&card, &[banked_choice])
+            .await
+            .unwrap();
+        let questions = llm_service.generate_quiz_questions(&card).await.unwrap();
+        assert_eq!(questions.len(), 2);
+    }
+}
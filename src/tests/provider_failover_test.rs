@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod provider_failover_tests {
+    use crate::{
+        api::*, card_service::CardService, llm_service::LLMService, models::*,
+        question_cache::QuestionCache,
+    };
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use chrono::Utc;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn create_test_card() -> Card {
+        Card {
+            id: Uuid::new_v4(),
+            zettel_id: "FAILOVER-001".to_string(),
+            title: None,
+            content: "Failover keeps quiz generation working when a provider is down.".to_string(),
+            creation_date: Utc::now(),
+            last_reviewed: None,
+            next_review: Utc::now(),
+            difficulty: 0.0,
+            stability: 0.0,
+            retrievability: 0.0,
+            reps: 0,
+            lapses: 0,
+            state: "New".to_string(),
+            links: None,
+        }
+    }
+
+    fn create_test_question() -> QuizQuestion {
+        QuizQuestion {
+            question: "What happens when the primary provider is down?".to_string(),
+            question_type: "short_answer".to_string(),
+            options: None,
+            correct_answer: Some("The next provider in the chain serves the request".to_string()),
+            prompt_version: None,
+            rubric: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quiz_generation_fails_over_to_next_provider() {
+        let llm_service = LLMService::new_mock_with_failover();
+        let card = create_test_card();
+
+        let questions = llm_service.generate_quiz_questions(&card).await.unwrap();
+
+        assert!(!questions.is_empty());
+        assert_eq!(
+            llm_service.provider_chain(),
+            vec!["Mock:mock-unavailable", "Mock:mock-model"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_grading_records_serving_provider() {
+        let llm_service = LLMService::new_mock_with_failover();
+        let card = create_test_card();
+
+        let result = llm_service
+            .grade_answer(&card, &create_test_question(), "The fallback answers")
+            .await
+            .unwrap();
+
+        assert!(result.is_correct);
+        assert_eq!(result.served_by.as_deref(), Some("Mock:mock-model"));
+    }
+
+    #[tokio::test]
+    async fn test_all_providers_unavailable_returns_error() {
+        let llm_service = LLMService::new_mock_all_unavailable();
+        let card = create_test_card();
+
+        let result = llm_service
+            .grade_answer(&card, &create_test_question(), "Anything")
+            .await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("All 2 LLM providers failed"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_metrics_report_providers_used() {
+        let card_service = CardService::new_in_memory().await.unwrap();
+        let review_sessions = Arc::new(Mutex::new(HashMap::new()));
+        let app = create_app(AppState {
+            card_service: card_service.clone(),
+            llm_service: LLMService::new_mock_with_failover(),
+            review_sessions: review_sessions.clone(),
+            question_cache: QuestionCache::new(100, 60),
+        });
+
+        let card = card_service
+            .create_card(CreateCardRequest {
+                zettel_id: "FAILOVER-002".to_string(),
+                title: None,
+                content: "Provider failover metrics".to_string(),
+                topic_ids: vec![],
+                links: None,
+            })
+            .await
+            .unwrap();
+
+        let session_id = Uuid::new_v4();
+        review_sessions.lock().unwrap().insert(
+            session_id,
+            ReviewSession {
+                session_id,
+                cards: vec![card.clone()],
+                questions: HashMap::from([(card.id, vec![create_test_question()])]),
+                current_card: 0,
+                created_at: Utc::now(),
+            },
+        );
+
+        let request_body = json!({
+            "answers": [{ "question_index": 0, "answer": "The next provider" }],
+            "processing_mode": "parallel"
+        });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!(
+                        "/api/review/session/{}/answers/{}/parallel",
+                        session_id, card.id
+                    ))
+                    .header("content-type", "application/json")
+                    .body(Body::from(request_body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json_response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            json_response["metrics"]["providers_used"],
+            json!(["Mock:mock-model"])
+        );
+        assert_eq!(json_response["data"][0]["served_by"], "Mock:mock-model");
+    }
+}
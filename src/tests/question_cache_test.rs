@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod question_cache_tests {
+    use crate::{
+        api::*,
+        card_service::CardService,
+        llm_service::LLMService,
+        models::*,
+        question_cache::{PreGenerationQueue, PreGenerationWorker, QuestionCache},
+    };
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    async fn create_card(card_service: &CardService, zettel_id: &str) -> Card {
+        card_service
+            .create_card(CreateCardRequest {
+                zettel_id: zettel_id.to_string(),
+                title: None,
+                content: format!("Content for {}", zettel_id),
+                topic_ids: vec![],
+                links: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    fn create_worker(
+        card_service: &CardService,
+        cache: &QuestionCache,
+        lookahead_hours: i64,
+    ) -> PreGenerationWorker {
+        PreGenerationWorker::new(
+            card_service.clone(),
+            LLMService::new_mock(),
+            cache.clone(),
+            PreGenerationQueue::new(100),
+            lookahead_hours,
+            10,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_worker_pregenerates_cards_due_within_lookahead() {
+        let card_service = CardService::new_in_memory().await.unwrap();
+        let cache = QuestionCache::new(100, 60);
+
+        let due = create_card(&card_service, "CACHE-001").await;
+        let later = create_card(&card_service, "CACHE-002").await;
+        // An "Easy" review pushes the next review days into the future
+        card_service.review_card(later.id, 4).await.unwrap();
+
+        let cached = create_worker(&card_service, &cache, 1)
+            .run_once()
+            .await
+            .unwrap();
+
+        assert_eq!(cached, 1);
+        assert!(cache.has_cached_questions(due.id).await);
+        assert!(!cache.has_cached_questions(later.id).await);
+
+        // A wider window picks up the later card; already-cached cards are not regenerated
+        let cached = create_worker(&card_service, &cache, 24 * 365)
+            .run_once()
+            .await
+            .unwrap();
+        assert_eq!(cached, 1);
+        assert!(cache.has_cached_questions(later.id).await);
+    }
+
+    #[tokio::test]
+    async fn test_review_session_uses_cached_questions() {
+        let card_service = CardService::new_in_memory().await.unwrap();
+        let cache = QuestionCache::new(100, 60);
+        let card = create_card(&card_service, "CACHE-003").await;
+
+        let pregenerated = QuizQuestion {
+            question: "Pre-generated question".to_string(),
+            question_type: "short_answer".to_string(),
+            options: None,
+            correct_answer: Some("Cached".to_string()),
+            prompt_version: None,
+            rubric: None,
+        };
+        cache
+            .cache_questions(card.id, vec![pregenerated.clone()])
+            .await;
+
+        let app = create_app(AppState {
+            card_service: card_service.clone(),
+            llm_service: LLMService::new_mock_with_incorrect_answers(),
+            review_sessions: Arc::new(Mutex::new(HashMap::new())),
+            question_cache: cache.clone(),
+        });
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/review/session/start")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json_response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let questions = &json_response["data"]["questions"][card.id.to_string()];
+        assert_eq!(questions.as_array().unwrap().len(), 1);
+        assert_eq!(questions[0]["question"], pregenerated.question);
+
+        // Reviewing the card drops its cached questions so the next session gets new ones
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/cards/{}/review", card.id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"rating": 3}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!cache.has_cached_questions(card.id).await);
+    }
+
+    #[tokio::test]
+    async fn test_review_session_serves_banked_questions() {
+        let card_service = CardService::new_in_memory().await.unwrap();
+        let card = create_card(&card_service, "CACHE-004").await;
+
+        let banked = QuizQuestion {
+            question: "Banked question".to_string(),
+            question_type: "short_answer".to_string(),
+            options: None,
+            correct_answer: Some("From the bank".to_string()),
+            prompt_version: Some("quiz_generation@v1".to_string()),
+            rubric: None,
+        };
+        card_service
+            .bank_questions(&card, std::slice::from_ref(&banked))
+            .await
+            .unwrap();
+
+        let app = create_app(AppState {
+            card_service: card_service.clone(),
+            llm_service: LLMService::new_mock(),
+            review_sessions: Arc::new(Mutex::new(HashMap::new())),
+            question_cache: QuestionCache::new(100, 60),
+        });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/review/session/start")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json_response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let questions = &json_response["data"]["questions"][card.id.to_string()];
+        assert_eq!(questions.as_array().unwrap().len(), 1);
+        assert_eq!(questions[0]["question"], banked.question);
+    }
+}
@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod question_feedback_tests {
+    use crate::{
+        api::*, card_service::CardService, llm_service::LLMService, models::*,
+        question_cache::QuestionCache,
+    };
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    fn create_question(text: &str) -> QuizQuestion {
+        QuizQuestion {
+            question: text.to_string(),
+            question_type: "short_answer".to_string(),
+            options: None,
+            correct_answer: Some("Answer".to_string()),
+            prompt_version: Some("quiz_generation@v5".to_string()),
+            rubric: None,
+        }
+    }
+
+    async fn setup() -> (Router, CardService, QuestionCache, Card) {
+        let card_service = CardService::new_in_memory().await.unwrap();
+        let card = card_service
+            .create_card(CreateCardRequest {
+                zettel_id: "FEEDBACK-001".to_string(),
+                title: None,
+                content: "Content for feedback".to_string(),
+                topic_ids: vec![],
+                links: None,
+            })
+            .await
+            .unwrap();
+        card_service
+            .bank_questions(
+                &card,
+                &[
+                    create_question("Which answer is right?"),
+                    create_question("Explain the idea"),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let cache = QuestionCache::new(100, 60);
+        let app = create_app(AppState {
+            card_service: card_service.clone(),
+            llm_service: LLMService::new_mock(),
+            review_sessions: Arc::new(Mutex::new(HashMap::new())),
+            question_cache: cache.clone(),
+        });
+        (app, card_service, cache, card)
+    }
+
+    async fn send(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<&str>,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if body.is_some() {
+            request = request.header("content-type", "application/json");
+        }
+        let response = app
+            .clone()
+            .oneshot(
+                request
+                    .body(
+                        body.map(|b| Body::from(b.to_string()))
+                            .unwrap_or_else(Body::empty),
+                    )
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    async fn question_id(app: &Router, card: &Card, text: &str) -> String {
+        let (status, json) = send(
+            app,
+            "GET",
+            &format!("/api/cards/{}/questions", card.id),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        json["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|q| q["question"]["question"] == text)
+            .map(|q| q["id"].as_str().unwrap().to_string())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_flagged_question_is_not_served_again() {
+        let (app, _, cache, card) = setup().await;
+        cache
+            .cache_questions(card.id, vec![create_question("Which answer is right?")])
+            .await;
+        let id = question_id(&app, &card, "Which answer is right?").await;
+
+        // A reason is required
+        let (status, _) = send(
+            &app,
+            "POST",
+            &format!("/api/questions/{}/flag", id),
+            Some(r#"{"reason": "  "}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, json) = send(
+            &app,
+            "POST",
+            &format!("/api/questions/{}/flag", id),
+            Some(r#"{"reason": "Two options are correct"}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["data"]["flag_reason"], "Two options are correct");
+        // Cached copies of the flagged question are dropped too
+        assert!(!cache.has_cached_questions(card.id).await);
+
+        let (status, json) = send(&app, "POST", "/api/review/session/start", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let served = json["data"]["questions"][card.id.to_string()]
+            .as_array()
+            .unwrap();
+        assert!(
+            served
+                .iter()
+                .all(|q| q["question"] != "Which answer is right?")
+        );
+
+        let (status, json) =
+            send(&app, "DELETE", &format!("/api/questions/{}/flag", id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(json["data"]["flag_reason"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_edit_and_pin_question() {
+        let (app, card_service, _, card) = setup().await;
+        let id = question_id(&app, &card, "Explain the idea").await;
+
+        let (status, json) = send(
+            &app,
+            "PUT",
+            &format!("/api/questions/{}", id),
+            Some(
+                r#"{"question": "Explain the idea in one sentence", "correct_answer": "It works"}"#,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            json["data"]["question"]["question"],
+            "Explain the idea in one sentence"
+        );
+        assert_eq!(json["data"]["question"]["correct_answer"], "It works");
+
+        let (status, json) = send(&app, "POST", &format!("/api/questions/{}/pin", id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["data"]["pinned"], true);
+
+        let served = card_service.get_banked_questions(&card, 1).await.unwrap();
+        assert_eq!(served[0].question, "Explain the idea in one sentence");
+
+        let (status, _) = send(
+            &app,
+            "POST",
+            &format!("/api/questions/{}/pin", uuid::Uuid::new_v4()),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod rating_calibration_tests {
+    use crate::{
+        api::*, calibration::MIN_CALIBRATION_SAMPLES, card_service::CardService,
+        llm_service::LLMService, models::*, question_cache::QuestionCache,
+    };
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    /// App with an open session of cards that each have one short answer question. The
+    /// mock grader suggests Easy (4) for every answer.
+    async fn setup(calibration: bool) -> (Router, Uuid, Vec<Uuid>) {
+        let card_service = CardService::new_in_memory()
+            .await
+            .unwrap()
+            .with_rating_calibration(calibration);
+        let mut cards = Vec::new();
+        for i in 0..=MIN_CALIBRATION_SAMPLES {
+            let card = card_service
+                .create_card(CreateCardRequest {
+                    zettel_id: format!("CALIBRATION-{:03}", i),
+                    title: None,
+                    content: "Osmosis moves water across a semipermeable membrane.".to_string(),
+                    topic_ids: vec![],
+                    links: None,
+                })
+                .await
+                .unwrap();
+            cards.push(card);
+        }
+
+        let question = QuizQuestion {
+            question: "What drives osmosis?".to_string(),
+            question_type: "short_answer".to_string(),
+            options: None,
+            correct_answer: Some("A difference in solute concentration".to_string()),
+            prompt_version: None,
+            rubric: None,
+        };
+        let session_id = Uuid::new_v4();
+        let session = ReviewSession {
+            session_id,
+            questions: cards
+                .iter()
+                .map(|card| (card.id, vec![question.clone()]))
+                .collect(),
+            cards: cards.clone(),
+            current_card: 0,
+            created_at: Utc::now(),
+        };
+        let app = create_app(AppState {
+            card_service,
+            llm_service: LLMService::new_mock(),
+            review_sessions: Arc::new(Mutex::new(HashMap::from([(session_id, session)]))),
+            question_cache: QuestionCache::new(100, 60),
+        });
+        (app, session_id, cards.iter().map(|card| card.id).collect())
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str, body: &str) -> serde_json::Value {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    /// Answer the question and return the suggested rating
+    async fn answer(app: &Router, session_id: Uuid, card_id: Uuid) -> i64 {
+        let json = send(
+            app,
+            "POST",
+            &format!("/api/review/session/{}/answer/{}", session_id, card_id),
+            r#"{"question_index": 0, "answer": "Water follows the solutes"}"#,
+        )
+        .await;
+        json["data"]["rating"].as_i64().unwrap()
+    }
+
+    async fn rate(app: &Router, session_id: Uuid, card_id: Uuid, rating: i32) {
+        send(
+            app,
+            "POST",
+            &format!("/api/cards/{}/review", card_id),
+            &format!(
+                r#"{{"rating": {}, "session_id": "{}"}}"#,
+                rating, session_id
+            ),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_suggestions_drift_toward_final_ratings() {
+        let (app, session_id, card_ids) = setup(true).await;
+
+        // The user keeps rating Good where the grader suggests Easy
+        for &card_id in &card_ids[..MIN_CALIBRATION_SAMPLES] {
+            assert_eq!(answer(&app, session_id, card_id).await, 4);
+            rate(&app, session_id, card_id, 3).await;
+        }
+
+        let json = send(&app, "GET", "/api/review/calibration", "").await;
+        let stats = json["data"].as_array().unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0]["served_by"], "Mock:mock-model");
+        assert_eq!(stats[0]["question_type"], "short_answer");
+        assert_eq!(stats[0]["samples"], MIN_CALIBRATION_SAMPLES);
+        assert_eq!(stats[0]["bias"], -1.0);
+        assert_eq!(stats[0]["agreement_rate"], 0.0);
+
+        assert_eq!(
+            answer(&app, session_id, card_ids[MIN_CALIBRATION_SAMPLES]).await,
+            3
+        );
+
+        // The stored suggestion stays the grader's own, so calibration doesn't feed on itself
+        let json = send(
+            &app,
+            "GET",
+            &format!("/api/review/session/{}/answers", session_id),
+            "",
+        )
+        .await;
+        let answers = json["data"].as_array().unwrap();
+        assert_eq!(answers.last().unwrap()["suggested_rating"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_calibration_is_opt_in() {
+        let (app, session_id, card_ids) = setup(false).await;
+
+        for &card_id in &card_ids[..MIN_CALIBRATION_SAMPLES] {
+            answer(&app, session_id, card_id).await;
+            rate(&app, session_id, card_id, 3).await;
+        }
+
+        // Statistics are still collected, but suggestions are left alone
+        let json = send(&app, "GET", "/api/review/calibration", "").await;
+        assert_eq!(json["data"][0]["samples"], MIN_CALIBRATION_SAMPLES);
+        assert_eq!(
+            answer(&app, session_id, card_ids[MIN_CALIBRATION_SAMPLES]).await,
+            4
+        );
+    }
+}
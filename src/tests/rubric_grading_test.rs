@@ -0,0 +1,167 @@
+#[cfg(test)]
+mod rubric_grading_tests {
+    use crate::{
+        api::*, card_service::CardService, llm_service::LLMService, models::*,
+        question_cache::QuestionCache,
+    };
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn rubric_question(weights: [f64; 2]) -> QuizQuestion {
+        QuizQuestion {
+            question: "Why does the Calvin cycle depend on the light reactions?".to_string(),
+            question_type: "problem_solving".to_string(),
+            options: None,
+            correct_answer: Some("It consumes the ATP and NADPH they produce".to_string()),
+            prompt_version: Some("quiz_generation@v5".to_string()),
+            rubric: Some(vec![
+                RubricCriterion {
+                    criterion: "Mentions ATP".to_string(),
+                    weight: weights[0],
+                },
+                RubricCriterion {
+                    criterion: "Mentions NADPH".to_string(),
+                    weight: weights[1],
+                },
+            ]),
+        }
+    }
+
+    /// App graded by a mock that marks answers wrong but credits the first criterion
+    async fn setup(questions: Vec<QuizQuestion>) -> (Router, Uuid, Uuid) {
+        let card_service = CardService::new_in_memory().await.unwrap();
+        let card = card_service
+            .create_card(CreateCardRequest {
+                zettel_id: "RUBRIC-001".to_string(),
+                title: None,
+                content: "The Calvin cycle uses ATP and NADPH from the light reactions."
+                    .to_string(),
+                topic_ids: vec![],
+                links: None,
+            })
+            .await
+            .unwrap();
+
+        let session_id = Uuid::new_v4();
+        let session = ReviewSession {
+            session_id,
+            cards: vec![card.clone()],
+            questions: HashMap::from([(card.id, questions)]),
+            current_card: 0,
+            created_at: Utc::now(),
+        };
+        let app = create_app(AppState {
+            card_service,
+            llm_service: LLMService::new_mock_with_incorrect_answers(),
+            review_sessions: Arc::new(Mutex::new(HashMap::from([(session_id, session)]))),
+            question_cache: QuestionCache::new(100, 60),
+        });
+        (app, session_id, card.id)
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str, body: &str) -> serde_json::Value {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_partial_credit_is_returned_and_stored() {
+        let (app, session_id, card_id) = setup(vec![rubric_question([0.75, 0.25])]).await;
+
+        let json = send(
+            &app,
+            "POST",
+            &format!("/api/review/session/{}/answer/{}", session_id, card_id),
+            r#"{"question_index": 0, "answer": "It needs ATP"}"#,
+        )
+        .await;
+        // Covering the heavier criterion passes, even though the grader said incorrect
+        assert_eq!(json["data"]["score"], 0.75);
+        assert_eq!(json["data"]["is_correct"], true);
+        assert_eq!(json["data"]["rating"], 3);
+        assert_eq!(json["data"]["criterion_scores"][1]["score"], 0.0);
+
+        let json = send(
+            &app,
+            "GET",
+            &format!("/api/review/session/{}/answers", session_id),
+            "",
+        )
+        .await;
+        let answers = json["data"].as_array().unwrap();
+        assert_eq!(answers.len(), 1);
+        assert_eq!(answers[0]["user_answer"], "It needs ATP");
+        assert_eq!(answers[0]["score"], 0.75);
+        assert_eq!(
+            answers[0]["criterion_scores"][0]["criterion"],
+            "Mentions ATP"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_answers_are_scored_against_their_own_rubric() {
+        let (app, session_id, card_id) = setup(vec![
+            rubric_question([0.75, 0.25]),
+            rubric_question([0.25, 0.75]),
+        ])
+        .await;
+
+        let json = send(
+            &app,
+            "POST",
+            &format!(
+                "/api/review/session/{}/answers/{}/batch",
+                session_id, card_id
+            ),
+            r#"{"answers": [
+                {"question_index": 0, "answer": "ATP is used"},
+                {"question_index": 1, "answer": "ATP is used"}
+            ]}"#,
+        )
+        .await;
+        let results = json["data"].as_array().unwrap();
+        assert_eq!(results[0]["score"], 0.75);
+        assert_eq!(results[0]["suggested_rating"], 3);
+        assert_eq!(results[1]["score"], 0.25);
+        assert_eq!(results[1]["is_correct"], false);
+        assert_eq!(results[1]["suggested_rating"], 1);
+
+        let json = send(
+            &app,
+            "GET",
+            &format!("/api/review/session/{}/answers", session_id),
+            "",
+        )
+        .await;
+        let stored: Vec<i64> = json["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|answer| answer["question_index"].as_i64().unwrap())
+            .collect();
+        assert_eq!(stored, vec![0, 1]);
+    }
+}
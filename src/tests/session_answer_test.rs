@@ -1,3 +1,5 @@
+#![allow(clippy::assertions_on_constants)]
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
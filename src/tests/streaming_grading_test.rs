@@ -0,0 +1,194 @@
+#[cfg(test)]
+mod streaming_grading_tests {
+    use crate::{
+        api::*, card_service::CardService, database::Database, llm_service::LLMService,
+        llm_usage::UsageTracker, models::*, question_cache::QuestionCache,
+    };
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use chrono::Utc;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn create_test_card() -> Card {
+        Card {
+            id: Uuid::new_v4(),
+            zettel_id: "STREAM-001".to_string(),
+            title: None,
+            content: "Streaming shows grading feedback while it is generated.".to_string(),
+            creation_date: Utc::now(),
+            last_reviewed: None,
+            next_review: Utc::now(),
+            difficulty: 0.0,
+            stability: 0.0,
+            retrievability: 0.0,
+            reps: 0,
+            lapses: 0,
+            state: "New".to_string(),
+            links: None,
+        }
+    }
+
+    fn create_test_question() -> QuizQuestion {
+        QuizQuestion {
+            question: "Why stream grading feedback?".to_string(),
+            question_type: "short_answer".to_string(),
+            options: None,
+            correct_answer: Some("So the review UI feels responsive".to_string()),
+            prompt_version: None,
+            rubric: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streaming_feedback_matches_final_result() {
+        let llm_service = LLMService::new_mock();
+        let mut streamed = Vec::new();
+
+        let result = llm_service
+            .grade_answer_streaming(
+                &create_test_card(),
+                &create_test_question(),
+                "It feels faster",
+                |feedback| streamed.push(feedback.to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert!(result.is_correct);
+        assert_eq!(result.suggested_rating, 4);
+        assert_eq!(result.served_by.as_deref(), Some("Mock:mock-model"));
+        assert!(streamed.len() > 1, "feedback should arrive in pieces");
+        assert_eq!(streamed.concat(), result.feedback);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_fails_over_and_records_usage() {
+        let db = Database::new_in_memory().await.unwrap();
+        let llm_service =
+            LLMService::new_mock_with_failover().with_usage_tracker(UsageTracker::new(db, None));
+
+        let result = llm_service
+            .grade_answer_streaming(
+                &create_test_card(),
+                &create_test_question(),
+                "It feels faster",
+                |_| {},
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.served_by.as_deref(), Some("Mock:mock-model"));
+
+        let summary = llm_service
+            .usage_tracker()
+            .unwrap()
+            .summary(1)
+            .await
+            .unwrap();
+        let served = summary
+            .daily
+            .iter()
+            .find(|d| d.model == "mock-model")
+            .unwrap();
+        assert_eq!(served.calls, 1);
+        assert!(served.completion_tokens > 0);
+        assert!(summary.daily.iter().any(|d| d.failed_calls == 1));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_all_providers_unavailable() {
+        let llm_service = LLMService::new_mock_all_unavailable();
+
+        let error = llm_service
+            .grade_answer_streaming(
+                &create_test_card(),
+                &create_test_question(),
+                "Anything",
+                |_| panic!("no feedback expected"),
+            )
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("All 2 LLM providers failed"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_stream_session_answer_endpoint() {
+        let card_service = CardService::new_in_memory().await.unwrap();
+        let review_sessions = Arc::new(Mutex::new(HashMap::new()));
+        let app = create_app(AppState {
+            card_service: card_service.clone(),
+            llm_service: LLMService::new_mock(),
+            review_sessions: review_sessions.clone(),
+            question_cache: QuestionCache::new(100, 60),
+        });
+
+        let card = card_service
+            .create_card(CreateCardRequest {
+                zettel_id: "STREAM-002".to_string(),
+                title: None,
+                content: "Server-sent events".to_string(),
+                topic_ids: vec![],
+                links: None,
+            })
+            .await
+            .unwrap();
+
+        let session_id = Uuid::new_v4();
+        review_sessions.lock().unwrap().insert(
+            session_id,
+            ReviewSession {
+                session_id,
+                cards: vec![card.clone()],
+                questions: HashMap::from([(card.id, vec![create_test_question()])]),
+                current_card: 0,
+                created_at: Utc::now(),
+            },
+        );
+
+        let stream_request = |question_index: usize| {
+            Request::builder()
+                .method("POST")
+                .uri(format!(
+                    "/api/review/session/{}/answer/{}/stream",
+                    session_id, card.id
+                ))
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "question_index": question_index, "answer": "Responsiveness" })
+                        .to_string(),
+                ))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(stream_request(0)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body.contains("event: feedback"), "{}", body);
+        let result_line = body
+            .lines()
+            .skip_while(|line| *line != "event: result")
+            .nth(1)
+            .unwrap();
+        let result: serde_json::Value =
+            serde_json::from_str(result_line.strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(result["is_correct"], true);
+        assert_eq!(result["rating"], 4);
+
+        // Invalid question indexes are rejected before the stream starts
+        let response = app.oneshot(stream_request(5)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
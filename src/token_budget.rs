@@ -0,0 +1,259 @@
+use std::borrow::Cow;
+
+/// Appended to text cut short by `truncate_to_tokens`
+pub const TRUNCATION_MARKER: &str = "...";
+
+/// Context window and reply limit of a model, in tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBudget {
+    pub context_window: usize,
+    pub max_output_tokens: usize,
+}
+
+// Known model families by name prefix, most specific first. Unknown models (local
+// servers in particular) get the conservative default.
+const MODEL_BUDGETS: &[(&str, usize, usize)] = &[
+    ("gpt-5", 400_000, 128_000),
+    ("gpt-4.1", 1_047_576, 32_768),
+    ("gpt-4o", 128_000, 16_384),
+    ("gpt-4-turbo", 128_000, 4_096),
+    ("gpt-4", 8_192, 4_096),
+    ("gpt-3.5-turbo", 16_385, 4_096),
+    ("o1", 200_000, 100_000),
+    ("o3", 200_000, 100_000),
+    ("o4", 200_000, 100_000),
+    ("gemini", 1_048_576, 8_192),
+    ("claude", 200_000, 8_192),
+];
+
+const DEFAULT_BUDGET: TokenBudget = TokenBudget {
+    context_window: 8_192,
+    max_output_tokens: 2_048,
+};
+
+impl TokenBudget {
+    pub fn for_model(model: &str) -> Self {
+        let model = model.to_lowercase();
+        // Strip a provider prefix such as "openai/gpt-4o"
+        let name = model.rsplit('/').next().unwrap_or(&model);
+
+        MODEL_BUDGETS
+            .iter()
+            .find(|(prefix, _, _)| name.starts_with(prefix))
+            .map(|&(_, context_window, max_output_tokens)| TokenBudget {
+                context_window,
+                max_output_tokens,
+            })
+            .unwrap_or(DEFAULT_BUDGET)
+    }
+
+    /// A budget that fits every one of the models, such as each provider in a failover
+    /// chain
+    pub fn for_models<'a>(models: impl IntoIterator<Item = &'a str>) -> Self {
+        models
+            .into_iter()
+            .map(Self::for_model)
+            .reduce(|a, b| TokenBudget {
+                context_window: a.context_window.min(b.context_window),
+                max_output_tokens: a.max_output_tokens.min(b.max_output_tokens),
+            })
+            .unwrap_or(DEFAULT_BUDGET)
+    }
+}
+
+/// Estimate how many tokens a BPE tokenizer splits `text` into. Runs of ASCII letters
+/// and digits count one token per four characters, while ASCII punctuation and every
+/// other non-whitespace character count one token each. This deliberately errs high
+/// for non-English text so budgets are not overrun.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut word_len: usize = 0;
+
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            word_len += 1;
+            continue;
+        }
+
+        tokens += word_len.div_ceil(4);
+        word_len = 0;
+        if !c.is_whitespace() {
+            tokens += 1;
+        }
+    }
+
+    tokens + word_len.div_ceil(4)
+}
+
+/// Byte ranges of inline and display LaTeX (`$...$`, `$$...$$`, `\(...\)`, `\[...\]`).
+/// An unclosed expression runs to the end of the text.
+fn math_spans(text: &str) -> Vec<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let (opener_len, closer): (usize, &[u8]) = match bytes[i] {
+            b'\\' if bytes.get(i + 1) == Some(&b'(') => (2, b"\\)"),
+            b'\\' if bytes.get(i + 1) == Some(&b'[') => (2, b"\\]"),
+            b'\\' => {
+                // Skip escaped characters such as \$
+                i += 2;
+                continue;
+            }
+            b'$' if bytes.get(i + 1) == Some(&b'$') => (2, b"$$"),
+            b'$' => (1, b"$"),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+
+        let start = i;
+        let mut j = i + opener_len;
+        let end = loop {
+            if j >= bytes.len() {
+                break bytes.len();
+            }
+            if bytes[j] == b'\\' && closer[0] != b'\\' {
+                j += 2;
+                continue;
+            }
+            if bytes[j..].starts_with(closer) {
+                break j + closer.len();
+            }
+            j += 1;
+        };
+
+        spans.push((start, end));
+        i = end;
+    }
+
+    spans
+}
+
+/// Shorten `text` to at most `max_tokens` estimated tokens, marker included. The cut
+/// falls on a character boundary, preferably between words, and never inside a LaTeX
+/// expression.
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> Cow<'_, str> {
+    if estimate_tokens(text) <= max_tokens {
+        return Cow::Borrowed(text);
+    }
+
+    let content_tokens = max_tokens.saturating_sub(estimate_tokens(TRUNCATION_MARKER));
+    let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+
+    // Longest prefix within the budget; token estimates grow with the prefix length
+    let fitting = boundaries.partition_point(|&i| estimate_tokens(&text[..i]) <= content_tokens);
+    let mut cut = boundaries[fitting.saturating_sub(1)];
+
+    // Back off to the start of a word cut in half
+    if !text[cut..].starts_with(char::is_whitespace)
+        && let Some(space) = text[..cut].rfind(char::is_whitespace)
+    {
+        cut = space;
+    }
+
+    if let Some(&(start, _)) = math_spans(text)
+        .iter()
+        .find(|&&(start, end)| start < cut && cut < end)
+    {
+        cut = start;
+    }
+
+    Cow::Owned(format!("{}{}", text[..cut].trim_end(), TRUNCATION_MARKER))
+}
+
+/// Split `items` into consecutive groups whose summed `cost` stays within `budget` and
+/// whose size stays within `max_items`. An item that alone exceeds the budget gets a
+/// group of its own.
+pub fn pack_by_tokens<T>(
+    items: Vec<T>,
+    cost: impl Fn(&T) -> usize,
+    budget: usize,
+    max_items: usize,
+) -> Vec<Vec<T>> {
+    let mut groups = Vec::new();
+    let mut current = Vec::new();
+    let mut current_cost = 0;
+
+    for item in items {
+        let item_cost = cost(&item);
+        let full = current.len() >= max_items.max(1) || current_cost + item_cost > budget;
+        if !current.is_empty() && full {
+            groups.push(std::mem::take(&mut current));
+            current_cost = 0;
+        }
+        current_cost += item_cost;
+        current.push(item);
+    }
+
+    if !current.is_empty() {
+        groups.push(current);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_lookup_by_model_prefix() {
+        assert_eq!(
+            TokenBudget::for_model("gpt-4o-mini").context_window,
+            128_000
+        );
+        assert_eq!(TokenBudget::for_model("gpt-4").context_window, 8_192);
+        assert_eq!(
+            TokenBudget::for_model("openai/gpt-4o").max_output_tokens,
+            16_384
+        );
+        assert_eq!(TokenBudget::for_model("llama3.1:8b"), DEFAULT_BUDGET);
+    }
+
+    #[test]
+    fn test_budget_for_models_fits_the_smallest() {
+        let budget = TokenBudget::for_models(["gpt-4o", "gpt-4", "gemini-1.5-pro"]);
+        assert_eq!(budget.context_window, 8_192);
+        assert_eq!(budget.max_output_tokens, 4_096);
+        assert_eq!(TokenBudget::for_models([]), DEFAULT_BUDGET);
+    }
+
+    #[test]
+    fn test_truncation_is_utf8_safe_and_within_budget() {
+        let text = "Ünïcödé façade naïveté 日本語のテキスト ".repeat(50);
+        let truncated = truncate_to_tokens(&text, 40);
+
+        assert!(truncated.ends_with(TRUNCATION_MARKER));
+        assert!(estimate_tokens(&truncated) <= 40);
+        assert!(matches!(
+            truncate_to_tokens("short", 40),
+            Cow::Borrowed("short")
+        ));
+    }
+
+    #[test]
+    fn test_truncation_never_splits_latex() {
+        let text = format!(
+            "{} the energy is $E = mc^2$ and \\[\\int_0^1 x^2 \\, dx = \\frac{{1}}{{3}}\\] follows",
+            "word ".repeat(10)
+        );
+
+        for max_tokens in 1..estimate_tokens(&text) {
+            let truncated = truncate_to_tokens(&text, max_tokens);
+            let kept = truncated.trim_end_matches(TRUNCATION_MARKER);
+            assert_eq!(kept.matches('$').count() % 2, 0, "{}", truncated);
+            assert_eq!(kept.matches("\\[").count(), kept.matches("\\]").count());
+        }
+    }
+
+    #[test]
+    fn test_packing_respects_budget_and_item_limit() {
+        let groups = pack_by_tokens(vec![40, 40, 40, 200, 10], |&cost| cost, 100, 10);
+        assert_eq!(groups, vec![vec![40, 40], vec![40], vec![200], vec![10]]);
+
+        let groups = pack_by_tokens(vec![1, 1, 1], |&cost| cost, 100, 2);
+        assert_eq!(groups, vec![vec![1, 1], vec![1]]);
+    }
+}
@@ -0,0 +1,425 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::markdown::{replace_wiki_links, wiki_links};
+use crate::models::Card;
+
+// Characters that can't appear in file names on common file systems
+static UNSAFE_FILE_NAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"[/\\:*?"<>|\x00-\x1f]"#).expect("unsafe file name pattern is valid")
+});
+
+const MATHJAX_URL: &str =
+    "https://cdnjs.cloudflare.com/ajax/libs/mathjax/3.2.2/es5/tex-mml-chtml.min.js";
+const MARKED_URL: &str = "https://cdnjs.cloudflare.com/ajax/libs/marked/4.3.0/marked.min.js";
+
+/// A card with what its exported page needs: topic names, linked and linking cards
+#[derive(Debug, Clone)]
+pub struct ExportNote {
+    pub card: Card,
+    pub file_stem: String,
+    pub topics: Vec<String>,
+    pub links: Vec<String>,     // zettel_ids of linked cards
+    pub backlinks: Vec<String>, // zettel_ids of cards linking here
+}
+
+/// What an export wrote
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExportReport {
+    pub files: Vec<PathBuf>,
+    pub unresolved_links: Vec<(String, Uuid)>, // (zettel_id, missing card id)
+}
+
+/// Gather every card with its topics, links and backlinks, in zettel_id order
+pub async fn collect_notes(db: &Database) -> Result<(Vec<ExportNote>, ExportReport)> {
+    let mut cards = db.get_all_cards().await?;
+    cards.sort_by(|a, b| a.zettel_id.cmp(&b.zettel_id));
+    let zettel_ids: HashMap<Uuid, String> = cards
+        .iter()
+        .map(|card| (card.id, card.zettel_id.clone()))
+        .collect();
+
+    let mut report = ExportReport::default();
+    let mut used_stems = HashSet::new();
+    let mut notes = Vec::new();
+    for card in cards {
+        let link_ids: Vec<Uuid> = card
+            .links
+            .as_deref()
+            .and_then(|links| serde_json::from_str(links).ok())
+            .unwrap_or_default();
+        let mut links = Vec::new();
+        for id in link_ids {
+            match zettel_ids.get(&id) {
+                Some(zettel_id) => links.push(zettel_id.clone()),
+                None => report.unresolved_links.push((card.zettel_id.clone(), id)),
+            }
+        }
+        let mut backlinks: Vec<String> = db
+            .get_backlinks(card.id)
+            .await?
+            .into_iter()
+            .map(|source| source.zettel_id)
+            .collect();
+        backlinks.sort();
+
+        notes.push(ExportNote {
+            file_stem: unique_file_stem(&card.zettel_id, &mut used_stems),
+            topics: db.get_topic_names_for_card(card.id).await?,
+            links,
+            backlinks,
+            card,
+        });
+    }
+    Ok((notes, report))
+}
+
+/// The zettel_id with characters that aren't allowed in file names replaced. Names are
+/// compared case-insensitively, and a clash gets a numbered suffix.
+fn unique_file_stem(zettel_id: &str, used: &mut HashSet<String>) -> String {
+    let base = UNSAFE_FILE_NAME.replace_all(zettel_id.trim(), "-");
+    let base = base.trim_matches('.');
+    let base = if base.is_empty() { "card" } else { base };
+
+    let mut stem = base.to_string();
+    let mut suffix = 2;
+    while !used.insert(stem.to_lowercase()) {
+        stem = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    stem
+}
+
+/// The note as Markdown: front matter with the title, topics and FSRS state, then the
+/// content as stored, then a `Links` section for linked cards the content doesn't
+/// already mention
+pub fn render_markdown(note: &ExportNote) -> String {
+    let card = &note.card;
+    let mut output = String::from("---\n");
+    output.push_str(&format!("zettel_id: {}\n", yaml_string(&card.zettel_id)));
+    if let Some(title) = &card.title {
+        output.push_str(&format!("title: {}\n", yaml_string(title)));
+    }
+    output.push_str(&format!("topics: {}\n", yaml_list(&note.topics)));
+    output.push_str(&format!("links: {}\n", yaml_list(&note.links)));
+    output.push_str(&format!("created: {}\n", card.creation_date.to_rfc3339()));
+    output.push_str("fsrs:\n");
+    output.push_str(&format!("  state: {}\n", card.state));
+    output.push_str(&format!("  difficulty: {}\n", card.difficulty));
+    output.push_str(&format!("  stability: {}\n", card.stability));
+    output.push_str(&format!("  retrievability: {}\n", card.retrievability));
+    output.push_str(&format!("  reps: {}\n", card.reps));
+    output.push_str(&format!("  lapses: {}\n", card.lapses));
+    if let Some(last_reviewed) = card.last_reviewed {
+        output.push_str(&format!(
+            "  last_reviewed: {}\n",
+            last_reviewed.to_rfc3339()
+        ));
+    }
+    output.push_str(&format!(
+        "  next_review: {}\n",
+        card.next_review.to_rfc3339()
+    ));
+    output.push_str("---\n");
+    output.push_str(card.content.trim_end());
+    output.push('\n');
+
+    let mentioned = wiki_links(&card.content);
+    let unmentioned: Vec<&String> = note
+        .links
+        .iter()
+        .filter(|link| !mentioned.contains(link))
+        .collect();
+    if !unmentioned.is_empty() {
+        output.push_str("\n## Links\n");
+        for link in unmentioned {
+            output.push_str(&format!("- [[{}]]\n", link));
+        }
+    }
+    output
+}
+
+/// A double-quoted YAML scalar, which JSON string syntax is a subset of
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).expect("strings serialize")
+}
+
+fn yaml_list(values: &[String]) -> String {
+    let items: Vec<String> = values.iter().map(|value| yaml_string(value)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Write every card to `<zettel_id>.md` in the directory, creating it if needed
+pub async fn export_markdown_vault(db: &Database, dir: &Path) -> Result<ExportReport> {
+    let (notes, mut report) = collect_notes(db).await?;
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+
+    for note in &notes {
+        let path = dir.join(format!("{}.md", note.file_stem));
+        fs::write(&path, render_markdown(note))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        report.files.push(path);
+    }
+
+    info!(
+        cards = notes.len(),
+        unresolved_links = report.unresolved_links.len(),
+        "Markdown vault exported"
+    );
+    Ok(report)
+}
+
+/// Write a static site to the directory: `index.html` listing cards by topic and one
+/// page per card with its links and backlinks. Pages render Markdown and LaTeX in the
+/// browser with marked and MathJax, like the app does.
+pub async fn export_html_site(db: &Database, dir: &Path) -> Result<ExportReport> {
+    let (notes, mut report) = collect_notes(db).await?;
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+    let pages: HashMap<&str, (&str, String)> = notes
+        .iter()
+        .map(|note| {
+            (
+                note.card.zettel_id.as_str(),
+                (note.file_stem.as_str(), display_title(&note.card)),
+            )
+        })
+        .collect();
+
+    for note in &notes {
+        let path = dir.join(format!("{}.html", note.file_stem));
+        fs::write(&path, render_page(note, &pages))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        report.files.push(path);
+    }
+
+    let path = dir.join("index.html");
+    fs::write(&path, render_index(&notes))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    report.files.push(path);
+
+    info!(
+        cards = notes.len(),
+        unresolved_links = report.unresolved_links.len(),
+        "HTML site exported"
+    );
+    Ok(report)
+}
+
+fn display_title(card: &Card) -> String {
+    card.title.clone().unwrap_or_else(|| card.zettel_id.clone())
+}
+
+/// One card's page. Wiki links to exported cards become Markdown links to their pages.
+fn render_page(note: &ExportNote, pages: &HashMap<&str, (&str, String)>) -> String {
+    let content = replace_wiki_links(&note.card.content, |link| {
+        let text = link.alias.unwrap_or(link.target).to_string();
+        match pages.get(link.target) {
+            Some((stem, _)) => format!("[{}]({}.html)", text, stem),
+            None => text,
+        }
+    });
+
+    let link_list = |zettel_ids: &[String]| -> String {
+        let items: Vec<String> = zettel_ids
+            .iter()
+            .filter_map(|zettel_id| pages.get(zettel_id.as_str()))
+            .map(|(stem, title)| {
+                format!(
+                    "<li><a href=\"{}.html\">{}</a></li>",
+                    escape_html(stem),
+                    escape_html(title)
+                )
+            })
+            .collect();
+        format!("<ul>{}</ul>", items.join(""))
+    };
+
+    let mut body = format!(
+        "<p class=\"meta\"><code>{}</code>",
+        escape_html(&note.card.zettel_id)
+    );
+    if !note.topics.is_empty() {
+        body.push_str(&format!(" · {}", escape_html(&note.topics.join(", "))));
+    }
+    body.push_str("</p>\n");
+    body.push_str(&format!(
+        "<div class=\"content\" data-markdown>{}</div>\n",
+        escape_html(&content)
+    ));
+    if !note.links.is_empty() {
+        body.push_str(&format!(
+            "<section class=\"links\"><h2>Links</h2>{}</section>\n",
+            link_list(&note.links)
+        ));
+    }
+    if !note.backlinks.is_empty() {
+        body.push_str(&format!(
+            "<section class=\"backlinks\"><h2>Backlinks</h2>{}</section>\n",
+            link_list(&note.backlinks)
+        ));
+    }
+    body.push_str("<p><a href=\"index.html\">All cards</a></p>\n");
+
+    page_html(&display_title(&note.card), &body)
+}
+
+/// Cards grouped by topic, with untagged cards last
+fn render_index(notes: &[ExportNote]) -> String {
+    let mut topics: Vec<&str> = notes
+        .iter()
+        .flat_map(|note| note.topics.iter().map(String::as_str))
+        .collect();
+    topics.sort();
+    topics.dedup();
+
+    let entry = |note: &ExportNote| {
+        format!(
+            "<li><a href=\"{}.html\">{}</a></li>",
+            escape_html(&note.file_stem),
+            escape_html(&display_title(&note.card))
+        )
+    };
+    let mut body = String::new();
+    for topic in topics {
+        let entries: Vec<String> = notes
+            .iter()
+            .filter(|note| note.topics.iter().any(|t| t == topic))
+            .map(entry)
+            .collect();
+        body.push_str(&format!(
+            "<h2>{}</h2>\n<ul>{}</ul>\n",
+            escape_html(topic),
+            entries.join("")
+        ));
+    }
+    let untagged: Vec<String> = notes
+        .iter()
+        .filter(|note| note.topics.is_empty())
+        .map(entry)
+        .collect();
+    if !untagged.is_empty() {
+        body.push_str(&format!(
+            "<h2>Other cards</h2>\n<ul>{}</ul>\n",
+            untagged.join("")
+        ));
+    }
+
+    page_html("Knowledge Base", &body)
+}
+
+fn page_html(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>{title}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.6; }}
+.meta {{ color: #666; }}
+.content {{ white-space: normal; }}
+</style>
+<script>
+window.MathJax = {{
+    tex: {{
+        inlineMath: [['$', '$'], ['\\(', '\\)']],
+        displayMath: [['$$', '$$'], ['\\[', '\\]']],
+        processEscapes: true
+    }}
+}};
+</script>
+<script src="{marked}"></script>
+<script src="{mathjax}" defer></script>
+</head>
+<body>
+<h1>{title}</h1>
+{body}<script>
+document.querySelectorAll('[data-markdown]').forEach((element) => {{
+    if (typeof marked !== 'undefined') {{
+        element.innerHTML = marked.parse(element.textContent, {{ breaks: true, gfm: true }});
+    }}
+}});
+</script>
+</body>
+</html>
+"#,
+        title = escape_html(title),
+        body = body,
+        marked = MARKED_URL,
+        mathjax = MATHJAX_URL,
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn note(zettel_id: &str, content: &str, links: &[&str]) -> ExportNote {
+        let date = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        ExportNote {
+            card: Card {
+                id: Uuid::new_v4(),
+                zettel_id: zettel_id.to_string(),
+                title: Some("Newton's \"second\" law".to_string()),
+                content: content.to_string(),
+                creation_date: date,
+                last_reviewed: None,
+                next_review: date,
+                difficulty: 5.0,
+                stability: 2.5,
+                retrievability: 0.9,
+                reps: 1,
+                lapses: 0,
+                state: "Review".to_string(),
+                links: None,
+            },
+            file_stem: zettel_id.to_string(),
+            topics: vec!["Physics".to_string()],
+            links: links.iter().map(|link| link.to_string()).collect(),
+            backlinks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_markdown_front_matter_and_links() {
+        let markdown = render_markdown(&note(
+            "PHYS-002",
+            "$F = ma$, see [[PHYS-001|the first law]]",
+            &["PHYS-001", "PHYS-003"],
+        ));
+        assert!(markdown.starts_with(
+            "---\nzettel_id: \"PHYS-002\"\ntitle: \"Newton's \\\"second\\\" law\"\ntopics: [\"Physics\"]\nlinks: [\"PHYS-001\", \"PHYS-003\"]\n"
+        ));
+        assert!(markdown.contains("fsrs:\n  state: Review\n  difficulty: 5\n"));
+        assert!(markdown.contains("---\n$F = ma$, see"));
+        // Only the link the content doesn't already mention is listed
+        assert!(markdown.ends_with("\n## Links\n- [[PHYS-003]]\n"));
+    }
+
+    #[test]
+    fn test_unique_file_stems() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_file_stem("BIO/001", &mut used), "BIO-001");
+        assert_eq!(unique_file_stem("BIO:001", &mut used), "BIO-001-2");
+        assert_eq!(unique_file_stem("bio-001", &mut used), "bio-001-3");
+        assert_eq!(unique_file_stem("..", &mut used), "card");
+    }
+}
@@ -0,0 +1,356 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::card_service::CardService;
+use crate::markdown::{extract_markdown_header, inline_tags, split_front_matter, wiki_links};
+use crate::models::{Card, CreateCardRequest, UpdateCardRequest};
+
+/// One markdown note read from a vault
+#[derive(Debug, Clone, PartialEq)]
+pub struct VaultNote {
+    pub path: PathBuf,
+    pub zettel_id: String,
+    pub title: Option<String>,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    pub links: Vec<String>, // Wiki link targets, as written
+}
+
+impl VaultNote {
+    /// Parse a note. The front matter `zettel_id` (or `id`) wins over the file name, and
+    /// the front matter `title` over a leading heading, which is then kept in the content.
+    pub fn parse(path: &Path, text: &str) -> Self {
+        let (front_matter, body) = split_front_matter(text);
+        let front_matter = front_matter.unwrap_or_default();
+        let file_stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let (title, content) = match front_matter.title {
+            Some(title) => (Some(title), body.trim().to_string()),
+            None => match extract_markdown_header(body.trim_start()) {
+                Some((title, content)) => (Some(title), content.trim_end().to_string()),
+                None => (None, body.trim().to_string()),
+            },
+        };
+
+        let mut tags: Vec<String> = Vec::new();
+        for tag in front_matter.tags.into_iter().chain(inline_tags(&content)) {
+            let tag = tag.trim_start_matches('#').to_string();
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+
+        VaultNote {
+            path: path.to_path_buf(),
+            zettel_id: front_matter.zettel_id.unwrap_or(file_stem),
+            title,
+            links: wiki_links(&content),
+            content,
+            tags,
+            aliases: front_matter.aliases,
+        }
+    }
+}
+
+/// Read every `.md` note under the vault directory, skipping hidden folders such as
+/// `.obsidian` and `.trash`. Notes are returned in path order.
+pub fn read_vault(dir: &Path) -> Result<Vec<VaultNote>> {
+    let mut paths = Vec::new();
+    collect_markdown_files(dir, &mut paths)?;
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read note {}", path.display()))?;
+            Ok(VaultNote::parse(&path, &text))
+        })
+        .collect()
+}
+
+fn collect_markdown_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_markdown_files(&path, paths)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// What an import did, note by note
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportReport {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub topics_created: Vec<String>,
+    pub unresolved_links: Vec<(String, String)>, // (zettel_id, link target)
+    pub errors: Vec<String>,
+}
+
+/// Import notes as cards. Notes are matched to existing cards by `zettel_id`, so
+/// importing the same vault again only updates the notes that changed. Wiki links are
+/// resolved by zettel_id, file name, path within the vault or alias, and become card
+/// links with backlinks; links to attachments are ignored.
+pub async fn import_notes(card_service: &CardService, notes: &[VaultNote]) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+
+    // Later notes with an already imported zettel_id are skipped, not merged
+    let mut seen: HashMap<&str, &Path> = HashMap::new();
+    let mut unique_notes = Vec::new();
+    for note in notes {
+        if let Some(first) = seen.get(note.zettel_id.as_str()) {
+            report.errors.push(format!(
+                "{}: zettel_id '{}' is already used by {}",
+                note.path.display(),
+                note.zettel_id,
+                first.display()
+            ));
+        } else {
+            seen.insert(&note.zettel_id, &note.path);
+            unique_notes.push(note);
+        }
+    }
+
+    let mut topics: HashMap<String, Uuid> = card_service
+        .get_all_topics()
+        .await?
+        .into_iter()
+        .map(|topic| (topic.name, topic.id))
+        .collect();
+
+    // First create or update every card, so links can point at notes imported later
+    let mut cards: Vec<(&VaultNote, Card)> = Vec::new();
+    for note in unique_notes {
+        let mut topic_ids = Vec::new();
+        for tag in &note.tags {
+            let topic_id = match topics.get(tag) {
+                Some(id) => *id,
+                None => {
+                    let topic = card_service.create_topic(tag.clone(), None).await?;
+                    report.topics_created.push(tag.clone());
+                    topics.insert(tag.clone(), topic.id);
+                    topic.id
+                }
+            };
+            topic_ids.push(topic_id);
+        }
+
+        match import_note(card_service, note, topic_ids, &mut report).await {
+            Ok(card) => cards.push((note, card)),
+            Err(e) => {
+                warn!(zettel_id = %note.zettel_id, error = %e, "Failed to import note");
+                report
+                    .errors
+                    .push(format!("{}: {}", note.path.display(), e));
+            }
+        }
+    }
+
+    let targets = link_targets(&cards);
+    for (note, card) in &cards {
+        let mut link_ids: Vec<Uuid> = Vec::new();
+        for target in &note.links {
+            if is_attachment(target) {
+                continue;
+            }
+            let resolved = match targets.get(&link_key(target)) {
+                Some(id) => Some(*id),
+                None => card_service
+                    .get_card_by_zettel_id(target)
+                    .await?
+                    .map(|card| card.id),
+            };
+            match resolved {
+                Some(id) if id != card.id && !link_ids.contains(&id) => link_ids.push(id),
+                Some(_) => {}
+                None => report
+                    .unresolved_links
+                    .push((note.zettel_id.clone(), target.clone())),
+            }
+        }
+
+        let current_links: Vec<Uuid> = card
+            .links
+            .as_deref()
+            .and_then(|links| serde_json::from_str(links).ok())
+            .unwrap_or_default();
+        if current_links == link_ids {
+            continue;
+        }
+
+        card_service
+            .update_card(
+                card.id,
+                UpdateCardRequest {
+                    zettel_id: None,
+                    title: None,
+                    content: None,
+                    topic_ids: None,
+                    links: Some(link_ids),
+                },
+            )
+            .await?;
+        if let Some(position) = report.unchanged.iter().position(|z| *z == note.zettel_id) {
+            report.unchanged.remove(position);
+            report.updated.push(note.zettel_id.clone());
+        }
+    }
+
+    info!(
+        created = report.created.len(),
+        updated = report.updated.len(),
+        unchanged = report.unchanged.len(),
+        unresolved_links = report.unresolved_links.len(),
+        errors = report.errors.len(),
+        "Vault import finished"
+    );
+    Ok(report)
+}
+
+/// Read a vault directory and import its notes
+pub async fn import_vault(card_service: &CardService, dir: &Path) -> Result<ImportReport> {
+    let notes = read_vault(dir)?;
+    import_notes(card_service, &notes).await
+}
+
+/// Create the note's card, or update its content and topics when they changed. Links
+/// are left to the second pass.
+async fn import_note(
+    card_service: &CardService,
+    note: &VaultNote,
+    topic_ids: Vec<Uuid>,
+    report: &mut ImportReport,
+) -> Result<Card> {
+    let Some(existing) = card_service.get_card_by_zettel_id(&note.zettel_id).await? else {
+        let card = card_service
+            .create_card(CreateCardRequest {
+                zettel_id: note.zettel_id.clone(),
+                title: note.title.clone(),
+                content: note.content.clone(),
+                topic_ids,
+                links: None,
+            })
+            .await?;
+        report.created.push(note.zettel_id.clone());
+        return Ok(card);
+    };
+
+    let mut current_topics = card_service.get_topic_names_for_card(existing.id).await?;
+    let mut note_topics = note.tags.clone();
+    current_topics.sort();
+    note_topics.sort();
+
+    let title_changed = note.title.is_some() && note.title != existing.title;
+    let content_changed = note.content != existing.content;
+    let topics_changed = current_topics != note_topics;
+    if !title_changed && !content_changed && !topics_changed {
+        report.unchanged.push(note.zettel_id.clone());
+        return Ok(existing);
+    }
+
+    let card = card_service
+        .update_card(
+            existing.id,
+            UpdateCardRequest {
+                zettel_id: None,
+                title: note.title.clone().filter(|_| title_changed),
+                content: content_changed.then(|| note.content.clone()),
+                topic_ids: topics_changed.then_some(topic_ids),
+                links: None,
+            },
+        )
+        .await?
+        .context("Card disappeared during import")?;
+    report.updated.push(note.zettel_id.clone());
+    Ok(card)
+}
+
+/// Card ids by every name a wiki link may use for the note
+fn link_targets(cards: &[(&VaultNote, Card)]) -> HashMap<String, Uuid> {
+    let mut targets = HashMap::new();
+    for (note, card) in cards {
+        let names = [
+            Some(note.zettel_id.as_str()),
+            note.path.file_stem().and_then(|stem| stem.to_str()),
+        ];
+        let aliases = note.aliases.iter().map(String::as_str);
+        for name in names.into_iter().flatten().chain(aliases) {
+            targets.entry(link_key(name)).or_insert(card.id);
+        }
+    }
+    targets
+}
+
+/// Wiki links are case-insensitive and may include a folder path or `.md` suffix
+fn link_key(target: &str) -> String {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    name.strip_suffix(".md")
+        .unwrap_or(name)
+        .trim()
+        .to_lowercase()
+}
+
+/// Embedded images and other files, which have an extension other than `.md`
+fn is_attachment(target: &str) -> bool {
+    Path::new(target).extension().is_some_and(|ext| {
+        let ext = ext.to_string_lossy();
+        ext != "md" && ext.len() <= 4 && ext.chars().all(char::is_alphanumeric)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_note_prefers_front_matter() {
+        let note = VaultNote::parse(
+            Path::new("vault/biology/Osmosis.md"),
+            "---\nid: BIO-001\ntags: [biology]\n---\n# Osmosis\n\nWater crosses a membrane. See [[Diffusion|diffusing]]. #cells",
+        );
+        assert_eq!(note.zettel_id, "BIO-001");
+        assert_eq!(note.title.as_deref(), Some("Osmosis"));
+        assert_eq!(
+            note.content,
+            "Water crosses a membrane. See [[Diffusion|diffusing]]. #cells"
+        );
+        assert_eq!(note.tags, vec!["biology", "cells"]);
+        assert_eq!(note.links, vec!["Diffusion"]);
+
+        let plain = VaultNote::parse(Path::new("Diffusion.md"), "Particles spread out.\n");
+        assert_eq!(plain.zettel_id, "Diffusion");
+        assert_eq!(plain.title, None);
+        assert_eq!(plain.content, "Particles spread out.");
+    }
+
+    #[test]
+    fn test_link_keys_and_attachments() {
+        assert_eq!(link_key("Biology/Osmosis.md"), "osmosis");
+        assert_eq!(link_key("Cell Membrane"), "cell membrane");
+        assert!(is_attachment("diagram.png"));
+        assert!(!is_attachment("Osmosis.md"));
+        assert!(!is_attachment("Newton's laws v2.0 notes"));
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::{Context, Result, bail};
+use std::io::{Cursor, Read, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Largest file read out of an archive. The size an entry declares is not trusted, so
+/// a small archive cannot expand into more than this.
+pub const MAX_ENTRY_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Largest total size of the files read out of one archive
+pub const MAX_ARCHIVE_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// A file read from or written to a zip archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZipEntry {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Read every file in a zip archive, skipping directories. Entries larger than
+/// `MAX_ENTRY_SIZE` or archives larger than `MAX_ARCHIVE_SIZE` once decompressed are
+/// rejected.
+pub fn read_zip(bytes: &[u8]) -> Result<Vec<ZipEntry>> {
+    read_zip_with_limits(bytes, MAX_ENTRY_SIZE, MAX_ARCHIVE_SIZE)
+}
+
+fn read_zip_with_limits(
+    bytes: &[u8],
+    max_entry_size: u64,
+    max_archive_size: u64,
+) -> Result<Vec<ZipEntry>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).context("Not a zip archive")?;
+
+    let mut entries = Vec::with_capacity(archive.len());
+    let mut total_size = 0;
+    for index in 0..archive.len() {
+        let file = archive.by_index(index)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+
+        let limit = max_entry_size.min(max_archive_size - total_size);
+        let mut data = Vec::new();
+        file.take(limit.saturating_add(1))
+            .read_to_end(&mut data)
+            .with_context(|| format!("Corrupt zip entry '{}'", name))?;
+        if data.len() as u64 > limit {
+            bail!("Zip entry '{}' is too large to extract", name);
+        }
+        total_size += data.len() as u64;
+        entries.push(ZipEntry { name, data });
+    }
+
+    Ok(entries)
+}
+
+/// Write files into a deflate-compressed zip archive
+pub fn write_zip(entries: &[ZipEntry]) -> Result<Vec<u8>> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(
+            entries
+                .iter()
+                .any(|entry| entry.data.len() > u32::MAX as usize),
+        );
+
+    for entry in entries {
+        writer.start_file(entry.name.as_str(), options)?;
+        writer.write_all(&entry.data)?;
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_entries() -> Vec<ZipEntry> {
+        vec![
+            ZipEntry {
+                name: "collection.anki2".to_string(),
+                data: vec![0, 1, 2, 3, 255],
+            },
+            ZipEntry {
+                name: "media".to_string(),
+                data: b"{}".to_vec(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_written_archive_reads_back() {
+        let entries = sample_entries();
+        let archive = write_zip(&entries).unwrap();
+        assert_eq!(read_zip(&archive).unwrap(), entries);
+        assert!(read_zip(b"not a zip").is_err());
+    }
+
+    #[test]
+    fn test_corrupt_entry_is_rejected() {
+        let entries = vec![ZipEntry {
+            name: "notes.txt".to_string(),
+            data: b"the quick brown fox jumps over the lazy dog ".repeat(100),
+        }];
+        let mut archive = write_zip(&entries).unwrap();
+
+        // Flip bits inside the compressed data, after the 30-byte local header and name
+        let data_start = 30 + "notes.txt".len();
+        for byte in &mut archive[data_start + 4..data_start + 12] {
+            *byte ^= 0xa5;
+        }
+        assert!(read_zip(&archive).is_err());
+    }
+
+    #[test]
+    fn test_oversized_entries_are_rejected() {
+        // Zeros compress about a thousandfold, the shape of a decompression bomb
+        let entries = vec![
+            ZipEntry {
+                name: "first".to_string(),
+                data: vec![0; 64 * 1024],
+            },
+            ZipEntry {
+                name: "second".to_string(),
+                data: vec![0; 64 * 1024],
+            },
+        ];
+        let archive = write_zip(&entries).unwrap();
+        assert!(archive.len() < 4 * 1024);
+
+        assert_eq!(
+            read_zip_with_limits(&archive, 64 * 1024, 128 * 1024).unwrap(),
+            entries
+        );
+        let error = read_zip_with_limits(&archive, 64 * 1024 - 1, u64::MAX).unwrap_err();
+        assert!(error.to_string().contains("'first' is too large"));
+        let error = read_zip_with_limits(&archive, u64::MAX, 128 * 1024 - 1).unwrap_err();
+        assert!(error.to_string().contains("'second' is too large"));
+    }
+}
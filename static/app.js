@@ -1492,7 +1492,10 @@ class LearningSystem {
             // Submit the final rating to update FSRS
             await this.apiCall(`/cards/${card.id}/review`, {
                 method: 'POST',
-                body: JSON.stringify({ rating: finalRating })
+                body: JSON.stringify({
+                    rating: finalRating,
+                    session_id: this.reviewSession.sessionId
+                })
             });
 
             // Card completed, move to next card or end session
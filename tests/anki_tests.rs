@@ -0,0 +1,164 @@
+use learning_system::anki::{export_apkg, import_apkg};
+use learning_system::{CardService, CreateCardRequest, Database};
+
+/// A schema 11 collection with a Basic note in "Biology::Cells" reviewed three times, a
+/// two-deletion Cloze note and a single-field note in the default deck
+const SAMPLE_PACKAGE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/anki/sample.apkg"
+);
+
+async fn create_database() -> Database {
+    Database::new("sqlite::memory:").await.unwrap()
+}
+
+#[tokio::test]
+async fn test_apkg_import_maps_notes_decks_and_history() {
+    let db = create_database().await;
+    let package = std::fs::read(SAMPLE_PACKAGE).unwrap();
+
+    let report = import_apkg(&db, &package).await.unwrap();
+    assert_eq!(
+        report.created,
+        vec![
+            "anki-1704100000000",
+            "anki-1704100000001",
+            "anki-1704100000002"
+        ]
+    );
+    assert_eq!(
+        report.topics_created,
+        vec!["Biology::Cells", "mitochondria"]
+    );
+    // The rescheduling entry (ease 0) is not a review
+    assert_eq!(report.reviews_imported, 4);
+
+    let basic = db
+        .get_card_by_zettel_id("anki-1704100000000")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        basic.title.as_deref(),
+        Some("What is the powerhouse of the cell?")
+    );
+    assert_eq!(
+        basic.content,
+        "The mitochondrion\nIt makes ATP via $\\text{ADP} + P_i$"
+    );
+    assert_eq!(basic.state, "Review");
+    assert_eq!(basic.stability, 14.5);
+    assert_eq!(basic.difficulty, 4.2);
+    assert_eq!((basic.reps, basic.lapses), (5, 1));
+    assert_eq!(basic.next_review.to_rfc3339(), "2024-02-10T00:00:00+00:00");
+    let reviews = db.get_reviews_for_card(basic.id).await.unwrap();
+    assert_eq!(
+        reviews.iter().map(|r| r.rating).collect::<Vec<_>>(),
+        vec![3, 1, 3]
+    );
+    assert_eq!(basic.last_reviewed, Some(reviews[2].review_date));
+    assert_eq!(
+        db.get_topic_names_for_card(basic.id).await.unwrap(),
+        vec!["Biology::Cells", "mitochondria"]
+    );
+
+    let cloze = db
+        .get_card_by_zettel_id("anki-1704100000001")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cloze.title, None);
+    assert_eq!(cloze.content, "The {{c1::nucleus}} stores {{c2::DNA}}.");
+    assert_eq!(cloze.state, "Learning");
+
+    let loose = db
+        .get_card_by_zettel_id("anki-1704100000002")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((loose.title, loose.content.as_str()), (None, "Loose front"));
+    assert_eq!(loose.state, "New");
+    assert!(
+        db.get_topic_names_for_card(loose.id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // Importing again skips what is already there
+    let report = import_apkg(&db, &package).await.unwrap();
+    assert!(report.created.is_empty());
+    assert_eq!(report.skipped.len(), 3);
+}
+
+#[tokio::test]
+async fn test_apkg_export_round_trips() {
+    let source = create_database().await;
+    let card_service = CardService::new(source.clone());
+    let physics = card_service
+        .create_topic("Physics".to_string(), None)
+        .await
+        .unwrap();
+    let mechanics = card_service
+        .create_topic("Classical Mechanics".to_string(), None)
+        .await
+        .unwrap();
+    let newton = card_service
+        .create_card(CreateCardRequest {
+            zettel_id: "PHYS-001".to_string(),
+            title: Some("Newton's second law".to_string()),
+            content: "Force equals mass times acceleration: $F = ma$".to_string(),
+            topic_ids: vec![physics.id, mechanics.id],
+            links: None,
+        })
+        .await
+        .unwrap();
+    card_service.review_card(newton.id, 3).await.unwrap();
+    card_service.review_card(newton.id, 4).await.unwrap();
+    card_service
+        .create_card(CreateCardRequest {
+            zettel_id: "PHYS-002".to_string(),
+            title: None,
+            content: "Light travels at {{c1::299,792}} km/s.".to_string(),
+            topic_ids: vec![],
+            links: None,
+        })
+        .await
+        .unwrap();
+
+    let package = export_apkg(&source).await.unwrap();
+
+    let target = create_database().await;
+    let report = import_apkg(&target, &package).await.unwrap();
+    assert_eq!(report.created, vec!["PHYS-001", "PHYS-002"]);
+    assert_eq!(report.reviews_imported, 2);
+
+    let reviewed = source.get_card(newton.id).await.unwrap().unwrap();
+    let imported = target
+        .get_card_by_zettel_id("PHYS-001")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(imported.title, reviewed.title);
+    assert_eq!(imported.content, reviewed.content);
+    assert_eq!(imported.state, reviewed.state);
+    assert_eq!(imported.stability, reviewed.stability);
+    assert_eq!(imported.difficulty, reviewed.difficulty);
+    assert_eq!(imported.reps, reviewed.reps);
+    assert_eq!(
+        imported.next_review.date_naive(),
+        reviewed.next_review.date_naive()
+    );
+    assert_eq!(
+        target.get_topic_names_for_card(imported.id).await.unwrap(),
+        vec!["Classical Mechanics", "Physics"]
+    );
+
+    let cloze = target
+        .get_card_by_zettel_id("PHYS-002")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cloze.content, "Light travels at {{c1::299,792}} km/s.");
+    assert_eq!(cloze.state, "New");
+}
@@ -1,6 +1,6 @@
 use axum::http::StatusCode;
 use axum_test::TestServer;
-use learning_system::{CardService, Database, LLMService, api::*};
+use learning_system::{CardService, Database, LLMService, api::*, question_cache::QuestionCache};
 use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -14,6 +14,7 @@ async fn create_test_server() -> TestServer {
         card_service,
         llm_service,
         review_sessions: Arc::new(Mutex::new(HashMap::new())),
+        question_cache: QuestionCache::new(100, 60),
     };
 
     let app = create_router(app_state);
@@ -506,3 +507,397 @@ async fn test_api_error_response_format() {
     assert!(body["error"].is_string());
     assert!(body["error"].as_str().unwrap().contains("not found"));
 }
+
+#[tokio::test]
+async fn test_api_csv_import_dry_run_then_import() {
+    let server = create_test_server().await;
+    server
+        .post("/api/cards")
+        .json(&json!({
+            "zettel_id": "PHYS-001",
+            "content": "Force equals mass times acceleration",
+            "topic_ids": []
+        }))
+        .await
+        .assert_status_ok();
+
+    let csv = "Term,Definition,Tags,See also\nMomentum,p = mv,physics,PHYS-001; CSV-002\nImpulse,\"Change in momentum, J = Δp\",physics;mechanics,\n";
+    let request = |dry_run: bool| {
+        json!({
+            "data": csv,
+            "mapping": { "title": "Term", "content": "Definition", "topics": "Tags", "links": "See also" },
+            "dry_run": dry_run
+        })
+    };
+
+    let response = server
+        .post("/api/cards/import/csv")
+        .json(&request(true))
+        .await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_eq!(body["data"]["created"], json!(["CSV-001", "CSV-002"]));
+    assert_eq!(
+        body["data"]["topics_created"],
+        json!(["physics", "mechanics"])
+    );
+    assert_eq!(body["data"]["errors"], json!([]));
+    let cards: Value = server.get("/api/cards").await.json();
+    assert_eq!(cards["data"].as_array().unwrap().len(), 1);
+
+    server
+        .post("/api/cards/import/csv")
+        .json(&request(false))
+        .await
+        .assert_status_ok();
+    let momentum: Value = server.get("/api/cards/zettel/CSV-001").await.json();
+    assert_eq!(momentum["data"]["title"], "Momentum");
+    let momentum_id = momentum["data"]["id"].as_str().unwrap();
+    let links: Value = server
+        .get(&format!("/api/cards/{}/links", momentum_id))
+        .await
+        .json();
+    assert_eq!(links["data"].as_array().unwrap().len(), 2);
+    let impulse: Value = server.get("/api/cards/zettel/CSV-002").await.json();
+    assert_eq!(impulse["data"]["content"], "Change in momentum, J = Δp");
+}
+
+#[tokio::test]
+async fn test_api_csv_import_reports_row_errors() {
+    let server = create_test_server().await;
+
+    let response = server
+        .post("/api/cards/import/csv")
+        .json(&json!({
+            "data": "zettel_id\tcontent\tlinks\nA-1\tFirst\tMISSING\nA-1\tDuplicate\t\nA-2\t\t\nA-3\tThird\t\textra\n"
+        }))
+        .await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    let errors: Vec<(u64, String)> = body["data"]["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["line"].as_u64().unwrap(),
+                e["message"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (2, "Link to unknown zettel_id 'MISSING'".to_string()),
+            (3, "zettel_id 'A-1' is already used on line 2".to_string()),
+            (4, "Content is empty".to_string()),
+            (5, "Expected 3 fields but found 4".to_string()),
+        ]
+    );
+    let cards: Value = server.get("/api/cards").await.json();
+    assert!(cards["data"].as_array().unwrap().is_empty());
+
+    let response = server
+        .post("/api/cards/import/csv")
+        .json(&json!({ "data": "front,back\nx,y\n" }))
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_api_card_reports_unresolved_inline_links() {
+    let server = create_test_server().await;
+
+    let response = server
+        .post("/api/cards")
+        .json(&json!({
+            "zettel_id": "LINK-001",
+            "content": "Depends on [[LINK-002]]",
+            "topic_ids": []
+        }))
+        .await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_eq!(body["data"]["zettel_id"], "LINK-001");
+    assert_eq!(body["data"]["unresolved_links"], json!(["LINK-002"]));
+    let first_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let response = server
+        .post("/api/cards")
+        .json(&json!({
+            "zettel_id": "LINK-002",
+            "content": "The foundation",
+            "topic_ids": []
+        }))
+        .await;
+    let body: Value = response.json();
+    assert_eq!(body["data"]["unresolved_links"], json!([]));
+    let second_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let links: Value = server
+        .get(&format!("/api/cards/{}/links", first_id))
+        .await
+        .json();
+    assert_eq!(links["data"][0]["id"], second_id);
+    let backlinks: Value = server
+        .get(&format!("/api/cards/{}/backlinks", second_id))
+        .await
+        .json();
+    assert_eq!(backlinks["data"][0]["id"], first_id);
+}
+
+#[tokio::test]
+async fn test_api_typed_links() {
+    let server = create_test_server().await;
+    let create =
+        |zettel_id: &str| json!({ "zettel_id": zettel_id, "content": zettel_id, "topic_ids": [] });
+    let limits: Value = server
+        .post("/api/cards")
+        .json(&create("TYPE-1"))
+        .await
+        .json();
+    let limits_id = limits["data"]["id"].as_str().unwrap().to_string();
+    server.post("/api/cards").json(&create("TYPE-2")).await;
+
+    let response = server
+        .post("/api/cards")
+        .json(&json!({
+            "zettel_id": "TYPE-3",
+            "content": "Derivatives",
+            "topic_ids": [],
+            "zettel_links": ["TYPE-2"],
+            "typed_links": [
+                { "zettel_id": "TYPE-1", "relation": "prerequisite", "note": "Limits first" }
+            ]
+        }))
+        .await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    let derivatives_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let links: Value = server
+        .get(&format!("/api/cards/{}/links", derivatives_id))
+        .await
+        .json();
+    assert_eq!(links["data"].as_array().unwrap().len(), 2);
+    assert_eq!(links["data"][0]["relation"], "see-also");
+    assert_eq!(links["data"][1]["zettel_id"], "TYPE-1");
+    assert_eq!(links["data"][1]["relation"], "prerequisite");
+    assert_eq!(links["data"][1]["note"], "Limits first");
+
+    let prerequisites: Value = server
+        .get(&format!(
+            "/api/cards/{}/links?relation=prerequisite",
+            derivatives_id
+        ))
+        .await
+        .json();
+    assert_eq!(prerequisites["data"].as_array().unwrap().len(), 1);
+
+    let backlinks: Value = server
+        .get(&format!("/api/cards/{}/backlinks", limits_id))
+        .await
+        .json();
+    assert_eq!(backlinks["data"][0]["id"], derivatives_id);
+    assert_eq!(backlinks["data"][0]["relation"], "prerequisite");
+
+    let unknown = server
+        .post("/api/cards")
+        .json(&json!({
+            "zettel_id": "TYPE-4",
+            "content": "Integrals",
+            "topic_ids": [],
+            "typed_links": [{ "zettel_id": "TYPE-1", "relation": "requires" }]
+        }))
+        .await;
+    assert_eq!(unknown.status_code(), 422);
+}
+
+#[tokio::test]
+async fn test_api_weak_foundations() {
+    let server = create_test_server().await;
+    server
+        .post("/api/cards")
+        .json(&json!({ "zettel_id": "WEAK-1", "content": "Limits", "topic_ids": [] }))
+        .await;
+    server
+        .post("/api/cards")
+        .json(&json!({
+            "zettel_id": "WEAK-2",
+            "content": "Derivatives",
+            "topic_ids": [],
+            "typed_links": [{ "zettel_id": "WEAK-1", "relation": "prerequisite" }]
+        }))
+        .await;
+
+    let response = server.get("/api/review/weak-foundations").await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["zettel_id"], "WEAK-2");
+    assert_eq!(body["data"][0]["prerequisites"][0]["zettel_id"], "WEAK-1");
+    assert_eq!(body["data"][0]["prerequisites"][0]["retrievability"], 0.0);
+}
+
+#[tokio::test]
+async fn test_api_rename_rewrites_references() {
+    let server = create_test_server().await;
+    let create = |zettel_id: &str, content: &str| json!({ "zettel_id": zettel_id, "content": content, "topic_ids": [] });
+    let target: Value = server
+        .post("/api/cards")
+        .json(&create("OLD-1", "Target card"))
+        .await
+        .json();
+    let target_id = target["data"]["id"].as_str().unwrap().to_string();
+    let referencing: Value = server
+        .post("/api/cards")
+        .json(&create("REF-1", "Builds on [[OLD-1]]"))
+        .await
+        .json();
+    let referencing_id = referencing["data"]["id"].as_str().unwrap().to_string();
+
+    let preview: Value = server
+        .post(&format!("/api/cards/{}/rename", target_id))
+        .json(&json!({ "zettel_id": "NEW-1", "preview": true }))
+        .await
+        .json();
+    assert_eq!(preview["data"]["preview"], true);
+    assert_eq!(preview["data"]["rewritten"][0]["zettel_id"], "REF-1");
+    let unchanged: Value = server
+        .get(&format!("/api/cards/{}", referencing_id))
+        .await
+        .json();
+    assert_eq!(unchanged["data"]["content"], "Builds on [[OLD-1]]");
+
+    let response = server
+        .put(&format!("/api/cards/{}?rewrite_references=true", target_id))
+        .json(&json!({ "zettel_id": "NEW-1", "title": "Renamed" }))
+        .await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_eq!(body["data"]["zettel_id"], "NEW-1");
+    assert_eq!(body["data"]["title"], "Renamed");
+    assert_eq!(
+        body["data"]["rewritten_references"][0]["card_id"],
+        referencing_id
+    );
+    let rewritten: Value = server
+        .get(&format!("/api/cards/{}", referencing_id))
+        .await
+        .json();
+    assert_eq!(rewritten["data"]["content"], "Builds on [[NEW-1]]");
+
+    // Without the option the zettel_id changes but content is left alone
+    let response = server
+        .put(&format!("/api/cards/{}", target_id))
+        .json(&json!({ "zettel_id": "NEW-2" }))
+        .await;
+    let body: Value = response.json();
+    assert_eq!(body["data"]["rewritten_references"], json!([]));
+    let untouched: Value = server
+        .get(&format!("/api/cards/{}", referencing_id))
+        .await
+        .json();
+    assert_eq!(untouched["data"]["content"], "Builds on [[NEW-1]]");
+
+    // The zettel_id is trimmed, and links to the card in the new content are rewritten
+    let response = server
+        .put(&format!("/api/cards/{}?rewrite_references=true", target_id))
+        .json(&json!({ "zettel_id": " NEW-3 ", "content": "Unlike [[NEW-2]]" }))
+        .await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_eq!(body["data"]["zettel_id"], "NEW-3");
+    assert_eq!(body["data"]["content"], "Unlike [[NEW-3]]");
+
+    // A failed update rolls back the rename and its rewrites
+    let other: Value = server
+        .post("/api/cards")
+        .json(&create("REF-2", "See [[NEW-3]]"))
+        .await
+        .json();
+    let other_id = other["data"]["id"].as_str().unwrap().to_string();
+    let response = server
+        .put(&format!("/api/cards/{}?rewrite_references=true", target_id))
+        .json(&json!({ "zettel_id": "NEW-4", "topic_ids": [Uuid::new_v4()] }))
+        .await;
+    assert!(!response.status_code().is_success());
+    let unchanged: Value = server
+        .get(&format!("/api/cards/{}", target_id))
+        .await
+        .json();
+    assert_eq!(unchanged["data"]["zettel_id"], "NEW-3");
+    assert_eq!(unchanged["data"]["content"], "Unlike [[NEW-3]]");
+    let unchanged: Value = server.get(&format!("/api/cards/{}", other_id)).await.json();
+    assert_eq!(unchanged["data"]["content"], "See [[NEW-3]]");
+}
+
+#[tokio::test]
+async fn test_api_knowledge_graph() {
+    let server = create_test_server().await;
+    let mut ids = HashMap::new();
+    for (zettel_id, content) in [
+        ("G-1", "Start"),
+        ("G-2", "Builds on [[G-1]]"),
+        ("G-3", "Builds on [[G-2]]"),
+        ("G-4", "Stands alone"),
+    ] {
+        let body: Value = server
+            .post("/api/cards")
+            .json(&json!({ "zettel_id": zettel_id, "content": content, "topic_ids": [] }))
+            .await
+            .json();
+        ids.insert(zettel_id, body["data"]["id"].as_str().unwrap().to_string());
+    }
+
+    let graph: Value = server.get("/api/graph").await.json();
+    assert_eq!(graph["data"]["nodes"].as_array().unwrap().len(), 4);
+    assert_eq!(graph["data"]["edges"].as_array().unwrap().len(), 2);
+
+    let neighborhood: Value = server
+        .get(&format!("/api/cards/{}/graph?depth=1", ids["G-1"]))
+        .await
+        .json();
+    let zettel_ids: Vec<&str> = neighborhood["data"]["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| node["zettel_id"].as_str().unwrap())
+        .collect();
+    assert_eq!(zettel_ids, vec!["G-1", "G-2"]);
+
+    let path: Value = server
+        .get(&format!(
+            "/api/graph/path?from={}&to={}",
+            ids["G-1"], ids["G-3"]
+        ))
+        .await
+        .json();
+    assert_eq!(path["data"].as_array().unwrap().len(), 3);
+    server
+        .get(&format!(
+            "/api/graph/path?from={}&to={}",
+            ids["G-1"], ids["G-4"]
+        ))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    let orphans: Value = server.get("/api/graph/orphans").await.json();
+    assert_eq!(orphans["data"][0]["zettel_id"], "G-4");
+
+    let dot = server.get("/api/graph?format=dot").await;
+    dot.assert_status_ok();
+    assert!(dot.text().starts_with("digraph cards {"));
+    let graphml = server
+        .get(&format!("/api/cards/{}/graph?format=graphml", ids["G-2"]))
+        .await;
+    assert!(
+        graphml
+            .text()
+            .contains("<data key=\"zettel_id\">G-3</data>")
+    );
+    server
+        .get(&format!("/api/cards/{}/graph", Uuid::new_v4()))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,158 @@
+use learning_system::backup::{BACKUP_FORMAT_VERSION, Backup, restore_backup};
+use learning_system::{CardService, CreateCardRequest, Database};
+
+async fn create_database() -> Database {
+    Database::new("sqlite::memory:").await.unwrap()
+}
+
+/// A Physics topic with two linked cards, one of them reviewed
+async fn populate(db: &Database) {
+    let card_service = CardService::new(db.clone());
+    let physics = card_service
+        .create_topic(
+            "Physics".to_string(),
+            Some("Mechanics and more".to_string()),
+        )
+        .await
+        .unwrap();
+    let force = card_service
+        .create_card(CreateCardRequest {
+            zettel_id: "PHYS-001".to_string(),
+            title: Some("Newton's second law".to_string()),
+            content: "Force equals mass times acceleration: $F = ma$".to_string(),
+            topic_ids: vec![physics.id],
+            links: None,
+        })
+        .await
+        .unwrap();
+    card_service
+        .create_card(CreateCardRequest {
+            zettel_id: "PHYS-002".to_string(),
+            title: Some("Momentum".to_string()),
+            content: "Force is the rate of change of momentum".to_string(),
+            topic_ids: vec![physics.id],
+            links: Some(vec![force.id]),
+        })
+        .await
+        .unwrap();
+    db.create_review(force.id, 3, 4.0, 2.5).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_backup_round_trips_into_empty_database() {
+    let source = create_database().await;
+    populate(&source).await;
+    let backup = Backup::from_database(&source).await.unwrap();
+    assert_eq!(backup.format_version, BACKUP_FORMAT_VERSION);
+    assert_eq!(
+        (
+            backup.cards.len(),
+            backup.topics.len(),
+            backup.card_topics.len(),
+            backup.reviews.len(),
+            backup.backlinks.len()
+        ),
+        (2, 1, 2, 1, 1)
+    );
+
+    for text in [backup.to_json().unwrap(), backup.to_jsonl().unwrap()] {
+        let target = create_database().await;
+        let report = restore_backup(&target, &Backup::parse(&text).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(report.cards_imported, 2);
+        assert!(report.remapped_card_ids.is_empty());
+
+        let restored = Backup::from_database(&target).await.unwrap();
+        let force = backup
+            .cards
+            .iter()
+            .find(|c| c.zettel_id == "PHYS-001")
+            .unwrap();
+        let momentum = target
+            .get_card_by_zettel_id("PHYS-002")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            target.get_card(force.id).await.unwrap().unwrap().reps,
+            force.reps
+        );
+        assert_eq!(
+            momentum.links.as_deref(),
+            Some(format!("[\"{}\"]", force.id).as_str())
+        );
+        assert_eq!(restored.card_topics.len(), 2);
+        assert_eq!(restored.reviews[0].id, backup.reviews[0].id);
+        assert_eq!(restored.backlinks[0].target_card_id, force.id);
+    }
+}
+
+#[tokio::test]
+async fn test_restore_remaps_conflicting_ids() {
+    let db = create_database().await;
+    populate(&db).await;
+    let backup = Backup::from_database(&db).await.unwrap();
+
+    // Restoring into the same database collides on every id and zettel_id
+    let report = restore_backup(&db, &backup).await.unwrap();
+    assert_eq!(report.cards_imported, 2);
+    assert_eq!(report.remapped_card_ids.len(), 2);
+    assert_eq!(report.renamed_zettel_ids["PHYS-001"], "PHYS-001-2");
+    assert_eq!(report.topics_merged, 1);
+    assert_eq!(report.topics_imported, 0);
+
+    let old_force = db.get_card_by_zettel_id("PHYS-001").await.unwrap().unwrap();
+    let new_force = db
+        .get_card_by_zettel_id("PHYS-001-2")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(report.remapped_card_ids[&old_force.id], new_force.id);
+    let new_momentum = db
+        .get_card_by_zettel_id("PHYS-002-2")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        new_momentum.links.as_deref(),
+        Some(format!("[\"{}\"]", new_force.id).as_str())
+    );
+    let backlinks = db.get_backlinks(new_force.id).await.unwrap();
+    assert_eq!(backlinks.len(), 1);
+    assert_eq!(backlinks[0].id, new_momentum.id);
+
+    let after = Backup::from_database(&db).await.unwrap();
+    assert_eq!(after.topics.len(), 1);
+    assert_eq!(after.card_topics.len(), 4);
+    assert_eq!(after.reviews.len(), 2);
+}
+
+#[tokio::test]
+async fn test_restore_rejects_dangling_references_without_changes() {
+    let source = create_database().await;
+    populate(&source).await;
+    let mut backup = Backup::from_database(&source).await.unwrap();
+    // Drop the linked-to card but keep everything that points at it
+    backup.cards.retain(|card| card.zettel_id != "PHYS-001");
+
+    let target = create_database().await;
+    let error = restore_backup(&target, &backup).await.unwrap_err();
+    let message = error.to_string();
+    assert!(message.contains("links to unknown card"), "{}", message);
+    assert!(message.contains("Review"), "{}", message);
+    assert!(target.get_all_cards().await.unwrap().is_empty());
+    assert!(target.get_all_topics().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_parse_rejects_newer_format_version() {
+    let db = create_database().await;
+    let mut backup = Backup::from_database(&db).await.unwrap();
+    backup.format_version = BACKUP_FORMAT_VERSION + 1;
+
+    for text in [backup.to_json().unwrap(), backup.to_jsonl().unwrap()] {
+        let error = Backup::parse(&text).unwrap_err();
+        assert!(error.to_string().contains("newer"), "{}", error);
+    }
+}
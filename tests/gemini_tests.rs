@@ -1,3 +1,5 @@
+#![allow(clippy::assertions_on_constants)]
+
 use chrono::Utc;
 use learning_system::llm_providers::LLMProviderType;
 use learning_system::{Card, LLMService};
@@ -133,7 +135,7 @@ async fn test_provider_compatibility() {
         let _service = LLMService::new_with_provider(
             "test-key".to_string(),
             None,
-            provider.clone(),
+            provider,
             Some(model.to_string()),
         );
 
@@ -151,12 +153,11 @@ async fn test_card_structure_compatibility() {
     let providers = vec![LLMProviderType::OpenAI, LLMProviderType::Gemini];
 
     for provider in providers {
-        let _service =
-            LLMService::new_with_provider("test-key".to_string(), None, provider.clone(), None);
+        let _service = LLMService::new_with_provider("test-key".to_string(), None, provider, None);
 
         // We can't call actual methods without API keys, but we can verify
         // that the service accepts the same card structure
-        assert_eq!(card.content.len() > 0, true);
+        assert!(!card.content.is_empty());
         println!("✅ {:?} provider compatible with card structure", provider);
     }
 }
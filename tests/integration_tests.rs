@@ -965,6 +965,7 @@ async fn test_backlinks_with_zettel_linking() {
             content: "Card A".to_string(),
             topic_ids: vec![],
             zettel_links: None,
+            typed_links: None,
         })
         .await
         .unwrap();
@@ -976,6 +977,7 @@ async fn test_backlinks_with_zettel_linking() {
             content: "Card B".to_string(),
             topic_ids: vec![],
             zettel_links: None,
+            typed_links: None,
         })
         .await
         .unwrap();
@@ -987,6 +989,7 @@ async fn test_backlinks_with_zettel_linking() {
         content: None,
         topic_ids: None,
         zettel_links: Some(vec!["ZETTEL-B".to_string()]),
+        typed_links: None,
     };
     card_service
         .update_card_with_zettel_links(card_a.id, update_request)
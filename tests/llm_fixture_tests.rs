@@ -0,0 +1,201 @@
+use axum::{Json, Router, routing::post};
+use chrono::{TimeZone, Utc};
+use learning_system::llm_providers::LLMProviderType;
+use learning_system::{Card, FixtureMode, LLMService, QuizQuestion};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Recorded responses committed with the tests; re-record with `LLM_FIXTURE_MODE=record`
+const FIXTURE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/llm");
+
+// Cards and questions must stay byte-for-byte stable: the fixture key is a hash of the prompt
+fn create_fixture_card() -> Card {
+    Card {
+        id: Uuid::nil(),
+        zettel_id: "FIXTURE-001".to_string(),
+        title: Some("Photosynthesis".to_string()),
+        content: "Photosynthesis converts light energy into chemical energy. In the light-dependent reactions, chlorophyll absorbs light and water is split, releasing oxygen. The Calvin cycle then fixes carbon dioxide into sugars using ATP and NADPH.".to_string(),
+        creation_date: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        last_reviewed: None,
+        next_review: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        difficulty: 0.0,
+        stability: 0.0,
+        retrievability: 0.0,
+        reps: 0,
+        lapses: 0,
+        state: "New".to_string(),
+        links: None,
+    }
+}
+
+fn create_fixture_question() -> QuizQuestion {
+    QuizQuestion {
+        question: "Which gas is released when water is split during the light-dependent reactions?"
+            .to_string(),
+        question_type: "short_answer".to_string(),
+        options: None,
+        correct_answer: Some("Oxygen".to_string()),
+        prompt_version: None,
+        rubric: None,
+    }
+}
+
+fn replay_service(dir: impl Into<PathBuf>) -> LLMService {
+    // The configured provider is never contacted in replay mode
+    LLMService::new(
+        "unused-key".to_string(),
+        Some("http://127.0.0.1:9".to_string()),
+    )
+    .with_fixtures(FixtureMode::Replay, dir)
+}
+
+/// Serve a fixed OpenAI chat completion so recording can run without network access
+async fn spawn_openai_server(content: &'static str) -> String {
+    let app = Router::new().route(
+        "/chat/completions",
+        post(move || async move {
+            Json(serde_json::json!({
+                "id": "chatcmpl-fixture",
+                "object": "chat.completion",
+                "created": 1_735_689_600,
+                "model": "gpt-4o-mini-2024-07-18",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": content },
+                    "finish_reason": "stop"
+                }],
+                "usage": { "prompt_tokens": 420, "completion_tokens": 64, "total_tokens": 484 }
+            }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", address)
+}
+
+fn fixture_files(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_replays_generation_wrapped_in_prose_and_code_fence() {
+    let questions = replay_service(FIXTURE_DIR)
+        .generate_quiz_questions(&create_fixture_card())
+        .await
+        .unwrap();
+
+    assert_eq!(questions.len(), 3);
+    assert_eq!(questions[0].question_type, "multiple_choice");
+    assert_eq!(questions[0].options.as_ref().map(Vec::len), Some(4));
+    // The model answered with a null for a non-multiple-choice question
+    assert!(questions[1].options.is_none());
+    assert_eq!(
+        questions[0].prompt_version.as_deref(),
+        Some("quiz_generation@v5")
+    );
+}
+
+#[tokio::test]
+async fn test_replays_grading_with_unicode_feedback() {
+    let result = replay_service(FIXTURE_DIR)
+        .grade_answer(
+            &create_fixture_card(),
+            &create_fixture_question(),
+            "O2 — the oxygen comes from the water",
+        )
+        .await
+        .unwrap();
+
+    assert!(result.is_correct);
+    assert_eq!(result.suggested_rating, 4);
+    assert!(result.feedback.contains("O₂"));
+    assert_eq!(result.served_by.as_deref(), Some("Fixture:replay"));
+}
+
+#[tokio::test]
+async fn test_streaming_grading_replays_fixture() {
+    let mut streamed = String::new();
+    let result = replay_service(FIXTURE_DIR)
+        .grade_answer_streaming(
+            &create_fixture_card(),
+            &create_fixture_question(),
+            "O2 — the oxygen comes from the water",
+            |feedback| streamed.push_str(feedback),
+        )
+        .await
+        .unwrap();
+
+    assert!(result.is_correct);
+    assert_eq!(streamed, result.feedback);
+}
+
+#[tokio::test]
+async fn test_replay_reports_missing_fixture() {
+    let mut question = create_fixture_question();
+    question.question = "A question nobody has recorded yet".to_string();
+
+    let error = replay_service(FIXTURE_DIR)
+        .grade_answer(&create_fixture_card(), &question, "anything")
+        .await
+        .unwrap_err();
+
+    assert!(error.to_string().contains("No LLM fixture recorded"));
+}
+
+#[tokio::test]
+async fn test_record_then_replay_round_trip() {
+    let dir = std::env::temp_dir().join(format!("llm-fixtures-{}", Uuid::new_v4()));
+    let base_url = spawn_openai_server(
+        "{\"is_correct\": false, \"feedback\": \"Carbon dioxide is consumed, not released.\", \"suggested_rating\": 1}",
+    )
+    .await;
+
+    let recorder = LLMService::new_with_provider(
+        "test-key".to_string(),
+        Some(base_url),
+        LLMProviderType::OpenAI,
+        Some("gpt-4o-mini".to_string()),
+    )
+    .with_fixtures(FixtureMode::Record, &dir);
+
+    let recorded = recorder
+        .grade_answer(
+            &create_fixture_card(),
+            &create_fixture_question(),
+            "Carbon dioxide",
+        )
+        .await
+        .unwrap();
+    assert!(!recorded.is_correct);
+    assert_eq!(recorded.served_by.as_deref(), Some("Fixture:gpt-4o-mini"));
+
+    let files = fixture_files(&dir);
+    assert_eq!(files.len(), 1);
+    let fixture: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&files[0]).unwrap()).unwrap();
+    assert_eq!(fixture["provider"], "OpenAI");
+    assert_eq!(fixture["usage"]["prompt_tokens"], 420);
+
+    let replayed = replay_service(&dir)
+        .grade_answer(
+            &create_fixture_card(),
+            &create_fixture_question(),
+            "Carbon dioxide",
+        )
+        .await
+        .unwrap();
+    assert_eq!(replayed.feedback, recorded.feedback);
+    assert_eq!(replayed.suggested_rating, 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::assertions_on_constants)]
+
 use chrono::Utc;
 use learning_system::llm_providers::LLMProviderType;
 use learning_system::{BatchGradingRequest, Card, LLMService, QuizQuestion};
@@ -33,6 +35,8 @@ fn create_test_question() -> QuizQuestion {
             "O(1)".to_string(),
         ]),
         correct_answer: Some("B".to_string()),
+        prompt_version: None,
+        rubric: None,
     }
 }
 
@@ -105,7 +109,7 @@ async fn test_model_customization() {
             let _service = LLMService::new_with_provider(
                 "test-key".to_string(),
                 None,
-                provider.clone(),
+                provider,
                 Some(model.to_string()),
             );
 
@@ -144,7 +148,7 @@ async fn test_base_url_customization() {
             let _service = LLMService::new_with_provider(
                 "test-key".to_string(),
                 Some(base_url.to_string()),
-                provider.clone(),
+                provider,
                 None,
             );
 
@@ -160,25 +164,26 @@ async fn test_batch_operations_interface() {
     // Test that batch operations work with both providers
     let card1 = create_test_card();
     let card2 = create_test_card();
-    let _cards = vec![card1.clone(), card2.clone()];
+    let _cards = [card1.clone(), card2.clone()];
 
     let providers = vec![LLMProviderType::OpenAI, LLMProviderType::Gemini];
 
     for provider in providers {
-        let _service =
-            LLMService::new_with_provider("test-key".to_string(), None, provider.clone(), None);
+        let _service = LLMService::new_with_provider("test-key".to_string(), None, provider, None);
 
         // Test that batch request structures are compatible
-        let _batch_requests = vec![
+        let _batch_requests = [
             BatchGradingRequest {
                 card_content: card1.content.clone(),
                 question: create_test_question(),
                 user_answer: "Test answer 1".to_string(),
+                card_id: None,
             },
             BatchGradingRequest {
                 card_content: card2.content.clone(),
                 question: create_test_question(),
                 user_answer: "Test answer 2".to_string(),
+                card_id: None,
             },
         ];
 
@@ -220,8 +225,7 @@ async fn test_json_response_structures() {
     "#;
 
     for provider in providers {
-        let _service =
-            LLMService::new_with_provider("test-key".to_string(), None, provider.clone(), None);
+        let _service = LLMService::new_with_provider("test-key".to_string(), None, provider, None);
 
         // Both providers should work with the same JSON structures
         println!("✅ {:?} provider expects quiz format: parsed OK", provider);
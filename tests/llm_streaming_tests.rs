@@ -0,0 +1,179 @@
+use axum::{Router, http::header, response::IntoResponse, routing::post};
+use chrono::Utc;
+use learning_system::llm_providers::LLMProviderType;
+use learning_system::{Card, LLMService, QuizQuestion};
+use uuid::Uuid;
+
+fn create_test_card() -> Card {
+    Card {
+        id: Uuid::new_v4(),
+        zettel_id: "TEST-STREAM-001".to_string(),
+        title: None,
+        content: "Binary search halves the search interval on every step.".to_string(),
+        creation_date: Utc::now(),
+        last_reviewed: None,
+        next_review: Utc::now(),
+        difficulty: 0.0,
+        stability: 0.0,
+        retrievability: 0.0,
+        reps: 0,
+        lapses: 0,
+        state: "New".to_string(),
+        links: None,
+    }
+}
+
+fn create_test_question() -> QuizQuestion {
+    QuizQuestion {
+        question: "What is the time complexity of binary search?".to_string(),
+        question_type: "short_answer".to_string(),
+        options: None,
+        correct_answer: Some("O(log n)".to_string()),
+        prompt_version: None,
+        rubric: None,
+    }
+}
+
+/// Grading JSON split into awkward pieces, including one in the middle of an escape
+const GRADING_PIECES: &[&str] = &[
+    "{\"is_correct\": true, \"feed",
+    "back\": \"Right: it\\u00e9",
+    "s O(log n) \\",
+    "\"logarithmic\\\".\", \"suggested_rating\": 4}",
+];
+
+fn openai_sse_body() -> String {
+    let mut body = String::new();
+    for piece in GRADING_PIECES {
+        let event = serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "choices": [{ "index": 0, "delta": { "content": piece } }]
+        });
+        body.push_str(&format!("data: {}\n\n", event));
+    }
+    body.push_str(
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":50,\"completion_tokens\":20,\"total_tokens\":70}}\n\n",
+    );
+    body.push_str("data: [DONE]\n\n");
+    body
+}
+
+fn gemini_sse_body() -> String {
+    let mut body = String::new();
+    for piece in GRADING_PIECES {
+        let event = serde_json::json!({
+            "candidates": [{ "content": { "parts": [{ "text": piece }], "role": "model" } }],
+            "usageMetadata": { "promptTokenCount": 50, "candidatesTokenCount": 5 }
+        });
+        body.push_str(&format!("data: {}\r\n\r\n", event));
+    }
+    body
+}
+
+async fn spawn_sse_server(path: &str, body: String) -> String {
+    let app = Router::new().route(
+        path,
+        post(move || {
+            let body = body.clone();
+            async move { ([(header::CONTENT_TYPE, "text/event-stream")], body).into_response() }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", address)
+}
+
+#[tokio::test]
+async fn test_openai_streaming_grading() {
+    let base_url = spawn_sse_server("/chat/completions", openai_sse_body()).await;
+    let llm_service = LLMService::new_with_provider(
+        "test-key".to_string(),
+        Some(base_url),
+        LLMProviderType::OpenAI,
+        Some("gpt-4o-mini".to_string()),
+    );
+
+    let mut streamed = String::new();
+    let result = llm_service
+        .grade_answer_streaming(
+            &create_test_card(),
+            &create_test_question(),
+            "logarithmic time",
+            |feedback| streamed.push_str(feedback),
+        )
+        .await
+        .unwrap();
+
+    assert!(result.is_correct);
+    assert_eq!(result.suggested_rating, 4);
+    assert_eq!(result.feedback, "Right: ités O(log n) \"logarithmic\".");
+    assert_eq!(streamed, result.feedback);
+    assert_eq!(result.served_by.as_deref(), Some("OpenAI:gpt-4o-mini"));
+}
+
+#[tokio::test]
+async fn test_gemini_streaming_grading() {
+    let base_url = spawn_sse_server(
+        "/models/gemini-1.5-pro:streamGenerateContent",
+        gemini_sse_body(),
+    )
+    .await;
+    let llm_service = LLMService::new_with_provider(
+        "test-key".to_string(),
+        Some(base_url),
+        LLMProviderType::Gemini,
+        Some("gemini-1.5-pro".to_string()),
+    );
+
+    let mut streamed = String::new();
+    let result = llm_service
+        .grade_answer_streaming(
+            &create_test_card(),
+            &create_test_question(),
+            "logarithmic time",
+            |feedback| streamed.push_str(feedback),
+        )
+        .await
+        .unwrap();
+
+    assert!(result.is_correct);
+    assert_eq!(streamed, result.feedback);
+    assert_eq!(result.served_by.as_deref(), Some("Gemini:gemini-1.5-pro"));
+}
+
+#[tokio::test]
+async fn test_streaming_http_error_is_reported() {
+    let app = Router::new().route(
+        "/chat/completions",
+        post(|| async { (axum::http::StatusCode::UNAUTHORIZED, "invalid api key") }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let llm_service = LLMService::new_with_provider(
+        "bad-key".to_string(),
+        Some(format!("http://{}", address)),
+        LLMProviderType::OpenAI,
+        None,
+    );
+
+    let error = llm_service
+        .grade_answer_streaming(
+            &create_test_card(),
+            &create_test_question(),
+            "logarithmic time",
+            |_| {},
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("invalid api key"), "{}", error);
+}
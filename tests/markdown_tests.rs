@@ -1,6 +1,6 @@
 use axum::http::StatusCode;
 use axum_test::TestServer;
-use learning_system::{CardService, Database, LLMService, api::*};
+use learning_system::{CardService, Database, LLMService, api::*, question_cache::QuestionCache};
 use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -13,6 +13,7 @@ async fn create_test_server() -> TestServer {
         card_service,
         llm_service,
         review_sessions: Arc::new(Mutex::new(HashMap::new())),
+        question_cache: QuestionCache::new(100, 60),
     };
 
     let application = create_router(app_state);
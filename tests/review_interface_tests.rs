@@ -1,6 +1,6 @@
 use axum_test::TestServer;
 use learning_system::llm_providers::LLMProviderType;
-use learning_system::{CardService, Database, LLMService, api::*};
+use learning_system::{CardService, Database, LLMService, api::*, question_cache::QuestionCache};
 use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -14,6 +14,7 @@ async fn create_test_server() -> TestServer {
         card_service,
         llm_service,
         review_sessions: Arc::new(Mutex::new(HashMap::new())),
+        question_cache: QuestionCache::new(100, 60),
     };
 
     let app = create_router(app_state);
@@ -308,7 +309,7 @@ async fn test_expand_collapse_content_lengths() {
     let server = create_test_server().await;
 
     // Test different content lengths for expand/collapse behavior
-    let content_cases = vec![
+    let content_cases = [
         ("Very short", "SHORT"),
         (
             "Medium length content that spans multiple lines and contains enough text to warrant collapsing in the review interface",
@@ -343,6 +344,6 @@ async fn test_expand_collapse_content_lengths() {
         );
 
         // The frontend will handle the expand/collapse logic
-        assert!(card_data["content"].as_str().unwrap().len() > 0);
+        assert!(!card_data["content"].as_str().unwrap().is_empty());
     }
 }
@@ -1,6 +1,8 @@
+#![allow(clippy::if_same_then_else)]
+
 use axum_test::TestServer;
 use learning_system::llm_providers::LLMProviderType;
-use learning_system::{CardService, Database, LLMService, api::*};
+use learning_system::{CardService, Database, LLMService, api::*, question_cache::QuestionCache};
 use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -13,6 +15,7 @@ async fn create_test_server() -> TestServer {
         card_service,
         llm_service,
         review_sessions: Arc::new(Mutex::new(HashMap::new())),
+        question_cache: QuestionCache::new(100, 60),
     };
 
     let app = create_router(app_state);
@@ -27,6 +30,7 @@ async fn create_test_server_with_provider(provider: LLMProviderType) -> TestServ
         card_service,
         llm_service,
         review_sessions: Arc::new(Mutex::new(HashMap::new())),
+        question_cache: QuestionCache::new(100, 60),
     };
 
     let app = create_router(app_state);
@@ -353,7 +357,7 @@ async fn test_typography_scale_data_validation() {
     let server = create_test_server().await;
 
     // Test different content lengths that would use different typography scales
-    let content_variations = vec![
+    let content_variations = [
         ("H1", "# Main Heading"),
         ("H2", "## Sub Heading"),
         ("H3", "### Section"),
@@ -426,7 +430,7 @@ async fn test_suggested_rating_response_format() {
     // Verify rating is in valid FSRS range (1-4)
     let rating = data["rating"].as_i64().unwrap();
     assert!(
-        rating >= 1 && rating <= 4,
+        (1..=4).contains(&rating),
         "Rating should be between 1-4 (FSRS range)"
     );
 
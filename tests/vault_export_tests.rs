@@ -0,0 +1,110 @@
+use learning_system::vault_export::{export_html_site, export_markdown_vault};
+use learning_system::vault_import::import_vault;
+use learning_system::{CardService, CreateCardRequest, Database};
+use std::fs;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+fn output_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}-{}", name, Uuid::new_v4()))
+}
+
+/// Two Physics cards, the second linking to the first
+async fn create_collection() -> Database {
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    let card_service = CardService::new(db.clone());
+    let physics = card_service
+        .create_topic("Physics".to_string(), None)
+        .await
+        .unwrap();
+    let force = card_service
+        .create_card(CreateCardRequest {
+            zettel_id: "PHYS-001".to_string(),
+            title: Some("Newton's second law".to_string()),
+            content: "Force equals mass times acceleration: $F = ma$".to_string(),
+            topic_ids: vec![physics.id],
+            links: None,
+        })
+        .await
+        .unwrap();
+    card_service
+        .create_card(CreateCardRequest {
+            zettel_id: "PHYS/002".to_string(),
+            title: Some("Momentum".to_string()),
+            content: "Momentum is $p = mv$ <b>not</b> energy".to_string(),
+            topic_ids: vec![physics.id],
+            links: Some(vec![force.id]),
+        })
+        .await
+        .unwrap();
+    db
+}
+
+#[tokio::test]
+async fn test_markdown_export_round_trips_through_vault_import() {
+    let db = create_collection().await;
+    let dir = output_dir("vault-export");
+
+    let report = export_markdown_vault(&db, &dir).await.unwrap();
+    assert_eq!(
+        report.files,
+        vec![dir.join("PHYS-001.md"), dir.join("PHYS-002.md")]
+    );
+    assert!(report.unresolved_links.is_empty());
+
+    let momentum = fs::read_to_string(dir.join("PHYS-002.md")).unwrap();
+    assert!(momentum.contains("zettel_id: \"PHYS/002\"\n"));
+    assert!(momentum.contains("topics: [\"Physics\"]\n"));
+    assert!(momentum.contains("  state: New\n"));
+    assert!(momentum.ends_with("\n## Links\n- [[PHYS-001]]\n"));
+
+    let imported = Database::new("sqlite::memory:").await.unwrap();
+    let card_service = CardService::new(imported.clone());
+    let import = import_vault(&card_service, &dir).await.unwrap();
+    assert_eq!(import.created.len(), 2);
+    assert!(import.unresolved_links.is_empty());
+
+    let force = imported
+        .get_card_by_zettel_id("PHYS-001")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(force.title.as_deref(), Some("Newton's second law"));
+    assert_eq!(
+        force.content,
+        "Force equals mass times acceleration: $F = ma$"
+    );
+    assert_eq!(
+        imported.get_topic_names_for_card(force.id).await.unwrap(),
+        vec!["Physics"]
+    );
+    let backlinks = imported.get_backlinks(force.id).await.unwrap();
+    assert_eq!(backlinks.len(), 1);
+    assert_eq!(backlinks[0].zettel_id, "PHYS/002");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_html_export_links_pages_and_escapes_content() {
+    let db = create_collection().await;
+    let dir = output_dir("site-export");
+
+    let report = export_html_site(&db, &dir).await.unwrap();
+    assert_eq!(report.files.len(), 3);
+
+    let index = fs::read_to_string(dir.join("index.html")).unwrap();
+    assert!(index.contains("<h2>Physics</h2>"));
+    assert!(index.contains("<a href=\"PHYS-001.html\">Newton's second law</a>"));
+
+    let force = fs::read_to_string(dir.join("PHYS-001.html")).unwrap();
+    assert!(force.contains("mathjax"));
+    assert!(force.contains(
+        "<section class=\"backlinks\"><h2>Backlinks</h2><ul><li><a href=\"PHYS-002.html\">Momentum</a></li></ul></section>"
+    ));
+    let momentum = fs::read_to_string(dir.join("PHYS-002.html")).unwrap();
+    assert!(momentum.contains("$p = mv$ &lt;b&gt;not&lt;/b&gt; energy"));
+    assert!(momentum.contains("<h2>Links</h2><ul><li><a href=\"PHYS-001.html\">"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,119 @@
+use learning_system::vault_import::import_vault;
+use learning_system::{CardService, Database};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+fn write_note(vault: &Path, relative: &str, text: &str) {
+    let path = vault.join(relative);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, text).unwrap();
+}
+
+fn create_vault() -> PathBuf {
+    let vault = std::env::temp_dir().join(format!("vault-import-{}", Uuid::new_v4()));
+    write_note(
+        &vault,
+        "Biology/Osmosis.md",
+        "---\nid: BIO-001\ntags: [biology]\n---\n# Osmosis\n\nWater moves toward higher solute concentration, a kind of [[Diffusion]]. See [[Missing note]] and ![[membrane.png]].",
+    );
+    write_note(
+        &vault,
+        "Biology/Diffusion.md",
+        "# Diffusion\n\nParticles spread from high to low concentration. #biology #physics",
+    );
+    write_note(&vault, ".obsidian/workspace.md", "Not a note");
+    vault
+}
+
+#[tokio::test]
+async fn test_vault_import_creates_linked_cards() {
+    let vault = create_vault();
+    let card_service = CardService::new(Database::new("sqlite::memory:").await.unwrap());
+
+    let report = import_vault(&card_service, &vault).await.unwrap();
+    assert_eq!(report.created, vec!["Diffusion", "BIO-001"]);
+    assert_eq!(report.topics_created, vec!["biology", "physics"]);
+    assert_eq!(
+        report.unresolved_links,
+        vec![("BIO-001".to_string(), "Missing note".to_string())]
+    );
+
+    let osmosis = card_service
+        .get_card_by_zettel_id("BIO-001")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(osmosis.title.as_deref(), Some("Osmosis"));
+    assert!(osmosis.content.starts_with("Water moves"));
+
+    let diffusion = card_service
+        .get_card_by_zettel_id("Diffusion")
+        .await
+        .unwrap()
+        .unwrap();
+    let linked = card_service.get_linked_cards(osmosis.id).await.unwrap();
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked[0].id, diffusion.id);
+    let backlinks = card_service.get_backlinks(diffusion.id).await.unwrap();
+    assert_eq!(backlinks.len(), 1);
+    assert_eq!(backlinks[0].id, osmosis.id);
+
+    fs::remove_dir_all(vault).ok();
+}
+
+#[tokio::test]
+async fn test_vault_reimport_only_updates_changed_notes() {
+    let vault = create_vault();
+    let card_service = CardService::new(Database::new("sqlite::memory:").await.unwrap());
+    import_vault(&card_service, &vault).await.unwrap();
+
+    let report = import_vault(&card_service, &vault).await.unwrap();
+    assert!(report.created.is_empty());
+    assert!(report.updated.is_empty());
+    assert_eq!(report.unchanged.len(), 2);
+
+    // Dropping the link and a tag updates the card, its backlinks and its topics
+    write_note(
+        &vault,
+        "Biology/Osmosis.md",
+        "---\nid: BIO-001\n---\n# Osmosis\n\nWater moves toward higher solute concentration.",
+    );
+    let report = import_vault(&card_service, &vault).await.unwrap();
+    assert_eq!(report.updated, vec!["BIO-001"]);
+    assert_eq!(report.unchanged, vec!["Diffusion"]);
+
+    let osmosis = card_service
+        .get_card_by_zettel_id("BIO-001")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(
+        card_service
+            .get_linked_cards(osmosis.id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert!(
+        card_service
+            .get_topic_names_for_card(osmosis.id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    let diffusion = card_service
+        .get_card_by_zettel_id("Diffusion")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(
+        card_service
+            .get_backlinks(diffusion.id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    fs::remove_dir_all(vault).ok();
+}